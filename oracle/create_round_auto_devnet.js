@@ -21,6 +21,7 @@
  *   REVEAL_WINDOW_SLOTS   (default 120)
  *   NIST_CHAIN_INDEX      (default 2)
  *   NIST_PULSE_OFFSET     (default 2)
+ *   GUESS_BITS            (default 1) -> pulse bits per ticket: 1, 2 or 4
  *   DEBUG_ACCOUNTS        (default 0) -> if 1, prints account metas before sending
  */
 
@@ -182,6 +183,8 @@ async function main() {
 
   const TIMLG_MINT_ENV = process.env.TIMLG_MINT; // optional override (must match config)
 
+  const GUESS_BITS = Number(process.env.GUESS_BITS || "1");
+
   const DEBUG_ACCOUNTS = String(process.env.DEBUG_ACCOUNTS || "0") === "1";
 
   if (!PROGRAM_ID) die("Missing env PROGRAM_ID");
@@ -325,6 +328,36 @@ async function main() {
       continue;
     }
 
+    if (n.includes("claim") && n.includes("grace")) {
+      args[a.name] = new anchor.BN(0); // 0 = config default
+      continue;
+    }
+
+    if (n === "label") {
+      args[a.name] = Array(32).fill(0); // untagged
+      continue;
+    }
+
+    if (n === "oraclepubkeyoverride") {
+      args[a.name] = PublicKey.default; // use config.oracle_pubkey
+      continue;
+    }
+
+    if (n === "maxtotalreward") {
+      args[a.name] = new anchor.BN(0); // no cap
+      continue;
+    }
+
+    if (n === "minparticipants") {
+      args[a.name] = new anchor.BN(0); // no minimum
+      continue;
+    }
+
+    if (n === "guessbits") {
+      args[a.name] = GUESS_BITS;
+      continue;
+    }
+
     if (n === "roundid" || (n.includes("round") && n.includes("id"))) {
       args[a.name] = roundIdBn;
       continue;
@@ -8,6 +8,10 @@
 /// 60 slots ~ 24 seconds (assuming 400ms/slot).
 pub const MIN_REVEAL_WINDOW_SLOTS: u64 = 60;
 
+/// Minimum number of slots between round creation and its Commit Deadline,
+/// so a freshly created round always accepts commits for a few slots.
+pub const MIN_COMMIT_WINDOW_SLOTS: u64 = 4;
+
 /// Timeout in slots after Reveal Deadline to allow a Refund.
 /// If the round is not finalized by (RevealDeadline + this_timeout),
 /// users can trigger 'recover_funds' to withdraw their stake.
@@ -28,6 +32,9 @@ pub const DEFAULT_REVEAL_WINDOW_SLOTS: u64 = 1000;
 /// Default grace period for claims before sweeping (slots).
 pub const DEFAULT_CLAIM_GRACE_SLOTS: u64 = 900;
 
+/// Default cap on reveal deadline extensions per round.
+pub const DEFAULT_MAX_EXTENSIONS: u8 = 3;
+
 /// Buffer to ensure users have time to reveal after pulse is set.
 pub const LATE_PULSE_SAFETY_BUFFER_SLOTS: u64 = 50;
 
@@ -37,6 +44,12 @@ pub const DEFAULT_STAKE_AMOUNT: u64 = 1_000_000_000;
 /// Default fee on minted rewards (basis points). 100 = 1%.
 pub const DEFAULT_REWARD_FEE_BPS: u16 = 100;
 
+/// Streak length beyond which `streak_bonus_bps` stops compounding.
+pub const STREAK_BONUS_CAP: u32 = 10;
+
+/// Lamports in 1 SOL; unit of `config.rent_price_timlg`.
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
 /// Initial version for account structures.
 pub const INITIAL_VERSION: u16 = 1;
 
@@ -3,7 +3,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::state::{Config, OracleSet, Round, RoundRegistry, Ticket, UserEscrow, Tokenomics, UserStats, GlobalStats};
+use crate::state::{
+    Config, OracleSet, Round, RoundRegistry, Ticket, UserEscrow, Tokenomics, UserStats, UserRoundStats,
+    GlobalStats, PulseAttestations, UserStreak,
+};
 
 #[derive(Accounts)]
 pub struct InitializeTokenomics<'info> {
@@ -310,19 +313,25 @@ pub struct SetOracleThreshold<'info> {
 }
 
 #[derive(Accounts)]
-pub struct SetOraclePubkey<'info> {
+pub struct ClearOracleSet<'info> {
     #[account(
-        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        mut,
+        seeds = [crate::ORACLE_SET_SEED, config.key().as_ref()],
+        bump = oracle_set.bump
+    )]
+    pub oracle_set: Account<'info, OracleSet>,
+
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct SetClaimGraceSlots<'info> {
+pub struct SetStakeInSol<'info> {
     #[account(
         mut,
         seeds = [crate::CONFIG_SEED],
@@ -334,291 +343,265 @@ pub struct SetClaimGraceSlots<'info> {
 }
 
 #[derive(Accounts)]
-pub struct UpdateWindows<'info> {
+pub struct SetAutoExtendReveal<'info> {
     #[account(
         mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
+
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateStakeAmount<'info> {
+pub struct SetMaxTicketsPerUser<'info> {
     #[account(
         mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
+
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateTokenomics<'info> {
+pub struct SetRentReimburse<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSweepBurnsTokens<'info> {
     #[account(
         mut,
-        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
-        bump
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub tokenomics: Account<'info, Tokenomics>,
+    pub config: Account<'info, Config>,
 
     pub admin: Signer<'info>,
 }
 
-// ----------------------------
-// P0: User Escrow (pre-deposit for gasless signed commits)
-// ----------------------------
 #[derive(Accounts)]
-pub struct InitUserEscrow<'info> {
+pub struct SetCrankerFee<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    #[account(mut, address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
-
-    #[account(
-        init,
-        payer = user,
-        space = 8 + UserEscrow::INIT_SPACE,
-        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
-        bump
-    )]
-    pub user_escrow: Account<'info, UserEscrow>,
+    pub admin: Signer<'info>,
+}
 
+#[derive(Accounts)]
+pub struct SetRescueAuthority<'info> {
     #[account(
-        init,
-        payer = user,
-        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
-        bump,
-        token::mint = timlg_mint,
-        token::authority = user_escrow
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub user_escrow_ata: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
+    pub config: Account<'info, Config>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    /// Current rescue authority.
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct DepositEscrow<'info> {
+pub struct EmergencyDrain<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    #[account(mut, address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
+    pub rescue_authority: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
-        bump = user_escrow.bump
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
     )]
-    pub user_escrow: Account<'info, UserEscrow>,
+    /// CHECK: Treasury SOL PDA (system-owned), address enforced.
+    pub treasury_sol: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
-        bump,
-        constraint = user_escrow_ata.mint == timlg_mint.key(),
-        constraint = user_escrow_ata.owner == user_escrow.key()
-    )]
-    pub user_escrow_ata: Account<'info, TokenAccount>,
+    #[account(mut, address = config.treasury)]
+    pub treasury: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    #[account(mut, address = config.rescue_destination)]
+    pub rescue_destination: SystemAccount<'info>,
 
     #[account(
         mut,
-        constraint = user_timlg_ata.owner == user.key(),
-        constraint = user_timlg_ata.mint == timlg_mint.key()
+        token::mint = config.timlg_mint,
+        token::authority = config.rescue_destination
     )]
-    pub user_timlg_ata: Account<'info, TokenAccount>,
+    pub rescue_destination_ata: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawEscrow<'info> {
+pub struct SetAcceptLegacyCommitMsg<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    #[account(mut, address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
+    pub admin: Signer<'info>,
+}
 
+#[derive(Accounts)]
+pub struct SetMaxWeight<'info> {
     #[account(
         mut,
-        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
-        bump = user_escrow.bump
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub user_escrow: Account<'info, UserEscrow>,
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
 
+#[derive(Accounts)]
+pub struct SetMaxOpenRounds<'info> {
     #[account(
-        mut,
-        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
-        bump,
-        constraint = user_escrow_ata.mint == timlg_mint.key(),
-        constraint = user_escrow_ata.owner == user_escrow.key()
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub user_escrow_ata: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
+    pub config: Account<'info, Config>,
 
     #[account(
         mut,
-        constraint = user_timlg_ata.owner == user.key(),
-        constraint = user_timlg_ata.mint == timlg_mint.key()
+        seeds = [crate::ROUND_REGISTRY_SEED, config.key().as_ref()],
+        bump = round_registry.bump,
     )]
-    pub user_timlg_ata: Account<'info, TokenAccount>,
+    pub round_registry: Account<'info, RoundRegistry>,
 
-    pub token_program: Program<'info, Token>,
+    pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct CreateRound<'info> {
+pub struct MigrateRoundRegistry<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    // Enforzamos que el mint usado sea el del config
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
-
+    /// CHECK: Manual migration of size (old layout can't be deserialized).
     #[account(
-        init,
-        payer = admin,
-        space = 8 + Round::INIT_SPACE,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        mut,
+        seeds = [crate::ROUND_REGISTRY_SEED, config.key().as_ref()],
         bump
     )]
-    pub round: Account<'info, Round>,
+    pub round_registry: UncheckedAccount<'info>,
 
-    /// CHECK: Vault SOL actual (se mantiene de momento)
-    #[account(
-        init,
-        payer = admin,
-        space = 0,
-        owner = anchor_lang::solana_program::system_program::ID,
-        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
 
-    // ✅ Nuevo: vault SPL (TIMLG) por ronda
-    #[account(
-        init,
-        payer = admin,
-        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump,
-        token::mint = timlg_mint,
-        token::authority = round
-    )]
-    pub timlg_vault: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
 
+/// Shared by `freeze_treasury` and `thaw_treasury`.
+#[derive(Accounts)]
+pub struct FreezeTreasury<'info> {
     #[account(
-        mut,
-        seeds = [crate::GLOBAL_STATS_SEED],
-        bump = global_stats.bump,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub global_stats: Account<'info, GlobalStats>,
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(mut, address = config.treasury)]
+    pub treasury: Account<'info, TokenAccount>,
 
-    #[account(mut)]
     pub admin: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct FundVault<'info> {
+pub struct TransferMintAuthority<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    #[account(
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
-    )]
-    pub round: Account<'info, Round>,
-
-    /// CHECK: System-owned PDA used only as a lamport vault. Address is enforced by seeds/bump.
-    #[account(
-        mut,
-        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.vault_bump
-    )]
-    pub vault: UncheckedAccount<'info>,
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
 
-    #[account(mut)]
-    pub funder: Signer<'info>,
+    pub admin: Signer<'info>,
 
-    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct SetPulseMock<'info> {
+pub struct SetMinRevealAfterPulseSlots<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinPulsePopcount<'info> {
     #[account(
         mut,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub round: Account<'info, Round>,
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
 
+#[derive(Accounts)]
+pub struct SetPulseDisputeWindow<'info> {
     #[account(
         mut,
-        seeds = [crate::GLOBAL_STATS_SEED],
-        bump = global_stats.bump,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub global_stats: Account<'info, GlobalStats>,
+    pub config: Account<'info, Config>,
 
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(round_id: u64)]
-pub struct SetPulseSigned<'info> {
-    pub admin: Signer<'info>,
-
+pub struct DisputePulse<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        seeds = [crate::ORACLE_SET_SEED, config.key().as_ref()],
+        bump = oracle_set.bump
+    )]
+    pub oracle_set: Account<'info, OracleSet>,
+
     #[account(
         mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
@@ -626,151 +609,2023 @@ pub struct SetPulseSigned<'info> {
     )]
     pub round: Account<'info, Round>,
 
+    /// The oracles' own record for the round: the evidence the stored pulse is wrong.
+    /// Reset when the dispute succeeds.
     #[account(
         mut,
-        seeds = [crate::GLOBAL_STATS_SEED],
-        bump = global_stats.bump,
+        seeds = [crate::ATTEST_SEED, round_id.to_le_bytes().as_ref()],
+        bump = attestations.bump
     )]
-    pub global_stats: Account<'info, GlobalStats>,
+    pub attestations: Account<'info, PulseAttestations>,
 
-    /// CHECK: instruction sysvar (for ed25519 introspection). Address enforced.
-    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
-    pub instructions: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct FinalizeRound<'info> {
+pub struct SetOraclePubkey<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimGraceSlots<'info> {
     #[account(
         mut,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
     )]
-    pub round: Account<'info, Round>,
+    pub config: Account<'info, Config>,
 
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct RecoverFunds<'info> {
+pub struct SetMaxExtensions<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddFeeExempt<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeExempt<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSeedVersion<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncTimlgDecimals<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateWindows<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakeAmount<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStakeBounds<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenomics<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUnrevealedPolicy<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardMode<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_type: u8)]
+pub struct InitializeAdditionalPool<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = timlg_mint,
+        token::authority = config,
+        seeds = [crate::pool_seed(pool_type), tokenomics.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetBurnDelay<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStreakBonusBps<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrefundFees<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTokenomics<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Manual migration of size (old layout can't be deserialized).
+    #[account(
+        mut,
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump
+    )]
+    pub tokenomics: UncheckedAccount<'info>,
+
     #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ----------------------------
+// P0: User Escrow (pre-deposit for gasless signed commits)
+// ----------------------------
+#[derive(Accounts)]
+pub struct InitUserEscrow<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
     pub config: Account<'info, Config>,
 
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserEscrow::INIT_SPACE,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_escrow: Account<'info, UserEscrow>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
+        bump,
+        token::mint = timlg_mint,
+        token::authority = user_escrow
+    )]
+    pub user_escrow_ata: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositEscrow<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
+    )]
+    pub user_escrow: Account<'info, UserEscrow>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
+        bump,
+        constraint = user_escrow_ata.mint == timlg_mint.key(),
+        constraint = user_escrow_ata.owner == user_escrow.key()
+    )]
+    pub user_escrow_ata: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_timlg_ata.owner == user.key(),
+        constraint = user_timlg_ata.mint == timlg_mint.key()
+    )]
+    pub user_timlg_ata: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEscrow<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
+    )]
+    pub user_escrow: Account<'info, UserEscrow>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
+        bump,
+        constraint = user_escrow_ata.mint == timlg_mint.key(),
+        constraint = user_escrow_ata.owner == user_escrow.key()
+    )]
+    pub user_escrow_ata: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_timlg_ata.owner == user.key(),
+        constraint = user_timlg_ata.mint == timlg_mint.key()
+    )]
+    pub user_timlg_ata: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump,
+        close = user
+    )]
+    pub user_escrow: Account<'info, UserEscrow>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
+        bump,
+        constraint = user_escrow_ata.owner == user_escrow.key()
+    )]
+    pub user_escrow_ata: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CreateRound<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    // Enforzamos que el mint usado sea el del config
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Round::INIT_SPACE,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, Round>,
+
+    /// CHECK: Vault SOL actual (se mantiene de momento)
+    #[account(
+        init,
+        payer = admin,
+        space = 0,
+        owner = anchor_lang::solana_program::system_program::ID,
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    // ✅ Nuevo: vault SPL (TIMLG) por ronda
+    #[account(
+        init,
+        payer = admin,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump,
+        token::mint = timlg_mint,
+        token::authority = round
+    )]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetRoundPublicMode<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetRoundMinParticipants<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetRoundMaxCommitted<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetRoundPause<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct ExtendRevealDeadline<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct ExtendCommitDeadline<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct FundVault<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    /// CHECK: System-owned PDA used only as a lamport vault. Address is enforced by seeds/bump.
+    #[account(
+        mut,
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct FundTimlgVault<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    /// `config.treasury` or an admin-owned token account.
+    #[account(
+        mut,
+        constraint = source.mint == config.timlg_mint
+    )]
+    pub source: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetPulseMock<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetPulseSigned<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: instruction sysvar (for ed25519 introspection). Address enforced.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SetPulseMultisig<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [crate::ORACLE_SET_SEED, config.key().as_ref()],
+        bump = oracle_set.bump
+    )]
+    pub oracle_set: Account<'info, OracleSet>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: instruction sysvar (for ed25519 introspection). Address enforced.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SubmitAttestation<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [crate::ORACLE_SET_SEED, config.key().as_ref()],
+        bump = oracle_set.bump
+    )]
+    pub oracle_set: Account<'info, OracleSet>,
+
+    #[account(
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PulseAttestations::INIT_SPACE,
+        seeds = [crate::ATTEST_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub attestations: Account<'info, PulseAttestations>,
+
+    /// CHECK: instruction sysvar (for ed25519 introspection). Address enforced.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct FinalizePulse<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [crate::ORACLE_SET_SEED, config.key().as_ref()],
+        bump = oracle_set.bump
+    )]
+    pub oracle_set: Account<'info, OracleSet>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        seeds = [crate::ATTEST_SEED, round_id.to_le_bytes().as_ref()],
+        bump = attestations.bump
+    )]
+    pub attestations: Account<'info, PulseAttestations>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct FinalizeRound<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub admin: Signer<'info>,
+
+    // public-guess rounds: tickets to resolve via remaining_accounts (writable)
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct RecoverFunds<'info> {
+    #[account(mut)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::TICKET_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref(), ticket.nonce.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        has_one = user,
+        close = user
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    // token::mint already pins the destination to config.timlg_mint (via timlg_mint's address).
+    #[account(
+        mut,
+        token::mint = timlg_mint,
+        token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump,
+        token::mint = timlg_mint,
+        token::authority = round,
+    )]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: System-owned PDA, refunds the stake when `round.stake_in_sol`.
+    #[account(
+        mut,
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA, refunds the SOL service fee
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the service fee refund: whoever paid it at commit (`ticket.fee_payer`,
+    /// the user or a relayer). Checked in the handler when the ticket recorded a fee.
+    #[account(mut)]
+    pub fee_payer: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct RecoverFundsAnyone<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::TICKET_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref(), ticket.nonce.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        has_one = user,
+        close = user
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    /// CHECK: The user who owns the ticket (receiver of refund).
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = timlg_mint,
+        token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.timlg_vault_bump,
+        token::mint = timlg_mint,
+        token::authority = round,
+    )]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA, refunds the SOL service fee
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the service fee refund: whoever paid it at commit (`ticket.fee_payer`,
+    /// the user or a relayer). Checked in the handler when the ticket recorded a fee.
+    #[account(mut)]
+    pub fee_payer: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct RecoverFundsAnyoneBatch<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.timlg_vault_bump,
+        token::mint = config.timlg_mint,
+        token::authority = round,
+    )]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA, refunds the SOL service fee of each ticket
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64, nonce: u64)]
+pub struct CloseTicket<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Only used to detect if the round is archived (lamports == 0).
+    /// Address verification is secondary as Ticket PDA already enforces the round_id.
+    #[account(mut)]
+    pub round: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TICKET_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        has_one = user,
+        close = user
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTicketBatch<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Only used to detect if the round is archived (lamports == 0).
+    /// Each ticket PDA is checked against round_id in the handler.
+    #[account(mut)]
+    pub round: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SweepUnclaimed<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: PDA verification and manual deserialization in instruction logic.
+    #[account(mut)]
+    pub round: AccountInfo<'info>,
+
+    /// CHECK: System-owned PDA vault. PDA verification in instruction logic.
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+
+
+    /// CHECK: PDA verification in instruction logic. Can be a SystemAccount for legacy rounds.
+    #[account(
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub timlg_vault: AccountInfo<'info>,
+
+    /// ✅ SPL destination (from config)
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SEED],
+        bump = config.treasury_bump,
+        token::mint = timlg_mint,
+        token::authority = config
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA, receives unclaimed SOL stakes
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SweepSol<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    /// CHECK: System-owned PDA used only as a lamport vault. Address is enforced by seeds/bump.
+    #[account(
+        mut,
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA, receives unclaimed SOL stakes
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct SweepTokens<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump,
+        token::authority = round
+    )]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SEED],
+        bump = config.treasury_bump,
+        token::mint = timlg_mint,
+        token::authority = config
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64, nonce: u64)]
+pub struct CommitTicket<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: System-owned PDA, receives the stake when `round.stake_in_sol`.
+    #[account(
+        mut,
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserRoundStats::INIT_SPACE,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_round_stats: Box<Account<'info, UserRoundStats>>,
+
+    #[account(
+        mut,
+        constraint = user_timlg_ata.mint == timlg_mint.key(),
+        constraint = user_timlg_ata.owner == user.key()
+    )]
+    pub user_timlg_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+
+/// Same as `CommitTicket`, but the stake moves via an SPL approval the user granted
+/// to the round PDA, and `payer` covers rent and the SOL service fee. `user` still
+/// signs (directly or via `invoke_signed` from a smart wallet) so nobody else can
+/// spend the approval under a commitment the user cannot reveal.
+#[derive(Accounts)]
+#[instruction(round_id: u64, nonce: u64)]
+pub struct CommitTicketDelegated<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserRoundStats::INIT_SPACE,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_round_stats: Box<Account<'info, UserRoundStats>>,
+
+    #[account(
+        mut,
+        constraint = user_timlg_ata.mint == timlg_mint.key(),
+        constraint = user_timlg_ata.owner == user.key()
+    )]
+    pub user_timlg_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64, nonce: u64)]
+pub struct RevealTicket<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            nonce.to_le_bytes().as_ref()
+        ],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserStats>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// Optional: when passed, `last_reveal_slot` is refreshed (relayer reminders).
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
+    )]
+    pub user_escrow: Option<Account<'info, UserEscrow>>,
+
+    /// Optional: when passed, `revealed` is bumped for this user's round counters.
+    #[account(
+        mut,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump = user_round_stats.bump
+    )]
+    pub user_round_stats: Option<Account<'info, UserRoundStats>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CommitBatch<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserRoundStats::INIT_SPACE,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_round_stats: Box<Account<'info, UserRoundStats>>,
+
+    #[account(
+        mut,
+        constraint = user_timlg_ata.mint == timlg_mint.key(),
+        constraint = user_timlg_ata.owner == user.key()
+    )]
+    pub user_timlg_ata: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct RevealBatch<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    /// Optional: when passed, `last_reveal_slot` is refreshed (relayer reminders).
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
+    )]
+    pub user_escrow: Option<Box<Account<'info, UserEscrow>>>,
+
+    /// Optional: when passed, `revealed` is bumped for this user's round counters.
+    #[account(
+        mut,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump = user_round_stats.bump
+    )]
+    pub user_round_stats: Option<Box<Account<'info, UserRoundStats>>>,
+
+    pub system_program: Program<'info, System>,
+
+    // tickets via remaining_accounts (writable)
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CommitRevealRoot<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    pub user: Signer<'info>,
+
+    /// Exists once the user committed in this round.
+    #[account(
+        mut,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump = user_round_stats.bump
+    )]
+    pub user_round_stats: Box<Account<'info, UserRoundStats>>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CommitBatchSigned<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(address = config.timlg_mint)]
+    pub timlg_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Relayer (paga fees)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserRoundStats::INIT_SPACE,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_round_stats: Box<Account<'info, UserRoundStats>>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
+    )]
+    pub user_escrow: Box<Account<'info, UserEscrow>>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_escrow_ata: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: user pubkey referenced in ed25519 msg
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: instructions sysvar for ed25519 introspection
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Relayer's TIMLG account for the rent reimbursement; required while it's non-zero.
+    #[account(
+        mut,
+        constraint = relayer_timlg_ata.mint == config.timlg_mint,
+        constraint = relayer_timlg_ata.owner == payer.key()
+    )]
+    pub relayer_timlg_ata: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64, nonce: u64)]
+pub struct CommitTicketSigned<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Relayer (paga fees + rent)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub ticket: Box<Account<'info, Ticket>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserRoundStats::INIT_SPACE,
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_round_stats: Box<Account<'info, UserRoundStats>>,
+
+    #[account(
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
+    )]
+    pub user_escrow: Box<Account<'info, UserEscrow>>,
+
+    #[account(
+        mut,
+        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub user_escrow_ata: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: user pubkey referenced in ed25519 msg
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: instructions sysvar for ed25519 introspection. Address enforced.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Per-user accounts travel in remaining_accounts; see `commit_batch_signed_multi`.
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CommitBatchSignedMulti<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Relayer (paga fees + rent)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: instructions sysvar for ed25519 introspection. Address enforced.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::TREASURY_SOL_SEED],
+        bump = config.treasury_sol_bump,
+        address = config.treasury_sol
+    )]
+    /// CHECK: Treasury SOL PDA
+    pub treasury_sol: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct RevealBatchSigned<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
     #[account(
         mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
         bump = round.bump
     )]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
+
+    /// Relayer paying tx fees (must sign tx)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: user pubkey referenced in ed25519 msg
+    pub user: UncheckedAccount<'info>,
 
     #[account(
-        mut,
-        // Relaxing seeds check to avoid ConstraintSeeds error (nonce read issue?).
-        // Security ensured by has_one=user and owner check.
-        has_one = user,
-        close = user
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserStats::INIT_SPACE,
+        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        bump
     )]
-    pub ticket: Account<'info, Ticket>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
+    pub user_stats: Box<Account<'info, UserStats>>,
 
     #[account(
         mut,
-        token::mint = timlg_mint,
-        token::authority = user,
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub global_stats: Box<Account<'info, GlobalStats>>,
 
+    /// Optional: when passed, `last_reveal_slot` is refreshed (relayer reminders).
     #[account(
         mut,
-        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump,
-        token::mint = timlg_mint,
-        token::authority = round,
+        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
+        bump = user_escrow.bump
     )]
-    pub timlg_vault: Account<'info, TokenAccount>,
+    pub user_escrow: Option<Box<Account<'info, UserEscrow>>>,
 
+    /// Optional: when passed, `revealed` is bumped for this user's round counters.
     #[account(
         mut,
-        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
-        bump
+        seeds = [crate::USER_ROUND_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump = user_round_stats.bump
     )]
-    pub user_stats: Account<'info, UserStats>,
-
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
+    pub user_round_stats: Option<Box<Account<'info, UserRoundStats>>>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: instruction sysvar (for ed25519 introspection). Address enforced.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct RecoverFundsAnyone<'info> {
+#[instruction(round_id: u64, nonce: u64)]
+pub struct ReclaimUnrevealedStake<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
-    pub config: Account<'info, Config>,
+    pub config: Box<Account<'info, Config>>,
 
     #[account(
-        mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
         bump = round.bump
     )]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
 
     #[account(
         mut,
-        seeds = [crate::TICKET_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref(), ticket.nonce.to_le_bytes().as_ref()],
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            &nonce.to_le_bytes()
+        ],
         bump = ticket.bump,
         has_one = user,
         close = user
     )]
-    pub ticket: Account<'info, Ticket>,
+    pub ticket: Box<Account<'info, Ticket>>,
 
-    /// CHECK: The user who owns the ticket (receiver of refund).
     #[account(mut)]
-    pub user: UncheckedAccount<'info>,
+    pub user: Signer<'info>,
 
-    #[account(
-        mut,
-        token::mint = timlg_mint,
-        token::authority = user,
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Account<'info, TokenAccount>,
 
+    /// CHECK: System-owned PDA, pays the refund when `round.stake_in_sol`.
     #[account(
         mut,
-        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.timlg_vault_bump,
-        token::mint = timlg_mint,
-        token::authority = round,
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
     )]
-    pub timlg_vault: Account<'info, TokenAccount>,
+    pub vault: UncheckedAccount<'info>,
 
     #[account(
-        init_if_needed,
-        payer = cranker,
-        space = 8 + UserStats::INIT_SPACE,
-        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
-        bump
+        mut,
+        constraint = user_timlg_ata.mint == config.timlg_mint,
+        constraint = user_timlg_ata.owner == user.key()
     )]
-    pub user_stats: Account<'info, UserStats>,
-
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
-
-    #[account(mut)]
-    pub cranker: Signer<'info>,
+    pub user_timlg_ata: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
@@ -778,26 +2633,40 @@ pub struct RecoverFundsAnyone<'info> {
 
 #[derive(Accounts)]
 #[instruction(round_id: u64, nonce: u64)]
-pub struct CloseTicket<'info> {
+pub struct ClaimReward<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
-    pub config: Account<'info, Config>,
+    pub config: Box<Account<'info, Config>>,
 
-    /// CHECK: Only used to detect if the round is archived (lamports == 0).
-    /// Address verification is secondary as Ticket PDA already enforces the round_id.
-    #[account(mut)]
-    pub round: UncheckedAccount<'info>,
+    #[account(
+    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+    bump = tokenomics.bump
+    )]
+    pub tokenomics: Box<Account<'info, Tokenomics>>,
 
     #[account(
         mut,
-        seeds = [crate::TICKET_SEED, round_id.to_le_bytes().as_ref(), user.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Box<Account<'info, Round>>,
+
+    #[account(
+        mut,
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            &nonce.to_le_bytes()
+        ],
         bump = ticket.bump,
         has_one = user,
         close = user
     )]
-    pub ticket: Account<'info, Ticket>,
+    pub ticket: Box<Account<'info, Ticket>>,
 
     #[account(mut)]
     pub user: Signer<'info>,
@@ -809,59 +2678,59 @@ pub struct CloseTicket<'info> {
         seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
         bump
     )]
-    pub user_stats: Account<'info, UserStats>,
-
-    pub system_program: Program<'info, System>,
-}
+    pub user_stats: Box<Account<'info, UserStats>>,
 
-#[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct SweepUnclaimed<'info> {
+    /// Streak tracking + `streak_bonus_bps` bonus; omit to claim without either.
     #[account(
-        seeds = [crate::CONFIG_SEED],
-        bump = config.bump
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStreak::INIT_SPACE,
+        seeds = [crate::STREAK_SEED, user.key().as_ref()],
+        bump
     )]
-    pub config: Account<'info, Config>,
+    pub user_streak: Option<Box<Account<'info, UserStreak>>>,
 
-    /// CHECK: PDA verification and manual deserialization in instruction logic.
-    #[account(mut)]
-    pub round: AccountInfo<'info>,
-
-    /// CHECK: System-owned PDA vault. PDA verification in instruction logic.
-    #[account(mut)]
-    pub vault: AccountInfo<'info>,
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
 
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Account<'info, TokenAccount>,
 
-    /// CHECK: PDA verification in instruction logic. Can be a SystemAccount for legacy rounds.
+    /// CHECK: System-owned PDA, refunds the stake when `round.stake_in_sol`.
     #[account(
         mut,
-        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
     )]
-    pub timlg_vault: AccountInfo<'info>,
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user_timlg_ata: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.reward_fee_pool)]
+    pub reward_fee_pool: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.replication_pool)]
+    pub replication_pool: Account<'info, TokenAccount>,
 
-    /// ✅ SPL destination (from config)
     #[account(
         mut,
-        seeds = [crate::TREASURY_SEED],
-        bump = config.treasury_bump,
-        token::mint = timlg_mint,
-        token::authority = config
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
     )]
-    pub treasury: Account<'info, TokenAccount>,
+    pub global_stats: Account<'info, GlobalStats>,
 
-    #[account(mut, address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
+    /// CHECK: instruction sysvar. Only needed for CPI calls when `config.cpi_allowlist` is set.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64, nonce: u64)]
-pub struct CommitTicket<'info> {
+#[instruction(round_id: u64, nonce: u64, destination_owner: Pubkey)]
+pub struct ClaimRewardTo<'info> {
     #[account(
         mut,
         seeds = [crate::CONFIG_SEED],
@@ -869,6 +2738,12 @@ pub struct CommitTicket<'info> {
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+    bump = tokenomics.bump
+    )]
+    pub tokenomics: Box<Account<'info, Tokenomics>>,
+
     #[account(
         mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
@@ -876,25 +2751,19 @@ pub struct CommitTicket<'info> {
     )]
     pub round: Box<Account<'info, Round>>,
 
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Box<Account<'info, Mint>>,
-
-    #[account(mut, address = round.timlg_vault)]
-    pub timlg_vault: Box<Account<'info, TokenAccount>>,
-
     #[account(
-        init,
-        payer = user,
-        space = 8 + Ticket::INIT_SPACE,
+        mut,
         seeds = [
             crate::TICKET_SEED,
             round_id.to_le_bytes().as_ref(),
             user.key().as_ref(),
-            nonce.to_le_bytes().as_ref(),
+            &nonce.to_le_bytes()
         ],
-        bump
+        bump = ticket.bump,
+        has_one = user,
+        close = user
     )]
-    pub ticket: Account<'info, Ticket>,
+    pub ticket: Box<Account<'info, Ticket>>,
 
     #[account(mut)]
     pub user: Signer<'info>,
@@ -908,70 +2777,32 @@ pub struct CommitTicket<'info> {
     )]
     pub user_stats: Box<Account<'info, UserStats>>,
 
-    #[account(
-        mut,
-        constraint = user_timlg_ata.mint == timlg_mint.key(),
-        constraint = user_timlg_ata.owner == user.key()
-    )]
-    pub user_timlg_ata: Box<Account<'info, TokenAccount>>,
-
-    #[account(
-        mut,
-        seeds = [crate::TREASURY_SOL_SEED],
-        bump = config.treasury_sol_bump,
-        address = config.treasury_sol
-    )]
-    /// CHECK: Treasury SOL PDA
-    pub treasury_sol: UncheckedAccount<'info>,
-
-    #[account(
-        mut,
-        seeds = [crate::GLOBAL_STATS_SEED],
-        bump = global_stats.bump,
-    )]
-    pub global_stats: Box<Account<'info, GlobalStats>>,
-
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
-
-
-#[derive(Accounts)]
-#[instruction(round_id: u64, nonce: u64)]
-pub struct RevealTicket<'info> {
-    #[account(
-        seeds = [crate::CONFIG_SEED],
-        bump = config.bump
-    )]
-    pub config: Account<'info, Config>,
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
 
-    #[account(
-        mut,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
-    )]
-    pub round: Account<'info, Round>,
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Account<'info, TokenAccount>,
 
+    /// CHECK: System-owned PDA, refunds the stake when `round.stake_in_sol`.
     #[account(
         mut,
-        seeds = [
-            crate::TICKET_SEED,
-            round_id.to_le_bytes().as_ref(),
-            user.key().as_ref(),
-            nonce.to_le_bytes().as_ref()
-        ],
-        bump = ticket.bump
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
     )]
-    pub ticket: Account<'info, Ticket>,
-
-    pub user: Signer<'info>,
+    pub vault: UncheckedAccount<'info>,
 
     #[account(
         mut,
-        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
-        bump
+        constraint = destination_ata.mint == timlg_mint.key(),
+        constraint = destination_ata.owner == destination_owner
     )]
-    pub user_stats: Account<'info, UserStats>,
+    pub destination_ata: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.reward_fee_pool)]
+    pub reward_fee_pool: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.replication_pool)]
+    pub replication_pool: Account<'info, TokenAccount>,
 
     #[account(
         mut,
@@ -980,12 +2811,17 @@ pub struct RevealTicket<'info> {
     )]
     pub global_stats: Account<'info, GlobalStats>,
 
+    /// CHECK: instruction sysvar. Only needed for CPI calls when `config.cpi_allowlist` is set.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(round_id: u64)]
-pub struct CommitBatch<'info> {
+pub struct ClaimRewardBatch<'info> {
     #[account(
         mut,
         seeds = [crate::CONFIG_SEED],
@@ -993,6 +2829,12 @@ pub struct CommitBatch<'info> {
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+    bump = tokenomics.bump
+    )]
+    pub tokenomics: Box<Account<'info, Tokenomics>>,
+
     #[account(
         mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
@@ -1000,12 +2842,6 @@ pub struct CommitBatch<'info> {
     )]
     pub round: Box<Account<'info, Round>>,
 
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Box<Account<'info, Mint>>,
-
-    #[account(mut, address = round.timlg_vault)]
-    pub timlg_vault: Box<Account<'info, TokenAccount>>,
-
     #[account(mut)]
     pub user: Signer<'info>,
 
@@ -1018,48 +2854,50 @@ pub struct CommitBatch<'info> {
     )]
     pub user_stats: Box<Account<'info, UserStats>>,
 
-    #[account(
-        mut,
-        constraint = user_timlg_ata.mint == timlg_mint.key(),
-        constraint = user_timlg_ata.owner == user.key()
-    )]
-    pub user_timlg_ata: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
 
-    #[account(
-        mut,
-        seeds = [crate::TREASURY_SOL_SEED],
-        bump = config.treasury_sol_bump,
-        address = config.treasury_sol
-    )]
-    /// CHECK: Treasury SOL PDA
-    pub treasury_sol: UncheckedAccount<'info>,
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = timlg_mint, token::authority = user)]
+    pub user_timlg_ata: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.reward_fee_pool)]
+    pub reward_fee_pool: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.replication_pool)]
+    pub replication_pool: Account<'info, TokenAccount>,
 
     #[account(
         mut,
         seeds = [crate::GLOBAL_STATS_SEED],
         bump = global_stats.bump,
     )]
-    pub global_stats: Box<Account<'info, GlobalStats>>,
+    pub global_stats: Account<'info, GlobalStats>,
+
+    /// CHECK: instruction sysvar. Only needed for CPI calls when `config.cpi_allowlist` is set.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
 
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct RevealBatch<'info> {
+pub struct ClaimMultiRound<'info> {
     #[account(
+        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Box<Account<'info, Config>>,
 
     #[account(
-        mut,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
+    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+    bump = tokenomics.bump
     )]
-    pub round: Box<Account<'info, Round>>,
+    pub tokenomics: Box<Account<'info, Tokenomics>>,
 
     #[account(mut)]
     pub user: Signer<'info>,
@@ -1073,200 +2911,158 @@ pub struct RevealBatch<'info> {
     )]
     pub user_stats: Box<Account<'info, UserStats>>,
 
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = timlg_mint, token::authority = user)]
+    pub user_timlg_ata: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.reward_fee_pool)]
+    pub reward_fee_pool: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.replication_pool)]
+    pub replication_pool: Account<'info, TokenAccount>,
+
     #[account(
         mut,
         seeds = [crate::GLOBAL_STATS_SEED],
         bump = global_stats.bump,
     )]
-    pub global_stats: Box<Account<'info, GlobalStats>>,
+    pub global_stats: Account<'info, GlobalStats>,
 
-    pub system_program: Program<'info, System>,
+    /// CHECK: instruction sysvar. Only needed for CPI calls when `config.cpi_allowlist` is set.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
 
-    // tickets via remaining_accounts (writable)
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(round_id: u64)]
-pub struct CommitBatchSigned<'info> {
+pub struct SettleRoundTokens<'info> {
     #[account(
         mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
-    pub config: Box<Account<'info, Config>>,
+    pub config: Account<'info, Config>,
+
+    #[account(
+    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+    bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
 
     #[account(
         mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
         bump = round.bump
     )]
-    pub round: Box<Account<'info, Round>>,
+    pub round: Account<'info, Round>,
 
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Box<Account<'info, Mint>>,
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
 
     #[account(mut, address = round.timlg_vault)]
-    pub timlg_vault: Box<Account<'info, TokenAccount>>,
+    pub timlg_vault: Account<'info, TokenAccount>,
+
+    // legacy treasury still exists (may be used later), but unrevealed now goes to replication pool
+    #[account(mut, address = config.treasury)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.reward_fee_pool)]
+    pub reward_fee_pool: Account<'info, TokenAccount>,
+
+    #[account(mut, address = tokenomics.replication_pool)]
+    pub replication_pool: Account<'info, TokenAccount>,
 
-    /// Relayer (paga fees)
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    #[account(
-        init_if_needed,
-        payer = payer,
-        space = 8 + UserStats::INIT_SPACE,
-        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
-        bump
-    )]
-    pub user_stats: Box<Account<'info, UserStats>>,
-
     #[account(
         mut,
-        seeds = [crate::USER_ESCROW_SEED, user.key().as_ref()],
-        bump = user_escrow.bump
+        seeds = [crate::GLOBAL_STATS_SEED],
+        bump = global_stats.bump,
     )]
-    pub user_escrow: Box<Account<'info, UserEscrow>>,
+    pub global_stats: Account<'info, GlobalStats>,
 
+    pub token_program: Program<'info, Token>,
+    /// CHECK: System-owned PDA holding the stakes when `round.stake_in_sol`.
     #[account(
         mut,
-        seeds = [crate::USER_ESCROW_VAULT_SEED, user.key().as_ref()],
-        bump
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
     )]
-    pub user_escrow_ata: Box<Account<'info, TokenAccount>>,
-
-    /// CHECK: user pubkey referenced in ed25519 msg
-    pub user: UncheckedAccount<'info>,
-
-    /// CHECK: instructions sysvar for ed25519 introspection
-    pub instructions: UncheckedAccount<'info>,
+    pub vault: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        seeds = [crate::TREASURY_SOL_SEED],
-        bump = config.treasury_sol_bump,
-        address = config.treasury_sol
-    )]
-    /// CHECK: Treasury SOL PDA
+    /// CHECK: Treasury SOL PDA, receives slashed SOL stakes, pays the crank fee. Address enforced.
+    #[account(mut, address = config.treasury_sol)]
     pub treasury_sol: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        seeds = [crate::GLOBAL_STATS_SEED],
-        bump = global_stats.bump,
-    )]
-    pub global_stats: Box<Account<'info, GlobalStats>>,
-
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(round_id: u64)]
-pub struct RevealBatchSigned<'info> {
+pub struct ExecuteBurns<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
-    pub config: Box<Account<'info, Config>>,
+    pub config: Account<'info, Config>,
 
     #[account(
         mut,
         seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
         bump = round.bump
     )]
-    pub round: Box<Account<'info, Round>>,
+    pub round: Account<'info, Round>,
 
-    /// Relayer paying tx fees (must sign tx)
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    #[account(mut, address = config.timlg_mint)]
+    pub timlg_mint: Account<'info, Mint>,
 
-    /// CHECK: user pubkey referenced in ed25519 msg
-    pub user: UncheckedAccount<'info>,
+    #[account(mut, address = round.timlg_vault)]
+    pub timlg_vault: Account<'info, TokenAccount>,
 
-    #[account(
-        init_if_needed,
-        payer = payer,
-        space = 8 + UserStats::INIT_SPACE,
-        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
-        bump
-    )]
-    pub user_stats: Box<Account<'info, UserStats>>,
+    pub payer: Signer<'info>,
 
     #[account(
         mut,
         seeds = [crate::GLOBAL_STATS_SEED],
         bump = global_stats.bump,
     )]
-    pub global_stats: Box<Account<'info, GlobalStats>>,
-
-    pub system_program: Program<'info, System>,
+    pub global_stats: Account<'info, GlobalStats>,
 
-    /// CHECK: instruction sysvar (for ed25519 introspection). Address enforced.
-    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
-    pub instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_id: u64, nonce: u64)]
-pub struct ClaimReward<'info> {
+#[instruction(round_id: u64)]
+pub struct CloseRound<'info> {
     #[account(
-        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
-    pub config: Box<Account<'info, Config>>,
-
-    #[account(
-    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
-    bump = tokenomics.bump
-    )]
-    pub tokenomics: Box<Account<'info, Tokenomics>>,
-
-    #[account(
-        mut,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
-    )]
-    pub round: Box<Account<'info, Round>>,
-
-    #[account(
-        mut,
-        seeds = [
-            crate::TICKET_SEED,
-            round_id.to_le_bytes().as_ref(),
-            user.key().as_ref(),
-            &nonce.to_le_bytes()
-        ],
-        bump = ticket.bump,
-        has_one = user,
-        close = user
-    )]
-    pub ticket: Box<Account<'info, Ticket>>,
+    pub config: Account<'info, Config>,
 
+    /// CHECK: PDA verification and manual deserialization in instruction logic.
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub round: AccountInfo<'info>,
 
+    /// CHECK: PDA verification in instruction logic. Can be a SystemAccount for legacy rounds.
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + UserStats::INIT_SPACE,
-        seeds = [crate::USER_STATS_SEED, user.key().as_ref()],
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
         bump
     )]
-    pub user_stats: Box<Account<'info, UserStats>>,
+    pub timlg_vault: AccountInfo<'info>,
 
-    #[account(mut, address = config.timlg_mint)]
+    #[account(address = config.timlg_mint)]
     pub timlg_mint: Account<'info, Mint>,
 
-    #[account(mut, address = round.timlg_vault)]
-    pub timlg_vault: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub user_timlg_ata: Account<'info, TokenAccount>,
-
-    #[account(mut, address = tokenomics.reward_fee_pool)]
-    pub reward_fee_pool: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
 
     #[account(
         mut,
@@ -1275,48 +3071,29 @@ pub struct ClaimReward<'info> {
     )]
     pub global_stats: Account<'info, GlobalStats>,
 
+    /// Required when the round was created by `create_round_auto` (frees an open-round slot).
+    #[account(
+        mut,
+        seeds = [crate::ROUND_REGISTRY_SEED, config.key().as_ref()],
+        bump = round_registry.bump,
+    )]
+    pub round_registry: Option<Account<'info, RoundRegistry>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
+/// remaining_accounts: `[round, timlg_vault]` pairs, both writable.
 #[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct SettleRoundTokens<'info> {
+pub struct CloseRoundBatch<'info> {
     #[account(
-        mut,
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    #[account(
-    seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
-    bump = tokenomics.bump
-    )]
-    pub tokenomics: Account<'info, Tokenomics>,
-
-    #[account(
-        mut,
-        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
-        bump = round.bump
-    )]
-    pub round: Account<'info, Round>,
-
-    #[account(mut, address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
-
-    #[account(mut, address = round.timlg_vault)]
-    pub timlg_vault: Account<'info, TokenAccount>,
-
-    // legacy treasury still exists (may be used later), but unrevealed now goes to replication pool
-    #[account(mut, address = config.treasury)]
-    pub treasury: Account<'info, TokenAccount>,
-
-    #[account(mut, address = tokenomics.replication_pool)]
-    pub replication_pool: Account<'info, TokenAccount>,
-
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub admin: Signer<'info>,
 
     #[account(
         mut,
@@ -1325,32 +3102,49 @@ pub struct SettleRoundTokens<'info> {
     )]
     pub global_stats: Account<'info, GlobalStats>,
 
+    /// Without it, registry-tracked rounds are skipped.
+    #[account(
+        mut,
+        seeds = [crate::ROUND_REGISTRY_SEED, config.key().as_ref()],
+        bump = round_registry.bump,
+    )]
+    pub round_registry: Option<Account<'info, RoundRegistry>>,
+
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 #[instruction(round_id: u64)]
-pub struct CloseRound<'info> {
+pub struct CancelRound<'info> {
     #[account(
         seeds = [crate::CONFIG_SEED],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    /// CHECK: PDA verification and manual deserialization in instruction logic.
-    #[account(mut)]
-    pub round: AccountInfo<'info>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
 
-    /// CHECK: PDA verification in instruction logic. Can be a SystemAccount for legacy rounds.
+    /// CHECK: System-owned PDA used only as a lamport vault. Address is enforced by seeds/bump.
     #[account(
         mut,
-        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
-        bump
+        seeds = [crate::VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.vault_bump
     )]
-    pub timlg_vault: AccountInfo<'info>,
+    pub vault: UncheckedAccount<'info>,
 
-    #[account(address = config.timlg_mint)]
-    pub timlg_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [crate::TIMLG_VAULT_SEED, round_id.to_le_bytes().as_ref()],
+        bump,
+        token::authority = round
+    )]
+    pub timlg_vault: Account<'info, TokenAccount>,
 
     #[account(mut)]
     pub admin: Signer<'info>,
@@ -1362,6 +3156,14 @@ pub struct CloseRound<'info> {
     )]
     pub global_stats: Account<'info, GlobalStats>,
 
+    /// Required when the round was created by `create_round_auto` (frees an open-round slot).
+    #[account(
+        mut,
+        seeds = [crate::ROUND_REGISTRY_SEED, config.key().as_ref()],
+        bump = round_registry.bump,
+    )]
+    pub round_registry: Option<Account<'info, RoundRegistry>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
@@ -1380,6 +3182,17 @@ pub struct CloseConfig<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct SetCpiAllowlist<'info> {
+    #[account(
+        mut,
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateSolServiceFee<'info> {
     #[account(
@@ -1442,6 +3255,114 @@ pub struct WithdrawTreasuryTokens<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawRewardFeePool<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    #[account(mut, address = tokenomics.reward_fee_pool)]
+    pub reward_fee_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == reward_fee_pool.mint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawReplicationPool<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()],
+        bump = tokenomics.bump
+    )]
+    pub tokenomics: Account<'info, Tokenomics>,
+
+    #[account(mut, address = tokenomics.replication_pool)]
+    pub replication_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == replication_pool.mint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct MigrateRound<'info> {
+    /// CHECK: Admin read at its fixed offset, so this works before `migrate_config`.
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Manual migration of size (old layout can't be deserialized).
+    #[account(
+        mut,
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless: only grows a ticket, and `payer` funds the extra rent.
+#[derive(Accounts)]
+pub struct MigrateTicket<'info> {
+    /// CHECK: Owner, discriminator and legacy size are checked in the handler.
+    #[account(mut)]
+    pub ticket: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless: only grows an escrow, and `payer` funds the extra rent.
+#[derive(Accounts)]
+pub struct MigrateUserEscrow<'info> {
+    /// CHECK: Owner, discriminator and legacy size are checked in the handler.
+    #[account(mut)]
+    pub user_escrow: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct MigrateConfig<'info> {
     #[account(
@@ -1468,3 +3389,99 @@ pub struct CloseUserStats<'info> {
 
     pub system_program: Program<'info, System>,
 }
+
+#[derive(Accounts)]
+pub struct HealthCheck<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: May be uninitialized; deserialized manually in the handler.
+    #[account(seeds = [crate::TOKENOMICS_SEED, config.key().as_ref()], bump)]
+    pub tokenomics: UncheckedAccount<'info>,
+
+    /// CHECK: May be uninitialized; deserialized manually in the handler.
+    #[account(seeds = [crate::ROUND_REGISTRY_SEED, config.key().as_ref()], bump)]
+    pub round_registry: UncheckedAccount<'info>,
+
+    #[account(address = config.treasury)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    /// CHECK: Treasury SOL PDA, address enforced. Only lamports are read.
+    #[account(address = config.treasury_sol)]
+    pub treasury_sol: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct RoundStatus<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct ComputeOutcome<'info> {
+    #[account(
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    /// CHECK: ticket owner; only its key is hashed into the bit index and commitment.
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64, nonce: u64)]
+pub struct GetTicketState<'info> {
+    #[account(
+        seeds = [crate::ROUND_SEED, round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        seeds = [
+            crate::TICKET_SEED,
+            round_id.to_le_bytes().as_ref(),
+            user.key().as_ref(),
+            &nonce.to_le_bytes()
+        ],
+        bump = ticket.bump,
+        has_one = user
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    /// CHECK: ticket owner; only its key is used in the ticket seeds.
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetProtocolStats<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteCommit<'info> {
+    #[account(
+        seeds = [crate::CONFIG_SEED],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
@@ -174,4 +174,184 @@ pub enum TimlgError {
 
     #[msg("Pulse too late (liveness hazard)")]
     PulseTooLate,
+
+    #[msg("Calling program is not allowed to CPI into this instruction")]
+    CpiNotAllowed,
+
+    #[msg("CPI allowlist is full")]
+    CpiAllowlistFull,
+
+    #[msg("CPI allowlist contains a duplicate program")]
+    CpiAllowlistDuplicate,
+
+    #[msg("Instruction does not match the round's guess mode (public vs commit-reveal)")]
+    RoundModeMismatch,
+
+    #[msg("Reveal is not allowed for public-guess rounds")]
+    PublicRoundRevealNotAllowed,
+
+    #[msg("Round already has tickets")]
+    RoundNotEmpty,
+
+    #[msg("Round was voided and is refunding")]
+    RoundRefunding,
+
+    #[msg("Max reveal deadline extensions reached")]
+    MaxExtensionsReached,
+
+    #[msg("Token account delegate is not the round PDA")]
+    InvalidDelegate,
+
+    #[msg("Delegated amount does not cover the stake")]
+    InsufficientDelegation,
+
+    #[msg("Fee exemption list is full")]
+    FeeExemptFull,
+
+    #[msg("User already fee exempt")]
+    FeeExemptAlreadyExists,
+
+    #[msg("User not in fee exemption list")]
+    FeeExemptNotFound,
+
+    #[msg("Invalid unrevealed stake policy")]
+    InvalidUnrevealedPolicy,
+
+    #[msg("Unrevealed stake is not refundable for this ticket")]
+    UnrevealedRefundNotAvailable,
+
+    #[msg("Invalid tokenomics pool type")]
+    InvalidPoolType,
+
+    #[msg("Round is full (max_committed reached)")]
+    RoundFull,
+
+    #[msg("Commits are frozen for this round")]
+    CommitFrozen,
+
+    #[msg("Invalid Merkle proof for reveal entry")]
+    InvalidMerkleProof,
+
+    #[msg("No reveal Merkle root committed for this user and round")]
+    RevealRootNotCommitted,
+
+    #[msg("Burn delay has not elapsed yet")]
+    BurnTooEarly,
+
+    #[msg("No pending burn for this round")]
+    NoPendingBurn,
+
+    #[msg("Commitment is all zeros (not computed?)")]
+    InvalidCommitment,
+
+    #[msg("Unsupported seed version")]
+    InvalidSeedVersion,
+
+    #[msg("Pulse dispute not allowed (needs multi-oracle threshold, contradicting attestations, not finalized)")]
+    PulseDisputeNotAllowed,
+
+    #[msg("Pulse dispute window closed")]
+    PulseDisputeWindowClosed,
+
+    #[msg("A disputed round needs its replacement pulse from the oracle threshold, in a later slot")]
+    PulseReplacementNotAllowed,
+
+    #[msg("Not enough distinct oracle attestations for the pulse")]
+    InsufficientAttestations,
+
+    #[msg("Oracle attested the pulse more than once")]
+    DuplicateAttestation,
+
+    #[msg("Round is paused")]
+    RoundPaused,
+
+    #[msg("Invalid reward mode")]
+    InvalidRewardMode,
+
+    #[msg("guess_bits must be 1, 2 or 4")]
+    InvalidGuessBits,
+
+    #[msg("Escrow token account is not empty")]
+    EscrowNotEmpty,
+
+    #[msg("Claim window closed")]
+    ClaimWindowClosed,
+
+    #[msg("Not supported for SOL-staked rounds")]
+    SolStakeUnsupported,
+
+    #[msg("Ticket limit per user reached for this round")]
+    TooManyTicketsPerUser,
+
+    #[msg("TIMLG mint already has circulating supply")]
+    MintSupplyNotZero,
+
+    #[msg("TIMLG mint authority is not the admin")]
+    InvalidMintAuthority,
+
+    #[msg("User escrow PDA mismatch")]
+    EscrowPdaMismatch,
+
+    #[msg("Duplicate nonce within one batch")]
+    DuplicateNonceInBatch,
+
+    #[msg("Attested pulse differs from the candidate pulse")]
+    AttestationPulseMismatch,
+
+    #[msg("Oracle set changed since the first attestation")]
+    OracleSetChanged,
+
+    #[msg("Stake amount outside the configured bounds")]
+    StakeOutOfBounds,
+
+    #[msg("Pulse is degenerate (constant bytes or too few set bits)")]
+    DegeneratePulse,
+
+    #[msg("Revealed guess does not fit in the round's guess_bits")]
+    GuessOutOfRange,
+
+    #[msg("Commit deadline is already in the past")]
+    CommitDeadlineInPast,
+
+    #[msg("Commit window is shorter than MIN_COMMIT_WINDOW_SLOTS")]
+    CommitWindowTooShort,
+
+    #[msg("Ticket account has an unexpected size")]
+    TicketSizeMismatch,
+
+    #[msg("Unprocessed ticket is already claimed or slashed")]
+    TicketStateInconsistent,
+
+    #[msg("Rent reimbursement needs the relayer's TIMLG token account")]
+    RelayerAtaRequired,
+
+    #[msg("Reveal not allowed after sweep")]
+    RevealAfterSweep,
+
+    #[msg("Escrow balance after deposit is below the expected minimum")]
+    EscrowBalanceTooLow,
+
+    #[msg("Commit landed after the relayer's max_land_slot")]
+    CommitLandedTooLate,
+
+    #[msg("TIMLG mint freeze authority is not the config PDA")]
+    FreezeAuthorityNotConfig,
+
+    #[msg("Too many open rounds in the registry")]
+    TooManyOpenRounds,
+
+    #[msg("Round is tracked by the round registry; pass round_registry")]
+    RoundRegistryRequired,
+
+    #[msg("Ticket weight must be between 1 and config.max_weight")]
+    InvalidWeight,
+
+    #[msg("Emergency drain requires the protocol to be paused")]
+    NotPaused,
+
+    #[msg("User escrow account has an unexpected size")]
+    EscrowSizeMismatch,
+
+    #[msg("Rescue authority must be set and distinct from the admin")]
+    InvalidRescueAuthority,
 }
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+/// Emitted once, when the last committed ticket of a round is settled.
+/// `slot` equals `round.token_settled_slot`.
+#[event]
+pub struct RoundSettlementComplete {
+    pub round_id: u64,
+    pub slot: u64,
+    pub winners: u64,
+    pub losers: u64,
+}
+
+/// Supply-audit record emitted with `RoundSettlementComplete`. `burned_total` covers the
+/// burns done during settlement; deferred burns are added to `round.burned_total` later.
+#[event]
+pub struct RoundSettled {
+    pub round_id: u64,
+    pub losers: u64,
+    pub burned_total: u64,
+    pub settled_count: u64,
+}
+
+/// One per committed ticket (batch paths emit one per entry).
+#[event]
+pub struct CommitMade {
+    pub round_id: u64,
+    pub user: Pubkey,
+    pub nonce: u64,
+    pub bit_index: u16,
+}
+
+/// One per revealed ticket (batch paths emit one per entry).
+#[event]
+pub struct RevealMade {
+    pub round_id: u64,
+    pub user: Pubkey,
+    pub nonce: u64,
+    pub guess: u8,
+    pub win: bool,
+}
+
+/// Emitted whenever a round's pulse is set. `oracle` is the round's oracle key (override or
+/// `config.oracle_pubkey`) for `set_pulse_signed` and the first attesting oracle for `set_pulse_multisig`.
+#[event]
+pub struct PulseSet {
+    pub round_id: u64,
+    pub pulse_index_target: u64,
+    pub pulse_set_slot: u64,
+    pub oracle: Pubkey,
+}
+
+/// Emitted when a late pulse pushes the reveal deadline (`config.auto_extend_reveal`).
+#[event]
+pub struct RevealDeadlineExtended {
+    pub round_id: u64,
+    pub old_reveal_deadline_slot: u64,
+    pub new_reveal_deadline_slot: u64,
+    pub slot: u64,
+}
+
+/// Emitted when the admin pushes a round's commit deadline (`extend_commit_deadline`).
+#[event]
+pub struct CommitDeadlineExtended {
+    pub round_id: u64,
+    pub old_commit_deadline_slot: u64,
+    pub new_commit_deadline_slot: u64,
+    pub slot: u64,
+}
+
+/// Emitted when the admin clears a disputed pulse; the round is back to pulse-pending.
+#[event]
+pub struct PulseDisputed {
+    pub round_id: u64,
+    pub admin: Pubkey,
+    pub disputed_pulse: [u8; 64],
+    pub pulse_set_slot: u64,
+    pub oracle_threshold: u8,
+    /// Reveals scored against the disputed pulse; they no longer count and may be redone.
+    pub voided_reveals: u64,
+    pub slot: u64,
+}
+
+/// Emitted per `submit_attestation`; `finalize_pulse` is callable once `count >= threshold`.
+#[event]
+pub struct PulseAttested {
+    pub round_id: u64,
+    pub oracle: Pubkey,
+    pub count: u8,
+    pub threshold: u8,
+    pub slot: u64,
+}
+
+/// Emitted by `create_round` and `create_round_auto`; enough to schedule commit/reveal cranks.
+#[event]
+pub struct RoundCreated {
+    pub round_id: u64,
+    pub pulse_index_target: u64,
+    pub commit_deadline_slot: u64,
+    pub reveal_deadline_slot: u64,
+    pub created_slot: u64,
+    pub vault: Pubkey,
+    pub timlg_vault: Pubkey,
+}
+
+/// Emitted by `emergency_drain`: both treasuries moved to the rescue destination.
+#[event]
+pub struct EmergencyDrained {
+    pub rescue_authority: Pubkey,
+    pub destination: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub slot: u64,
+}
@@ -1,18 +1,31 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{program::invoke, system_instruction};
+use anchor_lang::solana_program::{program::invoke, program_option::COption, system_instruction};
 
-use anchor_spl::token::{self, SetAuthority};
+use anchor_spl::token::{self, FreezeAccount, SetAuthority, ThawAccount, Token, TokenAccount};
 use anchor_spl::token::spl_token::instruction::AuthorityType;
 
 use crate::errors::TimlgError;
-use crate::state::{Config, RoundState};
+use crate::state::{Config, PoolType, RewardMode, Round, RoundRegistry, RoundState, Ticket, Tokenomics, UnrevealedPolicy, UserEscrow};
 use crate::{
-    CreateRound, CreateRoundAuto, FundVault, InitializeConfig, InitializeGlobalStats, InitializeRoundRegistry, SetPause, UpdateStakeAmount,
+    CreateRound, CreateRoundAuto, FundTimlgVault, FundVault, InitializeConfig, InitializeGlobalStats, InitializeRoundRegistry, SetPause, UpdateStakeAmount,
     UpdateSolServiceFee, WithdrawTreasurySol, WithdrawTreasuryTokens, CloseConfig, MigrateConfig,
-    InitializeTokenomics, UpdateTokenomics, UpdateWindows,
+    InitializeTokenomics, UpdateTokenomics, UpdateWindows, SetCpiAllowlist, SetRoundPublicMode,
+    SetRoundMinParticipants, SetMaxExtensions, ExtendRevealDeadline,
+    AddFeeExempt, RemoveFeeExempt, SetUnrevealedPolicy, MigrateTokenomics,
+    InitializeAdditionalPool, SetRoundMaxCommitted, SetBurnDelay, SetStreakBonusBps, SetPrefundFees, SyncTimlgDecimals,
+    SetSeedVersion, SetRoundPause, SetRewardMode, SetStakeInSol,
+    SetAutoExtendReveal, SetMaxTicketsPerUser, ExtendCommitDeadline, SetMinRevealAfterPulseSlots,
+    SetStakeBounds, MigrateRound, WithdrawRewardFeePool, WithdrawReplicationPool, SetRentReimburse,
+    TransferMintAuthority, SetSweepBurnsTokens, SetCrankerFee, FreezeTreasury, SetMaxOpenRounds,
+    MigrateRoundRegistry, SetMaxWeight, SetRescueAuthority, EmergencyDrain, SetAcceptLegacyCommitMsg, MigrateTicket, MigrateUserEscrow,
 };
 use crate::VAULT_SEED;
 use crate::constants::*;
+use crate::utils::{
+    apply_deadline_extension, freeze_commits_if_full, is_valid_guess_bits, require_spl_stake,
+    treasury_sol_payout, treasury_sol_spendable, CONFIG_PRE_EXTENSIONS_SPACE, NEXT_SEED_VERSION, SEED_VERSION,
+    TICKET_LEGACY_SPACE, TICKET_PRE_FEE_SPACE,
+};
 
 #[cfg(feature = "mock-pulse")]
 use crate::SetPulseMock;
@@ -40,24 +53,82 @@ pub fn initialize_tokenomics(
 
     tok.version = INITIAL_VERSION;
 
+    tok.unrevealed_policy = UnrevealedPolicy::Burn as u8;
+    tok.unrevealed_penalty_bps = 0;
+
+    tok.jackpot_pool = Pubkey::default();
+    tok.jackpot_pool_bump = 0;
+    tok.reward_reserve_pool = Pubkey::default();
+    tok.reward_reserve_pool_bump = 0;
+
+    tok.burn_delay_slots = 0;
+    tok.reward_mode = RewardMode::Mint as u8;
+    tok.slash_to_pool = false;
+    tok.fee_to_replication_bps = 0;
+    tok.streak_bonus_bps = 0;
+    tok.prefund_fees = false;
+
     Ok(())
 }
 
 pub fn update_tokenomics(
     ctx: Context<UpdateTokenomics>,
     reward_fee_bps: u16,
+    slash_to_pool: Option<bool>,
+    fee_to_replication_bps: Option<u16>,
 ) -> Result<()> {
     require!(reward_fee_bps <= 10_000, TimlgError::InvalidFeeBps);
+    require!(
+        fee_to_replication_bps.is_none_or(|bps| bps <= 10_000),
+        TimlgError::InvalidFeeBps
+    );
 
     let cfg = &ctx.accounts.config;
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
 
     let tok = &mut ctx.accounts.tokenomics;
     tok.reward_fee_bps = reward_fee_bps;
+    if let Some(flag) = slash_to_pool {
+        tok.slash_to_pool = flag;
+    }
+    if let Some(bps) = fee_to_replication_bps {
+        tok.fee_to_replication_bps = bps;
+    }
 
     Ok(())
 }
 
+pub fn set_unrevealed_policy(
+    ctx: Context<SetUnrevealedPolicy>,
+    policy: u8,
+    penalty_bps: u16,
+) -> Result<()> {
+    require!(
+        policy <= UnrevealedPolicy::DefaultGuessZero as u8,
+        TimlgError::InvalidUnrevealedPolicy
+    );
+    require!(penalty_bps <= 10_000, TimlgError::InvalidFeeBps);
+
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let tok = &mut ctx.accounts.tokenomics;
+    tok.unrevealed_policy = policy;
+    tok.unrevealed_penalty_bps = penalty_bps;
+
+    Ok(())
+}
+
+pub fn set_reward_mode(ctx: Context<SetRewardMode>, mode: u8) -> Result<()> {
+    require!(mode <= RewardMode::PariMutuel as u8, TimlgError::InvalidRewardMode);
+
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    ctx.accounts.tokenomics.reward_mode = mode;
+    Ok(())
+}
+
 pub fn initialize_round_registry(ctx: Context<InitializeRoundRegistry>, start_round_id: u64) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
@@ -67,19 +138,39 @@ pub fn initialize_round_registry(ctx: Context<InitializeRoundRegistry>, start_ro
     rr.bump = ctx.bumps.round_registry;
     rr.next_round_id = start_round_id;
     rr.version = INITIAL_VERSION;
+    rr.max_open_rounds = 0;
+    rr.open_rounds = 0;
+
+    Ok(())
+}
 
+/// A new round must still accept commits for at least `MIN_COMMIT_WINDOW_SLOTS`.
+fn require_commit_window_ahead(commit_deadline_slot: u64, current_slot: u64) -> Result<()> {
+    require!(commit_deadline_slot > current_slot, TimlgError::CommitDeadlineInPast);
+    require!(
+        commit_deadline_slot >= current_slot.saturating_add(MIN_COMMIT_WINDOW_SLOTS),
+        TimlgError::CommitWindowTooShort
+    );
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_round_auto(
     ctx: Context<CreateRoundAuto>,
     pulse_index_target: u64,
     commit_deadline_slot: u64,
     reveal_deadline_slot: u64,
+    claim_grace_slots_override: u64,
+    label: [u8; 32],
+    oracle_pubkey_override: Pubkey,
+    max_total_reward: u64,
+    min_participants: u64,
+    guess_bits: u8,
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(is_valid_guess_bits(guess_bits), TimlgError::InvalidGuessBits);
     require!(commit_deadline_slot < reveal_deadline_slot, TimlgError::InvalidDeadlines);
     require!(
         reveal_deadline_slot >= commit_deadline_slot + MIN_REVEAL_WINDOW_SLOTS,
@@ -87,9 +178,14 @@ pub fn create_round_auto(
     );
 
     let current_slot = Clock::get()?.slot;
+    require_commit_window_ahead(commit_deadline_slot, current_slot)?;
 
     let rr = &mut ctx.accounts.round_registry;
     let round_id = rr.next_round_id;
+    require!(
+        rr.max_open_rounds == 0 || rr.open_rounds < rr.max_open_rounds,
+        TimlgError::TooManyOpenRounds
+    );
 
     let round = &mut ctx.accounts.round;
     round.round_id = round_id;
@@ -133,7 +229,53 @@ pub fn create_round_auto(
     round.claimed_win_count = 0;
     round.close_burn_done = false;
     round.close_unclaimed_mint_done = false;
+    round.public_mode = false;
+    round.min_participants = min_participants;
+    round.extension_count = 0;
+    round.unrevealed_policy = UnrevealedPolicy::Burn as u8;
+    round.unrevealed_refund = 0;
+    round.max_committed = 0;
+    round.commit_frozen = false;
+    round.commit_closed_slot = 0;
+    round.burn_delay_slots = 0;
+    round.pending_burn = 0;
+    round.pulse_attestations = 0;
+    round.paused = false;
+    round.reward_mode = RewardMode::Mint as u8;
+    round.loser_stake_pool = 0;
+    round.guess_bits = guess_bits;
+    round.claim_deadline_slot = 0;
+    round.stake_in_sol = cfg.stake_in_sol;
+    round.reveal_extended_slot = 0;
+    round.burned_total = 0;
+    round.sol_swept = false;
+    round.tokens_swept = false;
+    round.claim_grace_slots_override = claim_grace_slots_override;
+    round.label = label;
+    round.oracle_pubkey_override = oracle_pubkey_override;
+    round.fees_prefunded = false;
+    round.fee_paid_claims = 0;
+    round.max_total_reward = max_total_reward;
+    round.reward_minted_total = 0;
+    round.seeded_amount = 0;
+    round.registry_tracked = true;
+    round.win_extra_weight = 0;
+    round.claimed_win_extra_weight = 0;
+    round.reveal_transcript = [0u8; 32];
+    round.pulse_disputed_slot = 0;
+    round.fees_outstanding = 0;
+    emit!(crate::events::RoundCreated {
+        round_id: round.round_id,
+        pulse_index_target: round.pulse_index_target,
+        commit_deadline_slot: round.commit_deadline_slot,
+        reveal_deadline_slot: round.reveal_deadline_slot,
+        created_slot: round.created_slot,
+        vault: round.vault,
+        timlg_vault: round.timlg_vault,
+    });
+
     rr.next_round_id = rr.next_round_id.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    rr.open_rounds = rr.open_rounds.checked_add(1).ok_or(TimlgError::MathOverflow)?;
 
     let gs = &mut ctx.accounts.global_stats;
     gs.total_rounds_created = gs.total_rounds_created.checked_add(1).unwrap_or(gs.total_rounds_created);
@@ -141,15 +283,33 @@ pub fn create_round_auto(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn initialize_config(
     ctx: Context<InitializeConfig>,
     stake_amount: u64,
     commit_window_slots: u64,
     reveal_window_slots: u64,
+    allow_nonzero_supply: bool,
+    min_stake_amount: u64,
+    max_stake_amount: u64,
+    rescue_authority: Pubkey,
+    rescue_destination: Pubkey,
 ) -> Result<()> {
     require!(stake_amount > 0, TimlgError::InvalidStakeAmount);
+    require_valid_stake_bounds(min_stake_amount, max_stake_amount)?;
+    require_stake_in_bounds(stake_amount, min_stake_amount, max_stake_amount)?;
     require!(commit_window_slots > 0, TimlgError::InvalidWindow);
     require!(reveal_window_slots > 0, TimlgError::InvalidWindow);
+    require_valid_rescue_authority(&ctx.accounts.admin.key(), &rescue_authority)?;
+
+    // Mint sanity: a mint with circulating supply breaks reward economics unless
+    // the deployer opts in explicitly; the authority must be `admin` for the CPI below.
+    let mint = &ctx.accounts.timlg_mint;
+    require!(allow_nonzero_supply || mint.supply == 0, TimlgError::MintSupplyNotZero);
+    require!(
+        mint.mint_authority == COption::Some(ctx.accounts.admin.key()),
+        TimlgError::InvalidMintAuthority
+    );
 
     let cfg: &mut Account<Config> = &mut ctx.accounts.config;
 
@@ -174,6 +334,31 @@ pub fn initialize_config(
     cfg.paused = false;
     // ✅ NUEVO: Tasa de servicio inicial a 0
     cfg.sol_service_fee_lamports = 0;
+    cfg.cpi_allowlist = Vec::new();
+    cfg.max_extensions = DEFAULT_MAX_EXTENSIONS;
+    cfg.fee_exempt = Vec::new();
+    cfg.timlg_decimals = ctx.accounts.timlg_mint.decimals;
+    cfg.seed_version = SEED_VERSION;
+    cfg.pulse_dispute_window_slots = 0;
+    cfg.stake_in_sol = false;
+    cfg.auto_extend_reveal = false;
+    cfg.max_tickets_per_user = 0;
+    cfg.min_reveal_after_pulse_slots = 0;
+    cfg.min_stake_amount = min_stake_amount;
+    cfg.max_stake_amount = max_stake_amount;
+    cfg.min_pulse_popcount = 0;
+    cfg.rent_reimburse = false;
+    cfg.rent_price_timlg = 0;
+    cfg.sweep_burns_tokens = false;
+    cfg.cranker_fee_lamports = 0;
+    cfg.max_weight = 0;
+    cfg.rescue_authority = rescue_authority;
+    cfg.rescue_destination = rescue_destination;
+    cfg.accept_legacy_commit_msg = false;
+    cfg.total_committed = 0;
+    cfg.total_revealed = 0;
+    cfg.total_rewards_minted = 0;
+    cfg.sol_fees_outstanding = 0;
 
     cfg.version = INITIAL_VERSION;
 
@@ -195,6 +380,20 @@ pub fn initialize_config(
         )?;
     }
 
+    // Freeze authority too, when the admin holds it (needed by `freeze_treasury`).
+    // Mints created without one can never gain it, so they're left as-is.
+    if ctx.accounts.timlg_mint.freeze_authority == COption::Some(ctx.accounts.admin.key()) {
+        let cpi_accounts = SetAuthority {
+            account_or_mint: ctx.accounts.timlg_mint.to_account_info(),
+            current_authority: ctx.accounts.admin.to_account_info(),
+        };
+        token::set_authority(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            AuthorityType::FreezeAccount,
+            Some(cfg.key()),
+        )?;
+    }
+
     Ok(())
 }
 
@@ -206,16 +405,27 @@ pub fn set_pause(ctx: Context<SetPause>, paused: bool) -> Result<()> {
     Ok(())
 }
 
+/// `min_participants` (0 = disabled) voids an under-participated round at finalize; see
+/// `set_round_min_participants` to change it while the round is still empty. `guess_bits`
+/// (1, 2 or 4) is how many pulse bits each ticket reads, fixed for the round's lifetime.
+#[allow(clippy::too_many_arguments)]
 pub fn create_round(
     ctx: Context<CreateRound>,
     round_id: u64,
     pulse_index_target: u64,
     commit_deadline_slot: u64,
     reveal_deadline_slot: u64,
+    claim_grace_slots_override: u64,
+    label: [u8; 32],
+    oracle_pubkey_override: Pubkey,
+    max_total_reward: u64,
+    min_participants: u64,
+    guess_bits: u8,
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(is_valid_guess_bits(guess_bits), TimlgError::InvalidGuessBits);
     require!(commit_deadline_slot < reveal_deadline_slot, TimlgError::InvalidDeadlines);
     require!(
         reveal_deadline_slot >= commit_deadline_slot + MIN_REVEAL_WINDOW_SLOTS,
@@ -223,6 +433,7 @@ pub fn create_round(
     );
 
     let current_slot = Clock::get()?.slot;
+    require_commit_window_ahead(commit_deadline_slot, current_slot)?;
 
     let round = &mut ctx.accounts.round;
     round.round_id = round_id;
@@ -266,6 +477,50 @@ pub fn create_round(
     round.claimed_win_count = 0;
     round.close_burn_done = false;
     round.close_unclaimed_mint_done = false;
+    round.public_mode = false;
+    round.min_participants = min_participants;
+    round.extension_count = 0;
+    round.unrevealed_policy = UnrevealedPolicy::Burn as u8;
+    round.unrevealed_refund = 0;
+    round.max_committed = 0;
+    round.commit_frozen = false;
+    round.commit_closed_slot = 0;
+    round.burn_delay_slots = 0;
+    round.pending_burn = 0;
+    round.pulse_attestations = 0;
+    round.paused = false;
+    round.reward_mode = RewardMode::Mint as u8;
+    round.loser_stake_pool = 0;
+    round.guess_bits = guess_bits;
+    round.claim_deadline_slot = 0;
+    round.stake_in_sol = cfg.stake_in_sol;
+    round.reveal_extended_slot = 0;
+    round.burned_total = 0;
+    round.sol_swept = false;
+    round.tokens_swept = false;
+    round.claim_grace_slots_override = claim_grace_slots_override;
+    round.label = label;
+    round.oracle_pubkey_override = oracle_pubkey_override;
+    round.fees_prefunded = false;
+    round.fee_paid_claims = 0;
+    round.max_total_reward = max_total_reward;
+    round.reward_minted_total = 0;
+    round.seeded_amount = 0;
+    round.registry_tracked = false;
+    round.win_extra_weight = 0;
+    round.claimed_win_extra_weight = 0;
+    round.reveal_transcript = [0u8; 32];
+    round.pulse_disputed_slot = 0;
+    round.fees_outstanding = 0;
+    emit!(crate::events::RoundCreated {
+        round_id: round.round_id,
+        pulse_index_target: round.pulse_index_target,
+        commit_deadline_slot: round.commit_deadline_slot,
+        reveal_deadline_slot: round.reveal_deadline_slot,
+        created_slot: round.created_slot,
+        vault: round.vault,
+        timlg_vault: round.timlg_vault,
+    });
 
     let gs = &mut ctx.accounts.global_stats;
     gs.total_rounds_created = gs.total_rounds_created.checked_add(1).unwrap_or(gs.total_rounds_created);
@@ -273,6 +528,149 @@ pub fn create_round(
     Ok(())
 }
 
+/// Switches a round between commit-reveal and public-guess mode.
+/// Only allowed before the first ticket, so every ticket in a round shares one mode.
+pub fn set_round_public_mode(
+    ctx: Context<SetRoundPublicMode>,
+    round_id: u64,
+    public_mode: bool,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(round.committed_count == 0, TimlgError::RoundNotEmpty);
+    require!(!round.pulse_set, TimlgError::PulseAlreadySet);
+
+    round.public_mode = public_mode;
+    Ok(())
+}
+
+/// Sets the minimum number of tickets for the round to resolve (0 = disabled).
+/// Below it, `finalize_round` voids the round into `Refunding` instead.
+pub fn set_round_min_participants(
+    ctx: Context<SetRoundMinParticipants>,
+    round_id: u64,
+    min_participants: u64,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(round.committed_count == 0, TimlgError::RoundNotEmpty);
+    require!(!round.pulse_set, TimlgError::PulseAlreadySet);
+
+    round.min_participants = min_participants;
+    Ok(())
+}
+
+/// Caps the number of tickets in a round (0 = unlimited). Can be set until the
+/// pulse, but never below the tickets already committed.
+pub fn set_round_max_committed(
+    ctx: Context<SetRoundMaxCommitted>,
+    round_id: u64,
+    max_committed: u64,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(!round.pulse_set, TimlgError::PulseAlreadySet);
+    require!(!round.commit_frozen, TimlgError::CommitFrozen);
+    require!(
+        max_committed == 0 || max_committed >= round.committed_count,
+        TimlgError::RoundFull
+    );
+
+    round.max_committed = max_committed;
+    freeze_commits_if_full(round, Clock::get()?.slot);
+    Ok(())
+}
+
+/// Freezes commits and reveals on one round; finalize/sweep/recover stay open.
+pub fn set_round_pause(ctx: Context<SetRoundPause>, round_id: u64, paused: bool) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    round.paused = paused;
+    Ok(())
+}
+
+/// Pushes the reveal deadline of a live round. Bounded by `config.max_extensions`,
+/// at most `reveal_window_slots` per call, and never once a pulse-less round has lapsed.
+pub fn extend_reveal_deadline(
+    ctx: Context<ExtendRevealDeadline>,
+    round_id: u64,
+    extra_slots: u64,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(!round.finalized, TimlgError::AlreadyFinalized);
+    require!(round.state != RoundState::Refunding as u8, TimlgError::RoundRefunding);
+    // A lapsed round without a pulse is on its way to refunds; reviving it would let
+    // tickets already refunded by `recover_funds_anyone_batch` play again.
+    require!(
+        round.pulse_set || Clock::get()?.slot <= round.reveal_deadline_slot,
+        TimlgError::RevealClosed
+    );
+
+    apply_deadline_extension(round, cfg, extra_slots)?;
+
+    msg!(
+        "Round {} reveal deadline -> {} (extension {}/{})",
+        round_id, round.reveal_deadline_slot, round.extension_count, cfg.max_extensions
+    );
+    Ok(())
+}
+
+/// Pushes the commit deadline of a live round forward. Never shortens it, and always
+/// leaves at least `MIN_REVEAL_WINDOW_SLOTS` before the reveal deadline.
+pub fn extend_commit_deadline(
+    ctx: Context<ExtendCommitDeadline>,
+    round_id: u64,
+    new_commit_deadline_slot: u64,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(!round.finalized, TimlgError::AlreadyFinalized);
+    require!(round.state != RoundState::Refunding as u8, TimlgError::RoundRefunding);
+    require!(!round.pulse_set, TimlgError::PulseAlreadySet);
+
+    let current_slot = Clock::get()?.slot;
+    require!(current_slot <= round.commit_deadline_slot, TimlgError::CommitClosed);
+
+    require!(
+        new_commit_deadline_slot > round.commit_deadline_slot,
+        TimlgError::InvalidDeadlines
+    );
+    require!(
+        new_commit_deadline_slot < round.reveal_deadline_slot.saturating_sub(MIN_REVEAL_WINDOW_SLOTS),
+        TimlgError::InvalidDeadlines
+    );
+
+    let old_commit_deadline_slot = round.commit_deadline_slot;
+    round.commit_deadline_slot = new_commit_deadline_slot;
+
+    emit!(crate::events::CommitDeadlineExtended {
+        round_id,
+        old_commit_deadline_slot,
+        new_commit_deadline_slot,
+        slot: current_slot,
+    });
+    Ok(())
+}
+
 pub fn fund_vault(ctx: Context<FundVault>, round_id: u64, amount: u64) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
@@ -306,6 +704,57 @@ pub fn fund_vault(ctx: Context<FundVault>, round_id: u64, amount: u64) -> Result
     Ok(())
 }
 
+/// Seeds the round's `timlg_vault` with TIMLG from `config.treasury` (config PDA signs)
+/// or from a token account owned by the admin. Tracked in `round.seeded_amount`.
+pub fn fund_timlg_vault(ctx: Context<FundTimlgVault>, round_id: u64, amount: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(!cfg.paused, TimlgError::Paused);
+
+    let round = &ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require_spl_stake(round)?;
+    require!(!round.token_settled, TimlgError::RoundTokensAlreadySettled);
+
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let transfer = token::Transfer {
+        from: ctx.accounts.source.to_account_info(),
+        to: ctx.accounts.timlg_vault.to_account_info(),
+        authority: ctx.accounts.admin.to_account_info(),
+    };
+    if ctx.accounts.source.key() == cfg.treasury {
+        let seeds = &[crate::CONFIG_SEED, &[cfg.bump]];
+        let signer = &[&seeds[..]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    authority: cfg.to_account_info(),
+                    ..transfer
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    } else {
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+            amount,
+        )?;
+    }
+
+    let round = &mut ctx.accounts.round;
+    round.seeded_amount = round
+        .seeded_amount
+        .checked_add(amount)
+        .ok_or(TimlgError::MathOverflow)?;
+
+    Ok(())
+}
+
 #[cfg(feature = "mock-pulse")]
 pub fn set_pulse_mock(
     ctx: Context<SetPulseMock>,
@@ -320,11 +769,13 @@ pub fn set_pulse_mock(
 
     let current_slot = Clock::get()?.slot;
     require!(current_slot >= round.commit_deadline_slot, TimlgError::CommitClosed);
+    // voided reveals are told apart by slot (see `reveal_voided`)
+    require!(current_slot > round.pulse_disputed_slot, TimlgError::PulseReplacementNotAllowed);
 
     round.pulse = pulse;
     round.pulse_set = true;
     round.pulse_set_slot = current_slot;
-    round.state = RoundState::PulseSet as u8;
+    crate::utils::advance_round_state(round, RoundState::PulseSet);
 
     let gs = &mut ctx.accounts.global_stats;
     gs.total_pulses_published = gs.total_pulses_published.checked_add(1).unwrap_or(gs.total_pulses_published);
@@ -351,12 +802,52 @@ pub fn update_stake_amount(ctx: Context<UpdateStakeAmount>, new_stake_amount: u6
 
     let cfg = &mut ctx.accounts.config;
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require_stake_in_bounds(new_stake_amount, cfg.min_stake_amount, cfg.max_stake_amount)?;
 
     cfg.stake_amount = new_stake_amount;
     
     Ok(())
 }
 
+/// Adjusts the guardrails `update_stake_amount` enforces. The current stake must fit the
+/// new bounds; `max_stake_amount = 0` removes the cap.
+pub fn set_stake_bounds(
+    ctx: Context<SetStakeBounds>,
+    min_stake_amount: u64,
+    max_stake_amount: u64,
+) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require_valid_stake_bounds(min_stake_amount, max_stake_amount)?;
+    require_stake_in_bounds(cfg.stake_amount, min_stake_amount, max_stake_amount)?;
+
+    cfg.min_stake_amount = min_stake_amount;
+    cfg.max_stake_amount = max_stake_amount;
+    Ok(())
+}
+
+/// The rescue key is defense-in-depth against a compromised admin: never unset, never the admin.
+fn require_valid_rescue_authority(admin: &Pubkey, rescue_authority: &Pubkey) -> Result<()> {
+    require!(
+        *rescue_authority != Pubkey::default() && rescue_authority != admin,
+        TimlgError::InvalidRescueAuthority
+    );
+    Ok(())
+}
+
+fn require_valid_stake_bounds(min: u64, max: u64) -> Result<()> {
+    require!(max == 0 || min <= max, TimlgError::InvalidStakeAmount);
+    Ok(())
+}
+
+fn require_stake_in_bounds(amount: u64, min: u64, max: u64) -> Result<()> {
+    require!(
+        amount >= min && (max == 0 || amount <= max),
+        TimlgError::StakeOutOfBounds
+    );
+    Ok(())
+}
+
 pub fn update_sol_service_fee(ctx: Context<UpdateSolServiceFee>, new_fee: u64) -> Result<()> {
     let cfg = &mut ctx.accounts.config;
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
@@ -364,6 +855,279 @@ pub fn update_sol_service_fee(ctx: Context<UpdateSolServiceFee>, new_fee: u64) -
     Ok(())
 }
 
+pub fn set_cpi_allowlist(ctx: Context<SetCpiAllowlist>, programs: Vec<Pubkey>) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(programs.len() <= crate::MAX_CPI_ALLOWLIST, TimlgError::CpiAllowlistFull);
+
+    for (i, pk) in programs.iter().enumerate() {
+        require!(!programs[..i].contains(pk), TimlgError::CpiAllowlistDuplicate);
+    }
+
+    cfg.cpi_allowlist = programs;
+    Ok(())
+}
+
+pub fn set_max_extensions(ctx: Context<SetMaxExtensions>, max_extensions: u8) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.max_extensions = max_extensions;
+    Ok(())
+}
+
+pub fn add_fee_exempt(ctx: Context<AddFeeExempt>, user: Pubkey) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    require!(cfg.fee_exempt.len() < crate::MAX_FEE_EXEMPT, TimlgError::FeeExemptFull);
+    require!(!cfg.fee_exempt.contains(&user), TimlgError::FeeExemptAlreadyExists);
+
+    cfg.fee_exempt.push(user);
+    Ok(())
+}
+
+pub fn remove_fee_exempt(ctx: Context<RemoveFeeExempt>, user: Pubkey) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let pos = cfg.fee_exempt.iter().position(|x| *x == user).ok_or(TimlgError::FeeExemptNotFound)?;
+    cfg.fee_exempt.remove(pos);
+    Ok(())
+}
+
+/// Refreshes `config.timlg_decimals` from the mint (e.g. after `migrate_config`).
+pub fn sync_timlg_decimals(ctx: Context<SyncTimlgDecimals>) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.timlg_decimals = ctx.accounts.timlg_mint.decimals;
+    Ok(())
+}
+
+/// Opens (`NEXT_SEED_VERSION`) or closes (`SEED_VERSION`) the seed migration window.
+pub fn set_seed_version(ctx: Context<SetSeedVersion>, seed_version: u8) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(
+        seed_version == SEED_VERSION || seed_version == NEXT_SEED_VERSION,
+        TimlgError::InvalidSeedVersion
+    );
+    cfg.seed_version = seed_version;
+    Ok(())
+}
+
+pub fn set_stake_in_sol(ctx: Context<SetStakeInSol>, stake_in_sol: bool) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    // Only rounds created from now on are affected (snapshot in create_round).
+    cfg.stake_in_sol = stake_in_sol;
+    Ok(())
+}
+
+pub fn set_auto_extend_reveal(ctx: Context<SetAutoExtendReveal>, enabled: bool) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.auto_extend_reveal = enabled;
+    Ok(())
+}
+
+pub fn set_max_tickets_per_user(ctx: Context<SetMaxTicketsPerUser>, max_tickets_per_user: u64) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.max_tickets_per_user = max_tickets_per_user;
+    Ok(())
+}
+
+pub fn set_rent_reimburse(
+    ctx: Context<SetRentReimburse>,
+    rent_reimburse: bool,
+    rent_price_timlg: u64,
+) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.rent_reimburse = rent_reimburse;
+    cfg.rent_price_timlg = rent_price_timlg;
+    Ok(())
+}
+
+pub fn set_sweep_burns_tokens(ctx: Context<SetSweepBurnsTokens>, sweep_burns_tokens: bool) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.sweep_burns_tokens = sweep_burns_tokens;
+    Ok(())
+}
+
+pub fn set_cranker_fee(ctx: Context<SetCrankerFee>, cranker_fee_lamports: u64) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.cranker_fee_lamports = cranker_fee_lamports;
+    Ok(())
+}
+
+/// Rotates the rescue key/destination. Signed by the current rescue authority only; configs
+/// created before these fields get them once through `migrate_config`.
+pub fn set_rescue_authority(
+    ctx: Context<SetRescueAuthority>,
+    rescue_authority: Pubkey,
+    rescue_destination: Pubkey,
+) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(ctx.accounts.authority.key(), cfg.rescue_authority, TimlgError::Unauthorized);
+    require_valid_rescue_authority(&cfg.admin, &rescue_authority)?;
+    cfg.rescue_authority = rescue_authority;
+    cfg.rescue_destination = rescue_destination;
+    Ok(())
+}
+
+/// Incident path independent of the admin key: while the protocol is paused, the rescue
+/// authority moves all of `treasury_sol` (above its rent floor) and the SPL treasury
+/// to `rescue_destination`. A frozen SPL treasury is left in place (the SOL still moves).
+pub fn emergency_drain(ctx: Context<EmergencyDrain>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(cfg.paused, TimlgError::NotPaused);
+    require_keys_eq!(
+        cfg.rescue_authority,
+        ctx.accounts.rescue_authority.key(),
+        TimlgError::Unauthorized
+    );
+
+    let treasury_sol = ctx.accounts.treasury_sol.to_account_info();
+    let sol_amount = treasury_sol_spendable(&treasury_sol)?;
+    treasury_sol_payout(
+        cfg,
+        &treasury_sol,
+        &ctx.accounts.rescue_destination.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        sol_amount,
+    )?;
+
+    let token_amount = if ctx.accounts.treasury.is_frozen() {
+        msg!("emergency_drain: SPL treasury is frozen, token leg skipped");
+        0
+    } else {
+        let amount = ctx.accounts.treasury.amount;
+        withdraw_config_owned_tokens(
+            cfg,
+            &ctx.accounts.treasury,
+            &ctx.accounts.rescue_destination_ata,
+            &ctx.accounts.token_program,
+            0,
+        )?;
+        amount
+    };
+
+    emit!(crate::events::EmergencyDrained {
+        rescue_authority: cfg.rescue_authority,
+        destination: cfg.rescue_destination,
+        sol_amount,
+        token_amount,
+        slot: Clock::get()?.slot,
+    });
+    Ok(())
+}
+
+/// Migration switch: lets relayers keep submitting `commit_v1`/`commit_v2` signatures.
+pub fn set_accept_legacy_commit_msg(ctx: Context<SetAcceptLegacyCommitMsg>, accept: bool) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.accept_legacy_commit_msg = accept;
+    Ok(())
+}
+
+/// Caps the stake multiple of weighted commits; 0/1 turns weighting off.
+pub fn set_max_weight(ctx: Context<SetMaxWeight>, max_weight: u8) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.max_weight = max_weight;
+    Ok(())
+}
+
+pub fn set_max_open_rounds(ctx: Context<SetMaxOpenRounds>, max_open_rounds: u16) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    ctx.accounts.round_registry.max_open_rounds = max_open_rounds;
+    Ok(())
+}
+
+/// Incident response: freezes the SPL treasury (config PDA is the mint's freeze authority).
+/// While frozen, every path paying into or out of `config.treasury` fails, except
+/// `emergency_drain`, which then only moves `treasury_sol`.
+pub fn freeze_treasury(ctx: Context<FreezeTreasury>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(
+        ctx.accounts.timlg_mint.freeze_authority == COption::Some(cfg.key()),
+        TimlgError::FreezeAuthorityNotConfig
+    );
+
+    let seeds = &[crate::CONFIG_SEED, &[cfg.bump]];
+    let signer = &[&seeds[..]];
+    token::freeze_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        FreezeAccount {
+            account: ctx.accounts.treasury.to_account_info(),
+            mint: ctx.accounts.timlg_mint.to_account_info(),
+            authority: cfg.to_account_info(),
+        },
+        signer,
+    ))
+}
+
+pub fn thaw_treasury(ctx: Context<FreezeTreasury>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(
+        ctx.accounts.timlg_mint.freeze_authority == COption::Some(cfg.key()),
+        TimlgError::FreezeAuthorityNotConfig
+    );
+
+    let seeds = &[crate::CONFIG_SEED, &[cfg.bump]];
+    let signer = &[&seeds[..]];
+    token::thaw_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        ThawAccount {
+            account: ctx.accounts.treasury.to_account_info(),
+            mint: ctx.accounts.timlg_mint.to_account_info(),
+            authority: cfg.to_account_info(),
+        },
+        signer,
+    ))
+}
+
+/// Moves the TIMLG mint authority from the config PDA to `new_authority`; `None` revokes
+/// it for good. Without it, mint-mode claims only refund the stake: no reward, no fee.
+pub fn transfer_mint_authority(ctx: Context<TransferMintAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let cpi_accounts = SetAuthority {
+        account_or_mint: ctx.accounts.timlg_mint.to_account_info(),
+        current_authority: cfg.to_account_info(),
+    };
+    let seeds = &[crate::CONFIG_SEED, &[cfg.bump]];
+    let signer = &[&seeds[..]];
+    token::set_authority(
+        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer),
+        AuthorityType::MintTokens,
+        new_authority,
+    )?;
+
+    match new_authority {
+        Some(a) => msg!("TIMLG mint authority moved to {}", a),
+        None => msg!("WARNING: TIMLG minting disabled permanently; claims refund stake only"),
+    }
+    Ok(())
+}
+
+pub fn set_min_reveal_after_pulse_slots(
+    ctx: Context<SetMinRevealAfterPulseSlots>,
+    min_reveal_after_pulse_slots: u64,
+) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    cfg.min_reveal_after_pulse_slots = min_reveal_after_pulse_slots;
+    Ok(())
+}
+
 pub fn update_windows(
     ctx: Context<UpdateWindows>,
     commit_window_slots: u64,
@@ -432,11 +1196,52 @@ pub fn withdraw_treasury_tokens(ctx: Context<WithdrawTreasuryTokens>, amount: u6
     let cfg = &ctx.accounts.config;
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
 
-    let transfer_amount = if amount == 0 {
-        ctx.accounts.source_vault.amount
-    } else {
-        amount
-    };
+    withdraw_config_owned_tokens(
+        cfg,
+        &ctx.accounts.source_vault,
+        &ctx.accounts.admin_ata,
+        &ctx.accounts.token_program,
+        amount,
+    )
+}
+
+/// Drains the `reward_fee_pool` (fee share of claims) into `destination`. `amount == 0` = everything.
+pub fn withdraw_reward_fee_pool(ctx: Context<WithdrawRewardFeePool>, amount: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    withdraw_config_owned_tokens(
+        cfg,
+        &ctx.accounts.reward_fee_pool,
+        &ctx.accounts.destination,
+        &ctx.accounts.token_program,
+        amount,
+    )
+}
+
+/// Drains the `replication_pool` (slashed stakes + fee share) into `destination`. `amount == 0` = everything.
+pub fn withdraw_replication_pool(ctx: Context<WithdrawReplicationPool>, amount: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    withdraw_config_owned_tokens(
+        cfg,
+        &ctx.accounts.replication_pool,
+        &ctx.accounts.destination,
+        &ctx.accounts.token_program,
+        amount,
+    )
+}
+
+/// Moves `amount` (0 = full balance) out of a token account whose authority is the config PDA.
+fn withdraw_config_owned_tokens<'info>(
+    cfg: &Account<'info, Config>,
+    source: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let transfer_amount = if amount == 0 { source.amount } else { amount };
 
     if transfer_amount == 0 {
         return Ok(());
@@ -450,10 +1255,10 @@ pub fn withdraw_treasury_tokens(ctx: Context<WithdrawTreasuryTokens>, amount: u6
 
     token::transfer(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            token_program.to_account_info(),
             token::Transfer {
-                from: ctx.accounts.source_vault.to_account_info(),
-                to: ctx.accounts.admin_ata.to_account_info(),
+                from: source.to_account_info(),
+                to: destination.to_account_info(),
                 authority: cfg.to_account_info(),
             },
             signer,
@@ -464,7 +1269,14 @@ pub fn withdraw_treasury_tokens(ctx: Context<WithdrawTreasuryTokens>, amount: u6
     Ok(())
 }
 
-pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+/// Reallocs Config to the current layout and seeds fields the old one lacked.
+/// `rescue_authority` / `rescue_destination` bootstrap the rescue key once, on configs
+/// that predate it; they are ignored when it is already set.
+pub fn migrate_config(
+    ctx: Context<MigrateConfig>,
+    rescue_authority: Pubkey,
+    rescue_destination: Pubkey,
+) -> Result<()> {
     let config_info = ctx.accounts.config.to_account_info();
     
     // 1. Minimum authorization check (since we can't deserialize the whole struct yet)
@@ -477,6 +1289,7 @@ pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
     if admin_on_chain != ctx.accounts.admin.key() {
         return Err(TimlgError::Unauthorized.into());
     }
+    let old_size = data.len();
     drop(data);
 
     // 2. Calculate new size and rent
@@ -503,12 +1316,311 @@ pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
 
     // 4. Resize
     config_info.resize(new_size)?; // Reverted to realloc since resize isn't always stable on old anchors
-    
+    if old_size < new_size {
+        // appended counters (`total_committed`, ...) start from zero
+        config_info.try_borrow_mut_data()?[old_size..].fill(0);
+    }
+
+    // 5. Seed defaults for fields the old layout did not have
+    let mut data = config_info.try_borrow_mut_data()?;
+    let mut cfg = Config::try_deserialize(&mut &data[..])?;
+    if old_size <= CONFIG_PRE_EXTENSIONS_SPACE {
+        // zero would mean "no extensions", turning every auto-extended late pulse
+        // into MaxExtensionsReached
+        cfg.max_extensions = DEFAULT_MAX_EXTENSIONS;
+    }
+    // one-time rescue bootstrap; afterwards only the rescue authority rotates it
+    if cfg.rescue_authority == Pubkey::default() {
+        require_valid_rescue_authority(&cfg.admin, &rescue_authority)?;
+        cfg.rescue_authority = rescue_authority;
+        cfg.rescue_destination = rescue_destination;
+    }
+    cfg.try_serialize(&mut &mut data[..])?;
+    drop(data);
+
     msg!("Config migrated to size: {}", new_size);
 
     Ok(())
 }
 
+/// Reallocs a Round created by an older build to the current `Round::INIT_SPACE`.
+/// Fields appended since then (settlement counters, caps, ...) are zero-initialized.
+pub fn migrate_round(ctx: Context<MigrateRound>, round_id: u64) -> Result<()> {
+    // 1. Authorization via the on-chain admin offset (Config may itself be pre-migration)
+    {
+        let data = ctx.accounts.config.try_borrow_data()?;
+        if data.len() < 40 {
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+        let admin_on_chain = Pubkey::new_from_array(data[8..40].try_into().unwrap());
+        require_keys_eq!(admin_on_chain, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    }
+
+    let round_info = ctx.accounts.round.to_account_info();
+    require!(round_info.owner == ctx.program_id, TimlgError::Unauthorized);
+
+    // round_id lives right after the discriminator in every layout
+    let old_size = {
+        let data = round_info.try_borrow_data()?;
+        require!(data.len() >= 16, TimlgError::TicketPdaMismatch);
+        let stored_id = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        require!(stored_id == round_id, TimlgError::TicketPdaMismatch);
+        data.len()
+    };
+
+    let new_size = Round::INIT_SPACE + 8;
+    if old_size >= new_size {
+        msg!("Round {} already at size: {}", round_id, old_size);
+        return Ok(());
+    }
+
+    // 2. Fund the rent difference from admin
+    let rent = Rent::get()?;
+    let lamports_diff = rent.minimum_balance(new_size).saturating_sub(round_info.lamports());
+    if lamports_diff > 0 {
+        invoke(
+            &system_instruction::transfer(&ctx.accounts.admin.key(), &round_info.key(), lamports_diff),
+            &[
+                ctx.accounts.admin.to_account_info(),
+                round_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    // 3. Resize and zero the new tail explicitly
+    round_info.resize(new_size)?;
+    round_info.try_borrow_mut_data()?[old_size..].fill(0);
+
+    msg!("Round {} migrated from {} to size: {}", round_id, old_size, new_size);
+
+    Ok(())
+}
+
+/// Reallocs a ticket created before `Ticket.weight` or the recorded service fee so
+/// `Account<Ticket>` paths (reveal, claim_reward, recover_funds, close_ticket) can load it.
+/// The new bytes read as weight 0 (1 stake unit) and no fee paid, so nothing is refunded.
+/// Batch paths read legacy tickets without migrating.
+pub fn migrate_ticket(ctx: Context<MigrateTicket>) -> Result<()> {
+    let ticket_info = ctx.accounts.ticket.to_account_info();
+    require!(ticket_info.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+
+    let old_size = {
+        let data = ticket_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *Ticket::DISCRIMINATOR,
+            TimlgError::TicketPdaMismatch
+        );
+        data.len()
+    };
+
+    let new_size = Ticket::INIT_SPACE + 8;
+    if old_size >= new_size {
+        msg!("Ticket already at size: {}", old_size);
+        return Ok(());
+    }
+    require!(
+        old_size == TICKET_LEGACY_SPACE || old_size == TICKET_PRE_FEE_SPACE,
+        TimlgError::TicketSizeMismatch
+    );
+
+    let rent = Rent::get()?;
+    let lamports_diff = rent.minimum_balance(new_size).saturating_sub(ticket_info.lamports());
+    if lamports_diff > 0 {
+        invoke(
+            &system_instruction::transfer(&ctx.accounts.payer.key(), &ticket_info.key(), lamports_diff),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ticket_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    ticket_info.resize(new_size)?;
+    ticket_info.try_borrow_mut_data()?[old_size..].fill(0);
+
+    msg!("Ticket migrated from {} to size: {}", old_size, new_size);
+
+    Ok(())
+}
+
+/// Reallocs an escrow created before `UserEscrow.last_reveal_slot` (8 bytes shorter) so
+/// `withdraw_escrow`, `close_user_escrow` and escrow-funded commits can load it again.
+/// The new field starts at 0 (= never revealed).
+pub fn migrate_user_escrow(ctx: Context<MigrateUserEscrow>) -> Result<()> {
+    let escrow_info = ctx.accounts.user_escrow.to_account_info();
+    require!(escrow_info.owner == ctx.program_id, TimlgError::Unauthorized);
+
+    let old_size = {
+        let data = escrow_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == *UserEscrow::DISCRIMINATOR,
+            TimlgError::EscrowPdaMismatch
+        );
+        data.len()
+    };
+
+    let new_size = UserEscrow::INIT_SPACE + 8;
+    if old_size >= new_size {
+        msg!("UserEscrow already at size: {}", old_size);
+        return Ok(());
+    }
+    require!(old_size == new_size - 8, TimlgError::EscrowSizeMismatch);
+
+    let rent = Rent::get()?;
+    let lamports_diff = rent.minimum_balance(new_size).saturating_sub(escrow_info.lamports());
+    if lamports_diff > 0 {
+        invoke(
+            &system_instruction::transfer(&ctx.accounts.payer.key(), &escrow_info.key(), lamports_diff),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                escrow_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    escrow_info.resize(new_size)?;
+    escrow_info.try_borrow_mut_data()?[old_size..].fill(0);
+
+    msg!("UserEscrow migrated from {} to size: {}", old_size, new_size);
+
+    Ok(())
+}
+
+
+pub fn set_burn_delay(ctx: Context<SetBurnDelay>, burn_delay_slots: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    ctx.accounts.tokenomics.burn_delay_slots = burn_delay_slots;
+    Ok(())
+}
+
+pub fn set_streak_bonus_bps(ctx: Context<SetStreakBonusBps>, streak_bonus_bps: u16) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    require!(streak_bonus_bps <= 10_000, TimlgError::InvalidBps);
+
+    ctx.accounts.tokenomics.streak_bonus_bps = streak_bonus_bps;
+    Ok(())
+}
+
+/// Toggles fee prefunding: `settle_round_tokens` mints the fees of the round's unclaimed
+/// winners into the fee pools once, and later claims skip their own fee mint.
+/// Settle skips the prefund while `config.fee_exempt` is non-empty: exempt winners owe no fee.
+/// Rounds with a `max_total_reward` are never prefunded: capped claims owe no fee either.
+pub fn set_prefund_fees(ctx: Context<SetPrefundFees>, prefund_fees: bool) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    ctx.accounts.tokenomics.prefund_fees = prefund_fees;
+    Ok(())
+}
+
+/// Creates (or just re-validates) one Tokenomics pool and records its address/bump.
+/// Idempotent: lets pools introduced by later features be added without
+/// re-initializing Tokenomics. Run `migrate_tokenomics` first on old accounts.
+pub fn initialize_additional_pool(ctx: Context<InitializeAdditionalPool>, pool_type: u8) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let pool = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool;
+    let tok = &mut ctx.accounts.tokenomics;
+
+    match pool_type {
+        t if t == PoolType::RewardFee as u8 => {
+            tok.reward_fee_pool = pool;
+            tok.reward_fee_pool_bump = bump;
+        }
+        t if t == PoolType::Replication as u8 => {
+            tok.replication_pool = pool;
+            tok.replication_pool_bump = bump;
+        }
+        t if t == PoolType::Jackpot as u8 => {
+            tok.jackpot_pool = pool;
+            tok.jackpot_pool_bump = bump;
+        }
+        t if t == PoolType::RewardReserve as u8 => {
+            tok.reward_reserve_pool = pool;
+            tok.reward_reserve_pool_bump = bump;
+        }
+        _ => return err!(TimlgError::InvalidPoolType),
+    }
+
+    Ok(())
+}
+
+/// Resizes the Tokenomics PDA to the current `Tokenomics::INIT_SPACE` (same approach as `migrate_config`).
+pub fn migrate_tokenomics(ctx: Context<MigrateTokenomics>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let tok_info = ctx.accounts.tokenomics.to_account_info();
+    require!(tok_info.owner == ctx.program_id, TimlgError::Unauthorized);
+
+    let new_size = Tokenomics::INIT_SPACE + 8;
+    let rent = Rent::get()?;
+    let lamports_diff = rent.minimum_balance(new_size).saturating_sub(tok_info.lamports());
+
+    if lamports_diff > 0 {
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.admin.key(),
+                &tok_info.key(),
+                lamports_diff,
+            ),
+            &[
+                ctx.accounts.admin.to_account_info(),
+                tok_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    // New trailing fields are zero-filled: unrevealed_policy = Burn, penalty = 0.
+    tok_info.resize(new_size)?;
+
+    msg!("Tokenomics migrated to size: {}", new_size);
+
+    Ok(())
+}
+
+/// Grows a pre-`max_open_rounds` registry; the new fields start at 0 (no cap, none counted).
+pub fn migrate_round_registry(ctx: Context<MigrateRoundRegistry>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let rr_info = ctx.accounts.round_registry.to_account_info();
+    require!(rr_info.owner == ctx.program_id, TimlgError::Unauthorized);
+
+    let new_size = RoundRegistry::INIT_SPACE + 8;
+    let rent = Rent::get()?;
+    let lamports_diff = rent.minimum_balance(new_size).saturating_sub(rr_info.lamports());
+
+    if lamports_diff > 0 {
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.admin.key(),
+                &rr_info.key(),
+                lamports_diff,
+            ),
+            &[
+                ctx.accounts.admin.to_account_info(),
+                rr_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    rr_info.resize(new_size)?;
+
+    msg!("RoundRegistry migrated to size: {}", new_size);
+
+    Ok(())
+}
 
 pub fn initialize_global_stats(ctx: Context<InitializeGlobalStats>) -> Result<()> {
     let cfg = &ctx.accounts.config;
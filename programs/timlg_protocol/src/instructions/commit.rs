@@ -1,19 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{program::invoke_signed, system_instruction};
-use anchor_lang::solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked,
-};
+use anchor_lang::solana_program::program_option::COption;
 use anchor_spl::token::{self, Transfer};
 
 use crate::{
     errors::TimlgError,
-    state::Ticket,
+    events::CommitMade,
+    state::{Ticket, UserEscrow, UserRoundStats, UserStats},
     utils::{
-        derive_bit_index, expected_commit_msg, init_user_stats_if_needed,
-        parse_ed25519_ix_pubkey_and_msg, CommitEntry, CommitSignedEntry, MAX_BATCH,
-        TICKET_SEED,
+        book_service_fee, derive_bit_index_for, ed25519_ixs_before_current, ed25519_sigs, require_commit_consent, freeze_commits_if_full,
+        guess_bits, init_user_stats_if_needed, record_user_round_commits, require_commit_capacity, require_commit_open, require_spl_stake, require_unique_nonces,
+        require_valid_guess, require_valid_weight, rent_in_timlg, sol_service_fee_for, CommitEntry, CommitSignedEntry,
+        WeightedCommitEntry, MAX_BATCH, TICKET_SEED, USER_ESCROW_SEED, USER_ESCROW_VAULT_SEED, USER_ROUND_SEED,
+        USER_STATS_SEED,
     },
-    CommitBatch, CommitBatchSigned, CommitTicket,
+    CommitBatch, CommitBatchSigned, CommitBatchSignedMulti, CommitTicket, CommitTicketDelegated,
+    CommitTicketSigned,
 };
 
 pub fn commit_ticket(
@@ -21,61 +23,280 @@ pub fn commit_ticket(
     round_id: u64,
     nonce: u64,
     commitment: [u8; 32],
+) -> Result<()> {
+    require!(commitment != [0u8; 32], TimlgError::InvalidCommitment);
+    commit_ticket_inner(ctx, round_id, nonce, commitment, None, 1)
+}
+
+/// Same as `commit_ticket`, staking `weight` times `stake_amount` (up to `config.max_weight`).
+/// The refund, reward and any slash of the ticket scale by the same weight.
+pub fn commit_ticket_weighted(
+    ctx: Context<CommitTicket>,
+    round_id: u64,
+    nonce: u64,
+    commitment: [u8; 32],
+    weight: u8,
+) -> Result<()> {
+    require!(commitment != [0u8; 32], TimlgError::InvalidCommitment);
+    commit_ticket_inner(ctx, round_id, nonce, commitment, None, weight)
+}
+
+/// Public-guess rounds: the guess is stored in clear (no commitment/salt) and
+/// resolved against the pulse at finalize/settle instead of being revealed.
+pub fn commit_ticket_public(
+    ctx: Context<CommitTicket>,
+    round_id: u64,
+    nonce: u64,
+    guess: u8,
+) -> Result<()> {
+    commit_ticket_inner(ctx, round_id, nonce, [0u8; 32], Some(guess), 1)
+}
+
+fn commit_ticket_inner(
+    ctx: Context<CommitTicket>,
+    round_id: u64,
+    nonce: u64,
+    commitment: [u8; 32],
+    public_guess: Option<u8>,
+    weight: u8,
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
+    require_valid_weight(cfg, weight)?;
 
     let round = &mut ctx.accounts.round;
+    require!(!round.paused, TimlgError::RoundPaused);
     require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(round.public_mode == public_guess.is_some(), TimlgError::RoundModeMismatch);
+    if let Some(g) = public_guess {
+        require_valid_guess(round, g)?;
+    }
     require!(!round.pulse_set, TimlgError::CommitAfterPulseSet);
 
     let current_slot = Clock::get()?.slot;
-    require!(current_slot <= round.commit_deadline_slot, TimlgError::CommitClosed);
+    require_commit_open(round, current_slot)?;
+    require_commit_capacity(round, 1)?;
+    record_user_round_commits(
+        &mut ctx.accounts.user_round_stats,
+        round_id,
+        ctx.accounts.user.key(),
+        ctx.bumps.user_round_stats,
+        1,
+        cfg.max_tickets_per_user,
+    )?;
+
+    let stake = cfg
+        .stake_amount
+        .checked_mul(weight as u64)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    if round.stake_in_sol {
+        // --- TRANSFER stake to the SOL vault (1 ticket) ---
+        let ix = system_instruction::transfer(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.vault.key(),
+            stake,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    } else {
+        // --- TRANSFER stake to timlg_vault (1 ticket) ---
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_timlg_ata.to_account_info(),
+                    to: ctx.accounts.timlg_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            stake,
+        )?;
+    }
+
+    // --- TRANSFER SOL service fee to treasury_sol ---
+    let sol_fee = sol_service_fee_for(cfg, &ctx.accounts.user.key());
+    if sol_fee > 0 {
+        let ix = system_instruction::transfer(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.treasury_sol.key(),
+            sol_fee,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.treasury_sol.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
 
-    // --- TRANSFER stake to timlg_vault (1 ticket) ---
+    init_user_stats_if_needed(
+        &mut ctx.accounts.user_stats,
+        ctx.accounts.user.key(),
+        ctx.bumps.user_stats,
+        current_slot,
+    )?;
+    let user_stats = &mut ctx.accounts.user_stats;
+    
+    // Asignamos índice a este ticket
+    let user_commit_index = user_stats.games_played.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    user_stats.games_played = user_commit_index;
+
+    // --- ticket ---
+    let user_pk = ctx.accounts.user.key();
+    let ticket = &mut ctx.accounts.ticket;
+
+    ticket.round_id = round.round_id;
+    ticket.user = user_pk;
+    ticket.nonce = nonce;
+    ticket.bump = ctx.bumps.ticket;
+
+    ticket.commitment = commitment;
+    ticket.stake_paid = true;
+    ticket.stake_slashed = false;
+    ticket.processed = false;
+
+    ticket.revealed = false;
+    ticket.guess = public_guess.unwrap_or(0);
+    ticket.win = false;
+
+    ticket.bit_index = derive_bit_index_for(round_id, &user_pk, nonce, guess_bits(round));
+
+    ticket.claimed = false;
+    ticket.claimed_slot = 0;
+
+    ticket.created_slot = current_slot;
+    ticket.revealed_slot = 0;
+
+    ticket.user_commit_index = user_commit_index;
+    ticket.weight = weight;
+    ticket.sol_fee_paid = sol_fee;
+    ticket.fee_payer = user_pk;
+
+    emit!(CommitMade {
+        round_id,
+        user: user_pk,
+        nonce,
+        bit_index: ticket.bit_index,
+    });
+
+    // counters
+    round.committed_count = round
+        .committed_count
+        .checked_add(1)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    freeze_commits_if_full(round, current_slot);
+
+    // global stats
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_tickets = gs.total_tickets.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    gs.total_sol_fees = gs.total_sol_fees.checked_add(sol_fee).ok_or(TimlgError::MathOverflow)?;
+    // refundable until finalize
+    book_service_fee(&mut ctx.accounts.config, round, sol_fee)?;
+
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_committed = cfg.total_committed.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Commit paid through an SPL delegate instead of the user's signature.
+/// The user must have approved this round's PDA as delegate on `user_timlg_ata` for
+/// at least `stake_amount`. A config-wide approval is not accepted: it would be
+/// spendable in any round.
+pub fn commit_ticket_delegated(
+    ctx: Context<CommitTicketDelegated>,
+    round_id: u64,
+    nonce: u64,
+    commitment: [u8; 32],
+) -> Result<()> {
+    require!(commitment != [0u8; 32], TimlgError::InvalidCommitment);
+
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+
+    let round = &ctx.accounts.round;
+    require!(!round.paused, TimlgError::RoundPaused);
+    require_spl_stake(round)?;
+    require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(!round.public_mode, TimlgError::RoundModeMismatch);
+    require!(!round.pulse_set, TimlgError::CommitAfterPulseSet);
+
+    let current_slot = Clock::get()?.slot;
+    require_commit_open(round, current_slot)?;
+    require_commit_capacity(round, 1)?;
+    record_user_round_commits(
+        &mut ctx.accounts.user_round_stats,
+        round_id,
+        ctx.accounts.user.key(),
+        ctx.bumps.user_round_stats,
+        1,
+        cfg.max_tickets_per_user,
+    )?;
+
+    let ata = &ctx.accounts.user_timlg_ata;
+    require!(ata.delegated_amount >= cfg.stake_amount, TimlgError::InsufficientDelegation);
+
+    require!(ata.delegate == COption::Some(round.key()), TimlgError::InvalidDelegate);
+
+    let round_le = round_id.to_le_bytes();
+    let round_seeds: &[&[u8]] = &[crate::ROUND_SEED, &round_le, &[round.bump]];
+    let signer_seeds = [round_seeds];
+
+    // --- TRANSFER stake to timlg_vault via delegate ---
     token::transfer(
-        CpiContext::new(
+        CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.user_timlg_ata.to_account_info(),
                 to: ctx.accounts.timlg_vault.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+                authority: round.to_account_info(),
             },
+            &signer_seeds,
         ),
         cfg.stake_amount,
     )?;
 
-    // --- TRANSFER SOL service fee to treasury_sol ---
-    if cfg.sol_service_fee_lamports > 0 {
+    // --- TRANSFER SOL service fee to treasury_sol (paid by payer) ---
+    let sol_fee = sol_service_fee_for(cfg, &ctx.accounts.user.key());
+    if sol_fee > 0 {
         let ix = system_instruction::transfer(
-            &ctx.accounts.user.key(),
+            &ctx.accounts.payer.key(),
             &ctx.accounts.treasury_sol.key(),
-            cfg.sol_service_fee_lamports,
+            sol_fee,
         );
         anchor_lang::solana_program::program::invoke(
             &ix,
             &[
-                ctx.accounts.user.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
                 ctx.accounts.treasury_sol.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
     }
 
+    let user_pk = ctx.accounts.user.key();
     init_user_stats_if_needed(
         &mut ctx.accounts.user_stats,
-        ctx.accounts.user.key(),
+        user_pk,
         ctx.bumps.user_stats,
         current_slot,
     )?;
     let user_stats = &mut ctx.accounts.user_stats;
-    
-    // Asignamos índice a este ticket
+
     let user_commit_index = user_stats.games_played.checked_add(1).ok_or(TimlgError::MathOverflow)?;
     user_stats.games_played = user_commit_index;
 
     // --- ticket ---
-    let user_pk = ctx.accounts.user.key();
+    let round = &mut ctx.accounts.round;
     let ticket = &mut ctx.accounts.ticket;
 
     ticket.round_id = round.round_id;
@@ -92,7 +313,7 @@ pub fn commit_ticket(
     ticket.guess = 0;
     ticket.win = false;
 
-    ticket.bit_index = derive_bit_index(round_id, &user_pk, nonce);
+    ticket.bit_index = derive_bit_index_for(round_id, &user_pk, nonce, guess_bits(round));
 
     ticket.claimed = false;
     ticket.claimed_slot = 0;
@@ -101,17 +322,34 @@ pub fn commit_ticket(
     ticket.revealed_slot = 0;
 
     ticket.user_commit_index = user_commit_index;
+    ticket.weight = 1;
+    ticket.sol_fee_paid = sol_fee;
+    ticket.fee_payer = ctx.accounts.payer.key();
+
+    emit!(CommitMade {
+        round_id,
+        user: user_pk,
+        nonce,
+        bit_index: ticket.bit_index,
+    });
 
     // counters
     round.committed_count = round
         .committed_count
         .checked_add(1)
         .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    freeze_commits_if_full(round, current_slot);
 
     // global stats
     let gs = &mut ctx.accounts.global_stats;
     gs.total_tickets = gs.total_tickets.checked_add(1).ok_or(TimlgError::MathOverflow)?;
-    gs.total_sol_fees = gs.total_sol_fees.checked_add(cfg.sol_service_fee_lamports).ok_or(TimlgError::MathOverflow)?;
+    gs.total_sol_fees = gs.total_sol_fees.checked_add(sol_fee).ok_or(TimlgError::MathOverflow)?;
+    // refundable until finalize
+    book_service_fee(&mut ctx.accounts.config, round, sol_fee)?;
+
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_committed = cfg.total_committed.checked_add(1).ok_or(TimlgError::MathOverflow)?;
 
     Ok(())
 }
@@ -120,25 +358,68 @@ pub fn commit_batch<'info>(
     ctx: Context<'_, '_, 'info, 'info, CommitBatch<'info>>,
     round_id: u64,
     entries: Vec<CommitEntry>,
+) -> Result<()> {
+    let entries = entries
+        .into_iter()
+        .map(|e| WeightedCommitEntry { nonce: e.nonce, commitment: e.commitment, weight: 1 })
+        .collect();
+    commit_batch_inner(ctx, round_id, entries)
+}
+
+/// `commit_batch` with a per-entry stake multiple (up to `config.max_weight`).
+pub fn commit_batch_weighted<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CommitBatch<'info>>,
+    round_id: u64,
+    entries: Vec<WeightedCommitEntry>,
+) -> Result<()> {
+    commit_batch_inner(ctx, round_id, entries)
+}
+
+fn commit_batch_inner<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CommitBatch<'info>>,
+    round_id: u64,
+    entries: Vec<WeightedCommitEntry>,
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
+    for e in entries.iter() {
+        require_valid_weight(cfg, e.weight)?;
+    }
 
     require!(entries.len() <= MAX_BATCH, TimlgError::TooManyEntries);
     require!(ctx.remaining_accounts.len() == entries.len(), TimlgError::TicketPdaMismatch);
+    require_unique_nonces(&entries.iter().map(|e| e.nonce).collect::<Vec<_>>())?;
+    require!(
+        entries.iter().all(|e| e.commitment != [0u8; 32]),
+        TimlgError::InvalidCommitment
+    );
 
     let round = &mut ctx.accounts.round;
+    require!(!round.paused, TimlgError::RoundPaused);
+    require_spl_stake(round)?;
     require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(!round.public_mode, TimlgError::RoundModeMismatch);
+    // Pulse before deadline: a set pulse implies the deadline passed, so report the more specific cause.
     require!(!round.pulse_set, TimlgError::CommitAfterPulseSet);
 
     let current_slot = Clock::get()?.slot;
-    require!(current_slot <= round.commit_deadline_slot, TimlgError::CommitClosed);
+    require_commit_open(round, current_slot)?;
+    require_commit_capacity(round, entries.len() as u64)?;
+    record_user_round_commits(
+        &mut ctx.accounts.user_round_stats,
+        round_id,
+        ctx.accounts.user.key(),
+        ctx.bumps.user_round_stats,
+        entries.len() as u64,
+        cfg.max_tickets_per_user,
+    )?;
 
     // --- TRANSFER stake (batch) ---
     let n = entries.len() as u64;
+    let units: u64 = entries.iter().map(|e| e.weight as u64).sum();
     let total = cfg
         .stake_amount
-        .checked_mul(n)
+        .checked_mul(units)
         .ok_or_else(|| error!(TimlgError::MathOverflow))?;
 
     token::transfer(
@@ -154,11 +435,11 @@ pub fn commit_batch<'info>(
     )?;
 
     // --- TRANSFER SOL service fee (batch) ---
-    if cfg.sol_service_fee_lamports > 0 {
-        let total_sol_fee = cfg.sol_service_fee_lamports
-            .checked_mul(n)
-            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
-            
+    let sol_fee = sol_service_fee_for(cfg, &ctx.accounts.user.key());
+    let total_sol_fee = sol_fee
+        .checked_mul(n)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    if total_sol_fee > 0 {
         let ix = system_instruction::transfer(
             &ctx.accounts.user.key(),
             &ctx.accounts.treasury_sol.key(),
@@ -249,12 +530,15 @@ pub fn commit_batch<'info>(
             revealed: false,
             guess: 0,
             win: false,
-            bit_index: derive_bit_index(round_id, &user_pk, e.nonce),
+            bit_index: derive_bit_index_for(round_id, &user_pk, e.nonce, guess_bits(round)),
             claimed: false,
             claimed_slot: 0,
             created_slot: current_slot,
             revealed_slot: 0,
             user_commit_index: 0, // se actualiza abajo
+            weight: e.weight,
+            sol_fee_paid: sol_fee,
+            fee_payer: user_pk,
         };
 
         user_stats.games_played = user_stats.games_played.checked_add(1).ok_or(TimlgError::MathOverflow)?;
@@ -264,6 +548,13 @@ pub fn commit_batch<'info>(
         ticket
             .try_serialize(&mut w)
             .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
+
+        emit!(CommitMade {
+            round_id,
+            user: user_pk,
+            nonce: e.nonce,
+            bit_index: ticket.bit_index,
+        });
     }
 
     // counters
@@ -271,17 +562,18 @@ pub fn commit_batch<'info>(
         .committed_count
         .checked_add(n)
         .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    freeze_commits_if_full(round, current_slot);
 
     // global stats
-    let total_sol_fee = if cfg.sol_service_fee_lamports > 0 {
-        cfg.sol_service_fee_lamports.checked_mul(n).ok_or(TimlgError::MathOverflow)?
-    } else {
-        0
-    };
-
     let gs = &mut ctx.accounts.global_stats;
     gs.total_tickets = gs.total_tickets.checked_add(n).ok_or(TimlgError::MathOverflow)?;
     gs.total_sol_fees = gs.total_sol_fees.checked_add(total_sol_fee).ok_or(TimlgError::MathOverflow)?;
+    // refundable until finalize
+    book_service_fee(&mut ctx.accounts.config, round, total_sol_fee)?;
+
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_committed = cfg.total_committed.checked_add(n).ok_or(TimlgError::MathOverflow)?;
 
     Ok(())
 }
@@ -290,19 +582,44 @@ pub fn commit_batch_signed<'info>(
     ctx: Context<'_, '_, 'info, 'info, CommitBatchSigned<'info>>,
     round_id: u64,
     entries: Vec<CommitSignedEntry>,
+    max_land_slot: Option<u64>,
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
 
+    // Relayer-side staleness bound: fail before any work or transfer. The commit
+    // deadline below stays the authoritative gate.
+    let current_slot = Clock::get()?.slot;
+    if let Some(max_land_slot) = max_land_slot {
+        require!(current_slot <= max_land_slot, TimlgError::CommitLandedTooLate);
+    }
+
     require!(entries.len() <= MAX_BATCH, TimlgError::TooManyEntries);
     require!(ctx.remaining_accounts.len() == entries.len(), TimlgError::TicketPdaMismatch);
+    require_unique_nonces(&entries.iter().map(|e| e.nonce).collect::<Vec<_>>())?;
+    require!(
+        entries.iter().all(|e| e.commitment != [0u8; 32]),
+        TimlgError::InvalidCommitment
+    );
 
     let round = &mut ctx.accounts.round;
+    require!(!round.paused, TimlgError::RoundPaused);
+    require_spl_stake(round)?;
     require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(!round.public_mode, TimlgError::RoundModeMismatch);
+    // Pulse before deadline: a set pulse implies the deadline passed, so report the more specific cause.
     require!(!round.pulse_set, TimlgError::CommitAfterPulseSet);
 
-    let current_slot = Clock::get()?.slot;
-    require!(current_slot <= round.commit_deadline_slot, TimlgError::CommitClosed);
+    require_commit_open(round, current_slot)?;
+    require_commit_capacity(round, entries.len() as u64)?;
+    record_user_round_commits(
+        &mut ctx.accounts.user_round_stats,
+        round_id,
+        ctx.accounts.user.key(),
+        ctx.bumps.user_round_stats,
+        entries.len() as u64,
+        cfg.max_tickets_per_user,
+    )?;
 
     // --- signed batch must be for a single user (ctx.accounts.user) ---
     let user_pk = ctx.accounts.user.key();
@@ -310,22 +627,21 @@ pub fn commit_batch_signed<'info>(
         require_keys_eq!(e.user, user_pk, TimlgError::SignedBatchMixedUsers);
     }
 
-    // --- ed25519 introspection: expects N ed25519 verify ix immediately before this ix ---
-    let ix_sys = ctx.accounts.instructions.to_account_info();
-    let current_ix = load_current_index_checked(&ix_sys)? as usize;
-    require!(current_ix >= entries.len(), TimlgError::MissingOrInvalidEd25519Ix);
-    let first_ed_ix = current_ix - entries.len();
-
-    for (i, e) in entries.iter().enumerate() {
-        let ix = load_instruction_at_checked(first_ed_ix + i, &ix_sys)
-            .map_err(|_| error!(TimlgError::MissingOrInvalidEd25519Ix))?;
+    // --- relayer rent reimbursement (TIMLG per ticket), priced into the signed `commit_v3` ---
+    let space = 8 + Ticket::INIT_SPACE;
+    let lamports = Rent::get()?.minimum_balance(space);
+    let rent_timlg = if cfg.rent_reimburse {
+        rent_in_timlg(lamports, cfg.rent_price_timlg).ok_or(TimlgError::MathOverflow)?
+    } else {
+        0
+    };
 
-        let (pk, msg) = parse_ed25519_ix_pubkey_and_msg(&ix)?;
-        require_keys_eq!(pk, e.user, TimlgError::Ed25519PubkeyMismatch);
+    // --- ed25519 introspection: one signature per entry, from one or more verify ixs before this ix ---
+    let ed25519_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+    let sigs = ed25519_sigs(&ed25519_ixs);
 
-        let expected =
-            expected_commit_msg(ctx.program_id, round_id, &e.user, e.nonce, &e.commitment);
-        require!(msg == expected, TimlgError::Ed25519MessageMismatch);
+    for e in entries.iter() {
+        require_commit_consent(&sigs, cfg, ctx.program_id, round, &e.user, e.nonce, &e.commitment, rent_timlg)?;
     }
 
     // --- PRECHECK: validate PDAs + reject replay BEFORE moving funds ---
@@ -371,12 +687,38 @@ pub fn commit_batch_signed<'info>(
         total,
     )?;
 
-    // --- TRANSFER SOL service fee from payer -> treasury_sol (batch signed) ---
-    if cfg.sol_service_fee_lamports > 0 {
-        let total_sol_fee = cfg.sol_service_fee_lamports
-            .checked_mul(n)
-            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    // --- TRANSFER rent reimbursement from escrow -> relayer ---
+    if rent_timlg > 0 {
+        let relayer_ata = ctx
+            .accounts
+            .relayer_timlg_ata
+            .as_ref()
+            .ok_or(TimlgError::RelayerAtaRequired)?;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_escrow_ata.to_account_info(),
+                    to: relayer_ata.to_account_info(),
+                    authority: ctx.accounts.user_escrow.to_account_info(),
+                },
+            )
+            .with_signer(&[&[
+                crate::USER_ESCROW_SEED,
+                user_pk.as_ref(),
+                &[ctx.accounts.user_escrow.bump],
+            ]]),
+            rent_timlg.checked_mul(n).ok_or(TimlgError::MathOverflow)?,
+        )?;
+    }
 
+    // --- TRANSFER SOL service fee from payer -> treasury_sol (batch signed) ---
+    // Exemption is keyed on the signed entry user, not the relayer/payer.
+    let sol_fee = sol_service_fee_for(cfg, &user_pk);
+    let total_sol_fee = sol_fee
+        .checked_mul(n)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    if total_sol_fee > 0 {
         let ix = system_instruction::transfer(
             &ctx.accounts.payer.key(),
             &ctx.accounts.treasury_sol.key(),
@@ -395,7 +737,7 @@ pub fn commit_batch_signed<'info>(
     // --- UserStats ---
     let user_stats = &mut ctx.accounts.user_stats;
     user_stats.user = user_pk;
-    user_stats.bump = user_stats.bump;
+    user_stats.bump = ctx.bumps.user_stats;
 
     if user_stats.last_reset_slot == 0 {
         user_stats.last_reset_slot = current_slot;
@@ -403,8 +745,6 @@ pub fn commit_batch_signed<'info>(
 
     // --- create ticket PDA accounts (payer = relayer/payer) ---
     let payer_pk = ctx.accounts.payer.key();
-    let space = 8 + Ticket::INIT_SPACE;
-    let lamports = Rent::get()?.minimum_balance(space);
 
     for (i, e) in entries.iter().enumerate() {
         let ticket_ai = ctx.remaining_accounts[i].clone();
@@ -464,12 +804,15 @@ pub fn commit_batch_signed<'info>(
             revealed: false,
             guess: 0,
             win: false,
-            bit_index: derive_bit_index(round_id, &user_pk, e.nonce),
+            bit_index: derive_bit_index_for(round_id, &user_pk, e.nonce, guess_bits(round)),
             claimed: false,
             claimed_slot: 0,
             created_slot: current_slot,
             revealed_slot: 0,
             user_commit_index: 0, // se actualiza abajo
+            weight: 1,
+            sol_fee_paid: sol_fee,
+            fee_payer: payer_pk,
         };
 
         user_stats.games_played = user_stats.games_played.checked_add(1).ok_or(TimlgError::MathOverflow)?;
@@ -479,23 +822,507 @@ pub fn commit_batch_signed<'info>(
         ticket
             .try_serialize(&mut w)
             .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
+
+        emit!(CommitMade {
+            round_id,
+            user: user_pk,
+            nonce: e.nonce,
+            bit_index: ticket.bit_index,
+        });
     }
 
     round.committed_count = round
         .committed_count
         .checked_add(n)
         .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    freeze_commits_if_full(round, current_slot);
 
     // global stats
-    let total_sol_fee = if cfg.sol_service_fee_lamports > 0 {
-        cfg.sol_service_fee_lamports.checked_mul(n).ok_or(TimlgError::MathOverflow)?
-    } else {
-        0
-    };
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_tickets = gs.total_tickets.checked_add(n).ok_or(TimlgError::MathOverflow)?;
+    gs.total_sol_fees = gs.total_sol_fees.checked_add(total_sol_fee).ok_or(TimlgError::MathOverflow)?;
+    // refundable until finalize
+    book_service_fee(&mut ctx.accounts.config, round, total_sol_fee)?;
+
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_committed = cfg.total_committed.checked_add(n).ok_or(TimlgError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Single-ticket `commit_batch_signed`: the relayer pays fees and ticket rent, the stake
+/// comes from `user_escrow_ata`, and `user` authorizes with one ed25519 commit signature
+/// (`commit_v3`, see `require_commit_consent`) anywhere before this ix.
+pub fn commit_ticket_signed(
+    ctx: Context<CommitTicketSigned>,
+    round_id: u64,
+    nonce: u64,
+    commitment: [u8; 32],
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    require!(commitment != [0u8; 32], TimlgError::InvalidCommitment);
+
+    let round = &mut ctx.accounts.round;
+    require!(!round.paused, TimlgError::RoundPaused);
+    require_spl_stake(round)?;
+    require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(!round.public_mode, TimlgError::RoundModeMismatch);
+    require!(!round.pulse_set, TimlgError::CommitAfterPulseSet);
+
+    let current_slot = Clock::get()?.slot;
+    require_commit_open(round, current_slot)?;
+    require_commit_capacity(round, 1)?;
+
+    let user_pk = ctx.accounts.user.key();
+    record_user_round_commits(
+        &mut ctx.accounts.user_round_stats,
+        round_id,
+        user_pk,
+        ctx.bumps.user_round_stats,
+        1,
+        cfg.max_tickets_per_user,
+    )?;
+
+    // --- ed25519 introspection ---
+    let ed25519_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+    let sigs = ed25519_sigs(&ed25519_ixs);
+    require_commit_consent(&sigs, cfg, ctx.program_id, round, &user_pk, nonce, &commitment, 0)?;
+
+    // --- TRANSFER stake from escrow -> timlg_vault ---
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_escrow_ata.to_account_info(),
+                to: ctx.accounts.timlg_vault.to_account_info(),
+                authority: ctx.accounts.user_escrow.to_account_info(),
+            },
+        )
+        .with_signer(&[&[
+            crate::USER_ESCROW_SEED,
+            user_pk.as_ref(),
+            &[ctx.accounts.user_escrow.bump],
+        ]]),
+        cfg.stake_amount,
+    )?;
+
+    // --- TRANSFER SOL service fee from payer -> treasury_sol ---
+    // Exemption is keyed on the signing user, not the relayer/payer.
+    let sol_fee = sol_service_fee_for(cfg, &user_pk);
+    if sol_fee > 0 {
+        let ix = system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.treasury_sol.key(),
+            sol_fee,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.treasury_sol.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    init_user_stats_if_needed(
+        &mut ctx.accounts.user_stats,
+        user_pk,
+        ctx.bumps.user_stats,
+        current_slot,
+    )?;
+    let user_stats = &mut ctx.accounts.user_stats;
+    let user_commit_index = user_stats.games_played.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    user_stats.games_played = user_commit_index;
+
+    // --- ticket ---
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.round_id = round_id;
+    ticket.user = user_pk;
+    ticket.nonce = nonce;
+    ticket.bump = ctx.bumps.ticket;
+    ticket.commitment = commitment;
+    ticket.stake_paid = true;
+    ticket.bit_index = derive_bit_index_for(round_id, &user_pk, nonce, guess_bits(round));
+    ticket.created_slot = current_slot;
+    ticket.user_commit_index = user_commit_index;
+    ticket.weight = 1;
+    ticket.sol_fee_paid = sol_fee;
+    ticket.fee_payer = ctx.accounts.payer.key();
+
+    emit!(CommitMade {
+        round_id,
+        user: user_pk,
+        nonce,
+        bit_index: ticket.bit_index,
+    });
+
+    round.committed_count = round
+        .committed_count
+        .checked_add(1)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    freeze_commits_if_full(round, current_slot);
+
+    // global stats
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_tickets = gs.total_tickets.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    gs.total_sol_fees = gs.total_sol_fees.checked_add(sol_fee).ok_or(TimlgError::MathOverflow)?;
+    // refundable until finalize
+    book_service_fee(&mut ctx.accounts.config, round, sol_fee)?;
+
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_committed = cfg.total_committed.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// remaining_accounts per entry in `commit_batch_signed_multi`.
+pub const MULTI_COMMIT_ACCOUNTS_PER_ENTRY: usize = 5;
+
+/// Signed commit batch whose entries may belong to different users; each entry is checked
+/// against its own ed25519 signature and pays its stake from its own escrow.
+///
+/// remaining_accounts, per entry and in entry order:
+/// `[user_escrow, user_escrow_ata, user_stats, user_round_stats, ticket]`.
+/// `user_stats` and `user_round_stats` are created (payer = relayer) when missing; the
+/// latter enforces `config.max_tickets_per_user` like the single-user commit paths.
+pub fn commit_batch_signed_multi<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CommitBatchSignedMulti<'info>>,
+    round_id: u64,
+    entries: Vec<CommitSignedEntry>,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
 
+    require!(entries.len() <= MAX_BATCH, TimlgError::TooManyEntries);
+    require!(
+        ctx.remaining_accounts.len() == entries.len() * MULTI_COMMIT_ACCOUNTS_PER_ENTRY,
+        TimlgError::TicketPdaMismatch
+    );
+    require_unique_nonces(&entries.iter().map(|e| (e.user, e.nonce)).collect::<Vec<_>>())?;
+    require!(
+        entries.iter().all(|e| e.commitment != [0u8; 32]),
+        TimlgError::InvalidCommitment
+    );
+
+    let round = &mut ctx.accounts.round;
+    require!(!round.paused, TimlgError::RoundPaused);
+    require_spl_stake(round)?;
+    require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(!round.public_mode, TimlgError::RoundModeMismatch);
+    // Pulse before deadline: a set pulse implies the deadline passed, so report the more specific cause.
+    require!(!round.pulse_set, TimlgError::CommitAfterPulseSet);
+
+    let current_slot = Clock::get()?.slot;
+    require_commit_open(round, current_slot)?;
+    require_commit_capacity(round, entries.len() as u64)?;
+
+    // --- ed25519 introspection: each entry signed by its own user ---
+    let ed25519_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+    let sigs = ed25519_sigs(&ed25519_ixs);
+    for e in entries.iter() {
+        require_commit_consent(&sigs, cfg, ctx.program_id, round, &e.user, e.nonce, &e.commitment, 0)?;
+    }
+
+    // --- PRECHECK: validate every PDA + reject replay BEFORE moving funds ---
+    let round_le = round_id.to_le_bytes();
+    for (e, accs) in entries
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(MULTI_COMMIT_ACCOUNTS_PER_ENTRY))
+    {
+        let user = e.user.as_ref();
+        let (escrow_pda, _) = Pubkey::find_program_address(&[USER_ESCROW_SEED, user], ctx.program_id);
+        require_keys_eq!(escrow_pda, *accs[0].key, TimlgError::EscrowPdaMismatch);
+        require_keys_eq!(*accs[0].owner, *ctx.program_id, TimlgError::EscrowPdaMismatch);
+
+        let (escrow_ata_pda, _) =
+            Pubkey::find_program_address(&[USER_ESCROW_VAULT_SEED, user], ctx.program_id);
+        require_keys_eq!(escrow_ata_pda, *accs[1].key, TimlgError::EscrowPdaMismatch);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[USER_STATS_SEED, user], ctx.program_id);
+        require_keys_eq!(stats_pda, *accs[2].key, TimlgError::UserStatsPdaMismatch);
+
+        let (round_stats_pda, _) =
+            Pubkey::find_program_address(&[USER_ROUND_SEED, &round_le, user], ctx.program_id);
+        require_keys_eq!(round_stats_pda, *accs[3].key, TimlgError::UserStatsPdaMismatch);
+
+        let nonce_le = e.nonce.to_le_bytes();
+        let (ticket_pda, _) =
+            Pubkey::find_program_address(&[TICKET_SEED, &round_le, user, &nonce_le], ctx.program_id);
+        require_keys_eq!(ticket_pda, *accs[4].key, TimlgError::TicketPdaMismatch);
+        require!(
+            accs[4].lamports() == 0 && accs[4].data_is_empty(),
+            TimlgError::TicketAlreadyExists
+        );
+    }
+
+    let payer_ai = ctx.accounts.payer.to_account_info();
+    let system_ai = ctx.accounts.system_program.to_account_info();
+    let ticket_space = 8 + Ticket::INIT_SPACE;
+    let stats_space = 8 + UserStats::INIT_SPACE;
+    let round_stats_space = 8 + UserRoundStats::INIT_SPACE;
+    let rent = Rent::get()?;
+    let mut total_sol_fee: u64 = 0;
+
+    for (e, accs) in entries
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(MULTI_COMMIT_ACCOUNTS_PER_ENTRY))
+    {
+        let user_pk = e.user;
+        let (escrow_ai, escrow_ata_ai, stats_ai, round_stats_ai, ticket_ai) =
+            (&accs[0], &accs[1], &accs[2], &accs[3], &accs[4]);
+
+        // --- UserRoundStats (created on first use): per-user round cap ---
+        let (_, round_stats_bump) =
+            Pubkey::find_program_address(&[USER_ROUND_SEED, &round_le, user_pk.as_ref()], ctx.program_id);
+        let mut round_stats = if round_stats_ai.data_is_empty() {
+            create_pda_account(
+                &payer_ai,
+                round_stats_ai,
+                &system_ai,
+                rent.minimum_balance(round_stats_space),
+                round_stats_space,
+                ctx.program_id,
+                &[USER_ROUND_SEED, &round_le, user_pk.as_ref(), &[round_stats_bump]],
+            )?;
+            UserRoundStats {
+                round_id: 0,
+                user: Pubkey::default(),
+                bump: 0,
+                committed: 0,
+                revealed: 0,
+                reveal_root: [0u8; 32],
+            }
+        } else {
+            require_keys_eq!(*round_stats_ai.owner, *ctx.program_id, TimlgError::UserStatsPdaMismatch);
+            let data = round_stats_ai.try_borrow_data()?;
+            let mut slice: &[u8] = &data;
+            UserRoundStats::try_deserialize(&mut slice)?
+        };
+        record_user_round_commits(
+            &mut round_stats,
+            round_id,
+            user_pk,
+            round_stats_bump,
+            1,
+            cfg.max_tickets_per_user,
+        )?;
+        {
+            let mut data = round_stats_ai.try_borrow_mut_data()?;
+            let mut w = std::io::Cursor::new(&mut data[..]);
+            round_stats.try_serialize(&mut w)?;
+        }
+
+        // --- stake: this entry's escrow -> timlg_vault ---
+        let escrow_bump = {
+            let data = escrow_ai.try_borrow_data()?;
+            let mut slice: &[u8] = &data;
+            UserEscrow::try_deserialize(&mut slice)?.bump
+        };
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: escrow_ata_ai.clone(),
+                    to: ctx.accounts.timlg_vault.to_account_info(),
+                    authority: escrow_ai.clone(),
+                },
+            )
+            .with_signer(&[&[USER_ESCROW_SEED, user_pk.as_ref(), &[escrow_bump]]]),
+            cfg.stake_amount,
+        )?;
+
+        // Exemption is keyed on the entry user, not the relayer/payer.
+        let sol_fee = sol_service_fee_for(cfg, &user_pk);
+        total_sol_fee = total_sol_fee
+            .checked_add(sol_fee)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+
+        // --- UserStats (created on first use) ---
+        let (_, stats_bump) =
+            Pubkey::find_program_address(&[USER_STATS_SEED, user_pk.as_ref()], ctx.program_id);
+        let mut user_stats = if stats_ai.data_is_empty() {
+            create_pda_account(
+                &payer_ai,
+                stats_ai,
+                &system_ai,
+                rent.minimum_balance(stats_space),
+                stats_space,
+                ctx.program_id,
+                &[USER_STATS_SEED, user_pk.as_ref(), &[stats_bump]],
+            )?;
+            UserStats {
+                user: user_pk,
+                bump: stats_bump,
+                games_played: 0,
+                games_won: 0,
+                games_lost: 0,
+                tickets_revealed: 0,
+                tickets_swept: 0,
+                tickets_claimed: 0,
+                tickets_refunded: 0,
+                last_reset_slot: current_slot,
+                current_streak: 0,
+                longest_streak: 0,
+                last_revealed_winning_index: 0,
+            }
+        } else {
+            require_keys_eq!(*stats_ai.owner, *ctx.program_id, TimlgError::UserStatsPdaMismatch);
+            let data = stats_ai.try_borrow_data()?;
+            let mut slice: &[u8] = &data;
+            UserStats::try_deserialize(&mut slice)?
+        };
+        user_stats.games_played = user_stats
+            .games_played
+            .checked_add(1)
+            .ok_or(TimlgError::MathOverflow)?;
+        {
+            let mut data = stats_ai.try_borrow_mut_data()?;
+            let mut w = std::io::Cursor::new(&mut data[..]);
+            user_stats.try_serialize(&mut w)?;
+        }
+
+        // --- ticket PDA (payer = relayer/payer) ---
+        // Defense in depth (duplicates are rejected up front).
+        require!(
+            ticket_ai.lamports() == 0 && ticket_ai.data_is_empty(),
+            TimlgError::TicketAlreadyExists
+        );
+        let nonce_le = e.nonce.to_le_bytes();
+        let (_, ticket_bump) = Pubkey::find_program_address(
+            &[TICKET_SEED, &round_le, user_pk.as_ref(), &nonce_le],
+            ctx.program_id,
+        );
+        create_pda_account(
+            &payer_ai,
+            ticket_ai,
+            &system_ai,
+            rent.minimum_balance(ticket_space),
+            ticket_space,
+            ctx.program_id,
+            &[TICKET_SEED, &round_le, user_pk.as_ref(), &nonce_le, &[ticket_bump]],
+        )?;
+
+        let ticket = Ticket {
+            round_id,
+            user: user_pk,
+            nonce: e.nonce,
+            bump: ticket_bump,
+            commitment: e.commitment,
+            stake_paid: true,
+            stake_slashed: false,
+            processed: false,
+            revealed: false,
+            guess: 0,
+            win: false,
+            bit_index: derive_bit_index_for(round_id, &user_pk, e.nonce, guess_bits(round)),
+            claimed: false,
+            claimed_slot: 0,
+            created_slot: current_slot,
+            revealed_slot: 0,
+            user_commit_index: user_stats.games_played,
+            weight: 1,
+            sol_fee_paid: sol_fee,
+            fee_payer: *payer_ai.key,
+        };
+        {
+            let mut data = ticket_ai
+                .try_borrow_mut_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            let mut w = std::io::Cursor::new(&mut data[..]);
+            ticket
+                .try_serialize(&mut w)
+                .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
+        }
+
+        emit!(CommitMade {
+            round_id,
+            user: user_pk,
+            nonce: e.nonce,
+            bit_index: ticket.bit_index,
+        });
+    }
+
+    // --- TRANSFER SOL service fee from payer -> treasury_sol (whole batch) ---
+    if total_sol_fee > 0 {
+        let ix = system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.treasury_sol.key(),
+            total_sol_fee,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                payer_ai.clone(),
+                ctx.accounts.treasury_sol.to_account_info(),
+                system_ai.clone(),
+            ],
+        )?;
+    }
+
+    let n = entries.len() as u64;
+    round.committed_count = round
+        .committed_count
+        .checked_add(n)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    freeze_commits_if_full(round, current_slot);
+
+    // global stats
     let gs = &mut ctx.accounts.global_stats;
     gs.total_tickets = gs.total_tickets.checked_add(n).ok_or(TimlgError::MathOverflow)?;
     gs.total_sol_fees = gs.total_sol_fees.checked_add(total_sol_fee).ok_or(TimlgError::MathOverflow)?;
+    // refundable until finalize
+    book_service_fee(&mut ctx.accounts.config, round, total_sol_fee)?;
+
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_committed = cfg.total_committed.checked_add(n).ok_or(TimlgError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Allocates a program-owned PDA funded by `payer` (the PDA signs with `seeds`).
+/// A PDA someone already sent lamports to cannot go through `create_account`, so it is
+/// topped up to `lamports`, then allocated and assigned (as Anchor's `init` does).
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let current = target.lamports();
+    if current == 0 {
+        let ix = system_instruction::create_account(payer.key, target.key, lamports, space as u64, owner);
+        invoke_signed(
+            &ix,
+            &[payer.clone(), target.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+        return Ok(());
+    }
 
+    let top_up = lamports.saturating_sub(current);
+    if top_up > 0 {
+        anchor_lang::solana_program::program::invoke(
+            &system_instruction::transfer(payer.key, target.key, top_up),
+            &[payer.clone(), target.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(target.key, space as u64),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(target.key, owner),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )?;
     Ok(())
 }
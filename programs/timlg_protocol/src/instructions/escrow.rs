@@ -1,8 +1,9 @@
 // programs/timlg_protocol/src/instructions/escrow.rs
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer};
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::token::{self, CloseAccount, Transfer};
 
-use crate::{errors::TimlgError, DepositEscrow, InitUserEscrow, WithdrawEscrow};
+use crate::{errors::TimlgError, CloseUserEscrow, DepositEscrow, InitUserEscrow, WithdrawEscrow};
 
 /// Creates the UserEscrow PDA and its PDA TokenAccount (user_escrow_ata)
 pub fn init_user_escrow(ctx: Context<InitUserEscrow>) -> Result<()> {
@@ -13,32 +14,60 @@ pub fn init_user_escrow(ctx: Context<InitUserEscrow>) -> Result<()> {
     escrow.bump = ctx.bumps.user_escrow;
     escrow.created_slot = slot;
     escrow.updated_slot = slot;
+    escrow.last_reveal_slot = 0;
 
     Ok(())
 }
 
 /// User deposits TIMLG into escrow (normal signed tx)
 pub fn deposit_escrow(ctx: Context<DepositEscrow>, amount: u64) -> Result<()> {
+    deposit(ctx.accounts, amount)
+}
+
+/// `deposit_escrow` that fails unless the escrow holds at least `min_expected_balance`
+/// afterwards, so a relayer-coordinated flow can't commit against a balance a concurrent
+/// withdraw already drained. Returns the new balance (u64 LE) via return data.
+pub fn deposit_escrow_checked(
+    ctx: Context<DepositEscrow>,
+    amount: u64,
+    min_expected_balance: u64,
+) -> Result<()> {
+    deposit(ctx.accounts, amount)?;
+
+    let escrow_ata = &mut ctx.accounts.user_escrow_ata;
+    escrow_ata.reload()?;
+    require!(escrow_ata.amount >= min_expected_balance, TimlgError::EscrowBalanceTooLow);
+
+    set_return_data(&escrow_ata.amount.to_le_bytes());
+    Ok(())
+}
+
+fn deposit(accounts: &mut DepositEscrow, amount: u64) -> Result<()> {
     require!(amount > 0, TimlgError::InvalidStakeAmount);
 
     token::transfer(
         CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
+            accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.user_timlg_ata.to_account_info(),
-                to: ctx.accounts.user_escrow_ata.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+                from: accounts.user_timlg_ata.to_account_info(),
+                to: accounts.user_escrow_ata.to_account_info(),
+                authority: accounts.user.to_account_info(),
             },
         ),
         amount,
     )?;
 
-    ctx.accounts.user_escrow.updated_slot = Clock::get()?.slot;
+    accounts.user_escrow.updated_slot = Clock::get()?.slot;
     Ok(())
 }
 
-/// User withdraws TIMLG from escrow (optional utility)
+/// User withdraws TIMLG from escrow (optional utility). amount == 0 withdraws the full balance.
 pub fn withdraw_escrow(ctx: Context<WithdrawEscrow>, amount: u64) -> Result<()> {
+    let amount = if amount == 0 {
+        ctx.accounts.user_escrow_ata.amount
+    } else {
+        amount
+    };
     require!(amount > 0, TimlgError::InvalidStakeAmount);
 
     let user_pk = ctx.accounts.user.key();
@@ -77,3 +106,38 @@ pub fn withdraw_escrow(ctx: Context<WithdrawEscrow>, amount: u64) -> Result<()>
 
     Ok(())
 }
+
+/// User closes an empty escrow and reclaims the rent of both the PDA and its token account
+pub fn close_user_escrow(ctx: Context<CloseUserEscrow>) -> Result<()> {
+    let user_pk = ctx.accounts.user.key();
+
+    require_keys_eq!(
+        ctx.accounts.user_escrow.user,
+        user_pk,
+        TimlgError::Unauthorized
+    );
+    require!(
+        ctx.accounts.user_escrow_ata.amount == 0,
+        TimlgError::EscrowNotEmpty
+    );
+
+    let escrow_bump = ctx.accounts.user_escrow.bump;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        crate::USER_ESCROW_SEED,
+        user_pk.as_ref(),
+        &[escrow_bump],
+    ]];
+
+    // CPI close of the escrow token account (PDA signs); the UserEscrow PDA is closed by `close = user`
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.user_escrow_ata.to_account_info(),
+            destination: ctx.accounts.user.to_account_info(),
+            authority: ctx.accounts.user_escrow.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    Ok(())
+}
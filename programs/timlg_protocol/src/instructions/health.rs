@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+use crate::state::{RoundRegistry, Tokenomics};
+use crate::HealthCheck;
+
+// Health bits (set = invariant holds). Returned as u32 LE via return data.
+pub const HEALTH_NOT_PAUSED: u32 = 1 << 0;
+pub const HEALTH_ORACLE_SET: u32 = 1 << 1;
+pub const HEALTH_TOKENOMICS_OK: u32 = 1 << 2;
+pub const HEALTH_TREASURY_SOLVENT: u32 = 1 << 3;
+pub const HEALTH_REGISTRY_OK: u32 = 1 << 4;
+
+pub const HEALTH_ALL_OK: u32 = HEALTH_NOT_PAUSED
+    | HEALTH_ORACLE_SET
+    | HEALTH_TOKENOMICS_OK
+    | HEALTH_TREASURY_SOLVENT
+    | HEALTH_REGISTRY_OK;
+
+/// Read-only diagnostic: inspects the protocol singletons and returns a status bitmask.
+/// Never fails on a broken invariant (only on wrong addresses); it just clears the bit.
+pub fn health_check(ctx: Context<HealthCheck>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    let mut status: u32 = 0;
+
+    if !cfg.paused {
+        status |= HEALTH_NOT_PAUSED;
+    }
+
+    if cfg.oracle_pubkey != Pubkey::default() {
+        status |= HEALTH_ORACLE_SET;
+    }
+
+    // Tokenomics / registry may not be initialized yet: deserialize manually.
+    let tokenomics_info = ctx.accounts.tokenomics.to_account_info();
+    if tokenomics_info.owner == ctx.program_id {
+        let data = tokenomics_info.try_borrow_data()?;
+        let mut slice: &[u8] = &data;
+        if let Ok(t) = Tokenomics::try_deserialize(&mut slice) {
+            if t.admin == cfg.admin && t.reward_fee_bps <= 10_000 {
+                status |= HEALTH_TOKENOMICS_OK;
+            }
+        }
+    }
+
+    let registry_info = ctx.accounts.round_registry.to_account_info();
+    if registry_info.owner == ctx.program_id {
+        let data = registry_info.try_borrow_data()?;
+        let mut slice: &[u8] = &data;
+        if let Ok(rr) = RoundRegistry::try_deserialize(&mut slice) {
+            if rr.admin == cfg.admin {
+                status |= HEALTH_REGISTRY_OK;
+            }
+        }
+    }
+
+    // Treasury: SOL PDA rent-exempt + SPL treasury wired to the right mint/authority.
+    let rent = Rent::get()?;
+    let treasury = &ctx.accounts.treasury;
+    if ctx.accounts.treasury_sol.lamports() >= rent.minimum_balance(0)
+        && treasury.mint == cfg.timlg_mint
+        && treasury.owner == cfg.key()
+    {
+        status |= HEALTH_TREASURY_SOLVENT;
+    }
+
+    msg!("health_check status={:#07b} ok={}", status, status == HEALTH_ALL_OK);
+    set_return_data(&status.to_le_bytes());
+
+    Ok(())
+}
@@ -1,18 +1,26 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{program::invoke_signed, system_instruction};
+use anchor_lang::solana_program::program::set_return_data;
 
-use anchor_spl::token::{self, Burn, Transfer, TokenAccount};
-use crate::state::{Ticket, Round};
+use anchor_spl::token::{self, Burn, Token, Transfer, TokenAccount};
+use crate::state::{Config, Ticket, Round, RoundRegistry, RewardMode, UnrevealedPolicy, UserStats};
 use crate::constants::*;
-use crate::{TICKET_SEED, ROUND_SEED, VAULT_SEED, errors::TimlgError, state::RoundState};
+use crate::{ROUND_SEED, TIMLG_VAULT_SEED, VAULT_SEED, errors::TimlgError, state::RoundState};
+use crate::utils::{
+    advance_round_state, claim_deadline_slot, clear_voided_reveal, effective_reveal_deadline, is_under_participated, mark_settlement_complete, resolve_public_ticket, reveal_voided, verify_ticket_pda,
+    config_can_mint, config_mint, deserialize_round_padded, deserialize_ticket_padded, is_known_ticket_size, serialize_ticket_into, fee_split, pay_cranker_fee, refund_service_fee, release_finalized_fees, require_spl_stake, ticket_weight, unclaimed_win_units, vault_lamports_transfer, win_units, MAX_BATCH,
+};
 
 use crate::contexts::{
     SettleRoundTokens,
-    FinalizeRound, CloseRound, SweepUnclaimed, CloseTicket, RecoverFunds, RecoverFundsAnyone,
-    CloseUserStats
+    FinalizeRound, CloseRound, CloseRoundBatch, SweepUnclaimed, CloseTicket, CloseTicketBatch, RecoverFunds, RecoverFundsAnyone,
+    RecoverFundsAnyoneBatch,
+    CloseUserStats, ExecuteBurns, CancelRound, SweepSol, SweepTokens,
 };
 
-pub fn finalize_round(ctx: Context<FinalizeRound>, round_id: u64) -> Result<()> {
+pub fn finalize_round<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeRound<'info>>,
+    round_id: u64,
+) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
     require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
@@ -20,123 +28,138 @@ pub fn finalize_round(ctx: Context<FinalizeRound>, round_id: u64) -> Result<()>
     let round = &mut ctx.accounts.round;
     require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
     require!(!round.finalized, TimlgError::AlreadyFinalized);
+    require!(round.state != RoundState::Refunding as u8, TimlgError::RoundRefunding);
+
+    let current_slot = Clock::get()?.slot;
+
+    // Too few participants: void the round instead of resolving it.
+    // Tickets are then refundable through recover_funds / recover_funds_anyone.
+    if is_under_participated(round) {
+        require!(
+            current_slot > round.reveal_deadline_slot,
+            TimlgError::CannotFinalizeYet
+        );
+        advance_round_state(round, RoundState::Refunding);
+        msg!(
+            "Round {} voided: committed={} < min_participants={}",
+            round_id, round.committed_count, round.min_participants
+        );
+        return Ok(());
+    }
 
     // ✅ P1: no se puede finalizar si no se ha fijado el pulso
     require!(round.pulse_set, TimlgError::PulseNotSet);
 
-    let current_slot = Clock::get()?.slot;
     require!(
-        current_slot > round.reveal_deadline_slot,
+        current_slot > effective_reveal_deadline(round, cfg),
         TimlgError::CannotFinalizeYet
     );
 
     round.finalized = true;
     round.finalized_slot = current_slot;
-    round.state = RoundState::Finalized as u8;
-
-    Ok(())
-}
-
-pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, round_id: u64) -> Result<()> {
-    let cfg = &ctx.accounts.config;
-    require!(!cfg.paused, TimlgError::Paused);
-    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
-
-    let round_ai = ctx.accounts.round.to_account_info();
-    let vault_ai = ctx.accounts.vault.to_account_info();
-
-    // ✅ Manual PDA verification (since using AccountInfo in context)
-    let round_le = round_id.to_le_bytes();
-    let (expected_round, _round_bump) = Pubkey::find_program_address(
-        &[ROUND_SEED, &round_le],
-        ctx.program_id,
-    );
-    require_keys_eq!(expected_round, round_ai.key(), TimlgError::TicketPdaMismatch);
+    advance_round_state(round, RoundState::Finalized);
+    round.claim_deadline_slot = claim_deadline_slot(round, cfg);
+
+    // Public-guess rounds: resolve the passed tickets straight from the pulse.
+    // Tickets not passed here are resolved lazily by settle_round_tokens.
+    if round.public_mode {
+        for ai in ctx.remaining_accounts.iter() {
+            require!(ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+
+            let mut data = ai
+                .try_borrow_mut_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+
+            let mut ticket: Ticket = deserialize_ticket_padded(&data)
+                .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
+
+            require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
+
+            verify_ticket_pda(
+                cfg.seed_version,
+                ai.key,
+                round_id,
+                &ticket.user,
+                ticket.nonce,
+                ctx.program_id,
+            )?;
 
-    let (expected_vault, _vault_bump) = Pubkey::find_program_address(
-        &[VAULT_SEED, &round_le],
-        ctx.program_id,
-    );
-    require_keys_eq!(expected_vault, vault_ai.key(), TimlgError::TicketPdaMismatch);
+            if ticket.revealed {
+                continue;
+            }
 
-    // ✅ Scoped borrow: read + validate, then release BEFORE any CPI
-    let (mut round, data_len) = {
-        let data = round_ai
-            .try_borrow_data()
-            .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
-        
-        // Flexible decoding: handle legacy 231-byte vs modern 258-byte
-        let round = if data.len() == 231 {
-            let mut padded = [0u8; 258];
-            padded[..231].copy_from_slice(&data);
-            let mut slice: &[u8] = &padded;
-            Round::try_deserialize(&mut slice)?
-        } else {
-            let mut slice: &[u8] = &data;
-            Round::try_deserialize(&mut slice)?
-        };
+            resolve_public_ticket(round, &mut ticket, current_slot)?;
 
-        require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
-        // Allow sweep of unfinalized rounds ONLY if they never received tickets
-        if round.committed_count > 0 {
-            require!(round.finalized, TimlgError::NotFinalized);
+            serialize_ticket_into(&ticket, &mut data)
+                .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
         }
-        require!(!round.swept, TimlgError::AlreadySwept);
+    }
 
-        (round, data.len())
-    }; // ← data borrow released here
+    release_finalized_fees(&mut ctx.accounts.config, round);
+    Ok(())
+}
 
-    // ✅ grace period gate
-    let current_slot = Clock::get()?.slot;
-    let min_sweep_slot = round
-        .reveal_deadline_slot
-        .saturating_add(cfg.claim_grace_slots);
+/// Sweep gate shared by the sweep paths: finalized (unless the round never got tickets)
+/// and past the claim grace period.
+fn require_sweep_window(cfg: &Config, round: &Round, current_slot: u64) -> Result<()> {
+    // Allow sweep of unfinalized rounds ONLY if they never received tickets
+    if round.committed_count > 0 {
+        require!(round.finalized, TimlgError::NotFinalized);
+    }
+
+    // ✅ grace period gate (same deadline claims were closed at)
+    let min_sweep_slot = if round.claim_deadline_slot > 0 {
+        round.claim_deadline_slot
+    } else {
+        claim_deadline_slot(round, cfg)
+    };
     require!(current_slot > min_sweep_slot, TimlgError::SweepTooEarly);
+    Ok(())
+}
 
-    // 1) SOL Sweep (Rent) — safe: no data borrow held
-    let vault_lamports = ctx.accounts.vault.to_account_info().lamports();
-    if vault_lamports > 0 {
-        let ix = system_instruction::transfer(
-            &ctx.accounts.vault.key(),
-            &ctx.accounts.admin.key(),
-            vault_lamports,
+/// Deferred burns are covered by the residue burn of the token sweep, but not before the audit window.
+fn require_burn_window(round: &Round, current_slot: u64) -> Result<()> {
+    if round.pending_burn > 0 {
+        require!(
+            current_slot > round.token_settled_slot.saturating_add(round.burn_delay_slots),
+            TimlgError::BurnTooEarly
         );
-
-        let round_le = round_id.to_le_bytes();
-        let signer_seeds: &[&[u8]] = &[VAULT_SEED, &round_le, &[round.vault_bump]];
-
-        invoke_signed(
-            &ix,
-            &[
-                ctx.accounts.vault.to_account_info(),
-                ctx.accounts.admin.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[signer_seeds],
-        )?;
     }
+    Ok(())
+}
 
-    // --- Token Sweep Logic ---
-    // We attempt to deserialize timlg_vault as a TokenAccount. 
-    // If it is a SystemAccount (legacy), we skip token logic but still mark round as swept.
-    let timlg_vault_info = ctx.accounts.timlg_vault.to_account_info();
-    let is_token_account = *timlg_vault_info.owner == ctx.accounts.token_program.key() && timlg_vault_info.data_len() == 165;
+/// Burns the loser/unrevealed residue of the token vault and moves the unclaimed winners'
+/// stake to the treasury. Legacy rounds (system-owned `timlg_vault`) are skipped.
+fn sweep_token_vault<'info>(
+    cfg: &Config,
+    round: &mut Round,
+    round_ai: &AccountInfo<'info>,
+    timlg_vault_info: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    timlg_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let round_id = round.round_id;
+    let is_token_account = *timlg_vault_info.owner == token_program.key() && timlg_vault_info.data_len() == 165;
 
     if is_token_account {
         // B) Quemar el Stake de los Losers y Unreveals (Deflación Garantizada)
         if !round.close_burn_done {
             let burn_amount = {
-                let data = ctx.accounts.timlg_vault.try_borrow_data()?;
+                let data = timlg_vault_info.try_borrow_data()?;
                 let mut slice: &[u8] = &data;
                 let timlg_vault = TokenAccount::try_deserialize(&mut slice)?;
                 let current_balance = timlg_vault.amount;
                 
                 // El stake que legalmente pertenece a los ganadores que aún no han reclamado
-                let unclaimed_winners = round.win_count.saturating_sub(round.claimed_win_count);
-                let winners_stake = unclaimed_winners.saturating_mul(cfg.stake_amount);
+                let winners_stake = unclaimed_win_units(round).saturating_mul(cfg.stake_amount);
+
+                // Seeded TIMLG not paid out to pari-mutuel winners goes back to treasury, not burned.
+                let paid_out = round.reward_mode == RewardMode::PariMutuel as u8 && round.win_count > 0;
+                let unspent_seed = if paid_out { 0 } else { round.seeded_amount };
                 
                 // Todo lo que exceda el stake de los ganadores es RESIDUO (Losses + Unrevealed) y debe quemarse.
-                current_balance.saturating_sub(winners_stake)
+                current_balance.saturating_sub(winners_stake).saturating_sub(unspent_seed)
             };
             
             if burn_amount > 0 {
@@ -144,9 +167,9 @@ pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, round_id: u64) -> Result<()
                 let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
                 token::burn(
                     CpiContext::new_with_signer(
-                        ctx.accounts.token_program.to_account_info(),
+                        token_program.clone(),
                         Burn {
-                            mint: ctx.accounts.timlg_mint.to_account_info(),
+                            mint: timlg_mint.clone(),
                             from: timlg_vault_info.clone(),
                             authority: round_ai.clone(),
                         },
@@ -154,31 +177,50 @@ pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, round_id: u64) -> Result<()
                     ),
                     burn_amount,
                 )?;
+                round.burned_total = round.burned_total.saturating_add(burn_amount);
             }
             round.close_burn_done = true;
+            round.pending_burn = 0;
         }
 
         // B) [MINTEO ELIMINADO]
         round.close_unclaimed_mint_done = true;
 
         // C) Transferir el remanente (Stake de los ganadores no reclamados) a Treasury
+        //    (or burn it when the deployment is configured as deflationary)
         let vault_tokens = {
-            let data = ctx.accounts.timlg_vault.try_borrow_data()?;
+            let data = timlg_vault_info.try_borrow_data()?;
             let mut slice: &[u8] = &data;
             let timlg_vault = TokenAccount::try_deserialize(&mut slice)?;
             timlg_vault.amount
         };
 
-        if vault_tokens > 0 {
+        if vault_tokens > 0 && cfg.sweep_burns_tokens {
+            let round_le = round_id.to_le_bytes();
+            let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
+            token::burn(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    Burn {
+                        mint: timlg_mint.clone(),
+                        from: timlg_vault_info.clone(),
+                        authority: round_ai.clone(),
+                    },
+                    signer_seeds,
+                ),
+                vault_tokens,
+            )?;
+            round.burned_total = round.burned_total.saturating_add(vault_tokens);
+        } else if vault_tokens > 0 {
             let round_le = round_id.to_le_bytes();
             let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
 
             token::transfer(
                 CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
+                    token_program.clone(),
                     Transfer {
                         from: timlg_vault_info.clone(),
-                        to: ctx.accounts.treasury.to_account_info(),
+                        to: treasury.clone(),
                         authority: round_ai.clone(),
                     },
                     signer_seeds,
@@ -191,10 +233,108 @@ pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, round_id: u64) -> Result<()
         round.close_burn_done = true;
         round.close_unclaimed_mint_done = true;
     }
+    Ok(())
+}
+
+/// Drains the lamport vault. On SOL-staked rounds everything above the rent floor is
+/// unclaimed winners' stake and goes to `treasury_sol`, like slashed stakes at settle;
+/// only the rent goes back to the admin.
+fn sweep_lamport_vault<'info>(
+    round: &Round,
+    vault: &AccountInfo<'info>,
+    admin: &AccountInfo<'info>,
+    treasury_sol: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if round.stake_in_sol {
+        let stakes = vault.lamports().saturating_sub(Rent::get()?.minimum_balance(0));
+        vault_lamports_transfer(vault, treasury_sol, system_program, round, stakes)?;
+    }
+    vault_lamports_transfer(vault, admin, system_program, round, vault.lamports())
+}
+
+/// Marks one half of the sweep done; `swept` only flips once both vaults are swept.
+fn mark_partially_swept(round: &mut Round, current_slot: u64) {
+    round.swept = round.sol_swept && round.tokens_swept;
+    if round.swept {
+        round.swept_slot = current_slot;
+        advance_round_state(round, RoundState::Swept);
+    }
+}
+
+pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, round_id: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round_ai = ctx.accounts.round.to_account_info();
+    let vault_ai = ctx.accounts.vault.to_account_info();
+
+    // ✅ Manual PDA verification (since using AccountInfo in context)
+    let round_le = round_id.to_le_bytes();
+    let (expected_round, _round_bump) = Pubkey::find_program_address(
+        &[ROUND_SEED, &round_le],
+        ctx.program_id,
+    );
+    require_keys_eq!(expected_round, round_ai.key(), TimlgError::TicketPdaMismatch);
+
+    let (expected_vault, _vault_bump) = Pubkey::find_program_address(
+        &[VAULT_SEED, &round_le],
+        ctx.program_id,
+    );
+    require_keys_eq!(expected_vault, vault_ai.key(), TimlgError::TicketPdaMismatch);
+
+    // ✅ Scoped borrow: read + validate, then release BEFORE any CPI
+    let (mut round, data_len) = {
+        let data = round_ai
+            .try_borrow_data()
+            .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+        
+        // Flexible decoding: legacy (shorter) layouts are zero-padded to the current size
+        let round = deserialize_round_padded(&data)?;
+
+        require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+        require!(!round.swept, TimlgError::AlreadySwept);
+
+        (round, data.len())
+    }; // ← data borrow released here
+
+    let current_slot = Clock::get()?.slot;
+    require_sweep_window(cfg, &round, current_slot)?;
+    if !round.tokens_swept {
+        require_burn_window(&round, current_slot)?;
+    }
+
+    // 1) SOL Sweep (rent, plus unclaimed SOL stakes) — safe: no data borrow held
+    if !round.sol_swept {
+        sweep_lamport_vault(
+            &round,
+            &vault_ai,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.treasury_sol.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
+
+    // --- Token Sweep Logic ---
+    if !round.tokens_swept {
+        sweep_token_vault(
+            cfg,
+            &mut round,
+            &round_ai,
+            &ctx.accounts.timlg_vault.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.timlg_mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
 
     // ✅ mark swept + write back (new borrow scope after CPIs)
+    round.sol_swept = true;
+    round.tokens_swept = true;
     round.swept = true;
     round.swept_slot = current_slot;
+    advance_round_state(&mut round, RoundState::Swept);
 
     {
         let mut data = round_ai
@@ -215,6 +355,62 @@ pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>, round_id: u64) -> Result<()
     Ok(())
 }
 
+/// Sweeps only the lamport vault: rent to the admin, unclaimed SOL stakes to `treasury_sol`.
+pub fn sweep_sol(ctx: Context<SweepSol>, round_id: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(!round.swept && !round.sol_swept, TimlgError::AlreadySwept);
+
+    let current_slot = Clock::get()?.slot;
+    require_sweep_window(cfg, round, current_slot)?;
+
+    sweep_lamport_vault(
+        round,
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.treasury_sol.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    round.sol_swept = true;
+    mark_partially_swept(round, current_slot);
+    Ok(())
+}
+
+/// Sweeps only the token vault: residue burn + unclaimed winners' stake to the treasury.
+pub fn sweep_tokens(ctx: Context<SweepTokens>, round_id: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round_ai = ctx.accounts.round.to_account_info();
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(!round.swept && !round.tokens_swept, TimlgError::AlreadySwept);
+
+    let current_slot = Clock::get()?.slot;
+    require_sweep_window(cfg, round, current_slot)?;
+    require_burn_window(round, current_slot)?;
+
+    sweep_token_vault(
+        cfg,
+        round,
+        &round_ai,
+        &ctx.accounts.timlg_vault.to_account_info(),
+        &ctx.accounts.treasury.to_account_info(),
+        &ctx.accounts.timlg_mint.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    round.tokens_swept = true;
+    mark_partially_swept(round, current_slot);
+    Ok(())
+}
+
 pub fn settle_round_tokens<'info>(
     ctx: Context<'_, '_, 'info, 'info, SettleRoundTokens<'info>>,
     round_id: u64,
@@ -230,60 +426,130 @@ pub fn settle_round_tokens<'info>(
     require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
     let current_slot = Clock::get()?.slot;
     require!(
-        current_slot > round.reveal_deadline_slot,
+        current_slot > effective_reveal_deadline(round, cfg),
         TimlgError::SettleTooEarly
     );
 
     // Auto-finalize if needed (Robustness: allow settle to trigger finalization)
+    require!(round.state != RoundState::Refunding as u8, TimlgError::RoundRefunding);
     if !round.finalized {
         require!(round.pulse_set, TimlgError::PulseNotSet);
+        require!(!is_under_participated(round), TimlgError::RoundRefunding);
         round.finalized = true;
         round.finalized_slot = current_slot;
-        round.state = crate::state::RoundState::Finalized as u8;
+        advance_round_state(round, RoundState::Finalized);
+        round.claim_deadline_slot = claim_deadline_slot(round, cfg);
     }
 
     require!(!round.token_settled, TimlgError::RoundTokensAlreadySettled);
 
     let stake = cfg.stake_amount;
+
+    // Snapshot the unrevealed policy before any ticket is settled, so it can't
+    // change halfway through an incremental settlement.
+    if round.settled_count == 0 {
+        let tok = &ctx.accounts.tokenomics;
+        require!(tok.unrevealed_penalty_bps <= 10_000, TimlgError::InvalidFeeBps);
+        let penalty = stake
+            .checked_mul(tok.unrevealed_penalty_bps as u64)
+            .ok_or(TimlgError::MathOverflow)?
+            / 10_000;
+        round.unrevealed_policy = tok.unrevealed_policy;
+        round.unrevealed_refund = stake - penalty;
+        round.burn_delay_slots = tok.burn_delay_slots;
+        round.reward_mode = tok.reward_mode;
+        // SOL-staked rounds: every slashed stake goes to treasury_sol, winners are minted.
+        if round.stake_in_sol {
+            round.unrevealed_policy = UnrevealedPolicy::Burn as u8;
+            round.unrevealed_refund = stake;
+            round.burn_delay_slots = 0;
+            round.reward_mode = RewardMode::Mint as u8;
+        }
+    }
+    let unrevealed_penalty = stake - round.unrevealed_refund;
+
     let mut losers: u64 = 0;
+    let mut unrevealed_to_treasury: u64 = 0;
+    let mut unrevealed_refunded: u64 = 0;
     // unrevealed count not needed for logic, just accounting if we wanted stats
 
     let round_le = round_id.to_le_bytes();
+    let settled_before = round.settled_count;
 
     for ai in ctx.remaining_accounts.iter() {
         require!(ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+        // Other program accounts (rounds, stats, ...) never reach the deserializer.
+        // Pre-`weight` tickets are one byte short and still settle (read zero-padded).
+        require!(is_known_ticket_size(ai.data_len()), TimlgError::TicketSizeMismatch);
 
         let mut data = ai
             .try_borrow_mut_data()
             .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
 
-        let mut slice: &[u8] = &data;
-        let mut ticket: Ticket = Ticket::try_deserialize(&mut slice)
+        let mut ticket: Ticket = deserialize_ticket_padded(&data)
             .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
 
         require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
         require!(ticket.stake_paid, TimlgError::StakeNotPaid);
 
         // --- PDA sanity ---
-        let nonce_le = ticket.nonce.to_le_bytes();
-        let (expected, bump) = Pubkey::find_program_address(
-            &[TICKET_SEED, &round_le, ticket.user.as_ref(), &nonce_le],
+        let bump = verify_ticket_pda(
+            cfg.seed_version,
+            ai.key,
+            round_id,
+            &ticket.user,
+            ticket.nonce,
             ctx.program_id,
-        );
-        require_keys_eq!(expected, *ai.key, TimlgError::TicketPdaMismatch);
+        )?;
         require!(bump == ticket.bump, TimlgError::TicketPdaMismatch);
 
         // ✅ Incremental settlement: skip already processed tickets
         if ticket.processed {
             continue;
         }
+        // a reveal against a disputed pulse settles as unrevealed
+        clear_voided_reveal(round, &mut ticket);
+        // claims and slashes always mark the ticket processed
+        require!(
+            ticket.stake_paid && !ticket.claimed && !ticket.stake_slashed,
+            TimlgError::TicketStateInconsistent
+        );
+
+        // Public-guess tickets not resolved at finalize are resolved here.
+        if round.public_mode && !ticket.revealed {
+            resolve_public_ticket(round, &mut ticket, current_slot)?;
+        }
 
-        // Classify and account this ticket exactly once
-        // Classify and account this ticket exactly once
-        // MVP-3.2: Burn unrevealed tickets same as losers
-        if !ticket.revealed || !ticket.win {
+        // DefaultGuessZero: the unrevealed commit plays guess 0, only its pulse bit matters.
+        // A win still pays the unrevealed penalty: burned here, kept back from its stake refund.
+        if !ticket.revealed && round.unrevealed_policy == UnrevealedPolicy::DefaultGuessZero as u8 {
+            ticket.guess = 0;
+            resolve_public_ticket(round, &mut ticket, current_slot)?;
+            if ticket.win {
+                unrevealed_refunded = unrevealed_refunded
+                    .checked_add(ticket_weight(&ticket))
+                    .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+            }
+        }
+
+        // Classify and account this ticket exactly once (in stake units: weighted tickets count `weight`)
+        // MVP-3.2: unrevealed tickets follow round.unrevealed_policy (default: burn like losers)
+        let weight = ticket_weight(&ticket);
+        if !ticket.revealed && round.unrevealed_policy == UnrevealedPolicy::Treasury as u8 {
+            unrevealed_to_treasury = unrevealed_to_treasury
+                .checked_add(weight)
+                .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+            ticket.stake_slashed = true; // transfer will happen for this call
+        } else if !ticket.revealed
+            && round.unrevealed_policy == UnrevealedPolicy::RefundMinusPenalty as u8
+        {
+            // penalty burned now, refund stays in vault for reclaim_unrevealed_stake
+            unrevealed_refunded = unrevealed_refunded
+                .checked_add(weight)
+                .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        } else if !ticket.revealed || !ticket.win {
             losers = losers
-                .checked_add(1)
+                .checked_add(weight)
                 .ok_or_else(|| error!(TimlgError::MathOverflow))?;
             ticket.stake_slashed = true; // burn will happen for this call
         } else {
@@ -298,25 +564,77 @@ pub fn settle_round_tokens<'info>(
             .ok_or_else(|| error!(TimlgError::MathOverflow))?;
 
         // write back
-        let mut w = std::io::Cursor::new(&mut data[..]);
-        ticket
-            .try_serialize(&mut w)
+        serialize_ticket_into(&ticket, &mut data)
             .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
     }
 
     // Tokenomics:
-    // - losers (incl unrevealed) => burn from timlg_vault
+    // - losers (incl unrevealed) => burn from timlg_vault, or kept for winners (pari-mutuel)
     // (winners stay in timlg_vault so claim_reward can refund stake)
 
-    let total_to_burn = stake
+    let losers_stake = stake
         .checked_mul(losers)
         .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    let pari_mutuel = round.reward_mode == RewardMode::PariMutuel as u8;
+    if pari_mutuel {
+        round.loser_stake_pool = round
+            .loser_stake_pool
+            .checked_add(losers_stake)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    }
+
+    let total_to_burn = unrevealed_penalty
+        .checked_mul(unrevealed_refunded)
+        .and_then(|v| v.checked_add(if pari_mutuel { 0 } else { losers_stake }))
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    let total_to_treasury = stake
+        .checked_mul(unrevealed_to_treasury)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
 
     let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
 
+    // SOL-staked rounds: no TIMLG in the vault, slashed lamports -> treasury_sol
+    let spl = !round.stake_in_sol;
+    if !spl {
+        let slashed = total_to_burn
+            .checked_add(total_to_treasury)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        vault_lamports_transfer(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.treasury_sol.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            round,
+            slashed,
+        )?;
+    }
+
+    // slash_to_pool: slashed stakes feed replication_pool instead of being burned
+    let slash_to_pool = ctx.accounts.tokenomics.slash_to_pool;
+    if spl && total_to_burn > 0 && slash_to_pool {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.timlg_vault.to_account_info(),
+                    to: ctx.accounts.replication_pool.to_account_info(),
+                    authority: round_ai.clone(),
+                },
+                signer_seeds,
+            ),
+            total_to_burn,
+        )?;
+    }
+
+    // Audit window: keep the stakes in the vault, execute_burns burns them later.
+    if spl && total_to_burn > 0 && !slash_to_pool && round.burn_delay_slots > 0 {
+        round.pending_burn = round
+            .pending_burn
+            .checked_add(total_to_burn)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    }
+
     // Burn losers from the round vault (authority = Round PDA)
-    // Burn losers from the round vault (authority = Round PDA)
-    if total_to_burn > 0 {
+    if spl && total_to_burn > 0 && !slash_to_pool && round.burn_delay_slots == 0 {
         token::burn(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
@@ -330,22 +648,160 @@ pub fn settle_round_tokens<'info>(
             total_to_burn,
         )?;
 
+        round.burned_total = round
+            .burned_total
+            .checked_add(total_to_burn)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+
         // global stats
         let gs = &mut ctx.accounts.global_stats;
         gs.total_timlg_burned = gs.total_timlg_burned.checked_add(total_to_burn).ok_or(TimlgError::MathOverflow)?;
     }
 
-    // Removed transfer to replication_pool (MVP-3.2)
+    // Unrevealed stakes under the Treasury policy -> SPL treasury
+    if spl && total_to_treasury > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.timlg_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: round_ai.clone(),
+                },
+                signer_seeds,
+            ),
+            total_to_treasury,
+        )?;
+    }
+
+    // Replication pool only gets slashed stakes when tokenomics.slash_to_pool is set (MVP-3.2 default: burn)
 
     // Only mark fully settled when all committed tickets have been processed
     if round.settled_count == round.committed_count {
-        round.token_settled = true;
-        round.token_settled_slot = current_slot;
+        // Seeded TIMLG is a prize, not stake: it was never burned above, and pari-mutuel
+        // winners share it with the losers' stakes.
+        if pari_mutuel && round.seeded_amount > 0 {
+            round.loser_stake_pool = round
+                .loser_stake_pool
+                .checked_add(round.seeded_amount)
+                .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        }
+
+        // prefund_fees: mint the fees of the winners that haven't claimed yet, exactly once
+        // (win units are final here; winners that already claimed minted their own fee).
+        // Skipped once the mint authority has left config: those claims pay no fee either.
+        // Also skipped while `config.fee_exempt` lists anyone: exempt winners pay no fee, so
+        // every claim mints its own fee instead. Same for rounds with a `max_total_reward`:
+        // a capped claim mints no fee, so the fees are left to the claims that fit the cap.
+        let tok = &ctx.accounts.tokenomics;
+        if tok.prefund_fees
+            && !round.fees_prefunded
+            && !pari_mutuel
+            && cfg.fee_exempt.is_empty()
+            && round.max_total_reward == 0
+            && config_can_mint(&ctx.accounts.timlg_mint.to_account_info(), &ctx.accounts.config.key())
+        {
+            require!(tok.reward_fee_bps <= 10_000, TimlgError::InvalidBps);
+            let fee = stake
+                .checked_mul(tok.reward_fee_bps as u64)
+                .ok_or_else(|| error!(TimlgError::MathOverflow))?
+                / 10_000;
+            let fee_total = fee
+                .checked_mul(win_units(round).saturating_sub(round.fee_paid_claims))
+                .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+            let (pool_fee, replication_fee) = fee_split(tok, fee_total)?;
+
+            let config_ai = ctx.accounts.config.to_account_info();
+            let mint_ai = ctx.accounts.timlg_mint.to_account_info();
+            config_mint(
+                &ctx.accounts.token_program,
+                &mint_ai,
+                &ctx.accounts.reward_fee_pool.to_account_info(),
+                &config_ai,
+                cfg.bump,
+                pool_fee,
+            )?;
+            config_mint(
+                &ctx.accounts.token_program,
+                &mint_ai,
+                &ctx.accounts.replication_pool.to_account_info(),
+                &config_ai,
+                cfg.bump,
+                replication_fee,
+            )?;
+            round.fees_prefunded = true;
+            // prefunded fees count toward the round's minted total (only uncapped rounds get here)
+            round.reward_minted_total = round
+                .reward_minted_total
+                .checked_add(fee_total)
+                .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+
+            let gs = &mut ctx.accounts.global_stats;
+            gs.total_timlg_minted = gs.total_timlg_minted.checked_add(fee_total).ok_or(TimlgError::MathOverflow)?;
+        }
+        mark_settlement_complete(round, current_slot);
+    }
+
+    // settle may have finalized the round: its fees are no longer reserved
+    release_finalized_fees(&mut ctx.accounts.config, round);
+
+    // Only calls that settled something earn the crank fee (no farming with empty calls).
+    if round.settled_count > settled_before {
+        pay_cranker_fee(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury_sol.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
     }
 
     Ok(())
 }
 
+/// Burns loser stakes deferred by `burn_delay_slots`, once the audit window after
+/// settlement has elapsed. Permissionless, like settle.
+pub fn execute_burns(ctx: Context<ExecuteBurns>, round_id: u64) -> Result<()> {
+    let round_ai = ctx.accounts.round.to_account_info();
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(round.token_settled, TimlgError::RoundTokensNotSettled);
+    require!(round.pending_burn > 0, TimlgError::NoPendingBurn);
+
+    let current_slot = Clock::get()?.slot;
+    require!(
+        current_slot > round.token_settled_slot.saturating_add(round.burn_delay_slots),
+        TimlgError::BurnTooEarly
+    );
+
+    let amount = round.pending_burn;
+    let round_le = round_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
+
+    token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.timlg_mint.to_account_info(),
+                from: ctx.accounts.timlg_vault.to_account_info(),
+                authority: round_ai,
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    round.pending_burn = 0;
+    round.burned_total = round
+        .burned_total
+        .checked_add(amount)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_timlg_burned = gs.total_timlg_burned.checked_add(amount).ok_or(TimlgError::MathOverflow)?;
+
+    Ok(())
+}
+
 pub fn close_round(ctx: Context<CloseRound>, round_id: u64) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
@@ -361,67 +817,148 @@ pub fn close_round(ctx: Context<CloseRound>, round_id: u64) -> Result<()> {
     );
     require_keys_eq!(expected_round, round_ai.key(), TimlgError::TicketPdaMismatch);
 
-    let (round_id_val, bump_val) = {
+    let timlg_vault_info = ctx.accounts.timlg_vault.to_account_info();
+    let round = {
         let data = round_ai
             .try_borrow_data()
             .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
-        
-        let round = if data.len() == 231 {
-            let mut padded = [0u8; 248];
-            padded[..231].copy_from_slice(&data);
-            let mut slice: &[u8] = &padded;
-            Round::try_deserialize(&mut slice)?
-        } else {
-            let mut slice: &[u8] = &data;
-            Round::try_deserialize(&mut slice)?
-        };
+        deserialize_round_padded(&data)?
+    };
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require_round_closable(&round, &timlg_vault_info, &ctx.accounts.token_program)?;
+    release_open_round(&round, ctx.accounts.round_registry.as_deref_mut())?;
+
+    close_round_accounts(
+        &round,
+        &round_ai,
+        &timlg_vault_info,
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.token_program,
+    )?;
 
-        require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
-        
-        // Safety checks: ensure round is completely done
-        if round.committed_count > 0 {
-            require!(round.finalized, TimlgError::NotFinalized);
+    // global stats
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_rounds_closed = gs.total_rounds_closed.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    
+    Ok(())
+}
+
+/// Archival sweep of `close_round`: remaining_accounts are `[round, timlg_vault]` pairs.
+/// Pairs that fail any `close_round` precondition are skipped; the number of rounds
+/// closed is returned as a little-endian u64 via `set_return_data`.
+pub fn close_round_batch<'info>(ctx: Context<'_, '_, 'info, 'info, CloseRoundBatch<'info>>) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let accs = ctx.remaining_accounts;
+    require!(accs.len() % 2 == 0, TimlgError::TicketPdaMismatch);
+    require!(accs.len() / 2 <= MAX_BATCH, TimlgError::TooManyEntries);
+
+    let admin_ai = ctx.accounts.admin.to_account_info();
+    let mut closed: u64 = 0;
+    for pair in accs.chunks(2) {
+        let (round_ai, timlg_vault_info) = (&pair[0], &pair[1]);
+        if round_ai.owner != ctx.program_id || !round_ai.is_writable || !timlg_vault_info.is_writable {
+            continue;
         }
-        // If no tickets were committed, token_settled might be false, which is fine.
-        let timlg_vault_info = ctx.accounts.timlg_vault.to_account_info();
-        let is_token_account = *timlg_vault_info.owner == ctx.accounts.token_program.key() && timlg_vault_info.data_len() == 165;
-
-        if is_token_account {
-             let data = ctx.accounts.timlg_vault.try_borrow_data()?;
-             let mut slice: &[u8] = &data;
-             let timlg_vault = TokenAccount::try_deserialize(&mut slice)?;
-             require!(
-                round.token_settled || round.committed_count == 0 || timlg_vault.amount == 0,
-                TimlgError::RoundTokensNotSettled
-             );
-             require!(timlg_vault.amount == 0, TimlgError::VaultNotEmpty);
+        let Ok(round) = round_ai
+            .try_borrow_data()
+            .map_err(|_| error!(TimlgError::AccountBorrowFailed))
+            .and_then(|data| deserialize_round_padded(&data))
+        else {
+            continue;
+        };
+
+        let round_le = round.round_id.to_le_bytes();
+        let (expected_round, _) = Pubkey::find_program_address(&[ROUND_SEED, &round_le], ctx.program_id);
+        let (expected_vault, _) = Pubkey::find_program_address(&[TIMLG_VAULT_SEED, &round_le], ctx.program_id);
+        if expected_round != round_ai.key()
+            || expected_vault != timlg_vault_info.key()
+            || require_round_closable(&round, timlg_vault_info, &ctx.accounts.token_program).is_err()
+            || release_open_round(&round, ctx.accounts.round_registry.as_deref_mut()).is_err()
+        {
+            continue;
         }
 
-        require!(round.swept, TimlgError::AlreadySwept);
-        (round.round_id, round.bump)
-    };
+        close_round_accounts(&round, round_ai, timlg_vault_info, &admin_ai, &ctx.accounts.token_program)?;
+        closed += 1;
+    }
+
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_rounds_closed = gs.total_rounds_closed.checked_add(closed).ok_or(TimlgError::MathOverflow)?;
+
+    set_return_data(&closed.to_le_bytes());
+    Ok(())
+}
+
+/// Frees the registry's open-round slot of a `create_round_auto` round being closed.
+fn release_open_round(round: &Round, registry: Option<&mut RoundRegistry>) -> Result<()> {
+    if !round.registry_tracked {
+        return Ok(());
+    }
+    let rr = registry.ok_or(TimlgError::RoundRegistryRequired)?;
+    rr.open_rounds = rr.open_rounds.saturating_sub(1);
+    Ok(())
+}
+
+/// `close_round` preconditions: the round is finalized (if anyone committed), swept,
+/// and its token vault (if any) is settled and empty.
+fn require_round_closable<'info>(
+    round: &Round,
+    timlg_vault_info: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    // Safety checks: ensure round is completely done
+    if round.committed_count > 0 {
+        require!(round.finalized, TimlgError::NotFinalized);
+    }
+    // If no tickets were committed, token_settled might be false, which is fine.
+    let is_token_account = *timlg_vault_info.owner == token_program.key() && timlg_vault_info.data_len() == 165;
+
+    if is_token_account {
+         let data = timlg_vault_info.try_borrow_data()?;
+         let mut slice: &[u8] = &data;
+         let timlg_vault = TokenAccount::try_deserialize(&mut slice)?;
+         require!(
+            round.token_settled || round.committed_count == 0 || timlg_vault.amount == 0,
+            TimlgError::RoundTokensNotSettled
+         );
+         require!(timlg_vault.amount == 0, TimlgError::VaultNotEmpty);
+    }
 
+    require!(round.swept, TimlgError::AlreadySwept);
+    Ok(())
+}
+
+/// Closes the round's token vault (legacy system-owned vaults are left alone) and the
+/// `Round` PDA, sending the rent to `dest_ai`.
+fn close_round_accounts<'info>(
+    round: &Round,
+    round_ai: &AccountInfo<'info>,
+    timlg_vault_info: &AccountInfo<'info>,
+    dest_ai: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
     // Close the Token Account via CPI (only if it is a TokenAccount)
-    let timlg_vault_info = ctx.accounts.timlg_vault.to_account_info();
-    let is_token_account = *timlg_vault_info.owner == ctx.accounts.token_program.key() && timlg_vault_info.data_len() == 165;
+    let is_token_account = *timlg_vault_info.owner == token_program.key() && timlg_vault_info.data_len() == 165;
 
     if is_token_account {
-        let round_id_bytes = round_id_val.to_le_bytes();
-        let bump = bump_val;
+        let round_id_bytes = round.round_id.to_le_bytes();
         let seeds = &[
             crate::ROUND_SEED,
             round_id_bytes.as_ref(),
-            &[bump],
+            &[round.bump],
         ];
         let signer = &[&seeds[..]];
 
         let cpi_accounts = token::CloseAccount {
             account: timlg_vault_info.clone(),
-            destination: ctx.accounts.admin.to_account_info(),
+            destination: dest_ai.clone(),
             authority: round_ai.clone(),
         };
         let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            token_program.to_account_info(),
             cpi_accounts,
             signer,
         );
@@ -429,73 +966,149 @@ pub fn close_round(ctx: Context<CloseRound>, round_id: u64) -> Result<()> {
     }
 
     // ✅ Manual Close of the Round PDA: transfer lamports and zero data
-    let dest_ai = ctx.accounts.admin.to_account_info();
-    let source_ai = round_ai.clone();
-    let source_lamports = source_ai.lamports();
+    let source_lamports = round_ai.lamports();
     let dest_lamports = dest_ai.lamports();
     **dest_ai.lamports.borrow_mut() = dest_lamports
         .checked_add(source_lamports)
         .ok_or(TimlgError::MathOverflow)?;
-    **source_ai.lamports.borrow_mut() = 0;
+    **round_ai.lamports.borrow_mut() = 0;
     
     // Zero out data to prevent any re-use
-    source_ai.data.borrow_mut().fill(0);
+    round_ai.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+/// Tears down a round nobody committed to: closes both vaults and the `Round` PDA
+/// (via `close = admin`) without the finalize/sweep sequence.
+pub fn cancel_round(ctx: Context<CancelRound>, round_id: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let round = &ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(round.committed_count == 0, TimlgError::RoundNotEmpty);
+    require!(!round.pulse_set, TimlgError::PulseAlreadySet);
+    require!(ctx.accounts.timlg_vault.amount == 0, TimlgError::VaultNotEmpty);
+    release_open_round(round, ctx.accounts.round_registry.as_deref_mut())?;
+
+    let round_le = round_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        token::CloseAccount {
+            account: ctx.accounts.timlg_vault.to_account_info(),
+            destination: ctx.accounts.admin.to_account_info(),
+            authority: ctx.accounts.round.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    // Draining a zero-data system account closes it.
+    let vault_ai = ctx.accounts.vault.to_account_info();
+    vault_lamports_transfer(
+        &vault_ai,
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        round,
+        vault_ai.lamports(),
+    )?;
 
-    // global stats
     let gs = &mut ctx.accounts.global_stats;
     gs.total_rounds_closed = gs.total_rounds_closed.checked_add(1).ok_or(TimlgError::MathOverflow)?;
-    
+
+    msg!("Round {} cancelled", round_id);
     Ok(())
 }
 
-pub fn recover_funds(ctx: Context<RecoverFunds>, round_id: u64) -> Result<()> {
-    let cfg = &ctx.accounts.config;
-    require!(!cfg.paused, TimlgError::Paused);
+/// Refund gate shared by the recover paths.
+/// A round is refundable when it was voided at finalize (`Refunding`), or when it is
+/// "stuck": never finalized, no pulse, and past the reveal deadline + timeout.
+fn require_refundable(round: &Round, current_slot: u64) -> Result<()> {
+    if round.state == RoundState::Refunding as u8 {
+        return Ok(());
+    }
 
-    let round = &mut ctx.accounts.round;
-    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
     require!(!round.finalized, TimlgError::AlreadyFinalized);
 
-    // Timeout Check: has the round been stuck for too long?
-    // "Stuck" means we passed the reveal deadline by a safety margin
-    // and the round was never finalized (no pulse, or oracle inactive).
-    let current_slot = Clock::get()?.slot;
-    
     // MVP-Refund: Configurable or hardcoded timeout.
-    let timeout_slots = REFUND_TIMEOUT_SLOTS; 
-
     require!(
-        current_slot > round.reveal_deadline_slot.saturating_add(timeout_slots),
+        current_slot > round.reveal_deadline_slot.saturating_add(REFUND_TIMEOUT_SLOTS),
         TimlgError::RefundTooEarly
     );
 
     // SECURITY: Cannot refund if pulse is already set (outcome determined), even if not finalized yet.
     require!(!round.pulse_set, TimlgError::PulseAlreadySet);
 
+    Ok(())
+}
+
+/// Not gated by `config.paused`: refunding a failed round is the emergency unwind path
+/// (same as `recover_funds_anyone`).
+pub fn recover_funds(ctx: Context<RecoverFunds>, round_id: u64) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+
+    let current_slot = Clock::get()?.slot;
+    require_refundable(round, current_slot)?;
+
     let ticket = &mut ctx.accounts.ticket;
     require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
     require!(!ticket.processed, TimlgError::TicketAlreadyProcessed);
     
-    // Refund: Transfer Stake from Vault -> User
-    // We only refund the STAKE amount (ticket price). rent is handled by 'close' logic.
-    let stake_amount = cfg.stake_amount;
+    // Refund: Transfer Stake from Vault -> User.
+    // The ticket rent goes back too: `close = user` runs on exit, after this transfer.
+    let stake_amount = cfg
+        .stake_amount
+        .checked_mul(ticket_weight(ticket))
+        .ok_or(TimlgError::MathOverflow)?;
 
-    let round_le = round_id.to_le_bytes();
-    let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
+    if round.stake_in_sol {
+        vault_lamports_transfer(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            round,
+            stake_amount,
+        )?;
+    } else {
+        let round_le = round_id.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
 
-    token::transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.timlg_vault.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
-                authority: round.to_account_info(),
-            },
-            signer_seeds,
-        ),
-        stake_amount,
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.timlg_vault.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: round.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            stake_amount,
+        )?;
+    }
+
+    // The round never produced an outcome: give the SOL service fee back too, exactly as
+    // charged at commit and to whoever paid it.
+    if ticket.sol_fee_paid > 0 {
+        require_keys_eq!(ctx.accounts.fee_payer.key(), ticket.fee_payer, TimlgError::Unauthorized);
+    }
+    refund_service_fee(
+        cfg,
+        round,
+        &ctx.accounts.treasury_sol.to_account_info(),
+        &ctx.accounts.fee_payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ticket.sol_fee_paid,
     )?;
 
+    // Update round stats (close_ticket skips processed tickets)
+    if round.committed_count > 0 {
+        round.committed_count -= 1;
+    }
+
     // ✅ Fix: Mark as processed to prevent double-refund and enable close_ticket
     ticket.processed = true;
 
@@ -530,8 +1143,79 @@ pub fn close_ticket(ctx: Context<CloseTicket>, round_id: u64, _nonce: u64) -> Re
     //    Ideally, when the ticket is fully "done" (processed).
     //    Or if the round is finalized and cleaned up.
     
+    close_ticket_guard(&ctx.accounts.round, round_id, ticket, user_stats)?;
+
+    // Context `close = user` handles the lamport transfer.
+    Ok(())
+}
+
+/// Closes several tickets of one round. `remaining_accounts[i]` is the ticket PDA for
+/// `nonces[i]`; every ticket must pass the `close_ticket` guard or the whole tx fails.
+pub fn close_ticket_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseTicketBatch<'info>>,
+    round_id: u64,
+    nonces: Vec<u64>,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+
+    require!(nonces.len() <= MAX_BATCH, TimlgError::TooManyEntries);
+    require!(
+        ctx.remaining_accounts.len() == nonces.len(),
+        TimlgError::TicketPdaMismatch
+    );
+
+    let current_slot = Clock::get()?.slot;
+    if ctx.accounts.user_stats.user == Pubkey::default() {
+        ctx.accounts.user_stats.user = ctx.accounts.user.key();
+        ctx.accounts.user_stats.bump = ctx.bumps.user_stats;
+        ctx.accounts.user_stats.last_reset_slot = current_slot;
+    }
+
+    let user_pk = ctx.accounts.user.key();
+    let user_ai = ctx.accounts.user.to_account_info();
+    let round_ai = ctx.accounts.round.to_account_info();
+    let user_stats = &mut ctx.accounts.user_stats;
+
+    for (i, nonce) in nonces.iter().enumerate() {
+        let ticket_ai = &ctx.remaining_accounts[i];
+        require!(ticket_ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+        verify_ticket_pda(cfg.seed_version, ticket_ai.key, round_id, &user_pk, *nonce, ctx.program_id)?;
+
+        let ticket: Ticket = {
+            let data = ticket_ai
+                .try_borrow_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            deserialize_ticket_padded(&data)?
+        };
+        require_keys_eq!(ticket.user, user_pk, TimlgError::Unauthorized);
+        require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
+        require!(ticket.nonce == *nonce, TimlgError::TicketPdaMismatch);
+
+        close_ticket_guard(&round_ai, round_id, &ticket, user_stats)?;
+
+        // Manual close: rent to user, zero data
+        let ticket_lamports = ticket_ai.lamports();
+        **user_ai.lamports.borrow_mut() = user_ai
+            .lamports()
+            .checked_add(ticket_lamports)
+            .ok_or(TimlgError::MathOverflow)?;
+        **ticket_ai.lamports.borrow_mut() = 0;
+        ticket_ai.data.borrow_mut().fill(0);
+    }
+
+    Ok(())
+}
+
+/// When can a ticket be closed (recover rent)? Shared by `close_ticket` and `close_ticket_batch`.
+fn close_ticket_guard(
+    round_ai: &AccountInfo,
+    round_id: u64,
+    ticket: &Ticket,
+    user_stats: &mut UserStats,
+) -> Result<()> {
     // Check if round is "alive"
-    let round_alive = ctx.accounts.round.lamports() > 0;
+    let round_alive = round_ai.lamports() > 0;
 
     if round_alive {
         // Condition A: Ticket processed (Settled or Refunded w/ new fix)
@@ -541,27 +1225,27 @@ pub fn close_ticket(ctx: Context<CloseTicket>, round_id: u64, _nonce: u64) -> Re
         // Need to deserialize round state from UncheckedAccount to check flags
         let mut is_refund_mode = false;
         let mut is_finalized_status = false;
+        // Once swept, an unclaimed winner can no longer claim (ClaimAfterSweep),
+        // so it must not be blocked from reclaiming the ticket rent either.
+        let mut is_swept = false;
+        // a win scored against a disputed pulse was never a win
+        let mut win = ticket.win;
         
-        if !ctx.accounts.round.data_is_empty() {
-             let round_data = ctx.accounts.round.try_borrow_data()?;
+        if !round_ai.data_is_empty() {
+             let round_data = round_ai.try_borrow_data()?;
              let data_len = round_data.len();
              
-             let round_state_opt = if data_len == 231 {
-                 let mut padded = [0u8; 248];
-                 padded[..231].copy_from_slice(&round_data);
-                 let mut slice: &[u8] = &padded;
-                 Round::try_deserialize(&mut slice).ok()
-             } else {
-                 let mut slice: &[u8] = &round_data;
-                 Round::try_deserialize(&mut slice).ok()
-             };
+             let round_state_opt = deserialize_round_padded(&round_data).ok();
 
              if let Some(mut round_state) = round_state_opt {
                  if round_state.round_id == round_id {
                      let current_slot = Clock::get()?.slot;
-                     is_refund_mode = !round_state.pulse_set && 
-                                      current_slot > round_state.reveal_deadline_slot.saturating_add(REFUND_TIMEOUT_SLOTS);
+                     is_refund_mode = round_state.state == RoundState::Refunding as u8
+                                      || (!round_state.pulse_set &&
+                                      current_slot > round_state.reveal_deadline_slot.saturating_add(REFUND_TIMEOUT_SLOTS));
                      is_finalized_status = round_state.finalized;
+                     is_swept = round_state.swept;
+                     win = ticket.win && !reveal_voided(&round_state, ticket);
 
                      if !is_processed && (is_refund_mode || is_finalized_status) {
                           let mut changed = false;
@@ -569,14 +1253,18 @@ pub fn close_ticket(ctx: Context<CloseTicket>, round_id: u64, _nonce: u64) -> Re
                               round_state.committed_count -= 1;
                               changed = true;
                           }
-                          if round_state.committed_count == round_state.settled_count && round_state.finalized {
-                              round_state.token_settled = true;
-                              round_state.token_settled_slot = current_slot;
+                          // Legacy 231-byte rounds only get `swept` written back below.
+                          if data_len != 231
+                              && round_state.committed_count == round_state.settled_count
+                              && round_state.finalized
+                              && !round_state.token_settled
+                          {
+                              mark_settlement_complete(&mut round_state, current_slot);
                               changed = true;
                           }
                           if changed {
                               drop(round_data);
-                              let mut round_data_mut = ctx.accounts.round.try_borrow_mut_data()?;
+                              let mut round_data_mut = round_ai.try_borrow_mut_data()?;
                               if data_len == 231 {
                                   round_data_mut[165] = if round_state.swept { 1 } else { 0 }; 
                               } else {
@@ -590,21 +1278,8 @@ pub fn close_ticket(ctx: Context<CloseTicket>, round_id: u64, _nonce: u64) -> Re
         }
 
         if is_processed || is_refund_mode || is_finalized_status {
-            if ticket.win && !ticket.claimed && !is_refund_mode { 
-                if !is_finalized_status {
-                    require!(ticket.claimed, TimlgError::WinnerMustClaimFirst);
-                } else {
-                    let round_data = ctx.accounts.round.try_borrow_data()?;
-                    let swept = if round_data.len() == 231 {
-                        round_data[165] == 1
-                    } else {
-                        let mut slice: &[u8] = &round_data;
-                        Round::try_deserialize(&mut slice).map(|r| r.swept).unwrap_or(false)
-                    };
-                    if !swept {
-                         require!(ticket.claimed, TimlgError::WinnerMustClaimFirst);
-                    }
-                }
+            if win && !ticket.claimed && !is_refund_mode && !is_swept {
+                return Err(error!(TimlgError::WinnerMustClaimFirst));
             }
             // OK to close
         } else {
@@ -613,39 +1288,35 @@ pub fn close_ticket(ctx: Context<CloseTicket>, round_id: u64, _nonce: u64) -> Re
         
         // Count as swept ONLY if the ticket was a winner but wasn't claimed.
         // Expired and Loss tickets just die here (reclaiming rent is silent).
-        if ticket.win && !ticket.claimed && !is_refund_mode {
-            if ticket.created_slot >= user_stats.last_reset_slot {
-                user_stats.tickets_swept = user_stats.tickets_swept.saturating_add(1);
-            }
+        if win && !ticket.claimed && !is_refund_mode && ticket.created_slot >= user_stats.last_reset_slot {
+            user_stats.tickets_swept = user_stats.tickets_swept.saturating_add(1);
         }
     } else {
         // If round is dead (deleted/archived), allow closing any ticket to reclaim rent.
         // It's safe because the round state no longer exists to pay out rewards.
     }
 
-    // Context `close = user` handles the lamport transfer.
     Ok(())
 }
 
 pub fn recover_funds_anyone(ctx: Context<RecoverFundsAnyone>, round_id: u64) -> Result<()> {
-    let cfg = &ctx.accounts.config;
+    let cfg = &mut ctx.accounts.config;
     let round = &mut ctx.accounts.round;
     require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
-    require!(!round.finalized, TimlgError::AlreadyFinalized);
 
     let current_slot = Clock::get()?.slot;
-    let timeout_slots = REFUND_TIMEOUT_SLOTS;
-
-    require!(
-        current_slot > round.reveal_deadline_slot.saturating_add(timeout_slots),
-        TimlgError::RefundTooEarly
-    );
+    require_refundable(round, current_slot)?;
+    require_spl_stake(round)?;
 
     let ticket = &mut ctx.accounts.ticket; // Mutable for processed flag
     require!(!ticket.processed, TimlgError::TicketAlreadyProcessed);
 
-    // Refund: Transfer Stake from Vault -> User
-    let stake_amount = cfg.stake_amount;
+    // Refund: Transfer Stake from Vault -> User.
+    // `close = user` (has_one = user) sends the ticket rent to the owner, not the cranker.
+    let stake_amount = cfg
+        .stake_amount
+        .checked_mul(ticket_weight(ticket))
+        .ok_or(TimlgError::MathOverflow)?;
 
     let round_le = round_id.to_le_bytes();
     let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
@@ -663,6 +1334,24 @@ pub fn recover_funds_anyone(ctx: Context<RecoverFundsAnyone>, round_id: u64) ->
         stake_amount,
     )?;
 
+    if ticket.sol_fee_paid > 0 {
+        require_keys_eq!(ctx.accounts.fee_payer.key(), ticket.fee_payer, TimlgError::Unauthorized);
+    }
+    refund_service_fee(
+        cfg,
+        round,
+        &ctx.accounts.treasury_sol.to_account_info(),
+        &ctx.accounts.fee_payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ticket.sol_fee_paid,
+    )?;
+    pay_cranker_fee(
+        cfg,
+        &ctx.accounts.treasury_sol.to_account_info(),
+        &ctx.accounts.cranker.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
     // Update round stats
     if round.committed_count > 0 {
         round.committed_count -= 1;
@@ -671,8 +1360,6 @@ pub fn recover_funds_anyone(ctx: Context<RecoverFundsAnyone>, round_id: u64) ->
     // ✅ Fix: Mark as processed
     ticket.processed = true;
 
-    let current_slot = Clock::get()?.slot;
-
     if ctx.accounts.user_stats.user == Pubkey::default() {
         ctx.accounts.user_stats.user = ctx.accounts.user.key();
         ctx.accounts.user_stats.bump = ctx.bumps.user_stats;
@@ -687,6 +1374,105 @@ pub fn recover_funds_anyone(ctx: Context<RecoverFundsAnyone>, round_id: u64) ->
     Ok(())
 }
 
+/// Refunds many tickets of a stuck round in one crank.
+/// remaining_accounts: `[ticket, user_token_account, fee_payer]` per ticket (max `MAX_BATCH`);
+/// `fee_payer` must be `ticket.fee_payer` when the ticket recorded a service fee, which is
+/// returned to it like in `recover_funds`. Already-processed tickets are skipped, so the
+/// crank is idempotent. Tickets stay open (owners reclaim rent with `close_ticket`) with
+/// `stake_paid` cleared, and user stats are not touched.
+pub fn recover_funds_anyone_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RecoverFundsAnyoneBatch<'info>>,
+    round_id: u64,
+) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+
+    let current_slot = Clock::get()?.slot;
+    require_refundable(round, current_slot)?;
+    require_spl_stake(round)?;
+
+    let entries = ctx.remaining_accounts.len() / 3;
+    require!(ctx.remaining_accounts.len() % 3 == 0, TimlgError::TicketPdaMismatch);
+    require!(entries <= MAX_BATCH, TimlgError::TooManyEntries);
+
+    let round_ai = round.to_account_info();
+    let round_le = round_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[ROUND_SEED, &round_le, &[round.bump]]];
+
+    for entry in ctx.remaining_accounts.chunks(3) {
+        let (ticket_ai, user_ata_ai, fee_payer_ai) = (&entry[0], &entry[1], &entry[2]);
+        require!(ticket_ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+
+        let mut ticket: Ticket = {
+            let data = ticket_ai
+                .try_borrow_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            deserialize_ticket_padded(&data)?
+        };
+        verify_ticket_pda(
+            cfg.seed_version,
+            ticket_ai.key,
+            round_id,
+            &ticket.user,
+            ticket.nonce,
+            ctx.program_id,
+        )?;
+        require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
+
+        if ticket.processed {
+            continue;
+        }
+
+        let user_ata = Account::<TokenAccount>::try_from(user_ata_ai)?;
+        require_keys_eq!(user_ata.owner, ticket.user, TimlgError::Unauthorized);
+        require_keys_eq!(user_ata.mint, cfg.timlg_mint, TimlgError::Unauthorized);
+        let stake_amount = cfg
+            .stake_amount
+            .checked_mul(ticket_weight(&ticket))
+            .ok_or(TimlgError::MathOverflow)?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.timlg_vault.to_account_info(),
+                    to: user_ata_ai.clone(),
+                    authority: round_ai.clone(),
+                },
+                signer_seeds,
+            ),
+            stake_amount,
+        )?;
+
+        if ticket.sol_fee_paid > 0 {
+            require_keys_eq!(*fee_payer_ai.key, ticket.fee_payer, TimlgError::Unauthorized);
+        }
+        refund_service_fee(
+            cfg,
+            round,
+            &ctx.accounts.treasury_sol.to_account_info(),
+            fee_payer_ai,
+            &ctx.accounts.system_program.to_account_info(),
+            ticket.sol_fee_paid,
+        )?;
+
+        if round.committed_count > 0 {
+            round.committed_count -= 1;
+        }
+
+        ticket.processed = true;
+        // refunded: reveal and claim paths require `stake_paid`
+        ticket.stake_paid = false;
+        let mut data = ticket_ai
+            .try_borrow_mut_data()
+            .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+        serialize_ticket_into(&ticket, &mut data)?;
+    }
+
+    Ok(())
+}
+
 pub fn close_user_stats(ctx: Context<CloseUserStats>) -> Result<()> {
     let user_stats = &ctx.accounts.user_stats;
     let user = &ctx.accounts.user;
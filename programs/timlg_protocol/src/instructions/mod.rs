@@ -6,3 +6,5 @@ pub mod oracle;
 pub mod lifecycle;
 pub mod escrow;
 pub mod oracle_set;
+pub mod health;
+pub mod status;
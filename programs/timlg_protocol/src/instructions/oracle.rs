@@ -1,13 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked,
-};
 
 use crate::{
     errors::TimlgError,
-    state::RoundState,
-    utils::{assert_ed25519_ix_matches, expected_pulse_msg},
-    SetOraclePubkey, SetPulseSigned,
+    events::{PulseAttested, PulseDisputed, PulseSet, RevealDeadlineExtended},
+    state::{Config, Round, RoundState},
+    utils::{
+        advance_round_state, apply_deadline_extension, ed25519_ix_signer, ed25519_ixs_before_current, expected_pulse_msg,
+        oracle_set_digest, require_ed25519_ix_present, require_nondegenerate_pulse, round_oracle,
+    },
+    DisputePulse, FinalizePulse, SetMinPulsePopcount, SetOraclePubkey, SetPulseDisputeWindow,
+    SetPulseMultisig, SetPulseSigned, SubmitAttestation,
     constants::LATE_PULSE_SAFETY_BUFFER_SLOTS,
 };
 
@@ -19,21 +21,248 @@ pub fn set_oracle_pubkey(ctx: Context<SetOraclePubkey>, oracle_pubkey: Pubkey) -
     Ok(())
 }
 
-// Tx layout must be: [ ed25519_verify, set_pulse_signed ]
+// Tx must carry an ed25519_verify ix somewhere before set_pulse_signed (not necessarily adjacent).
 pub fn set_pulse_signed(ctx: Context<SetPulseSigned>, round_id: u64, pulse: [u8; 64]) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
 
+    let round = &mut ctx.accounts.round;
+
     // opcional, pero recomendable si config.oracle_pubkey puede ser Pubkey::default()
-    require!(cfg.oracle_pubkey != Pubkey::default(), TimlgError::OracleNotSet);
+    let oracle = round_oracle(cfg, round);
+    require!(oracle != Pubkey::default(), TimlgError::OracleNotSet);
+
+    let current_slot = Clock::get()?.slot;
+    require_pulse_window(cfg, round, round_id, current_slot)?;
+    require_nondegenerate_pulse(&pulse, cfg.min_pulse_popcount)?;
+    // a disputed pulse is replaced by the oracle threshold, never by one key again
+    require!(round.pulse_disputed_slot == 0, TimlgError::PulseReplacementNotAllowed);
+
+    // --- ed25519 introspection ---
+    let ed_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+
+    // build expected message (canonical)
+    let expected = expected_pulse_msg(
+        ctx.program_id,
+        round_id,
+        round.pulse_index_target,
+        &pulse,
+    );
+
+    // validate ed25519 ix pubkey + msg
+    require_ed25519_ix_present(&ed_ixs, &oracle, expected.as_slice())?;
+
+    // commit state
+    round.pulse = pulse;
+    round.pulse_set = true;
+    round.pulse_set_slot = current_slot;
+    advance_round_state(round, RoundState::PulseSet);
+    round.pulse_attestations = 1;
+    
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_pulses_published = gs.total_pulses_published.checked_add(1).unwrap_or(gs.total_pulses_published);
+
+    emit!(PulseSet {
+        round_id,
+        pulse_index_target: round.pulse_index_target,
+        pulse_set_slot: current_slot,
+        oracle,
+    });
+
+    Ok(())
+}
 
+/// `set_pulse_signed` that also records the commit window as closed (`commit_frozen`,
+/// `commit_closed_slot`), so one crank ix moves the round from commit to reveal.
+/// Same accounts and ed25519 requirements as `set_pulse_signed`; finalize stays separate.
+pub fn set_pulse_and_close_commit(ctx: Context<SetPulseSigned>, round_id: u64, pulse: [u8; 64]) -> Result<()> {
     let round = &mut ctx.accounts.round;
+    if !round.commit_frozen {
+        round.commit_frozen = true;
+        round.commit_closed_slot = Clock::get()?.slot;
+    }
+    set_pulse_signed(ctx, round_id, pulse)
+}
+
+// Tx must carry N ed25519_verify ixs before set_pulse_multisig (any position).
+// Each ed25519 ix signs the canonical pulse msg with a distinct OracleSet key; N >= threshold.
+pub fn set_pulse_multisig(
+    ctx: Context<SetPulseMultisig>,
+    round_id: u64,
+    pulse: [u8; 64],
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+
+    let os = &ctx.accounts.oracle_set;
+    let round = &mut ctx.accounts.round;
+    let current_slot = Clock::get()?.slot;
+    require_pulse_window(cfg, round, round_id, current_slot)?;
+    require_nondegenerate_pulse(&pulse, cfg.min_pulse_popcount)?;
+
+    let expected = expected_pulse_msg(
+        ctx.program_id,
+        round_id,
+        round.pulse_index_target,
+        &pulse,
+    );
+
+    // every ed25519 ix before this one that signs the pulse msg counts; others are ignored
+    let ed_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+    let mut signers: Vec<Pubkey> = Vec::with_capacity(os.oracles.len());
+    for ix in ed_ixs.iter() {
+        let Ok(signer) = ed25519_ix_signer(ix, expected.as_slice()) else {
+            continue;
+        };
+        require!(os.oracles.contains(&signer), TimlgError::OracleNotFound);
+        require!(!signers.contains(&signer), TimlgError::DuplicateAttestation);
+        signers.push(signer);
+    }
+
+    require!(
+        os.threshold > 0 && signers.len() >= os.threshold as usize,
+        TimlgError::InsufficientAttestations
+    );
+
+    round.pulse = pulse;
+    round.pulse_set = true;
+    round.pulse_set_slot = current_slot;
+    advance_round_state(round, RoundState::PulseSet);
+    round.pulse_attestations = signers.len() as u8;
+
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_pulses_published = gs.total_pulses_published.checked_add(1).unwrap_or(gs.total_pulses_published);
+
+    emit!(PulseSet {
+        round_id,
+        pulse_index_target: round.pulse_index_target,
+        pulse_set_slot: current_slot,
+        oracle: signers[0],
+    });
+
+    Ok(())
+}
+
+/// Records one oracle's attestation in the round's `PulseAttestations` accumulator.
+///
+/// Tx must carry an ed25519_verify ix (before this one) signing the canonical pulse msg with
+/// an OracleSet key. The first attestation fixes the candidate pulse; later ones must match
+/// it exactly, and each oracle counts once. Accepted until finalize, also once a pulse is
+/// stored: attestations of a different pulse are the evidence for `dispute_pulse`.
+pub fn submit_attestation(
+    ctx: Context<SubmitAttestation>,
+    round_id: u64,
+    pulse: [u8; 64],
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+
+    let os = &ctx.accounts.oracle_set;
+    let round = &ctx.accounts.round;
     require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
 
-    // window checks
     let current_slot = Clock::get()?.slot;
     require!(current_slot >= round.commit_deadline_slot, TimlgError::CommitClosed);
     require!(!round.finalized, TimlgError::RoundFinalized);
+    require_nondegenerate_pulse(&pulse, cfg.min_pulse_popcount)?;
+
+    let digest = oracle_set_digest(os);
+    let att = &mut ctx.accounts.attestations;
+    if att.count > 0 {
+        require!(att.pulse == pulse, TimlgError::AttestationPulseMismatch);
+        require!(att.oracle_set_digest == digest, TimlgError::OracleSetChanged);
+    }
+
+    let expected = expected_pulse_msg(
+        ctx.program_id,
+        round_id,
+        round.pulse_index_target,
+        &pulse,
+    );
+
+    // the nearest ed25519 ix that signs the pulse msg is the attestation
+    let ed_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+    let signer = ed_ixs
+        .iter()
+        .find_map(|ix| ed25519_ix_signer(ix, expected.as_slice()).ok())
+        .ok_or_else(|| error!(TimlgError::MissingOrInvalidEd25519Ix))?;
+    let idx = os
+        .oracles
+        .iter()
+        .position(|k| *k == signer)
+        .ok_or_else(|| error!(TimlgError::OracleNotFound))?;
+
+    let bit = 1u16 << idx;
+    require!(att.attested & bit == 0, TimlgError::DuplicateAttestation);
+
+    if att.count == 0 {
+        att.round_id = round_id;
+        att.bump = ctx.bumps.attestations;
+        att.pulse = pulse;
+        att.oracle_set_digest = digest;
+        att.first_slot = current_slot;
+    }
+    att.attested |= bit;
+    att.count = att.count.checked_add(1).ok_or_else(|| error!(TimlgError::MathOverflow))?;
+
+    emit!(PulseAttested {
+        round_id,
+        oracle: signer,
+        count: att.count,
+        threshold: os.threshold,
+        slot: current_slot,
+    });
+
+    Ok(())
+}
+
+/// Copies the accumulated pulse into the round once `oracle_set.threshold` distinct oracles
+/// attested it. Permissionless; same timing checks as the other pulse setters.
+pub fn finalize_pulse(ctx: Context<FinalizePulse>, round_id: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+
+    let os = &ctx.accounts.oracle_set;
+    let att = &ctx.accounts.attestations;
+    let round = &mut ctx.accounts.round;
+    let current_slot = Clock::get()?.slot;
+    require_pulse_window(cfg, round, round_id, current_slot)?;
+
+    require!(
+        os.threshold > 0 && att.count >= os.threshold,
+        TimlgError::InsufficientAttestations
+    );
+    require!(att.oracle_set_digest == oracle_set_digest(os), TimlgError::OracleSetChanged);
+    require_nondegenerate_pulse(&att.pulse, cfg.min_pulse_popcount)?;
+
+    round.pulse = att.pulse;
+    round.pulse_set = true;
+    round.pulse_set_slot = current_slot;
+    advance_round_state(round, RoundState::PulseSet);
+    round.pulse_attestations = att.count;
+
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_pulses_published = gs.total_pulses_published.checked_add(1).unwrap_or(gs.total_pulses_published);
+
+    emit!(PulseSet {
+        round_id,
+        pulse_index_target: round.pulse_index_target,
+        pulse_set_slot: current_slot,
+        oracle: os.oracles[att.attested.trailing_zeros() as usize],
+    });
+
+    Ok(())
+}
+
+/// Shared timing/one-shot checks for every pulse setter.
+/// With `config.auto_extend_reveal`, a late (but not expired) pulse first pushes the reveal
+/// deadline to `current_slot + reveal_window_slots`; it never shortens it.
+fn require_pulse_window(cfg: &Config, round: &mut Round, round_id: u64, current_slot: u64) -> Result<()> {
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+
+    // window checks
+    require!(current_slot >= round.commit_deadline_slot, TimlgError::CommitClosed);
+    require!(!round.finalized, TimlgError::RoundFinalized);
 
     // Liveness Hazard Check:
     // If we are too close to (or past) the reveal deadline, we must reject the pulse.
@@ -45,6 +274,24 @@ pub fn set_pulse_signed(ctx: Context<SetPulseSigned>, round_id: u64, pulse: [u8;
     if current_slot >= round.reveal_deadline_slot.saturating_sub(min_reveal_window) {
         msg!("PulseTooLate Triggered: current={} deadline={} limit={}", 
             current_slot, round.reveal_deadline_slot, round.reveal_deadline_slot.saturating_sub(min_reveal_window));
+
+        let new_deadline = current_slot.saturating_add(cfg.reveal_window_slots);
+        if cfg.auto_extend_reveal
+            && !round.pulse_set
+            && current_slot <= round.reveal_deadline_slot
+            && new_deadline > round.reveal_deadline_slot
+        {
+            let old_deadline = round.reveal_deadline_slot;
+            apply_deadline_extension(round, cfg, new_deadline - old_deadline)?;
+            round.reveal_extended_slot = current_slot;
+
+            emit!(RevealDeadlineExtended {
+                round_id,
+                old_reveal_deadline_slot: old_deadline,
+                new_reveal_deadline_slot: round.reveal_deadline_slot,
+                slot: current_slot,
+            });
+        }
     }
 
     require!(
@@ -54,34 +301,117 @@ pub fn set_pulse_signed(ctx: Context<SetPulseSigned>, round_id: u64, pulse: [u8;
 
     // one-shot
     require!(!round.pulse_set, TimlgError::PulseAlreadySet);
+    // voided reveals are told apart by slot (see `reveal_voided`)
+    require!(current_slot > round.pulse_disputed_slot, TimlgError::PulseReplacementNotAllowed);
 
-    // --- ed25519 introspection ---
-    let ix_sys = ctx.accounts.instructions.to_account_info();
-    let current_ix = load_current_index_checked(&ix_sys)? as usize;
-    require!(current_ix >= 1, TimlgError::MissingOrInvalidEd25519Ix);
+    Ok(())
+}
 
-    let ed_ix = load_instruction_at_checked(current_ix - 1, &ix_sys)
-        .map_err(|_| error!(TimlgError::MissingOrInvalidEd25519Ix))?;
+pub fn set_pulse_dispute_window(ctx: Context<SetPulseDisputeWindow>, slots: u64) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
 
-    // build expected message (canonical)
-    let expected = expected_pulse_msg(
-        ctx.program_id,
+    cfg.pulse_dispute_window_slots = slots;
+    Ok(())
+}
+
+pub fn set_min_pulse_popcount(ctx: Context<SetMinPulsePopcount>, min_pulse_popcount: u32) -> Result<()> {
+    let cfg = &mut ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+    // a 64-byte pulse has 512 bits
+    require!(min_pulse_popcount <= 512, TimlgError::InvalidThreshold);
+
+    cfg.min_pulse_popcount = min_pulse_popcount;
+    Ok(())
+}
+
+/// Clears a bad pulse and returns the round to pulse-pending so a new one can be set.
+///
+/// Only for multi-oracle deployments, only if the stored pulse was signed by fewer than
+/// `oracle_set.threshold` oracles (`round.pulse_attestations`; a `set_pulse_signed` pulse
+/// has one), and only with evidence: the round's `PulseAttestations`, accumulated under the
+/// current oracle set (before or after the pulse landed), must hold a different pulse. Also
+/// gated on the dispute window and no finalize.
+///
+/// Reveals already made against the cleared pulse are voided rather than blocking the
+/// dispute: the round's reveal counters and transcript restart from zero and those tickets
+/// read as unrevealed (`utils::reveal_voided`) until revealed again. Lifetime stats
+/// (user, user-round, config and global totals) keep the voided reveals and do not count
+/// the re-reveals (see `utils::clear_voided_reveal`). The attestations are reset,
+/// and the replacement pulse must come from the oracle threshold (`set_pulse_multisig` or
+/// `finalize_pulse`), not `set_pulse_signed`.
+///
+/// PulseSet -> Announced is the single sanctioned backward step of `round.state`; every
+/// other transition goes through `advance_round_state`. Only a round still in PulseSet can
+/// take it, so a voided (Refunding) round is never reopened.
+pub fn dispute_pulse(ctx: Context<DisputePulse>, round_id: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let os = &ctx.accounts.oracle_set;
+    let threshold = os.threshold;
+    let att = &mut ctx.accounts.attestations;
+    let round = &mut ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(round.pulse_set, TimlgError::PulseNotSet);
+    require!(
+        round.state == RoundState::PulseSet as u8,
+        TimlgError::PulseDisputeNotAllowed
+    );
+    require!(
+        threshold > 1 && round.pulse_attestations < threshold && !round.finalized,
+        TimlgError::PulseDisputeNotAllowed
+    );
+    // the oracles attested something else than what was stored
+    require!(
+        att.count > 0 && att.oracle_set_digest == oracle_set_digest(os) && att.pulse != round.pulse,
+        TimlgError::PulseDisputeNotAllowed
+    );
+
+    let current_slot = Clock::get()?.slot;
+    require!(
+        cfg.pulse_dispute_window_slots > 0
+            && current_slot <= round.pulse_set_slot.saturating_add(cfg.pulse_dispute_window_slots),
+        TimlgError::PulseDisputeWindowClosed
+    );
+
+    emit!(PulseDisputed {
         round_id,
-        round.pulse_index_target,
-        &pulse,
+        admin: ctx.accounts.admin.key(),
+        disputed_pulse: round.pulse,
+        pulse_set_slot: round.pulse_set_slot,
+        oracle_threshold: threshold,
+        voided_reveals: round.revealed_count,
+        slot: current_slot,
+    });
+    msg!(
+        "PULSE DISPUTED: round={} pulse_set_slot={} voided_reveals={}",
+        round_id,
+        round.pulse_set_slot,
+        round.revealed_count
     );
 
-    // validate ed25519 ix pubkey + msg
-    assert_ed25519_ix_matches(&ed_ix, &cfg.oracle_pubkey, expected.as_slice())?;
+    round.pulse = [0u8; 64];
+    round.pulse_set = false;
+    round.pulse_set_slot = 0;
+    // the one step back the state machine allows (checked above: PulseSet only)
+    round.state = RoundState::Announced as u8;
+    round.pulse_attestations = 0;
+    round.pulse_disputed_slot = current_slot;
 
-    // commit state
-    round.pulse = pulse;
-    round.pulse_set = true;
-    round.pulse_set_slot = current_slot;
-    round.state = RoundState::PulseSet as u8;
-    
-    let gs = &mut ctx.accounts.global_stats;
-    gs.total_pulses_published = gs.total_pulses_published.checked_add(1).unwrap_or(gs.total_pulses_published);
+    // void the reveals scored against the cleared pulse (nothing is claimable before finalize)
+    round.revealed_count = 0;
+    round.win_count = 0;
+    round.win_revealed_count = 0;
+    round.win_extra_weight = 0;
+    round.reveal_transcript = [0u8; 32];
+
+    // fresh accumulator for the replacement pulse
+    att.pulse = [0u8; 64];
+    att.attested = 0;
+    att.count = 0;
+    att.oracle_set_digest = [0u8; 32];
+    att.first_slot = 0;
 
     Ok(())
 }
@@ -3,7 +3,7 @@ use anchor_lang::prelude::*;
 use crate::{
     errors::TimlgError,
     state::{Config, OracleSet},
-    InitializeOracleSet, AddOracle, RemoveOracle, SetOracleThreshold,
+    InitializeOracleSet, AddOracle, RemoveOracle, SetOracleThreshold, ClearOracleSet,
     MAX_ORACLES,
 };
 
@@ -89,7 +89,8 @@ pub fn set_oracle_threshold(ctx: Context<SetOracleThreshold>, threshold: u8) ->
 
     let os: &mut Account<OracleSet> = &mut ctx.accounts.oracle_set;
 
-    require!(threshold > 0, TimlgError::InvalidThreshold);
+    // 0 only describes a decommissioned (empty) set; see `clear_oracle_set`.
+    require!(threshold > 0 || os.oracles.is_empty(), TimlgError::InvalidThreshold);
     require!((threshold as usize) <= MAX_ORACLES, TimlgError::InvalidThreshold);
 
     // Threshold cannot be higher than allowlist size.
@@ -102,3 +103,16 @@ pub fn set_oracle_threshold(ctx: Context<SetOracleThreshold>, threshold: u8) ->
 
     Ok(())
 }
+
+/// Decommissions the oracle set: empties the allowlist and resets the threshold to 0.
+/// Rebuild with `add_oracle` then `set_oracle_threshold`.
+pub fn clear_oracle_set(ctx: Context<ClearOracleSet>) -> Result<()> {
+    let cfg: &Account<Config> = &ctx.accounts.config;
+    require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), TimlgError::Unauthorized);
+
+    let os: &mut Account<OracleSet> = &mut ctx.accounts.oracle_set;
+    os.oracles.clear();
+    os.threshold = 0;
+
+    Ok(())
+}
@@ -1,16 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked,
-};
 
 use crate::{
     errors::TimlgError,
-    state::{Round, Ticket},
+    events::RevealMade,
+    state::{Config, Round, Ticket},
     utils::{
-        MAX_BATCH, TICKET_SEED, expected_reveal_msg, assert_ed25519_ix_matches, 
-        reveal_core, RevealEntry, RevealSignedEntry
+        MAX_BATCH, verify_ticket_pda, expected_reveal_msg, ed25519_ixs_before_current, effective_reveal_deadline,
+        ed25519_sigs, require_ed25519_sig_present, reveal_core, RevealEntry, RevealSignedEntry, MerkleRevealEntry, merkle_reveal_leaf,
+        merkle_verify, record_win_weight, deserialize_ticket_padded, serialize_ticket_into, require_guess_in_range, reveal_transcript_next,
+        clear_voided_reveal, require_commit_open,
     },
-    RevealBatch, RevealBatchSigned, RevealTicket,
+    CommitRevealRoot, RevealBatch, RevealBatchSigned, RevealTicket,
 };
 
 pub fn update_streak(user_stats: &mut crate::state::UserStats, ticket: &Ticket) {
@@ -43,17 +43,29 @@ pub fn update_streak(user_stats: &mut crate::state::UserStats, ticket: &Ticket)
     }
 }
 
+/// Round counters always count the reveal; the global ones skip a re-reveal after
+/// `dispute_pulse` (`re_reveal`), whose voided reveal they already counted.
 #[inline(always)]
-fn inc_reveal_counters(round: &mut Round, gs: &mut crate::state::GlobalStats, did_win: bool) -> Result<()> {
+fn inc_reveal_counters(
+    round: &mut Round,
+    gs: &mut crate::state::GlobalStats,
+    ticket: &Ticket,
+    re_reveal: bool,
+) -> Result<()> {
     round.revealed_count = round
         .revealed_count
         .checked_add(1)
         .ok_or_else(|| error!(TimlgError::MathOverflow))?;
 
     // global stats
-    gs.total_reveals = gs.total_reveals.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    if !re_reveal {
+        gs.total_reveals = gs.total_reveals.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    }
+
+    // transcript: folded here (not in reveal_core) so lenient batches only chain persisted reveals
+    round.reveal_transcript = reveal_transcript_next(&round.reveal_transcript, ticket);
 
-    if did_win {
+    if ticket.win {
         round.win_count = round
             .win_count
             .checked_add(1)
@@ -63,13 +75,31 @@ fn inc_reveal_counters(round: &mut Round, gs: &mut crate::state::GlobalStats, di
             .win_revealed_count
             .checked_add(1)
             .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        record_win_weight(round, ticket)?;
 
         // global stats
-        gs.total_wins = gs.total_wins.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+        if !re_reveal {
+            gs.total_wins = gs.total_wins.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+        }
     }
     Ok(())
 }
 
+/// Reveal-phase gate shared by every reveal path. Pulse is checked before the
+/// deadline so a missing oracle pulse (`PulseNotSet`) is never reported as a
+/// missed window (`RevealClosed`). The deadline honours `min_reveal_after_pulse_slots`.
+/// A swept round is always finalized too, so `RevealAfterSweep` is checked first.
+#[inline(always)]
+fn require_reveal_open(round: &Round, cfg: &Config, current_slot: u64) -> Result<()> {
+    require!(!round.paused, TimlgError::RoundPaused);
+    require!(!round.public_mode, TimlgError::PublicRoundRevealNotAllowed);
+    require!(!round.swept, TimlgError::RevealAfterSweep);
+    require!(!round.finalized, TimlgError::RoundFinalized);
+    require!(round.pulse_set, TimlgError::PulseNotSet);
+    require!(current_slot <= effective_reveal_deadline(round, cfg), TimlgError::RevealClosed);
+    Ok(())
+}
+
 pub fn reveal_ticket(
     ctx: Context<RevealTicket>,
     round_id: u64,
@@ -79,17 +109,15 @@ pub fn reveal_ticket(
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
-    require!(guess <= 1, TimlgError::InvalidGuess);
 
     // ✅ round mutable para actualizar contadores
     let round = &mut ctx.accounts.round;
-    require!(!round.finalized, TimlgError::RoundFinalized);
-
     let current_slot = Clock::get()?.slot;
-    require!(current_slot <= round.reveal_deadline_slot, TimlgError::RevealClosed);
-    require!(round.pulse_set, TimlgError::PulseNotSet);
+    require_reveal_open(round, cfg, current_slot)?;
+    require_guess_in_range(round, guess)?;
 
     let ticket = &mut ctx.accounts.ticket;
+    let re_reveal = clear_voided_reveal(round, ticket);
     require!(!ticket.revealed, TimlgError::AlreadyRevealed);
 
     // reveal_core necesita &Round (no &mut Round)
@@ -105,10 +133,33 @@ pub fn reveal_ticket(
     )?;
 
     // ✅ counters (solo 1 vez: ya garantizamos !ticket.revealed arriba)
-    inc_reveal_counters(round, &mut ctx.accounts.global_stats, ticket.win)?;
+    inc_reveal_counters(round, &mut ctx.accounts.global_stats, ticket, re_reveal)?;
+
+    // lifetime stats already hold the voided reveal of a re-revealed ticket
+    if !re_reveal {
+        update_streak(&mut ctx.accounts.user_stats, ticket);
+    }
+
+    emit!(RevealMade {
+        round_id,
+        user: ticket.user,
+        nonce,
+        guess,
+        win: ticket.win,
+    });
 
-    let user_stats = &mut ctx.accounts.user_stats;
-    update_streak(user_stats, ticket);
+    if let Some(escrow) = ctx.accounts.user_escrow.as_mut() {
+        escrow.last_reveal_slot = current_slot;
+    }
+    if !re_reveal {
+        if let Some(urs) = ctx.accounts.user_round_stats.as_mut() {
+            urs.revealed = urs.revealed.saturating_add(1);
+        }
+
+        // protocol totals
+        let cfg = &mut ctx.accounts.config;
+        cfg.total_revealed = cfg.total_revealed.checked_add(1).ok_or(TimlgError::MathOverflow)?;
+    }
 
     Ok(())
 }
@@ -117,6 +168,73 @@ pub fn reveal_batch<'info>(
     ctx: Context<'_, '_, '_, 'info, RevealBatch<'info>>,
     round_id: u64,
     entries: Vec<RevealEntry>,
+) -> Result<()> {
+    reveal_batch_inner(ctx, round_id, entries, false)
+}
+
+/// Like `reveal_batch`, but a bad entry (wrong salt, already revealed, bad PDA...)
+/// is skipped instead of aborting the tx, so the good ones and their counters persist.
+pub fn reveal_batch_lenient<'info>(
+    ctx: Context<'_, '_, '_, 'info, RevealBatch<'info>>,
+    round_id: u64,
+    entries: Vec<RevealEntry>,
+) -> Result<()> {
+    reveal_batch_inner(ctx, round_id, entries, true)
+}
+
+/// Stores the user's Merkle root over `merkle_reveal_leaf(round, user, nonce, guess, salt)`
+/// leaves for `reveal_merkle`. Only while commits are open, so the root is fixed before the pulse.
+pub fn commit_reveal_root(ctx: Context<CommitRevealRoot>, round_id: u64, root: [u8; 32]) -> Result<()> {
+    require!(!ctx.accounts.config.paused, TimlgError::Paused);
+    let round = &ctx.accounts.round;
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
+    require!(!round.pulse_set, TimlgError::CommitClosed);
+    require_commit_open(round, Clock::get()?.slot)?;
+    require!(root != [0u8; 32], TimlgError::RevealRootNotCommitted);
+
+    ctx.accounts.user_round_stats.reveal_root = root;
+    Ok(())
+}
+
+/// Reveal a subset of the user's tickets against the root stored by `commit_reveal_root`
+/// (`user_round_stats` is required). Each proof is checked before the usual per-ticket
+/// reveal (commitment + bit index) runs.
+pub fn reveal_merkle<'info>(
+    ctx: Context<'_, '_, '_, 'info, RevealBatch<'info>>,
+    round_id: u64,
+    entries: Vec<MerkleRevealEntry>,
+) -> Result<()> {
+    let user_pk = ctx.accounts.user.key();
+    let root = ctx
+        .accounts
+        .user_round_stats
+        .as_ref()
+        .map(|urs| urs.reveal_root)
+        .filter(|r| *r != [0u8; 32])
+        .ok_or(TimlgError::RevealRootNotCommitted)?;
+
+    let mut plain: Vec<RevealEntry> = Vec::with_capacity(entries.len());
+    for e in entries.iter() {
+        let leaf = merkle_reveal_leaf(round_id, &user_pk, e.nonce, e.guess, &e.salt);
+        require!(
+            merkle_verify(leaf, &e.proof, &root),
+            TimlgError::InvalidMerkleProof
+        );
+        plain.push(RevealEntry {
+            nonce: e.nonce,
+            guess: e.guess,
+            salt: e.salt,
+        });
+    }
+
+    reveal_batch_inner(ctx, round_id, plain, false)
+}
+
+fn reveal_batch_inner<'info>(
+    ctx: Context<'_, '_, '_, 'info, RevealBatch<'info>>,
+    round_id: u64,
+    entries: Vec<RevealEntry>,
+    lenient: bool,
 ) -> Result<()> {
     let cfg = &ctx.accounts.config;
     require!(!cfg.paused, TimlgError::Paused);
@@ -129,66 +247,127 @@ pub fn reveal_batch<'info>(
 
     // ✅ round mutable para actualizar contadores
     let round = &mut ctx.accounts.round;
-    require!(!round.finalized, TimlgError::RoundFinalized);
-    require!(round.pulse_set, TimlgError::PulseNotSet);
-
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
     let current_slot = Clock::get()?.slot;
-    require!(current_slot <= round.reveal_deadline_slot, TimlgError::RevealClosed);
+    require_reveal_open(round, cfg, current_slot)?;
 
     let user_pk = ctx.accounts.user.key();
-    let round_le = round_id.to_le_bytes();
+    let mut revealed: u32 = 0;
+    // reveals not already counted in the lifetime stats (see `clear_voided_reveal`)
+    let mut first_reveals: u64 = 0;
 
     for (i, e) in entries.iter().enumerate() {
-        require!(e.guess <= 1, TimlgError::InvalidGuess);
-        let ticket_ai = ctx.remaining_accounts[i].clone();
-
-        let nonce_le = e.nonce.to_le_bytes();
-        let (expected_pda, _bump) = Pubkey::find_program_address(
-            &[TICKET_SEED, &round_le, user_pk.as_ref(), &nonce_le],
+        let res = reveal_batch_entry(
             ctx.program_id,
-        );
-        require_keys_eq!(expected_pda, *ticket_ai.key, TimlgError::TicketPdaMismatch);
-        require!(
-            ticket_ai.owner == ctx.program_id,
-            TimlgError::TicketNotOwnedByProgram
+            cfg.seed_version,
+            round,
+            &ctx.remaining_accounts[i],
+            user_pk,
+            round_id,
+            e,
+            current_slot,
         );
 
-        let mut ticket: Ticket = {
-            let data = ticket_ai
-                .try_borrow_data()
-                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
-            let mut slice: &[u8] = &data;
-            Ticket::try_deserialize(&mut slice)?
+        let (ticket, re_reveal) = match res {
+            Ok(t) => t,
+            Err(err) if lenient => {
+                msg!("reveal_batch_lenient: skipped entry {} ({})", i, err);
+                continue;
+            }
+            Err(err) => return Err(err),
         };
 
-        require!(!ticket.revealed, TimlgError::AlreadyRevealed);
-
-        reveal_core(
-            &*round,
-            &mut ticket,
-            user_pk,
+        // ✅ counters por ticket revelado (only after the ticket was persisted)
+        inc_reveal_counters(round, &mut ctx.accounts.global_stats, &ticket, re_reveal)?;
+        if !re_reveal {
+            update_streak(&mut ctx.accounts.user_stats, &ticket);
+            first_reveals += 1;
+        }
+        emit!(RevealMade {
             round_id,
-            e.nonce,
-            e.guess,
-            e.salt,
-            current_slot,
-        )?;
+            user: ticket.user,
+            nonce: ticket.nonce,
+            guess: ticket.guess,
+            win: ticket.win,
+        });
+        revealed += 1;
+    }
 
-        // ✅ counters por ticket revelado
-        inc_reveal_counters(round, &mut ctx.accounts.global_stats, ticket.win)?;
-        update_streak(&mut ctx.accounts.user_stats, &ticket);
+    if lenient {
+        msg!("reveal_batch_lenient: revealed {}/{}", revealed, entries.len());
+    }
 
-        // persist ticket
-        let mut data_mut = ticket_ai
-            .try_borrow_mut_data()
-            .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
-        let mut cursor = std::io::Cursor::new(&mut data_mut[..]);
-        ticket.try_serialize(&mut cursor)?;
+    if revealed > 0 {
+        if let Some(escrow) = ctx.accounts.user_escrow.as_mut() {
+            escrow.last_reveal_slot = current_slot;
+        }
+        if let Some(urs) = ctx.accounts.user_round_stats.as_mut() {
+            urs.revealed = urs.revealed.saturating_add(first_reveals);
+        }
     }
 
+    // protocol totals
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_revealed = cfg
+        .total_revealed
+        .checked_add(first_reveals)
+        .ok_or(TimlgError::MathOverflow)?;
+
     Ok(())
 }
 
+/// Validates, reveals and persists one batch entry. Fails before writing anything,
+/// so a lenient caller can skip the entry with no partial state. Also returns whether
+/// it re-revealed a ticket voided by `dispute_pulse`.
+#[allow(clippy::too_many_arguments)]
+fn reveal_batch_entry(
+    program_id: &Pubkey,
+    seed_version: u8,
+    round: &Round,
+    ticket_ai: &AccountInfo,
+    user_pk: Pubkey,
+    round_id: u64,
+    e: &RevealEntry,
+    current_slot: u64,
+) -> Result<(Ticket, bool)> {
+    require_guess_in_range(round, e.guess)?;
+
+    verify_ticket_pda(seed_version, ticket_ai.key, round_id, &user_pk, e.nonce, program_id)?;
+    require!(
+        ticket_ai.owner == program_id,
+        TimlgError::TicketNotOwnedByProgram
+    );
+
+    let mut ticket: Ticket = {
+        let data = ticket_ai
+            .try_borrow_data()
+            .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+        deserialize_ticket_padded(&data)?
+    };
+
+    let re_reveal = clear_voided_reveal(round, &mut ticket);
+    require!(!ticket.revealed, TimlgError::AlreadyRevealed);
+
+    reveal_core(
+        round,
+        &mut ticket,
+        user_pk,
+        round_id,
+        e.nonce,
+        e.guess,
+        e.salt,
+        current_slot,
+    )?;
+
+    // persist ticket
+    let mut data_mut = ticket_ai
+        .try_borrow_mut_data()
+        .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+    serialize_ticket_into(&ticket, &mut data_mut)?;
+
+    Ok((ticket, re_reveal))
+}
+
 pub fn reveal_batch_signed<'info>(
     ctx: Context<'_, '_, 'info, 'info, RevealBatchSigned<'info>>,
     round_id: u64,
@@ -204,11 +383,9 @@ pub fn reveal_batch_signed<'info>(
     );
 
     let round = &mut ctx.accounts.round;
-    require!(!round.finalized, TimlgError::RoundFinalized);
-
+    require!(round.round_id == round_id, TimlgError::TicketPdaMismatch);
     let current_slot = Clock::get()?.slot;
-    require!(current_slot <= round.reveal_deadline_slot, TimlgError::RevealClosed);
-    require!(round.pulse_set, TimlgError::PulseNotSet);
+    require_reveal_open(round, cfg, current_slot)?;
 
     // ✅ HARDENING: freeze comportamiento -> un batch signed NO puede mezclar usuarios
     if let Some(first) = entries.first() {
@@ -217,46 +394,41 @@ pub fn reveal_batch_signed<'info>(
         }
     }
 
-    let ix_sys = ctx.accounts.instructions.to_account_info();
-    let current_ix = load_current_index_checked(&ix_sys)? as usize;
-    require!(current_ix >= entries.len(), TimlgError::MissingOrInvalidEd25519Ix);
-    let first_ed_ix = current_ix - entries.len();
+    // one signature per entry, from one or more (multi-sig) verify ixs before this ix
+    let ed25519_ixs = ed25519_ixs_before_current(&ctx.accounts.instructions.to_account_info())?;
+    let sigs = ed25519_sigs(&ed25519_ixs);
+    // reveals not already counted in the lifetime stats (see `clear_voided_reveal`)
+    let mut first_reveals: u64 = 0;
 
     for (i, e) in entries.iter().enumerate() {
-        require!(e.guess <= 1, TimlgError::InvalidGuess);
-
-        let ix = load_instruction_at_checked(first_ed_ix + i, &ix_sys)
-            .map_err(|_| error!(TimlgError::MissingOrInvalidEd25519Ix))?;
+        require_guess_in_range(round, e.guess)?;
 
         let expected_msg =
             expected_reveal_msg(ctx.program_id, round_id, &e.user, e.nonce, e.guess, &e.salt);
-        assert_ed25519_ix_matches(&ix, &e.user, expected_msg.as_slice())?;
-
-        let (expected_ticket_pda, _bump) = Pubkey::find_program_address(
-            &[
-                TICKET_SEED,
-                &round_id.to_le_bytes(),
-                e.user.as_ref(),
-                &e.nonce.to_le_bytes(),
-            ],
-            ctx.program_id,
-        );
+        require_ed25519_sig_present(&sigs, &e.user, expected_msg.as_slice())?;
 
         let ticket_ai: &AccountInfo = &ctx.remaining_accounts[i];
-        require_keys_eq!(ticket_ai.key(), expected_ticket_pda, TimlgError::TicketPdaMismatch);
+        verify_ticket_pda(
+            cfg.seed_version,
+            ticket_ai.key,
+            round_id,
+            &e.user,
+            e.nonce,
+            ctx.program_id,
+        )?;
         require_keys_eq!(*ticket_ai.owner, *ctx.program_id, TimlgError::TicketPdaMismatch);
 
         let mut data = ticket_ai
             .try_borrow_mut_data()
             .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
 
-        let mut slice: &[u8] = &data;
-        let mut ticket: Ticket = Ticket::try_deserialize(&mut slice)
+        let mut ticket: Ticket = deserialize_ticket_padded(&data)
             .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
 
         require_keys_eq!(ticket.user, e.user, TimlgError::TicketPdaMismatch);
         require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
         require!(ticket.nonce == e.nonce, TimlgError::TicketPdaMismatch);
+        let re_reveal = clear_voided_reveal(round, &mut ticket);
         require!(!ticket.revealed, TimlgError::AlreadyRevealed);
 
         reveal_core(
@@ -270,14 +442,41 @@ pub fn reveal_batch_signed<'info>(
             current_slot,
         )?;
 
-        inc_reveal_counters(round, &mut ctx.accounts.global_stats, ticket.win)?;
-        update_streak(&mut ctx.accounts.user_stats, &ticket);
+        inc_reveal_counters(round, &mut ctx.accounts.global_stats, &ticket, re_reveal)?;
+        if !re_reveal {
+            update_streak(&mut ctx.accounts.user_stats, &ticket);
+            first_reveals += 1;
+        }
 
-        let mut w = std::io::Cursor::new(&mut data[..]);
-        ticket
-            .try_serialize(&mut w)
+        serialize_ticket_into(&ticket, &mut data)
             .map_err(|_| error!(TimlgError::TicketPdaMismatch))?;
+
+        emit!(RevealMade {
+            round_id,
+            user: e.user,
+            nonce: e.nonce,
+            guess: e.guess,
+            win: ticket.win,
+        });
     }
 
+    // Escrow is seeded by `user`; only touch it if the batch was for that user.
+    if let (Some(escrow), Some(first)) = (ctx.accounts.user_escrow.as_mut(), entries.first()) {
+        require_keys_eq!(escrow.user, first.user, TimlgError::Unauthorized);
+        escrow.last_reveal_slot = current_slot;
+    }
+    // Same for the per-round counters.
+    if let (Some(urs), Some(first)) = (ctx.accounts.user_round_stats.as_mut(), entries.first()) {
+        require_keys_eq!(urs.user, first.user, TimlgError::Unauthorized);
+        urs.revealed = urs.revealed.saturating_add(first_reveals);
+    }
+
+    // protocol totals (the batch is all-or-nothing)
+    let cfg = &mut ctx.accounts.config;
+    cfg.total_revealed = cfg
+        .total_revealed
+        .checked_add(first_reveals)
+        .ok_or(TimlgError::MathOverflow)?;
+
     Ok(())
 }
@@ -1,133 +1,921 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, MintTo, Transfer};
+use anchor_spl::token::{self, Token, Transfer};
 
-use crate::{errors::TimlgError, ClaimReward};
+use crate::{
+    constants::STREAK_BONUS_CAP,
+    errors::TimlgError,
+    state::{
+        Config, GlobalStats, RewardMode, Round, RoundState, Ticket, Tokenomics, UnrevealedPolicy,
+        UserStats, UserStreak,
+    },
+    utils::{
+        advance_round_state, assert_cpi_caller_allowed, claim_deadline_slot, claims_swept, clear_voided_reveal, config_can_mint, effective_reveal_deadline, is_fee_exempt,
+        is_under_participated, config_mint, fee_split, require_spl_stake, resolve_public_ticket, deserialize_ticket_padded, serialize_ticket_into, ticket_weight, vault_lamports_transfer, verify_ticket_pda,
+        release_finalized_fees, take_finalized_fees, win_units, winner_stake_refund, MAX_BATCH, MAX_MULTI_CLAIM, ROUND_SEED,
+    },
+    ClaimMultiRound, ClaimReward, ClaimRewardBatch, ClaimRewardTo, ReclaimUnrevealedStake,
+};
 
-pub fn claim_reward(ctx: Context<ClaimReward>, _round_id: u64, _nonce: u64) -> Result<()> {
-    let cfg = &ctx.accounts.config;
-    let round = &mut ctx.accounts.round;
-    let ticket = &mut ctx.accounts.ticket;
-    let tokenomics = &ctx.accounts.tokenomics;
-    let current_slot = Clock::get()?.slot;
-
-    let user_stats = &mut ctx.accounts.user_stats;
-
-    // --- AUTO-FINALIZE lógica ---
-    // Si la ronda aún no está finalizada formalmente pero ya venció y tiene pulso...
+/// Claim-time auto-finalize: an expired round with a pulse doesn't need `finalize_round` first.
+fn finalize_for_claim(cfg: &Config, round: &mut Round, current_slot: u64) -> Result<()> {
+    require!(round.state != RoundState::Refunding as u8, TimlgError::RoundRefunding);
     if !round.finalized {
         require!(round.pulse_set, TimlgError::PulseNotSet);
+        require!(!is_under_participated(round), TimlgError::RoundRefunding);
         require!(
-            current_slot > round.reveal_deadline_slot,
+            current_slot > effective_reveal_deadline(round, cfg),
             TimlgError::CannotFinalizeYet
         );
         round.finalized = true;
         round.finalized_slot = current_slot;
-        round.state = crate::state::RoundState::Finalized as u8;
+        advance_round_state(round, RoundState::Finalized);
+        round.claim_deadline_slot = claim_deadline_slot(round, cfg);
     }
 
     // si ya se hizo sweep, se cerró la ventana de claim
-    require!(!round.swept, TimlgError::ClaimAfterSweep);
-
-    // Defensa extra (además de seeds del Context)
-    require_keys_eq!(ticket.user, ctx.accounts.user.key(), TimlgError::Unauthorized);
-    require!(ticket.round_id == round.round_id, TimlgError::TicketPdaMismatch);
+    require!(!claims_swept(round), TimlgError::ClaimAfterSweep);
 
-    require!(ticket.stake_paid, TimlgError::StakeNotPaid);
-    require!(ticket.revealed, TimlgError::TicketNotRevealed);
-    require!(ticket.win, TimlgError::NotWinner);
-    require!(!ticket.claimed, TimlgError::AlreadyClaimed);
+    // Rounds finalized before claim_deadline_slot existed get it on first claim.
+    if round.claim_deadline_slot == 0 {
+        round.claim_deadline_slot = claim_deadline_slot(round, cfg);
+    }
+    require!(
+        current_slot <= round.claim_deadline_slot,
+        TimlgError::ClaimWindowClosed
+    );
+    Ok(())
+}
 
-    // --- AUTO-SETTLE lógica (Paso A) ---
-    // Asentamos el ticket ganador si no estaba procesado previamente
+/// Claim-time auto-settle of a winning ticket not yet processed by `settle_round_tokens`.
+fn settle_winner_for_claim(round: &mut Round, ticket: &mut Ticket) -> Result<()> {
     if !ticket.processed {
         ticket.processed = true;
-        
         round.claimed_win_count = round
             .claimed_win_count
             .checked_add(1)
             .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        round.claimed_win_extra_weight = round
+            .claimed_win_extra_weight
+            .checked_add(ticket_weight(ticket) - 1)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
     }
+    Ok(())
+}
 
-    // 1) refund stake: transfer stake_amount desde timlg_vault al user ATA
-    let round_le = round.round_id.to_le_bytes();
-    let signer_seeds: &[&[&[u8]]] = &[&[
-        crate::ROUND_SEED,
-        &round_le,
-        &[round.bump],
-    ]];
+/// Pari-mutuel payouts need the final loser pool, so they wait for settlement.
+/// Before that, the round follows the live tokenomics mode. SOL-staked rounds always mint.
+fn is_pari_mutuel(round: &Round, tokenomics: &Tokenomics) -> Result<bool> {
+    if round.stake_in_sol {
+        return Ok(false);
+    }
+    if round.token_settled {
+        return Ok(round.reward_mode == RewardMode::PariMutuel as u8);
+    }
+    require!(
+        tokenomics.reward_mode != RewardMode::PariMutuel as u8,
+        TimlgError::RoundTokensNotSettled
+    );
+    Ok(false)
+}
+
+/// Gross reward per winning stake unit: minted `stake_amount`, or an even share of the
+/// loser pool (rounding dust stays in the vault for sweep). A ticket earns it `weight` times.
+fn winner_reward(cfg: &Config, round: &Round, pari_mutuel: bool) -> u64 {
+    let units = win_units(round);
+    if !pari_mutuel {
+        cfg.stake_amount
+    } else if units == 0 {
+        0
+    } else {
+        round.loser_stake_pool / units
+    }
+}
 
+/// (user_reward, fee) for a winning ticket: fee = reward_total * bps / 10000.
+fn reward_split(
+    cfg: &Config,
+    tokenomics: &Tokenomics,
+    user: &Pubkey,
+    reward_total: u64,
+) -> Result<(u64, u64)> {
+    require!(tokenomics.reward_fee_bps <= 10_000, TimlgError::InvalidBps);
+
+    let fee = if is_fee_exempt(cfg, user) {
+        0
+    } else {
+        reward_total
+            .checked_mul(tokenomics.reward_fee_bps as u64)
+            .ok_or(TimlgError::MathOverflow)?
+            .checked_div(10_000)
+            .ok_or(TimlgError::MathOverflow)?
+    };
+    let user_reward = reward_total.checked_sub(fee).ok_or(TimlgError::MathOverflow)?;
+    Ok((user_reward, fee))
+}
+
+/// How many of `n` winners minting `per_ticket` each still fit under `round.max_total_reward`.
+/// Books them into `round.reward_minted_total` in the same step; the rest only get their stake back.
+fn reward_cap_grants(round: &mut Round, per_ticket: u64, n: u64) -> Result<u64> {
+    let granted = if round.max_total_reward == 0 || per_ticket == 0 {
+        n
+    } else {
+        let room = round.max_total_reward.saturating_sub(round.reward_minted_total);
+        (room / per_ticket).min(n)
+    };
+    round.reward_minted_total = per_ticket
+        .checked_mul(granted)
+        .and_then(|v| v.checked_add(round.reward_minted_total))
+        .ok_or(TimlgError::MathOverflow)?;
+    Ok(granted)
+}
+
+/// Part of a mint-mode claim's `fee` that still has to be minted: none once settlement
+/// prefunded the round's fees, otherwise all of it (and the claims' `units` are left out of the prefund).
+fn unprefunded_fee(round: &mut Round, fee: u64, units: u64) -> Result<u64> {
+    if round.fees_prefunded {
+        return Ok(0);
+    }
+    round.fee_paid_claims = round
+        .fee_paid_claims
+        .checked_add(units)
+        .ok_or(TimlgError::MathOverflow)?;
+    Ok(fee)
+}
+
+/// Books claim-minted TIMLG into the lifetime `config.total_rewards_minted`.
+fn add_rewards_minted(cfg: &mut Config, minted: u64) -> Result<()> {
+    cfg.total_rewards_minted = cfg
+        .total_rewards_minted
+        .checked_add(minted)
+        .ok_or(TimlgError::MathOverflow)?;
+    Ok(())
+}
+
+/// Round-vault transfer signed by the Round PDA. No-op for 0.
+fn vault_transfer<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    round_ai: &AccountInfo<'info>,
+    round_id: u64,
+    round_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let round_le = round_id.to_le_bytes();
     token::transfer(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.timlg_vault.to_account_info(),
-                to: ctx.accounts.user_timlg_ata.to_account_info(),
-                authority: ctx.accounts.round.to_account_info(),
+                from: vault.clone(),
+                to: to.clone(),
+                authority: round_ai.clone(),
             },
-            signer_seeds,
+            &[&[ROUND_SEED, &round_le, &[round_bump]]],
         ),
-        cfg.stake_amount,
+        amount,
+    )
+}
+
+pub fn claim_reward(ctx: Context<ClaimReward>, _round_id: u64, _nonce: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    assert_cpi_caller_allowed(
+        &cfg.cpi_allowlist,
+        ctx.accounts.instructions.as_ref().map(|ix| ix.as_ref()),
     )?;
 
-    // 2) mint reward, applying fee bps:
-    // reward_total = stake_amount
-    // fee = reward_total * bps / 10000
-    // user gets (reward_total - fee), fee goes to reward_fee_pool
-    require!(tokenomics.reward_fee_bps <= 10_000, TimlgError::InvalidBps);
+    let (reward_total, minted) = claim_winning_ticket(
+        cfg,
+        &ctx.accounts.config.to_account_info(),
+        &ctx.accounts.tokenomics,
+        &mut ctx.accounts.round,
+        &mut ctx.accounts.ticket,
+        &ctx.accounts.user.to_account_info(),
+        &mut ctx.accounts.user_stats,
+        &mut ctx.accounts.global_stats,
+        &ctx.accounts.timlg_mint.to_account_info(),
+        &ctx.accounts.timlg_vault.to_account_info(),
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.user_timlg_ata.to_account_info(),
+        &ctx.accounts.reward_fee_pool.to_account_info(),
+        &ctx.accounts.replication_pool.to_account_info(),
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    add_rewards_minted(&mut ctx.accounts.config, minted)?;
+    release_finalized_fees(&mut ctx.accounts.config, &mut ctx.accounts.round);
+
+    let Some(streak) = ctx.accounts.user_streak.as_mut() else {
+        return Ok(());
+    };
+    let bonus = advance_streak(
+        streak,
+        ctx.accounts.user.key(),
+        ctx.bumps.user_streak.unwrap_or_default(),
+        ctx.accounts.round.round_id,
+        reward_total,
+        ctx.accounts.tokenomics.streak_bonus_bps,
+    )?;
+    let can_mint = config_can_mint(&ctx.accounts.timlg_mint.to_account_info(), &ctx.accounts.config.key());
+    if bonus > 0 && can_mint && reward_cap_grants(&mut ctx.accounts.round, bonus, 1)? == 1 {
+        config_mint(
+            &ctx.accounts.token_program,
+            &ctx.accounts.timlg_mint.to_account_info(),
+            &ctx.accounts.user_timlg_ata.to_account_info(),
+            &ctx.accounts.config.to_account_info(),
+            ctx.accounts.config.bump,
+            bonus,
+        )?;
+        let gs = &mut ctx.accounts.global_stats;
+        gs.total_timlg_minted = gs.total_timlg_minted.checked_add(bonus).ok_or(TimlgError::MathOverflow)?;
+        add_rewards_minted(&mut ctx.accounts.config, bonus)?;
+    }
+    Ok(())
+}
 
-    let reward_total = cfg.stake_amount;
-    let fee = reward_total
-        .checked_mul(tokenomics.reward_fee_bps as u64)
+/// Records a claimed win in `round_id` and returns the streak bonus to mint:
+/// reward_total * bonus_bps * min(streak, STREAK_BONUS_CAP) / 10000.
+/// The streak grows when the previous win was `round_id - 1` and resets to 1 otherwise;
+/// further wins in the same round leave it (and the bonus) untouched.
+fn advance_streak(
+    streak: &mut UserStreak,
+    user: Pubkey,
+    bump: u8,
+    round_id: u64,
+    reward_total: u64,
+    bonus_bps: u16,
+) -> Result<u64> {
+    if streak.user == Pubkey::default() {
+        streak.user = user;
+        streak.bump = bump;
+    }
+    if streak.current_streak > 0 && streak.last_won_round == round_id {
+        return Ok(0);
+    }
+
+    let consecutive =
+        streak.current_streak > 0 && round_id == streak.last_won_round.saturating_add(1);
+    streak.current_streak = if consecutive {
+        streak.current_streak.saturating_add(1)
+    } else {
+        1
+    };
+    streak.last_won_round = round_id;
+
+    let steps = streak.current_streak.min(STREAK_BONUS_CAP) as u64;
+    Ok(reward_total
+        .checked_mul(bonus_bps as u64)
+        .and_then(|v| v.checked_mul(steps))
         .ok_or(TimlgError::MathOverflow)?
-        .checked_div(10_000)
+        / 10_000)
+}
+
+/// Same as `claim_reward`, but the stake refund and the reward go to
+/// `destination_ata` (any TIMLG account owned by `destination_owner`).
+pub fn claim_reward_to(
+    ctx: Context<ClaimRewardTo>,
+    _round_id: u64,
+    _nonce: u64,
+    _destination_owner: Pubkey,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    assert_cpi_caller_allowed(
+        &cfg.cpi_allowlist,
+        ctx.accounts.instructions.as_ref().map(|ix| ix.as_ref()),
+    )?;
+
+    let (_, minted) = claim_winning_ticket(
+        cfg,
+        &ctx.accounts.config.to_account_info(),
+        &ctx.accounts.tokenomics,
+        &mut ctx.accounts.round,
+        &mut ctx.accounts.ticket,
+        &ctx.accounts.user.to_account_info(),
+        &mut ctx.accounts.user_stats,
+        &mut ctx.accounts.global_stats,
+        &ctx.accounts.timlg_mint.to_account_info(),
+        &ctx.accounts.timlg_vault.to_account_info(),
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.destination_ata.to_account_info(),
+        &ctx.accounts.reward_fee_pool.to_account_info(),
+        &ctx.accounts.replication_pool.to_account_info(),
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    add_rewards_minted(&mut ctx.accounts.config, minted)?;
+    release_finalized_fees(&mut ctx.accounts.config, &mut ctx.accounts.round);
+    Ok(())
+}
+
+/// Shared body of `claim_reward` / `claim_reward_to`: pays one winning ticket to `destination`.
+/// SOL-staked rounds refund the stake in lamports from `vault` to the user wallet instead.
+/// Returns the ticket's gross reward (before fees), 0 if the round's reward cap withheld it,
+/// and the TIMLG minted for it (reward plus fees).
+#[allow(clippy::too_many_arguments)]
+fn claim_winning_ticket<'info>(
+    cfg: &Config,
+    config_ai: &AccountInfo<'info>,
+    tokenomics: &Tokenomics,
+    round: &mut Account<'info, Round>,
+    ticket: &mut Ticket,
+    user: &AccountInfo<'info>,
+    user_stats: &mut UserStats,
+    global_stats: &mut GlobalStats,
+    timlg_mint: &AccountInfo<'info>,
+    timlg_vault: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    reward_fee_pool: &AccountInfo<'info>,
+    replication_pool: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(u64, u64)> {
+    let round_ai = round.to_account_info();
+    let current_slot = Clock::get()?.slot;
+
+    // --- AUTO-FINALIZE lógica ---
+    // Si la ronda aún no está finalizada formalmente pero ya venció y tiene pulso...
+    finalize_for_claim(cfg, round, current_slot)?;
+
+    // Defensa extra (además de seeds del Context)
+    require_keys_eq!(ticket.user, *user.key, TimlgError::Unauthorized);
+    require!(ticket.round_id == round.round_id, TimlgError::TicketPdaMismatch);
+
+    require!(ticket.stake_paid, TimlgError::StakeNotPaid);
+    clear_voided_reveal(round, ticket);
+
+    // Public-guess rounds: resolve on the fly if finalize/settle didn't cover this ticket.
+    if round.public_mode && !ticket.revealed {
+        resolve_public_ticket(round, ticket, current_slot)?;
+    }
+
+    require!(ticket.revealed, TimlgError::TicketNotRevealed);
+    require!(ticket.win, TimlgError::NotWinner);
+    require!(!ticket.claimed, TimlgError::AlreadyClaimed);
+
+    // --- AUTO-SETTLE lógica (Paso A) ---
+    // Asentamos el ticket ganador si no estaba procesado previamente
+    settle_winner_for_claim(round, ticket)?;
+
+    // 1) refund stake (+ pari-mutuel share) desde timlg_vault al destination ATA
+    // 2) reward with fee bps: user gets (reward_total - fee), fee is split between
+    //    reward_fee_pool and replication_pool
+    let pari_mutuel = is_pari_mutuel(round, tokenomics)?;
+    let weight = ticket_weight(ticket);
+    let reward_total = winner_reward(cfg, round, pari_mutuel)
+        .checked_mul(weight)
         .ok_or(TimlgError::MathOverflow)?;
-    let user_reward = reward_total.checked_sub(fee).ok_or(TimlgError::MathOverflow)?;
+    let (user_reward, fee) = reward_split(cfg, tokenomics, &ticket.user, reward_total)?;
+    let round_id = round.round_id;
+    let round_bump = round.bump;
 
-    let cfg_seeds: &[&[&[u8]]] = &[&[
-        crate::CONFIG_SEED,
-        &[cfg.bump],
-    ]];
+    let stake = winner_stake_refund(cfg, round, ticket)?;
+    let from_vault = if pari_mutuel {
+        stake.checked_add(user_reward).ok_or(TimlgError::MathOverflow)?
+    } else {
+        stake
+    };
+    if round.stake_in_sol {
+        vault_lamports_transfer(vault, user, system_program, round, from_vault)?;
+    } else {
+        vault_transfer(
+            token_program,
+            timlg_vault,
+            destination,
+            &round_ai,
+            round_id,
+            round_bump,
+            from_vault,
+        )?;
+    }
 
-    if user_reward > 0 {
-        token::mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.timlg_mint.to_account_info(),
-                    to: ctx.accounts.user_timlg_ata.to_account_info(),
-                    authority: ctx.accounts.config.to_account_info(),
-                },
-                cfg_seeds,
-            ),
-            user_reward,
+    let mut capped = false;
+    let minted = if pari_mutuel {
+        let (pool_fee, replication_fee) = fee_split(tokenomics, fee)?;
+        vault_transfer(
+            token_program,
+            timlg_vault,
+            reward_fee_pool,
+            &round_ai,
+            round_id,
+            round_bump,
+            pool_fee,
+        )?;
+        vault_transfer(
+            token_program,
+            timlg_vault,
+            replication_pool,
+            &round_ai,
+            round_id,
+            round_bump,
+            replication_fee,
         )?;
+        0
+    } else if !config_can_mint(timlg_mint, config_ai.key) {
+        // mint authority handed away from config: stake refund only
+        msg!("round {} minting disabled, refunding stake only", round_id);
+        capped = true;
+        0
+    } else {
+        let fee = unprefunded_fee(round, fee, weight)?;
+        let cost = user_reward.checked_add(fee).ok_or(TimlgError::MathOverflow)?;
+        if reward_cap_grants(round, cost, 1)? == 0 {
+            // over the round's reward cap: stake refund only
+            msg!("round {} reward cap reached, refunding stake only", round_id);
+            capped = true;
+            0
+        } else {
+            let (pool_fee, replication_fee) = fee_split(tokenomics, fee)?;
+            config_mint(token_program, timlg_mint, destination, config_ai, cfg.bump, user_reward)?;
+            config_mint(token_program, timlg_mint, reward_fee_pool, config_ai, cfg.bump, pool_fee)?;
+            config_mint(token_program, timlg_mint, replication_pool, config_ai, cfg.bump, replication_fee)?;
+            cost
+        }
+    };
+
+    ticket.claimed = true;
+    ticket.claimed_slot = Clock::get()?.slot;
+    
+    if ticket.created_slot >= user_stats.last_reset_slot {
+        user_stats.tickets_claimed = user_stats.tickets_claimed.saturating_add(1);
     }
 
-    if fee > 0 {
-        token::mint_to(
+    // global stats
+    global_stats.total_timlg_minted = global_stats
+        .total_timlg_minted
+        .checked_add(minted)
+        .ok_or(TimlgError::MathOverflow)?;
+
+    Ok((if capped { 0 } else { reward_total }, minted))
+}
+
+/// Claims several winning tickets of one round. `remaining_accounts[i]` is the ticket
+/// PDA for `nonces[i]`. Already-claimed tickets are skipped so a retry is safe;
+/// claimed tickets stay open (close them with `close_ticket`).
+pub fn claim_reward_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimRewardBatch<'info>>,
+    round_id: u64,
+    nonces: Vec<u64>,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    assert_cpi_caller_allowed(
+        &cfg.cpi_allowlist,
+        ctx.accounts.instructions.as_ref().map(|ix| ix.as_ref()),
+    )?;
+
+    require!(nonces.len() <= MAX_BATCH, TimlgError::TooManyEntries);
+    require!(
+        ctx.remaining_accounts.len() == nonces.len(),
+        TimlgError::TicketPdaMismatch
+    );
+
+    let user_pk = ctx.accounts.user.key();
+    let current_slot = Clock::get()?.slot;
+    let round = &mut ctx.accounts.round;
+    require_spl_stake(round)?;
+    finalize_for_claim(cfg, round, current_slot)?;
+
+    let mut claimed: u64 = 0;
+    let mut stake_total: u64 = 0;
+    for (i, nonce) in nonces.iter().enumerate() {
+        let ticket_ai = &ctx.remaining_accounts[i];
+        require!(ticket_ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+        verify_ticket_pda(cfg.seed_version, ticket_ai.key, round_id, &user_pk, *nonce, ctx.program_id)?;
+
+        let mut ticket: Ticket = {
+            let data = ticket_ai
+                .try_borrow_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            deserialize_ticket_padded(&data)?
+        };
+        require_keys_eq!(ticket.user, user_pk, TimlgError::Unauthorized);
+        require!(ticket.round_id == round_id, TimlgError::TicketPdaMismatch);
+
+        if ticket.claimed {
+            continue;
+        }
+
+        require!(ticket.stake_paid, TimlgError::StakeNotPaid);
+        clear_voided_reveal(round, &mut ticket);
+        if round.public_mode && !ticket.revealed {
+            resolve_public_ticket(round, &mut ticket, current_slot)?;
+        }
+        require!(ticket.revealed, TimlgError::TicketNotRevealed);
+        require!(ticket.win, TimlgError::NotWinner);
+
+        settle_winner_for_claim(round, &mut ticket)?;
+        ticket.claimed = true;
+        ticket.claimed_slot = current_slot;
+        if ticket.created_slot >= ctx.accounts.user_stats.last_reset_slot {
+            ctx.accounts.user_stats.tickets_claimed =
+                ctx.accounts.user_stats.tickets_claimed.saturating_add(1);
+        }
+
+        let mut data = ticket_ai
+            .try_borrow_mut_data()
+            .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+        serialize_ticket_into(&ticket, &mut data)?;
+
+        claimed = claimed
+            .checked_add(ticket_weight(&ticket))
+            .ok_or(TimlgError::MathOverflow)?;
+        stake_total = stake_total
+            .checked_add(winner_stake_refund(cfg, round, &ticket)?)
+            .ok_or(TimlgError::MathOverflow)?;
+    }
+
+    // `claimed` counts stake units: a weighted ticket is paid as `weight` tickets.
+    if claimed == 0 {
+        release_finalized_fees(&mut ctx.accounts.config, &mut ctx.accounts.round);
+        return Ok(());
+    }
+
+    // 1) refund stakes (+ pari-mutuel shares) in one transfer
+    // 2) rewards with fee split
+    let round = &mut ctx.accounts.round;
+    let pari_mutuel = is_pari_mutuel(round, &ctx.accounts.tokenomics)?;
+    let reward_total = winner_reward(cfg, round, pari_mutuel);
+    let (user_reward, fee) = reward_split(cfg, &ctx.accounts.tokenomics, &user_pk, reward_total)?;
+    let user_total = user_reward.checked_mul(claimed).ok_or(TimlgError::MathOverflow)?;
+    let (user_total, fee_total) = if pari_mutuel {
+        let fee_total = fee.checked_mul(claimed).ok_or(TimlgError::MathOverflow)?;
+        (user_total, fee_total)
+    } else if !config_can_mint(&ctx.accounts.timlg_mint.to_account_info(), &ctx.accounts.config.key()) {
+        // mint authority handed away from config: stake refunds only
+        (0, 0)
+    } else {
+        // minted rewards: only the tickets that still fit under the round's reward cap
+        let fee = unprefunded_fee(round, fee, claimed)?;
+        let cost = user_reward.checked_add(fee).ok_or(TimlgError::MathOverflow)?;
+        let rewarded = reward_cap_grants(round, cost, claimed)?;
+        (
+            user_reward.checked_mul(rewarded).ok_or(TimlgError::MathOverflow)?,
+            fee.checked_mul(rewarded).ok_or(TimlgError::MathOverflow)?,
+        )
+    };
+    let (pool_fee_total, replication_fee_total) = fee_split(&ctx.accounts.tokenomics, fee_total)?;
+
+    let round = &ctx.accounts.round;
+    let round_ai = round.to_account_info();
+    let vault_ai = ctx.accounts.timlg_vault.to_account_info();
+    let from_vault = if pari_mutuel {
+        stake_total.checked_add(user_total).ok_or(TimlgError::MathOverflow)?
+    } else {
+        stake_total
+    };
+    vault_transfer(
+        &ctx.accounts.token_program,
+        &vault_ai,
+        &ctx.accounts.user_timlg_ata.to_account_info(),
+        &round_ai,
+        round_id,
+        round.bump,
+        from_vault,
+    )?;
+
+    if pari_mutuel {
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &vault_ai,
+            &ctx.accounts.reward_fee_pool.to_account_info(),
+            &round_ai,
+            round_id,
+            round.bump,
+            pool_fee_total,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &vault_ai,
+            &ctx.accounts.replication_pool.to_account_info(),
+            &round_ai,
+            round_id,
+            round.bump,
+            replication_fee_total,
+        )?;
+        return Ok(());
+    }
+
+    let mint_ai = ctx.accounts.timlg_mint.to_account_info();
+    let config_ai = ctx.accounts.config.to_account_info();
+    config_mint(
+        &ctx.accounts.token_program,
+        &mint_ai,
+        &ctx.accounts.user_timlg_ata.to_account_info(),
+        &config_ai,
+        cfg.bump,
+        user_total,
+    )?;
+    config_mint(
+        &ctx.accounts.token_program,
+        &mint_ai,
+        &ctx.accounts.reward_fee_pool.to_account_info(),
+        &config_ai,
+        cfg.bump,
+        pool_fee_total,
+    )?;
+    config_mint(
+        &ctx.accounts.token_program,
+        &mint_ai,
+        &ctx.accounts.replication_pool.to_account_info(),
+        &config_ai,
+        cfg.bump,
+        replication_fee_total,
+    )?;
+
+    let minted = user_total.checked_add(fee_total).ok_or(TimlgError::MathOverflow)?;
+    let gs = &mut ctx.accounts.global_stats;
+    gs.total_timlg_minted = gs
+        .total_timlg_minted
+        .checked_add(minted)
+        .ok_or(TimlgError::MathOverflow)?;
+    add_rewards_minted(&mut ctx.accounts.config, minted)?;
+    release_finalized_fees(&mut ctx.accounts.config, &mut ctx.accounts.round);
+
+    Ok(())
+}
+
+/// Unrevealed ticket under `RefundMinusPenalty`: after settle burned the penalty,
+/// the owner takes back `round.unrevealed_refund` and the ticket is closed.
+/// SOL-staked rounds pay it in lamports from `vault`, where settle left it.
+pub fn reclaim_unrevealed_stake(
+    ctx: Context<ReclaimUnrevealedStake>,
+    _round_id: u64,
+    _nonce: u64,
+) -> Result<()> {
+    let round = &ctx.accounts.round;
+    let ticket = &mut ctx.accounts.ticket;
+
+    require!(!claims_swept(round), TimlgError::ClaimAfterSweep);
+    require!(ticket.round_id == round.round_id, TimlgError::TicketPdaMismatch);
+    require!(ticket.stake_paid, TimlgError::StakeNotPaid);
+    require!(ticket.processed, TimlgError::TicketNotProcessed);
+    require!(
+        round.unrevealed_policy == UnrevealedPolicy::RefundMinusPenalty as u8
+            && !ticket.revealed
+            && !ticket.stake_slashed,
+        TimlgError::UnrevealedRefundNotAvailable
+    );
+    require!(!ticket.claimed, TimlgError::AlreadyClaimed);
+
+    let amount = round
+        .unrevealed_refund
+        .checked_mul(ticket_weight(ticket))
+        .ok_or(TimlgError::MathOverflow)?;
+    if round.stake_in_sol {
+        vault_lamports_transfer(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            round,
+            amount,
+        )?;
+    } else if amount > 0 {
+        let round_le = round.round_id.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            crate::ROUND_SEED,
+            &round_le,
+            &[round.bump],
+        ]];
+
+        token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.timlg_mint.to_account_info(),
-                    to: ctx.accounts.reward_fee_pool.to_account_info(),
-                    authority: ctx.accounts.config.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.timlg_vault.to_account_info(),
+                    to: ctx.accounts.user_timlg_ata.to_account_info(),
+                    authority: ctx.accounts.round.to_account_info(),
                 },
-                cfg_seeds,
+                signer_seeds,
             ),
-            fee,
+            amount,
         )?;
     }
 
     ticket.claimed = true;
     ticket.claimed_slot = Clock::get()?.slot;
-    
-    if ticket.created_slot >= user_stats.last_reset_slot {
-        user_stats.tickets_claimed = user_stats.tickets_claimed.saturating_add(1);
+
+    Ok(())
+}
+
+/// Claims winning tickets across several rounds in one tx.
+/// remaining_accounts: `[round, ticket, timlg_vault]` per round (max `MAX_MULTI_CLAIM`).
+/// Tokenomics and the fee pools are global and come from the context.
+/// Tickets that aren't claimable winners are skipped; claimed tickets stay open
+/// (close them with `close_ticket`).
+pub fn claim_multi_round<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimMultiRound<'info>>,
+) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    require!(!cfg.paused, TimlgError::Paused);
+    assert_cpi_caller_allowed(
+        &cfg.cpi_allowlist,
+        ctx.accounts.instructions.as_ref().map(|ix| ix.as_ref()),
+    )?;
+
+    let groups = ctx.remaining_accounts.len() / 3;
+    require!(
+        groups > 0 && ctx.remaining_accounts.len() % 3 == 0,
+        TimlgError::TicketPdaMismatch
+    );
+    require!(groups <= MAX_MULTI_CLAIM, TimlgError::TooManyEntries);
+
+    let user_pk = ctx.accounts.user.key();
+    let current_slot = Clock::get()?.slot;
+    let user_ata_ai = ctx.accounts.user_timlg_ata.to_account_info();
+    let fee_pool_ai = ctx.accounts.reward_fee_pool.to_account_info();
+    let replication_pool_ai = ctx.accounts.replication_pool.to_account_info();
+    let can_mint = config_can_mint(&ctx.accounts.timlg_mint.to_account_info(), &ctx.accounts.config.key());
+    let mut claimed: u64 = 0;
+    // minted rewards are accumulated and minted once after the loop
+    let mut mint_user_total: u64 = 0;
+    let mut mint_fee_total: u64 = 0;
+    let mut minted: u64 = 0;
+    // service fees of the rounds finalized here, released from config after the loop
+    let mut fees_released: u64 = 0;
+
+    for g in ctx.remaining_accounts.chunks(3) {
+        let (round_ai, ticket_ai, vault_ai) = (&g[0], &g[1], &g[2]);
+        require!(round_ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+        require!(ticket_ai.owner == ctx.program_id, TimlgError::TicketNotOwnedByProgram);
+
+        let mut round: Round = {
+            let data = round_ai
+                .try_borrow_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            let mut slice: &[u8] = &data;
+            Round::try_deserialize(&mut slice)?
+        };
+        let mut ticket: Ticket = {
+            let data = ticket_ai
+                .try_borrow_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            deserialize_ticket_padded(&data)?
+        };
+
+        let round_le = round.round_id.to_le_bytes();
+        let (expected_round, _) =
+            Pubkey::find_program_address(&[ROUND_SEED, &round_le], ctx.program_id);
+        require_keys_eq!(expected_round, *round_ai.key, TimlgError::TicketPdaMismatch);
+        verify_ticket_pda(
+            cfg.seed_version,
+            ticket_ai.key,
+            round.round_id,
+            &user_pk,
+            ticket.nonce,
+            ctx.program_id,
+        )?;
+        require_keys_eq!(ticket.user, user_pk, TimlgError::Unauthorized);
+        require!(ticket.round_id == round.round_id, TimlgError::TicketPdaMismatch);
+        require_keys_eq!(round.timlg_vault, *vault_ai.key, TimlgError::VaultPdaMismatch);
+
+        require_spl_stake(&round)?;
+        finalize_for_claim(cfg, &mut round, current_slot)?;
+        clear_voided_reveal(&round, &mut ticket);
+        if round.public_mode && !ticket.revealed && ticket.stake_paid {
+            resolve_public_ticket(&mut round, &mut ticket, current_slot)?;
+        }
+
+        // skip non-winners (and anything already claimed)
+        if !(ticket.stake_paid && ticket.revealed && ticket.win && !ticket.claimed) {
+            continue;
+        }
+
+        settle_winner_for_claim(&mut round, &mut ticket)?;
+
+        let pari_mutuel = is_pari_mutuel(&round, &ctx.accounts.tokenomics)?;
+        let weight = ticket_weight(&ticket);
+        let reward_total = winner_reward(cfg, &round, pari_mutuel)
+            .checked_mul(weight)
+            .ok_or(TimlgError::MathOverflow)?;
+        let (user_reward, fee) =
+            reward_split(cfg, &ctx.accounts.tokenomics, &user_pk, reward_total)?;
+        let stake = winner_stake_refund(cfg, &round, &ticket)?;
+
+        if pari_mutuel {
+            let from_vault = stake.checked_add(user_reward).ok_or(TimlgError::MathOverflow)?;
+            vault_transfer(
+                &ctx.accounts.token_program,
+                vault_ai,
+                &user_ata_ai,
+                round_ai,
+                round.round_id,
+                round.bump,
+                from_vault,
+            )?;
+            let (pool_fee, replication_fee) = fee_split(&ctx.accounts.tokenomics, fee)?;
+            vault_transfer(
+                &ctx.accounts.token_program,
+                vault_ai,
+                &fee_pool_ai,
+                round_ai,
+                round.round_id,
+                round.bump,
+                pool_fee,
+            )?;
+            vault_transfer(
+                &ctx.accounts.token_program,
+                vault_ai,
+                &replication_pool_ai,
+                round_ai,
+                round.round_id,
+                round.bump,
+                replication_fee,
+            )?;
+        } else {
+            vault_transfer(
+                &ctx.accounts.token_program,
+                vault_ai,
+                &user_ata_ai,
+                round_ai,
+                round.round_id,
+                round.bump,
+                stake,
+            )?;
+            // without the mint authority (or over the round's reward cap): stake refund only
+            if can_mint {
+                let fee = unprefunded_fee(&mut round, fee, weight)?;
+                let cost = user_reward.checked_add(fee).ok_or(TimlgError::MathOverflow)?;
+                if reward_cap_grants(&mut round, cost, 1)? == 1 {
+                    mint_user_total = mint_user_total
+                        .checked_add(user_reward)
+                        .ok_or(TimlgError::MathOverflow)?;
+                    mint_fee_total = mint_fee_total.checked_add(fee).ok_or(TimlgError::MathOverflow)?;
+                    minted = minted.checked_add(cost).ok_or(TimlgError::MathOverflow)?;
+                }
+            }
+        }
+
+        ticket.claimed = true;
+        ticket.claimed_slot = current_slot;
+        if ticket.created_slot >= ctx.accounts.user_stats.last_reset_slot {
+            ctx.accounts.user_stats.tickets_claimed =
+                ctx.accounts.user_stats.tickets_claimed.saturating_add(1);
+        }
+
+        fees_released = fees_released
+            .checked_add(take_finalized_fees(&mut round))
+            .ok_or(TimlgError::MathOverflow)?;
+
+        // persist round + ticket
+        {
+            let mut data = round_ai
+                .try_borrow_mut_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            let mut cursor = std::io::Cursor::new(&mut data[..]);
+            round.try_serialize(&mut cursor)?;
+        }
+        {
+            let mut data = ticket_ai
+                .try_borrow_mut_data()
+                .map_err(|_| error!(TimlgError::AccountBorrowFailed))?;
+            serialize_ticket_into(&ticket, &mut data)?;
+        }
+
+        claimed += 1;
     }
 
-    // global stats
+    let mint_ai = ctx.accounts.timlg_mint.to_account_info();
+    let config_ai = ctx.accounts.config.to_account_info();
+    config_mint(
+        &ctx.accounts.token_program,
+        &mint_ai,
+        &user_ata_ai,
+        &config_ai,
+        cfg.bump,
+        mint_user_total,
+    )?;
+    let (pool_fee_total, replication_fee_total) = fee_split(&ctx.accounts.tokenomics, mint_fee_total)?;
+    config_mint(
+        &ctx.accounts.token_program,
+        &mint_ai,
+        &fee_pool_ai,
+        &config_ai,
+        cfg.bump,
+        pool_fee_total,
+    )?;
+    config_mint(
+        &ctx.accounts.token_program,
+        &mint_ai,
+        &replication_pool_ai,
+        &config_ai,
+        cfg.bump,
+        replication_fee_total,
+    )?;
+
     let gs = &mut ctx.accounts.global_stats;
-    gs.total_timlg_minted = gs.total_timlg_minted.checked_add(reward_total).ok_or(TimlgError::MathOverflow)?;
+    gs.total_timlg_minted = gs
+        .total_timlg_minted
+        .checked_add(minted)
+        .ok_or(TimlgError::MathOverflow)?;
+    add_rewards_minted(&mut ctx.accounts.config, minted)?;
+    let cfg = &mut ctx.accounts.config;
+    cfg.sol_fees_outstanding = cfg.sol_fees_outstanding.saturating_sub(fees_released);
 
+    msg!("claim_multi_round: claimed {}/{}", claimed, groups);
     Ok(())
 }
@@ -0,0 +1,293 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+use crate::constants::REFUND_TIMEOUT_SLOTS;
+use crate::errors::TimlgError;
+use crate::state::{Config, Round, RoundState};
+use crate::utils::{
+    claims_swept, commit_hash, commit_window_open, derive_bit_index_for, effective_reveal_deadline, get_pulse_bits,
+    guess_bits, reveal_voided, sol_service_fee_for,
+};
+use crate::{ComputeOutcome, GetProtocolStats, GetTicketState, QuoteCommit, RoundStatus};
+
+/// Snapshot of a round's lifecycle, Borsh-encoded into return data.
+/// Clients should compute the claim window from `token_settled_slot` once `token_settled`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RoundStatusData {
+    pub round_id: u64,
+    pub state: u8,
+    pub pulse_set: bool,
+    pub finalized: bool,
+    pub token_settled: bool,
+    pub swept: bool,
+
+    pub commit_deadline_slot: u64,
+    pub reveal_deadline_slot: u64,
+    pub finalized_slot: u64,
+    pub token_settled_slot: u64,
+
+    pub committed_count: u64,
+    pub revealed_count: u64,
+    pub win_count: u64,
+    pub settled_count: u64,
+
+    pub max_committed: u64,
+    pub commit_frozen: bool,
+    pub commit_closed_slot: u64,
+}
+
+/// Read-only: returns `RoundStatusData` via return data. Mutates nothing.
+pub fn round_status(ctx: Context<RoundStatus>, _round_id: u64) -> Result<()> {
+    let r = &ctx.accounts.round;
+
+    let status = RoundStatusData {
+        round_id: r.round_id,
+        state: r.state,
+        pulse_set: r.pulse_set,
+        finalized: r.finalized,
+        token_settled: r.token_settled,
+        swept: r.swept,
+        commit_deadline_slot: r.commit_deadline_slot,
+        reveal_deadline_slot: r.reveal_deadline_slot,
+        finalized_slot: r.finalized_slot,
+        token_settled_slot: r.token_settled_slot,
+        committed_count: r.committed_count,
+        revealed_count: r.revealed_count,
+        win_count: r.win_count,
+        settled_count: r.settled_count,
+        max_committed: r.max_committed,
+        commit_frozen: r.commit_frozen,
+        commit_closed_slot: r.commit_closed_slot,
+    };
+
+    let mut data = Vec::new();
+    status.serialize(&mut data)?;
+    set_return_data(&data);
+
+    Ok(())
+}
+
+// Phases reported by `round_phase_compact` (derived from state + current slot).
+pub const PHASE_COMMIT: u8 = 0;
+pub const PHASE_AWAITING_PULSE: u8 = 1;
+pub const PHASE_REVEAL: u8 = 2;
+pub const PHASE_FINALIZABLE: u8 = 3;
+pub const PHASE_FINALIZED: u8 = 4;
+pub const PHASE_REFUNDING: u8 = 5;
+pub const PHASE_SWEPT: u8 = 6;
+
+/// Minimal fixed-size snapshot for high-frequency polling (35 bytes Borsh).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RoundPhaseCompact {
+    pub round_id: u64,
+    pub phase: u8,
+    pub commit_deadline_slot: u64,
+    pub reveal_deadline_slot: u64,
+    pub pulse_set: bool,
+    pub finalized: bool,
+    pub committed_count: u64,
+}
+
+/// A pulse-less round past its reveal deadline can't be finalized: it waits for the
+/// pulse until the refund timeout, then reads as refunding (same gate as recover).
+fn round_phase(r: &Round, cfg: &Config, current_slot: u64) -> u8 {
+    // `swept` covers rounds swept before RoundState::Swept existed
+    if r.state == RoundState::Swept as u8 || r.swept {
+        PHASE_SWEPT
+    } else if r.state == RoundState::Refunding as u8 {
+        PHASE_REFUNDING
+    } else if r.finalized {
+        PHASE_FINALIZED
+    } else if commit_window_open(r.commit_deadline_slot, current_slot) && !r.commit_frozen {
+        PHASE_COMMIT
+    } else if !r.pulse_set {
+        if current_slot > r.reveal_deadline_slot.saturating_add(REFUND_TIMEOUT_SLOTS) {
+            PHASE_REFUNDING
+        } else {
+            PHASE_AWAITING_PULSE
+        }
+    } else if current_slot > effective_reveal_deadline(r, cfg) {
+        PHASE_FINALIZABLE
+    } else {
+        PHASE_REVEAL
+    }
+}
+
+/// Read-only: returns `RoundPhaseCompact` via return data. Lighter than `round_status`.
+pub fn round_phase_compact(ctx: Context<RoundStatus>, _round_id: u64) -> Result<()> {
+    let r = &ctx.accounts.round;
+
+    let snap = RoundPhaseCompact {
+        round_id: r.round_id,
+        phase: round_phase(r, &ctx.accounts.config, Clock::get()?.slot),
+        commit_deadline_slot: r.commit_deadline_slot,
+        reveal_deadline_slot: r.reveal_deadline_slot,
+        pulse_set: r.pulse_set,
+        finalized: r.finalized,
+        committed_count: r.committed_count,
+    };
+
+    let mut data = Vec::new();
+    snap.serialize(&mut data)?;
+    set_return_data(&data);
+
+    Ok(())
+}
+
+/// Round phase plus counts, for frontends that don't want to redo the slot math.
+/// `phase` uses the `PHASE_*` values above.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RoundStatusView {
+    pub round_id: u64,
+    pub phase: u8,
+    pub committed_count: u64,
+    pub revealed_count: u64,
+    pub win_count: u64,
+    pub refunds_allowed: bool,
+    /// `round.label` as set at creation.
+    pub label: [u8; 32],
+    /// `round.reveal_transcript`: hash chain of the reveals so far (zero before the first).
+    pub reveal_transcript: [u8; 32],
+}
+
+/// Read-only: returns `RoundStatusView` as the instruction return value.
+pub fn get_round_status(ctx: Context<RoundStatus>, _round_id: u64) -> Result<RoundStatusView> {
+    let r = &ctx.accounts.round;
+    let current_slot = Clock::get()?.slot;
+
+    // Same gate as the recover paths.
+    let refunds_allowed = r.state == RoundState::Refunding as u8
+        || (!r.finalized
+            && !r.pulse_set
+            && current_slot > r.reveal_deadline_slot.saturating_add(REFUND_TIMEOUT_SLOTS));
+
+    Ok(RoundStatusView {
+        round_id: r.round_id,
+        phase: round_phase(r, &ctx.accounts.config, current_slot),
+        committed_count: r.committed_count,
+        revealed_count: r.revealed_count,
+        win_count: r.win_count,
+        refunds_allowed,
+        label: r.label,
+        reveal_transcript: r.reveal_transcript,
+    })
+}
+
+/// Lifetime protocol totals from `Config`, so dashboards don't have to sum rounds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ProtocolStatsView {
+    pub total_committed: u64,
+    pub total_revealed: u64,
+    /// TIMLG minted by claims (rewards, their fees and streak bonuses), in raw base units.
+    pub total_rewards_minted: u64,
+}
+
+/// Read-only: returns `ProtocolStatsView` as the instruction return value.
+pub fn get_protocol_stats(ctx: Context<GetProtocolStats>) -> Result<ProtocolStatsView> {
+    let cfg = &ctx.accounts.config;
+    Ok(ProtocolStatsView {
+        total_committed: cfg.total_committed,
+        total_revealed: cfg.total_revealed,
+        total_rewards_minted: cfg.total_rewards_minted,
+    })
+}
+
+/// Where a ticket stands on its way to a payout, e.g. "won, waiting for settlement".
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TicketState {
+    pub revealed: bool,
+    pub win: bool,
+    pub processed: bool,
+    pub claimed: bool,
+    /// `claim_reward` would pay out now: settled, not swept, a winner not yet claimed, and
+    /// the round's `claim_deadline_slot` (once set) not yet passed.
+    pub claimable: bool,
+}
+
+/// Read-only: returns `TicketState` as the instruction return value.
+pub fn get_ticket_state(ctx: Context<GetTicketState>, _round_id: u64, _nonce: u64) -> Result<TicketState> {
+    let r = &ctx.accounts.round;
+    let t = &ctx.accounts.ticket;
+    let current_slot = Clock::get()?.slot;
+    let claim_window_open = r.claim_deadline_slot == 0 || current_slot <= r.claim_deadline_slot;
+    // a reveal against a disputed pulse no longer counts
+    let voided = reveal_voided(r, t);
+
+    Ok(TicketState {
+        revealed: t.revealed && !voided,
+        win: t.win && !voided,
+        processed: t.processed,
+        claimed: t.claimed,
+        claimable: r.token_settled && !claims_swept(r) && t.win && !voided && !t.claimed && claim_window_open,
+    })
+}
+
+/// What `reveal_core` would compute for a ticket, so clients can check their local
+/// `derive_bit_index` / `get_pulse_bit` / `commit_hash` against the program.
+/// `pulse_bit` is the `guess_bits`-wide outcome for multi-bit rounds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct OutcomePreview {
+    pub bit_index: u16,
+    pub pulse_bit: u8,
+    pub win: bool,
+    pub commitment: [u8; 32],
+}
+
+/// Read-only: returns `OutcomePreview` as the instruction return value (return data).
+/// Diagnostic only; needs the round's pulse and mutates nothing.
+pub fn compute_outcome(
+    ctx: Context<ComputeOutcome>,
+    round_id: u64,
+    nonce: u64,
+    guess: u8,
+    salt: [u8; 32],
+) -> Result<OutcomePreview> {
+    let r = &ctx.accounts.round;
+    require!(r.pulse_set, TimlgError::PulseNotSet);
+
+    let user = ctx.accounts.user.key();
+    let bits = guess_bits(r);
+    let bit_index = derive_bit_index_for(round_id, &user, nonce, bits);
+    let pulse_bit = get_pulse_bits(&r.pulse, bit_index, bits);
+
+    Ok(OutcomePreview {
+        bit_index,
+        pulse_bit,
+        win: pulse_bit == guess,
+        commitment: commit_hash(round_id, &user, nonce, guess, &salt),
+    })
+}
+
+/// Cost of committing `n_tickets` for `user`, plus what a UI needs to display it.
+/// Amounts are raw base units; format with `timlg_decimals`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CommitQuote {
+    pub stake_per_ticket: u64,
+    pub sol_fee_per_ticket: u64,
+    pub total_stake: u64,
+    pub total_sol_fee_lamports: u64,
+    pub timlg_decimals: u8,
+}
+
+/// Read-only: returns `CommitQuote` via return data. Mutates nothing.
+pub fn quote_commit(ctx: Context<QuoteCommit>, user: Pubkey, n_tickets: u64) -> Result<()> {
+    let cfg = &ctx.accounts.config;
+    let sol_fee = sol_service_fee_for(cfg, &user);
+
+    let quote = CommitQuote {
+        stake_per_ticket: cfg.stake_amount,
+        sol_fee_per_ticket: sol_fee,
+        total_stake: cfg
+            .stake_amount
+            .checked_mul(n_tickets)
+            .ok_or(TimlgError::MathOverflow)?,
+        total_sol_fee_lamports: sol_fee.checked_mul(n_tickets).ok_or(TimlgError::MathOverflow)?,
+        timlg_decimals: cfg.timlg_decimals,
+    };
+
+    let mut data = Vec::new();
+    quote.serialize(&mut data)?;
+    set_return_data(&data);
+
+    Ok(())
+}
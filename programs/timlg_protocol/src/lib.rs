@@ -6,6 +6,7 @@ pub mod state;
 pub mod utils;
 pub mod contexts;
 pub mod constants;
+pub mod events;
 
 pub use utils::*;
 pub use instructions::*;
@@ -13,6 +14,7 @@ pub use state::*;
 pub use errors::*;
 pub use contexts::*;
 pub use constants::*;
+pub use events::*;
 
 use solana_security_txt::security_txt;
 
@@ -40,17 +42,28 @@ pub mod timlg_protocol {
     use super::*;
     use crate::instructions::{admin, oracle_set, oracle, lifecycle, commit, reveal, reward, escrow};
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_config(
         ctx: Context<InitializeConfig>,
         stake_amount: u64,
         commit_window_slots: u64,
         reveal_window_slots: u64,
+        allow_nonzero_supply: bool,
+        min_stake_amount: u64,
+        max_stake_amount: u64,
+        rescue_authority: Pubkey,
+        rescue_destination: Pubkey,
     ) -> Result<()> {
         admin::initialize_config(
             ctx,
             stake_amount,
             commit_window_slots,
             reveal_window_slots,
+            allow_nonzero_supply,
+            min_stake_amount,
+            max_stake_amount,
+            rescue_authority,
+            rescue_destination,
         )
     }
 
@@ -85,16 +98,27 @@ pub mod timlg_protocol {
         oracle_set::set_oracle_threshold(ctx, threshold)
     }
 
+    pub fn clear_oracle_set(ctx: Context<ClearOracleSet>) -> Result<()> {
+        oracle_set::clear_oracle_set(ctx)
+    }
+
     pub fn set_oracle_pubkey(ctx: Context<SetOraclePubkey>, oracle_pubkey: Pubkey) -> Result<()> {
         oracle::set_oracle_pubkey(ctx, oracle_pubkey)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_round(
         ctx: Context<CreateRound>,
         round_id: u64,
         pulse_index_target: u64,
         commit_deadline_slot: u64,
         reveal_deadline_slot: u64,
+        claim_grace_slots_override: u64,
+        label: [u8; 32],
+        oracle_pubkey_override: Pubkey,
+        max_total_reward: u64,
+        min_participants: u64,
+        guess_bits: u8,
     ) -> Result<()> {
         admin::create_round(
             ctx,
@@ -102,13 +126,67 @@ pub mod timlg_protocol {
             pulse_index_target,
             commit_deadline_slot,
             reveal_deadline_slot,
+            claim_grace_slots_override,
+            label,
+            oracle_pubkey_override,
+            max_total_reward,
+            min_participants,
+            guess_bits,
         )
     }
 
+    pub fn set_round_public_mode(
+        ctx: Context<SetRoundPublicMode>,
+        round_id: u64,
+        public_mode: bool,
+    ) -> Result<()> {
+        admin::set_round_public_mode(ctx, round_id, public_mode)
+    }
+
+    pub fn set_round_min_participants(
+        ctx: Context<SetRoundMinParticipants>,
+        round_id: u64,
+        min_participants: u64,
+    ) -> Result<()> {
+        admin::set_round_min_participants(ctx, round_id, min_participants)
+    }
+
+    pub fn set_round_max_committed(
+        ctx: Context<SetRoundMaxCommitted>,
+        round_id: u64,
+        max_committed: u64,
+    ) -> Result<()> {
+        admin::set_round_max_committed(ctx, round_id, max_committed)
+    }
+
+    pub fn set_round_pause(ctx: Context<SetRoundPause>, round_id: u64, paused: bool) -> Result<()> {
+        admin::set_round_pause(ctx, round_id, paused)
+    }
+
+    pub fn extend_reveal_deadline(
+        ctx: Context<ExtendRevealDeadline>,
+        round_id: u64,
+        extra_slots: u64,
+    ) -> Result<()> {
+        admin::extend_reveal_deadline(ctx, round_id, extra_slots)
+    }
+
+    pub fn extend_commit_deadline(
+        ctx: Context<ExtendCommitDeadline>,
+        round_id: u64,
+        new_commit_deadline_slot: u64,
+    ) -> Result<()> {
+        admin::extend_commit_deadline(ctx, round_id, new_commit_deadline_slot)
+    }
+
     pub fn fund_vault(ctx: Context<FundVault>, round_id: u64, amount: u64) -> Result<()> {
         admin::fund_vault(ctx, round_id, amount)
     }
 
+    pub fn fund_timlg_vault(ctx: Context<FundTimlgVault>, round_id: u64, amount: u64) -> Result<()> {
+        admin::fund_timlg_vault(ctx, round_id, amount)
+    }
+
     #[cfg(feature = "mock-pulse")]
     pub fn set_pulse_mock(
         ctx: Context<SetPulseMock>,
@@ -122,8 +200,51 @@ pub mod timlg_protocol {
         oracle::set_pulse_signed(ctx, round_id, pulse)
     }
 
+    pub fn set_pulse_and_close_commit(
+        ctx: Context<SetPulseSigned>,
+        round_id: u64,
+        pulse: [u8; 64],
+    ) -> Result<()> {
+        oracle::set_pulse_and_close_commit(ctx, round_id, pulse)
+    }
+
+    pub fn set_pulse_multisig(
+        ctx: Context<SetPulseMultisig>,
+        round_id: u64,
+        pulse: [u8; 64],
+    ) -> Result<()> {
+        oracle::set_pulse_multisig(ctx, round_id, pulse)
+    }
+
+    pub fn set_pulse_dispute_window(ctx: Context<SetPulseDisputeWindow>, slots: u64) -> Result<()> {
+        oracle::set_pulse_dispute_window(ctx, slots)
+    }
+
+    pub fn set_min_pulse_popcount(ctx: Context<SetMinPulsePopcount>, min_pulse_popcount: u32) -> Result<()> {
+        oracle::set_min_pulse_popcount(ctx, min_pulse_popcount)
+    }
+
+    pub fn dispute_pulse(ctx: Context<DisputePulse>, round_id: u64) -> Result<()> {
+        oracle::dispute_pulse(ctx, round_id)
+    }
+
+    pub fn submit_attestation(
+        ctx: Context<SubmitAttestation>,
+        round_id: u64,
+        pulse: [u8; 64],
+    ) -> Result<()> {
+        oracle::submit_attestation(ctx, round_id, pulse)
+    }
+
+    pub fn finalize_pulse(ctx: Context<FinalizePulse>, round_id: u64) -> Result<()> {
+        oracle::finalize_pulse(ctx, round_id)
+    }
+
     // ✅ lifecycle
-    pub fn finalize_round(ctx: Context<FinalizeRound>, round_id: u64) -> Result<()> {
+    pub fn finalize_round<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeRound<'info>>,
+        round_id: u64,
+    ) -> Result<()> {
         lifecycle::finalize_round(ctx, round_id)
     }
 
@@ -131,10 +252,32 @@ pub mod timlg_protocol {
         lifecycle::sweep_unclaimed(ctx, round_id)
     }
 
+    pub fn sweep_sol(ctx: Context<SweepSol>, round_id: u64) -> Result<()> {
+        lifecycle::sweep_sol(ctx, round_id)
+    }
+
+    pub fn sweep_tokens(ctx: Context<SweepTokens>, round_id: u64) -> Result<()> {
+        lifecycle::sweep_tokens(ctx, round_id)
+    }
+
+    pub fn execute_burns(ctx: Context<ExecuteBurns>, round_id: u64) -> Result<()> {
+        lifecycle::execute_burns(ctx, round_id)
+    }
+
     pub fn close_round(ctx: Context<CloseRound>, round_id: u64) -> Result<()> {
         lifecycle::close_round(ctx, round_id)
     }
 
+    pub fn close_round_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseRoundBatch<'info>>,
+    ) -> Result<()> {
+        lifecycle::close_round_batch(ctx)
+    }
+
+    pub fn cancel_round(ctx: Context<CancelRound>, round_id: u64) -> Result<()> {
+        lifecycle::cancel_round(ctx, round_id)
+    }
+
     pub fn recover_funds(ctx: Context<RecoverFunds>, round_id: u64) -> Result<()> {
         lifecycle::recover_funds(ctx, round_id)
     }
@@ -143,10 +286,25 @@ pub mod timlg_protocol {
         lifecycle::recover_funds_anyone(ctx, round_id)
     }
 
+    pub fn recover_funds_anyone_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecoverFundsAnyoneBatch<'info>>,
+        round_id: u64,
+    ) -> Result<()> {
+        lifecycle::recover_funds_anyone_batch(ctx, round_id)
+    }
+
     pub fn close_ticket(ctx: Context<CloseTicket>, round_id: u64, nonce: u64) -> Result<()> {
         lifecycle::close_ticket(ctx, round_id, nonce)
     }
 
+    pub fn close_ticket_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseTicketBatch<'info>>,
+        round_id: u64,
+        nonces: Vec<u64>,
+    ) -> Result<()> {
+        lifecycle::close_ticket_batch(ctx, round_id, nonces)
+    }
+
     // core
     pub fn commit_ticket(
         ctx: Context<CommitTicket>,
@@ -157,6 +315,34 @@ pub mod timlg_protocol {
         commit::commit_ticket(ctx, round_id, nonce, commitment)
     }
 
+    pub fn commit_ticket_weighted(
+        ctx: Context<CommitTicket>,
+        round_id: u64,
+        nonce: u64,
+        commitment: [u8; 32],
+        weight: u8,
+    ) -> Result<()> {
+        commit::commit_ticket_weighted(ctx, round_id, nonce, commitment, weight)
+    }
+
+    pub fn commit_ticket_public(
+        ctx: Context<CommitTicket>,
+        round_id: u64,
+        nonce: u64,
+        guess: u8,
+    ) -> Result<()> {
+        commit::commit_ticket_public(ctx, round_id, nonce, guess)
+    }
+
+    pub fn commit_ticket_delegated(
+        ctx: Context<CommitTicketDelegated>,
+        round_id: u64,
+        nonce: u64,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        commit::commit_ticket_delegated(ctx, round_id, nonce, commitment)
+    }
+
     pub fn reveal_ticket(
         ctx: Context<RevealTicket>,
         round_id: u64,
@@ -176,6 +362,14 @@ pub mod timlg_protocol {
         commit::commit_batch(ctx, round_id, entries)
     }
 
+    pub fn commit_batch_weighted<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CommitBatch<'info>>,
+        round_id: u64,
+        entries: Vec<WeightedCommitEntry>,
+    ) -> Result<()> {
+        commit::commit_batch_weighted(ctx, round_id, entries)
+    }
+
     pub fn reveal_batch<'info>(
         ctx: Context<'_, '_, 'info, 'info, RevealBatch<'info>>,
         round_id: u64,
@@ -184,12 +378,50 @@ pub mod timlg_protocol {
         reveal::reveal_batch(ctx, round_id, entries)
     }
 
+    pub fn reveal_batch_lenient<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevealBatch<'info>>,
+        round_id: u64,
+        entries: Vec<RevealEntry>,
+    ) -> Result<()> {
+        reveal::reveal_batch_lenient(ctx, round_id, entries)
+    }
+
+    pub fn commit_reveal_root(ctx: Context<CommitRevealRoot>, round_id: u64, root: [u8; 32]) -> Result<()> {
+        reveal::commit_reveal_root(ctx, round_id, root)
+    }
+
+    pub fn reveal_merkle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevealBatch<'info>>,
+        round_id: u64,
+        entries: Vec<MerkleRevealEntry>,
+    ) -> Result<()> {
+        reveal::reveal_merkle(ctx, round_id, entries)
+    }
+
     pub fn commit_batch_signed<'info>(
         ctx: Context<'_, '_, 'info, 'info, CommitBatchSigned<'info>>,
         round_id: u64,
         entries: Vec<CommitSignedEntry>,
+        max_land_slot: Option<u64>,
     ) -> Result<()> {
-        commit::commit_batch_signed(ctx, round_id, entries)
+        commit::commit_batch_signed(ctx, round_id, entries, max_land_slot)
+    }
+
+    pub fn commit_ticket_signed(
+        ctx: Context<CommitTicketSigned>,
+        round_id: u64,
+        nonce: u64,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        commit::commit_ticket_signed(ctx, round_id, nonce, commitment)
+    }
+
+    pub fn commit_batch_signed_multi<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CommitBatchSignedMulti<'info>>,
+        round_id: u64,
+        entries: Vec<CommitSignedEntry>,
+    ) -> Result<()> {
+        commit::commit_batch_signed_multi(ctx, round_id, entries)
     }
 
     pub fn reveal_batch_signed<'info>(
@@ -204,6 +436,41 @@ pub mod timlg_protocol {
         reward::claim_reward(ctx, round_id, nonce)
     }
 
+    pub fn claim_reward_to(
+        ctx: Context<ClaimRewardTo>,
+        round_id: u64,
+        nonce: u64,
+        destination_owner: Pubkey,
+    ) -> Result<()> {
+        reward::claim_reward_to(ctx, round_id, nonce, destination_owner)
+    }
+
+    pub fn claim_reward_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRewardBatch<'info>>,
+        round_id: u64,
+        nonces: Vec<u64>,
+    ) -> Result<()> {
+        reward::claim_reward_batch(ctx, round_id, nonces)
+    }
+
+    pub fn claim_multi_round<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimMultiRound<'info>>,
+    ) -> Result<()> {
+        reward::claim_multi_round(ctx)
+    }
+
+    pub fn set_reward_mode(ctx: Context<SetRewardMode>, mode: u8) -> Result<()> {
+        admin::set_reward_mode(ctx, mode)
+    }
+
+    pub fn reclaim_unrevealed_stake(
+        ctx: Context<ReclaimUnrevealedStake>,
+        round_id: u64,
+        nonce: u64,
+    ) -> Result<()> {
+        reward::reclaim_unrevealed_stake(ctx, round_id, nonce)
+    }
+
     pub fn set_claim_grace_slots(ctx: Context<SetClaimGraceSlots>, claim_grace_slots: u64) -> Result<()> {
         admin::set_claim_grace_slots(ctx, claim_grace_slots)
     }
@@ -212,10 +479,123 @@ pub mod timlg_protocol {
         admin::update_stake_amount(ctx, new_stake_amount)
     }
 
+    pub fn set_stake_bounds(
+        ctx: Context<SetStakeBounds>,
+        min_stake_amount: u64,
+        max_stake_amount: u64,
+    ) -> Result<()> {
+        admin::set_stake_bounds(ctx, min_stake_amount, max_stake_amount)
+    }
+
     pub fn update_sol_service_fee(ctx: Context<UpdateSolServiceFee>, new_fee: u64) -> Result<()> {
         admin::update_sol_service_fee(ctx, new_fee)
     }
 
+    pub fn set_cpi_allowlist(ctx: Context<SetCpiAllowlist>, programs: Vec<Pubkey>) -> Result<()> {
+        admin::set_cpi_allowlist(ctx, programs)
+    }
+
+    pub fn set_max_extensions(ctx: Context<SetMaxExtensions>, max_extensions: u8) -> Result<()> {
+        admin::set_max_extensions(ctx, max_extensions)
+    }
+
+    pub fn add_fee_exempt(ctx: Context<AddFeeExempt>, user: Pubkey) -> Result<()> {
+        admin::add_fee_exempt(ctx, user)
+    }
+
+    pub fn remove_fee_exempt(ctx: Context<RemoveFeeExempt>, user: Pubkey) -> Result<()> {
+        admin::remove_fee_exempt(ctx, user)
+    }
+
+    pub fn sync_timlg_decimals(ctx: Context<SyncTimlgDecimals>) -> Result<()> {
+        admin::sync_timlg_decimals(ctx)
+    }
+
+    pub fn set_seed_version(ctx: Context<SetSeedVersion>, seed_version: u8) -> Result<()> {
+        admin::set_seed_version(ctx, seed_version)
+    }
+
+    pub fn set_stake_in_sol(ctx: Context<SetStakeInSol>, stake_in_sol: bool) -> Result<()> {
+        admin::set_stake_in_sol(ctx, stake_in_sol)
+    }
+
+    pub fn set_auto_extend_reveal(ctx: Context<SetAutoExtendReveal>, enabled: bool) -> Result<()> {
+        admin::set_auto_extend_reveal(ctx, enabled)
+    }
+
+    pub fn set_max_tickets_per_user(
+        ctx: Context<SetMaxTicketsPerUser>,
+        max_tickets_per_user: u64,
+    ) -> Result<()> {
+        admin::set_max_tickets_per_user(ctx, max_tickets_per_user)
+    }
+
+    pub fn set_rent_reimburse(
+        ctx: Context<SetRentReimburse>,
+        rent_reimburse: bool,
+        rent_price_timlg: u64,
+    ) -> Result<()> {
+        admin::set_rent_reimburse(ctx, rent_reimburse, rent_price_timlg)
+    }
+
+    pub fn set_sweep_burns_tokens(ctx: Context<SetSweepBurnsTokens>, sweep_burns_tokens: bool) -> Result<()> {
+        admin::set_sweep_burns_tokens(ctx, sweep_burns_tokens)
+    }
+
+    pub fn set_cranker_fee(ctx: Context<SetCrankerFee>, cranker_fee_lamports: u64) -> Result<()> {
+        admin::set_cranker_fee(ctx, cranker_fee_lamports)
+    }
+
+    pub fn set_max_weight(ctx: Context<SetMaxWeight>, max_weight: u8) -> Result<()> {
+        admin::set_max_weight(ctx, max_weight)
+    }
+
+    pub fn set_rescue_authority(
+        ctx: Context<SetRescueAuthority>,
+        rescue_authority: Pubkey,
+        rescue_destination: Pubkey,
+    ) -> Result<()> {
+        admin::set_rescue_authority(ctx, rescue_authority, rescue_destination)
+    }
+
+    pub fn emergency_drain(ctx: Context<EmergencyDrain>) -> Result<()> {
+        admin::emergency_drain(ctx)
+    }
+
+    pub fn set_accept_legacy_commit_msg(ctx: Context<SetAcceptLegacyCommitMsg>, accept: bool) -> Result<()> {
+        admin::set_accept_legacy_commit_msg(ctx, accept)
+    }
+
+    pub fn set_max_open_rounds(ctx: Context<SetMaxOpenRounds>, max_open_rounds: u16) -> Result<()> {
+        admin::set_max_open_rounds(ctx, max_open_rounds)
+    }
+
+    pub fn migrate_round_registry(ctx: Context<MigrateRoundRegistry>) -> Result<()> {
+        admin::migrate_round_registry(ctx)
+    }
+
+    pub fn freeze_treasury(ctx: Context<FreezeTreasury>) -> Result<()> {
+        admin::freeze_treasury(ctx)
+    }
+
+    pub fn thaw_treasury(ctx: Context<FreezeTreasury>) -> Result<()> {
+        admin::thaw_treasury(ctx)
+    }
+
+    pub fn transfer_mint_authority(
+        ctx: Context<TransferMintAuthority>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        admin::transfer_mint_authority(ctx, new_authority)
+    }
+
+    pub fn set_min_reveal_after_pulse_slots(
+        ctx: Context<SetMinRevealAfterPulseSlots>,
+        min_reveal_after_pulse_slots: u64,
+    ) -> Result<()> {
+        admin::set_min_reveal_after_pulse_slots(ctx, min_reveal_after_pulse_slots)
+    }
+
     pub fn update_windows(
         ctx: Context<UpdateWindows>,
         commit_window_slots: u64,
@@ -224,8 +604,24 @@ pub mod timlg_protocol {
         admin::update_windows(ctx, commit_window_slots, reveal_window_slots)
     }
 
-    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
-        admin::migrate_config(ctx)
+    pub fn migrate_config(
+        ctx: Context<MigrateConfig>,
+        rescue_authority: Pubkey,
+        rescue_destination: Pubkey,
+    ) -> Result<()> {
+        admin::migrate_config(ctx, rescue_authority, rescue_destination)
+    }
+
+    pub fn migrate_round(ctx: Context<MigrateRound>, round_id: u64) -> Result<()> {
+        admin::migrate_round(ctx, round_id)
+    }
+
+    pub fn migrate_ticket(ctx: Context<MigrateTicket>) -> Result<()> {
+        admin::migrate_ticket(ctx)
+    }
+
+    pub fn migrate_user_escrow(ctx: Context<MigrateUserEscrow>) -> Result<()> {
+        admin::migrate_user_escrow(ctx)
     }
 
     pub fn withdraw_treasury_sol(ctx: Context<WithdrawTreasurySol>, amount: u64) -> Result<()> {
@@ -236,6 +632,14 @@ pub mod timlg_protocol {
         admin::withdraw_treasury_tokens(ctx, amount)
     }
 
+    pub fn withdraw_reward_fee_pool(ctx: Context<WithdrawRewardFeePool>, amount: u64) -> Result<()> {
+        admin::withdraw_reward_fee_pool(ctx, amount)
+    }
+
+    pub fn withdraw_replication_pool(ctx: Context<WithdrawReplicationPool>, amount: u64) -> Result<()> {
+        admin::withdraw_replication_pool(ctx, amount)
+    }
+
     pub fn init_user_escrow(ctx: Context<InitUserEscrow>) -> Result<()> {
         escrow::init_user_escrow(ctx)
     }
@@ -244,10 +648,22 @@ pub mod timlg_protocol {
         escrow::deposit_escrow(ctx, amount)
     }
 
+    pub fn deposit_escrow_checked(
+        ctx: Context<DepositEscrow>,
+        amount: u64,
+        min_expected_balance: u64,
+    ) -> Result<()> {
+        escrow::deposit_escrow_checked(ctx, amount, min_expected_balance)
+    }
+
     pub fn withdraw_escrow(ctx: Context<WithdrawEscrow>, amount: u64) -> Result<()> {
         escrow::withdraw_escrow(ctx, amount)
     }
 
+    pub fn close_user_escrow(ctx: Context<CloseUserEscrow>) -> Result<()> {
+        escrow::close_user_escrow(ctx)
+    }
+
     // ✅ FIX lifetimes: debe coincidir con lifecycle::settle_round_tokens
     pub fn settle_round_tokens<'info>(
         ctx: Context<'_, '_, 'info, 'info, SettleRoundTokens<'info>>,
@@ -260,13 +676,31 @@ pub mod timlg_protocol {
         instructions::admin::initialize_round_registry(ctx, start_round_id)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_round_auto(
         ctx: Context<CreateRoundAuto>,
         pulse_index_target: u64,
         commit_deadline_slot: u64,
         reveal_deadline_slot: u64,
+        claim_grace_slots_override: u64,
+        label: [u8; 32],
+        oracle_pubkey_override: Pubkey,
+        max_total_reward: u64,
+        min_participants: u64,
+        guess_bits: u8,
     ) -> Result<()> {
-        instructions::admin::create_round_auto(ctx, pulse_index_target, commit_deadline_slot, reveal_deadline_slot)
+        instructions::admin::create_round_auto(
+            ctx,
+            pulse_index_target,
+            commit_deadline_slot,
+            reveal_deadline_slot,
+            claim_grace_slots_override,
+            label,
+            oracle_pubkey_override,
+            max_total_reward,
+            min_participants,
+            guess_bits,
+        )
     }
 
     pub fn initialize_tokenomics(
@@ -283,11 +717,89 @@ pub mod timlg_protocol {
     pub fn update_tokenomics(
         ctx: Context<UpdateTokenomics>,
         reward_fee_bps: u16,
+        slash_to_pool: Option<bool>,
+        fee_to_replication_bps: Option<u16>,
+    ) -> Result<()> {
+        admin::update_tokenomics(ctx, reward_fee_bps, slash_to_pool, fee_to_replication_bps)
+    }
+
+    pub fn set_unrevealed_policy(
+        ctx: Context<SetUnrevealedPolicy>,
+        policy: u8,
+        penalty_bps: u16,
+    ) -> Result<()> {
+        admin::set_unrevealed_policy(ctx, policy, penalty_bps)
+    }
+
+    pub fn initialize_additional_pool(
+        ctx: Context<InitializeAdditionalPool>,
+        pool_type: u8,
     ) -> Result<()> {
-        admin::update_tokenomics(ctx, reward_fee_bps)
+        admin::initialize_additional_pool(ctx, pool_type)
+    }
+
+    pub fn set_burn_delay(ctx: Context<SetBurnDelay>, burn_delay_slots: u64) -> Result<()> {
+        admin::set_burn_delay(ctx, burn_delay_slots)
+    }
+
+    pub fn set_streak_bonus_bps(ctx: Context<SetStreakBonusBps>, streak_bonus_bps: u16) -> Result<()> {
+        admin::set_streak_bonus_bps(ctx, streak_bonus_bps)
+    }
+
+    pub fn set_prefund_fees(ctx: Context<SetPrefundFees>, prefund_fees: bool) -> Result<()> {
+        admin::set_prefund_fees(ctx, prefund_fees)
+    }
+
+    pub fn migrate_tokenomics(ctx: Context<MigrateTokenomics>) -> Result<()> {
+        admin::migrate_tokenomics(ctx)
     }
 
     pub fn close_user_stats(ctx: Context<CloseUserStats>) -> Result<()> {
         lifecycle::close_user_stats(ctx)
     }
+
+    pub fn health_check(ctx: Context<HealthCheck>) -> Result<()> {
+        health::health_check(ctx)
+    }
+
+    pub fn round_status(ctx: Context<RoundStatus>, round_id: u64) -> Result<()> {
+        status::round_status(ctx, round_id)
+    }
+
+    pub fn round_phase_compact(ctx: Context<RoundStatus>, round_id: u64) -> Result<()> {
+        status::round_phase_compact(ctx, round_id)
+    }
+
+    pub fn get_round_status(
+        ctx: Context<RoundStatus>,
+        round_id: u64,
+    ) -> Result<status::RoundStatusView> {
+        status::get_round_status(ctx, round_id)
+    }
+
+    pub fn get_protocol_stats(ctx: Context<GetProtocolStats>) -> Result<status::ProtocolStatsView> {
+        status::get_protocol_stats(ctx)
+    }
+
+    pub fn get_ticket_state(
+        ctx: Context<GetTicketState>,
+        round_id: u64,
+        nonce: u64,
+    ) -> Result<status::TicketState> {
+        status::get_ticket_state(ctx, round_id, nonce)
+    }
+
+    pub fn compute_outcome(
+        ctx: Context<ComputeOutcome>,
+        round_id: u64,
+        nonce: u64,
+        guess: u8,
+        salt: [u8; 32],
+    ) -> Result<status::OutcomePreview> {
+        status::compute_outcome(ctx, round_id, nonce, guess, salt)
+    }
+
+    pub fn quote_commit(ctx: Context<QuoteCommit>, user: Pubkey, n_tickets: u64) -> Result<()> {
+        status::quote_commit(ctx, user, n_tickets)
+    }
 }
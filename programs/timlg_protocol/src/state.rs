@@ -7,6 +7,9 @@ pub struct RoundRegistry {
     pub bump: u8,
     pub next_round_id: u64,
     pub version: u16,
+    /// Cap on `create_round_auto` rounds that are not yet closed or cancelled. 0 = unlimited.
+    pub max_open_rounds: u16,
+    pub open_rounds: u16,
 }
 
 #[account]
@@ -25,6 +28,8 @@ pub struct Config {
     // MVP-2: oracle pubkey allowed to set pulse via ed25519 introspection
     pub oracle_pubkey: Pubkey,
 
+    /// Emergency stop: blocks commits, reveals, pulses, settlement, claims and sweeps.
+    /// Refund paths (`recover_funds*`, `reclaim_unrevealed_stake`) and escrow withdrawals stay open.
     pub paused: bool,
     pub version: u16,
 
@@ -41,6 +46,75 @@ pub struct Config {
 
     // ✅ NUEVO: Tasa de servicio en SOL por ticket (lamports)
     pub sol_service_fee_lamports: u64,
+
+    /// Programs allowed to CPI into `claim_reward`. Empty = any caller.
+    /// Only a direct CPI from an allowlisted top-level program passes: the caller is read
+    /// from the instructions sysvar, which cannot name an intermediate program, so nested
+    /// CPI (allowlisted program -> other program -> this one) is rejected.
+    /// NOTE: fixed max_len to keep account size deterministic.
+    #[max_len(8)]
+    pub cpi_allowlist: Vec<Pubkey>,
+
+    /// Max reveal deadline extensions per round (admin or automatic).
+    pub max_extensions: u8,
+
+    /// Users exempt from the SOL service fee and the reward fee (e.g. market makers).
+    #[max_len(16)]
+    pub fee_exempt: Vec<Pubkey>,
+    /// Decimals of `timlg_mint`, cached for client-facing quotes.
+    pub timlg_decimals: u8,
+    /// PDA seed generation (see `utils::SEED_VERSION`). `NEXT_SEED_VERSION` opens the
+    /// dual-read migration window.
+    pub seed_version: u8,
+    /// Slots after `pulse_set_slot` during which the admin may `dispute_pulse`. 0 = disabled.
+    pub pulse_dispute_window_slots: u64,
+    /// New rounds take stakes in SOL (lamports into the round `vault`) instead of TIMLG.
+    pub stake_in_sol: bool,
+    /// A pulse arriving inside the late-pulse buffer pushes the reveal deadline instead of failing.
+    pub auto_extend_reveal: bool,
+    /// Max tickets a single user may commit per round. 0 = unlimited.
+    pub max_tickets_per_user: u64,
+    /// Minimum reveal time after the pulse lands; extends `reveal_deadline_slot` when needed. 0 = off.
+    pub min_reveal_after_pulse_slots: u64,
+    /// `stake_amount` must stay within `[min_stake_amount, max_stake_amount]`. max 0 = no cap.
+    pub min_stake_amount: u64,
+    pub max_stake_amount: u64,
+    /// Oracle pulses need at least this many set bits (all-0x00/0xFF are always rejected). 0 = off.
+    pub min_pulse_popcount: u32,
+    /// `commit_batch_signed` repays the relayer's ticket rent in TIMLG from the user's escrow,
+    /// priced at `rent_price_timlg` base units per SOL. Users consent via the `commit_v2` message.
+    pub rent_reimburse: bool,
+    pub rent_price_timlg: u64,
+    /// Token sweeps burn the unclaimed winners' stake instead of sending it to `treasury`.
+    pub sweep_burns_tokens: bool,
+    /// Lamports paid from `treasury_sol` to whoever runs a permissionless crank
+    /// (`settle_round_tokens`, `recover_funds_anyone`) that did some work. 0 = off.
+    /// Only paid out of the surplus over every round's outstanding service fees
+    /// (`sol_fees_outstanding`); user refunds rank first.
+    pub cranker_fee_lamports: u64,
+    /// Highest stake multiple a ticket may commit with (`commit_ticket_weighted`, `commit_batch_weighted`).
+    /// 0/1 = weighting off, every ticket stakes `stake_amount`.
+    pub max_weight: u8,
+    /// Key (independent of `admin`) allowed to `emergency_drain` a paused protocol
+    /// into `rescue_destination`. Set at init, rotated only by itself.
+    pub rescue_authority: Pubkey,
+    pub rescue_destination: Pubkey,
+    /// Signed commits (`commit_batch_signed`, `commit_ticket_signed`, `commit_batch_signed_multi`)
+    /// still take the deadline-less `commit_v1`/`commit_v2` messages (migration only); otherwise
+    /// they require `commit_v3`. Because v3 covers the live deadlines, `extend_commit_deadline`,
+    /// `extend_reveal_deadline` or an automatic reveal extension invalidates v3 signatures still
+    /// in flight: users must re-sign against the new deadlines.
+    pub accept_legacy_commit_msg: bool,
+    /// Lifetime totals for dashboards (`get_protocol_stats`). Zero-filled by `migrate_config`
+    /// on accounts created before them.
+    pub total_committed: u64,
+    pub total_revealed: u64,
+    /// TIMLG minted by claims: rewards, their fees and streak bonuses, in raw base units.
+    pub total_rewards_minted: u64,
+    /// SOL service fees still refundable across all rounds: the sum of `Round.fees_outstanding`.
+    /// `pay_cranker_fee` keeps it in `treasury_sol`. Fees booked before `migrate_config`
+    /// added it are not counted.
+    pub sol_fees_outstanding: u64,
 }
 
 #[account]
@@ -60,6 +134,25 @@ pub struct OracleSet {
     pub version: u16,
 }
 
+/// Async multisig accumulator (`[ATTEST_SEED, round_id]`): oracles attest one tx at a time
+/// and `finalize_pulse` copies `pulse` into the round once `oracle_set.threshold` bits are set.
+/// Attestations still land after a pulse is stored: they are the evidence `dispute_pulse`
+/// checks, and a successful dispute resets the account.
+#[account]
+#[derive(InitSpace)]
+pub struct PulseAttestations {
+    pub round_id: u64,
+    pub bump: u8,
+    /// Candidate pulse, fixed by the first attestation.
+    pub pulse: [u8; 64],
+    /// Bit `i` set = `oracle_set.oracles[i]` attested (`MAX_ORACLES` = 16).
+    pub attested: u16,
+    pub count: u8,
+    /// `utils::oracle_set_digest` at the first attestation; bit indexes are only valid for it.
+    pub oracle_set_digest: [u8; 32],
+    pub first_slot: u64,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct UserEscrow {
@@ -67,6 +160,11 @@ pub struct UserEscrow {
     pub bump: u8,
     pub created_slot: u64,
     pub updated_slot: u64,
+
+    /// Last slot in which any of this user's tickets was revealed (0 = never).
+    /// Lets relayers spot users who committed but have not revealed recently.
+    /// Escrows created before this field are 8 bytes short: run `migrate_user_escrow`.
+    pub last_reveal_slot: u64,
 }
 
 #[repr(u8)]
@@ -74,6 +172,34 @@ pub enum RoundState {
     Announced = 0,
     PulseSet = 1,
     Finalized = 2,
+    /// Voided at finalize (e.g. below `min_participants`): every ticket is refundable.
+    Refunding = 3,
+    /// Both vaults swept (`sweep_unclaimed`, or `sweep_sol` + `sweep_tokens`). The next step,
+    /// `close_round`, deletes the account, so there is no stored "closed" state.
+    Swept = 4,
+}
+
+#[repr(u8)]
+pub enum UnrevealedPolicy {
+    /// Unrevealed stake is burned like a loss.
+    Burn = 0,
+    /// Penalty is burned, the rest stays in the vault for `reclaim_unrevealed_stake`.
+    RefundMinusPenalty = 1,
+    /// Unrevealed stake goes to the SPL treasury.
+    Treasury = 2,
+    /// Unrevealed tickets play guess 0 at settle (commitment unchecked) and win if their bit is 0;
+    /// such a win gets its stake back minus the `unrevealed_penalty_bps` penalty.
+    /// Trade-off: in 1-bit rounds a losing guess is better left unrevealed (a coin flip instead
+    /// of a sure loss), so revealing is optional for losers; the penalty only prices that option.
+    DefaultGuessZero = 3,
+}
+
+#[repr(u8)]
+pub enum RewardMode {
+    /// Winners get their stake back plus a freshly minted `stake_amount`.
+    Mint = 0,
+    /// Loser stakes stay in the vault and are split evenly among winners.
+    PariMutuel = 1,
 }
 
 #[account]
@@ -126,6 +252,84 @@ pub struct Round {
     pub close_burn_done: bool,
     pub close_unclaimed_mint_done: bool,
 
+    // Public-guess round: guesses are committed in clear and there is no reveal phase.
+    pub public_mode: bool,
+
+    // Minimum committed tickets for the round to resolve (0 = disabled).
+    pub min_participants: u64,
+
+    // Reveal deadline extensions granted so far (bounded by config.max_extensions).
+    pub extension_count: u8,
+    // Unrevealed policy snapshotted from Tokenomics at the first settle call,
+    // and the per-ticket amount reclaimable under RefundMinusPenalty.
+    pub unrevealed_policy: u8,
+    pub unrevealed_refund: u64,
+    // Ticket cap (0 = unlimited). Reaching it freezes commits and stamps the close slot.
+    pub max_committed: u64,
+    pub commit_frozen: bool,
+    pub commit_closed_slot: u64,
+    // Deferred burn: delay snapshotted at first settle, amount awaiting execute_burns.
+    pub burn_delay_slots: u64,
+    pub pending_burn: u64,
+    /// Distinct oracle signatures behind the current pulse (1 for `set_pulse_signed`).
+    pub pulse_attestations: u8,
+    /// Per-round freeze of commits and reveals (admin). Lifecycle paths ignore it.
+    pub paused: bool,
+    /// `RewardMode` snapshot taken at first settle.
+    pub reward_mode: u8,
+    /// Pari-mutuel: loser stakes kept in the vault for winners (split by `win_count`).
+    pub loser_stake_pool: u64,
+    /// Pulse bits read per ticket: 1, 2 or 4 (0 = legacy round, read as 1).
+    pub guess_bits: u8,
+    /// Last slot a winner can claim: reveal_deadline_slot + claim grace (round override or config), fixed at finalize.
+    pub claim_deadline_slot: u64,
+    /// `config.stake_in_sol` snapshot taken at creation: stakes live in `vault` as lamports.
+    pub stake_in_sol: bool,
+    /// Slot at which a late pulse auto-extended the reveal deadline (0 = never).
+    pub reveal_extended_slot: u64,
+    /// TIMLG burned from this round's vault (settle, execute_burns and sweep_unclaimed).
+    pub burned_total: u64,
+    /// Lamport vault swept (`sweep_sol` / `sweep_unclaimed`).
+    pub sol_swept: bool,
+    /// Token vault swept (`sweep_tokens` / `sweep_unclaimed`). `swept` = both.
+    pub tokens_swept: bool,
+    /// Per-round claim grace; 0 = use `config.claim_grace_slots`.
+    pub claim_grace_slots_override: u64,
+    /// Opaque tag set at creation (UTF-8 slug, category id, ...). Never read on-chain.
+    pub label: [u8; 32],
+    /// Oracle key for `set_pulse_signed` on this round; default = `config.oracle_pubkey`.
+    /// Lets new rounds move to a new key while in-flight rounds keep the old one.
+    pub oracle_pubkey_override: Pubkey,
+    /// Set once settlement minted the outstanding reward fees (`tokenomics.prefund_fees`);
+    /// claims then skip their fee mint.
+    pub fees_prefunded: bool,
+    /// Stake units (ticket weights) of mint-mode claims that minted their own fee before
+    /// the prefund, which excludes them.
+    pub fee_paid_claims: u64,
+    /// Ceiling on TIMLG minted for this round (rewards, fees, streak bonuses); 0 = unlimited.
+    pub max_total_reward: u64,
+    /// TIMLG minted so far for this round, checked against `max_total_reward`.
+    pub reward_minted_total: u64,
+    /// TIMLG seeded into `timlg_vault` by `fund_timlg_vault`, on top of the stakes.
+    /// Pari-mutuel rounds pay it out to winners; otherwise the sweep sends it to treasury.
+    pub seeded_amount: u64,
+    /// Created by `create_round_auto`: counted in `RoundRegistry.open_rounds` until closed.
+    pub registry_tracked: bool,
+    /// Extra stake units of weighted winners: sum of (weight - 1) over winning tickets,
+    /// so `win_count + win_extra_weight` is what pari-mutuel shares and sweeps divide by.
+    pub win_extra_weight: u64,
+    /// Same, for the winners counted in `claimed_win_count`.
+    pub claimed_win_extra_weight: u64,
+    /// Running hash over every reveal in order: sha256(prev, user, nonce, guess, win).
+    /// Lets a third party replay the reveal log and check it against the round.
+    pub reveal_transcript: [u8; 32],
+    /// Slot of the last successful `dispute_pulse` (0 = never). Reveals made at or before it
+    /// were scored against the cleared pulse and no longer count (`utils::reveal_voided`).
+    pub pulse_disputed_slot: u64,
+    /// SOL service fees charged on this round's tickets and not yet refunded: what the refund
+    /// paths may still owe, counted in `Config.sol_fees_outstanding` until finalize
+    /// (a finalized round keeps its fees and drops to 0). Rounds created before this field read 0.
+    pub fees_outstanding: u64,
 }
 
 #[account]
@@ -138,7 +342,8 @@ pub struct Ticket {
 
     pub commitment: [u8; 32],
 
-    // stake fue realmente aportado (transfer a vault)
+    // stake fue realmente aportado (transfer a vault); cleared when the stake is refunded
+    // and the ticket stays open (`recover_funds_anyone_batch`), so it can't reveal or claim
     pub stake_paid: bool,
 
     // stake ya fue liquidado por settle (burn o treasury) → idempotencia
@@ -163,6 +368,15 @@ pub struct Ticket {
 
     // NEW: chronoligcal counter of user's played tickets
     pub user_commit_index: u64,
+
+    // stake multiple paid at commit (stake_amount * weight); 0 reads as 1. Tickets created
+    // before this field are one byte short: batch paths pad them, `migrate_ticket` grows them.
+    pub weight: u8,
+
+    // SOL service fee actually charged at commit and who paid it (the user or a relayer);
+    // refunds return exactly this. Tickets created before these fields read 0 / default.
+    pub sol_fee_paid: u64,
+    pub fee_payer: Pubkey,
 }
 
 #[account]
@@ -183,6 +397,30 @@ pub struct UserStats {
     pub last_revealed_winning_index: u64,
 }
 
+/// Consecutive winning rounds (`[STREAK_SEED, user]`), updated on `claim_reward`.
+#[account]
+#[derive(InitSpace)]
+pub struct UserStreak {
+    pub user: Pubkey,
+    pub bump: u8,
+    pub current_streak: u32,
+    pub last_won_round: u64,
+}
+
+/// Per-user, per-round ticket counters (`[USER_ROUND_SEED, round_id, user]`).
+#[account]
+#[derive(InitSpace)]
+pub struct UserRoundStats {
+    pub round_id: u64,
+    pub user: Pubkey,
+    pub bump: u8,
+    pub committed: u64,
+    pub revealed: u64,
+    /// Merkle root over the user's `merkle_reveal_leaf` leaves, set by `commit_reveal_root`
+    /// while commits are open; `reveal_merkle` proofs are checked against it. Zero = none.
+    pub reveal_root: [u8; 32],
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct GlobalStats {
@@ -215,4 +453,39 @@ pub struct Tokenomics {
     pub replication_pool_bump: u8,
 
     pub version: u16,
+
+    /// What settle does with unrevealed stakes (see `UnrevealedPolicy`).
+    pub unrevealed_policy: u8,
+    /// Penalty (bps of stake) burned under `RefundMinusPenalty`.
+    pub unrevealed_penalty_bps: u16,
+    /// Pools added after initialization via `initialize_additional_pool`
+    /// (Pubkey::default() = not created yet).
+    pub jackpot_pool: Pubkey,
+    pub jackpot_pool_bump: u8,
+
+    pub reward_reserve_pool: Pubkey,
+    pub reward_reserve_pool_bump: u8,
+    /// Audit window: loser stakes stay in the vault this many slots after
+    /// settlement before `execute_burns` can burn them (0 = burn at settle).
+    pub burn_delay_slots: u64,
+    /// `RewardMode`; snapshotted onto the round at first settle.
+    pub reward_mode: u8,
+    /// Send slashed stakes to `replication_pool` instead of burning them.
+    pub slash_to_pool: bool,
+    /// Share of each reward fee (bps) routed to `replication_pool`; the rest goes to `reward_fee_pool`.
+    pub fee_to_replication_bps: u16,
+    /// Extra mint per streak step on `claim_reward`: reward_total * bps * min(streak, cap) / 10000.
+    pub streak_bonus_bps: u16,
+    /// Mint the winners' reward fees at settlement instead of on each claim.
+    /// Inactive while `config.fee_exempt` is non-empty (exempt winners owe no fee), and for
+    /// rounds with a `max_total_reward`.
+    pub prefund_fees: bool,
+}
+
+#[repr(u8)]
+pub enum PoolType {
+    RewardFee = 0,
+    Replication = 1,
+    Jackpot = 2,
+    RewardReserve = 3,
 }
@@ -1,5 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::instruction::{
+    get_stack_height, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT,
+};
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
 use solana_sha256_hasher::hashv;
 
 // Ed25519SigVerify111111111111111111111111111
@@ -11,9 +16,13 @@ pub fn ed25519_program_id() -> Pubkey {
 }
 
 
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, Mint, MintTo, Token};
+
 use crate::{
+    constants::LAMPORTS_PER_SOL,
     errors::TimlgError,
-    state::{Round, Ticket},
+    state::{Config, OracleSet, Round, RoundState, Ticket, Tokenomics, UnrevealedPolicy},
 };
 
 // -----------------
@@ -31,20 +40,113 @@ pub const TIMLG_VAULT_SEED: &[u8] = b"timlg_vault_v3";
 pub const TREASURY_SOL_SEED: &[u8] = b"treasury_sol_v3";
 
 pub const MAX_BATCH: usize = 16;
+/// Rounds per `claim_multi_round` (each claim is a transfer CPI + account rewrite).
+pub const MAX_MULTI_CLAIM: usize = 6;
+
+// -----------------
+// Seed versioning
+// -----------------
+// Every seed above carries the `_v3` suffix. Bumping the suffix is a redeploy, and
+// without a bridge it would orphan every existing PDA. The bridge is a migration
+// window: the admin sets `config.seed_version = NEXT_SEED_VERSION` and every path
+// that derives PDAs by hand accepts either generation. Anchor `seeds = [...]`
+// constraints stay pinned to `SEED_VERSION` until the next build bumps it.
+// Accounts are always created with the current generation.
+
+/// Seed generation baked into this build.
+pub const SEED_VERSION: u8 = 3;
+/// Generation also accepted while `config.seed_version == NEXT_SEED_VERSION`.
+pub const NEXT_SEED_VERSION: u8 = SEED_VERSION + 1;
+
+pub const TICKET_SEED_V4: &[u8] = b"ticket_v4";
+
+pub fn ticket_seed(version: u8) -> Option<&'static [u8]> {
+    match version {
+        3 => Some(TICKET_SEED),
+        4 => Some(TICKET_SEED_V4),
+        _ => None,
+    }
+}
+
+/// Generations accepted under `config.seed_version` (0 = legacy config = current).
+pub fn accepted_seed_versions(cfg_seed_version: u8) -> &'static [u8] {
+    if cfg_seed_version == NEXT_SEED_VERSION {
+        &[SEED_VERSION, NEXT_SEED_VERSION]
+    } else {
+        &[SEED_VERSION]
+    }
+}
+
+pub fn find_ticket_pda(
+    version: u8,
+    round_id: u64,
+    user: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> Option<(Pubkey, u8)> {
+    let seed = ticket_seed(version)?;
+    Some(Pubkey::find_program_address(
+        &[seed, &round_id.to_le_bytes(), user.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    ))
+}
+
+/// Dual-read ticket check: `key` must match an accepted generation. Returns its bump.
+pub fn verify_ticket_pda(
+    cfg_seed_version: u8,
+    key: &Pubkey,
+    round_id: u64,
+    user: &Pubkey,
+    nonce: u64,
+    program_id: &Pubkey,
+) -> Result<u8> {
+    for v in accepted_seed_versions(cfg_seed_version) {
+        if let Some((pda, bump)) = find_ticket_pda(*v, round_id, user, nonce, program_id) {
+            if pda == *key {
+                return Ok(bump);
+            }
+        }
+    }
+    err!(TimlgError::TicketPdaMismatch)
+}
+
 
 pub const USER_ESCROW_SEED: &[u8] = b"user_escrow_v3";
 pub const USER_ESCROW_VAULT_SEED: &[u8] = b"user_escrow_vault_v3";
 
 pub const USER_STATS_SEED: &[u8] = b"user_stats_v3";
+pub const USER_ROUND_SEED: &[u8] = b"user_round";
+pub const STREAK_SEED: &[u8] = b"streak";
 
 // OracleSet
 pub const ORACLE_SET_SEED: &[u8] = b"oracle_set_v3";
 pub const MAX_ORACLES: usize = 16;
+pub const ATTEST_SEED: &[u8] = b"attest";
+
+// Config.cpi_allowlist
+pub const MAX_CPI_ALLOWLIST: usize = 8;
+
+// Config.fee_exempt
+pub const MAX_FEE_EXEMPT: usize = 16;
 
 // Tokenomics
 pub const TOKENOMICS_SEED: &[u8] = b"tokenomics_v3";
 pub const REWARD_FEE_POOL_SEED: &[u8] = b"reward_fee_pool_v3";
 pub const REPLICATION_POOL_SEED: &[u8] = b"replication_pool_v3";
+pub const JACKPOT_POOL_SEED: &[u8] = b"jackpot_pool_v3";
+pub const REWARD_RESERVE_POOL_SEED: &[u8] = b"reward_reserve_pool_v3";
+
+/// PDA seed for a Tokenomics pool type (see `PoolType`). Unknown types map to an
+/// unused seed; the handler rejects them (and the tx rolls back).
+pub fn pool_seed(pool_type: u8) -> &'static [u8] {
+    match pool_type {
+        0 => REWARD_FEE_POOL_SEED,
+        1 => REPLICATION_POOL_SEED,
+        2 => JACKPOT_POOL_SEED,
+        3 => REWARD_RESERVE_POOL_SEED,
+        _ => b"invalid_pool_v3",
+    }
+}
 
 pub const GLOBAL_STATS_SEED: &[u8] = b"global_stats_v4";
 
@@ -58,6 +160,15 @@ pub struct CommitEntry {
     pub commitment: [u8; 32],
 }
 
+/// `commit_batch_weighted` entry. Kept apart from `CommitEntry` so `commit_batch`'s
+/// wire format stays the same for existing clients.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WeightedCommitEntry {
+    pub nonce: u64,
+    pub commitment: [u8; 32],
+    pub weight: u8, // stake multiple, 1..=config.max_weight
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct RevealEntry {
     pub nonce: u64,
@@ -80,9 +191,22 @@ pub struct RevealSignedEntry {
     pub salt: [u8; 32],
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MerkleRevealEntry {
+    pub nonce: u64,
+    pub guess: u8,
+    pub salt: [u8; 32],
+    /// Sibling hashes leaf -> root (sorted-pair tree, see `merkle_verify`).
+    pub proof: Vec<[u8; 32]>,
+}
+
+// Merkle reveal: max proof length (2^16 leaves is far beyond any realistic user).
+pub const MAX_MERKLE_DEPTH: usize = 16;
+
 // -------------------------
 // Shared reveal logic
 // -------------------------
+#[allow(clippy::too_many_arguments)]
 pub fn reveal_core(
     round: &Round,
     ticket: &mut Ticket,
@@ -93,19 +217,524 @@ pub fn reveal_core(
     salt: [u8; 32],
     current_slot: u64,
 ) -> Result<()> {
+    // A refunded ticket (stake returned, ticket left open) never plays again.
+    require!(ticket.stake_paid, TimlgError::StakeNotPaid);
+    // Range first, so a malformed guess is not reported as a bad salt/commitment.
+    require_guess_in_range(round, guess)?;
+
     let computed = commit_hash(round_id, &user_pk, nonce, guess, &salt);
     require!(computed == ticket.commitment, TimlgError::CommitmentMismatch);
 
-    let derived = derive_bit_index(round_id, &user_pk, nonce);
+    let bits = guess_bits(round);
+    let derived = derive_bit_index_for(round_id, &user_pk, nonce, bits);
     require!(ticket.bit_index == derived, TimlgError::BitIndexMismatch);
 
-    let bit = get_pulse_bit(&round.pulse, ticket.bit_index);
+    let outcome = get_pulse_bits(&round.pulse, ticket.bit_index, bits);
 
     ticket.revealed = true;
     ticket.guess = guess;
-    ticket.win = bit == guess;
+    ticket.win = outcome == guess;
+    ticket.revealed_slot = current_slot;
+
+    Ok(())
+}
+
+/// True when `ticket` was revealed against a pulse that `dispute_pulse` later cleared.
+/// Replacement pulses land after `pulse_disputed_slot`, so the slot tells them apart.
+pub fn reveal_voided(round: &Round, ticket: &Ticket) -> bool {
+    ticket.revealed && ticket.revealed_slot <= round.pulse_disputed_slot
+}
+
+/// Drops a voided reveal (see `reveal_voided`): the ticket reads as unrevealed again, so it
+/// can reveal against the replacement pulse and settle/claim never score the old outcome.
+/// Returns whether it dropped one: that reveal is already in the lifetime (non-round) stats.
+pub fn clear_voided_reveal(round: &Round, ticket: &mut Ticket) -> bool {
+    if !reveal_voided(round, ticket) {
+        return false;
+    }
+    ticket.revealed = false;
+    ticket.guess = 0;
+    ticket.win = false;
+    ticket.revealed_slot = 0;
+    true
+}
+
+/// Next `round.reveal_transcript` after `ticket` was revealed:
+/// sha256(prev || user || nonce_le || guess || win).
+pub fn reveal_transcript_next(prev: &[u8; 32], ticket: &Ticket) -> [u8; 32] {
+    hashv(&[
+        prev.as_ref(),
+        ticket.user.as_ref(),
+        ticket.nonce.to_le_bytes().as_ref(),
+        &[ticket.guess],
+        &[ticket.win as u8],
+    ])
+    .to_bytes()
+}
+
+/// Whether `user` skips commit (SOL service) and reward fees.
+pub fn is_fee_exempt(cfg: &Config, user: &Pubkey) -> bool {
+    cfg.fee_exempt.contains(user)
+}
+
+/// SOL service fee per ticket for `user` (0 if exempt).
+pub fn sol_service_fee_for(cfg: &Config, user: &Pubkey) -> u64 {
+    if is_fee_exempt(cfg, user) {
+        0
+    } else {
+        cfg.sol_service_fee_lamports
+    }
+}
+
+/// Pushes the reveal deadline by `extra_slots`, bounded by `config.max_extensions`.
+/// A single extension is capped at one `reveal_window_slots`.
+/// Every extension path (admin or automatic) must go through here.
+pub fn apply_deadline_extension(round: &mut Round, cfg: &Config, extra_slots: u64) -> Result<()> {
+    require!(
+        extra_slots > 0 && extra_slots <= cfg.reveal_window_slots,
+        TimlgError::InvalidWindow
+    );
+    require!(round.extension_count < cfg.max_extensions, TimlgError::MaxExtensionsReached);
+
+    round.reveal_deadline_slot = round
+        .reveal_deadline_slot
+        .checked_add(extra_slots)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    round.extension_count += 1;
+    Ok(())
+}
+
+/// Marks the round fully settled (stamping `token_settled_slot`) and emits
+/// `RoundSettlementComplete`. Every path that completes settlement goes through here.
+pub fn mark_settlement_complete(round: &mut Round, current_slot: u64) {
+    round.token_settled = true;
+    round.token_settled_slot = current_slot;
+
+    emit!(crate::events::RoundSettlementComplete {
+        round_id: round.round_id,
+        slot: current_slot,
+        winners: round.win_count,
+        losers: round.settled_count.saturating_sub(round.win_count),
+    });
+    emit!(crate::events::RoundSettled {
+        round_id: round.round_id,
+        losers: round.settled_count.saturating_sub(round.win_count),
+        burned_total: round.burned_total,
+        settled_count: round.settled_count,
+    });
+}
+
+/// Moves `amount` lamports out of `treasury_sol` (treasury PDA signs). No-op for 0.
+/// Callers decide how much of the balance is spendable; see `treasury_sol_spendable`.
+pub fn treasury_sol_payout<'info>(
+    cfg: &Config,
+    treasury_sol: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let ix = anchor_lang::solana_program::system_instruction::transfer(treasury_sol.key, to.key, amount);
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        &[treasury_sol.clone(), to.clone(), system_program.clone()],
+        &[&[crate::TREASURY_SOL_SEED, &[cfg.treasury_sol_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Lamports `treasury_sol` can pay out without dropping below its rent-exempt minimum.
+pub fn treasury_sol_spendable(treasury_sol: &AccountInfo) -> Result<u64> {
+    let min_rent = Rent::get()?.minimum_balance(0);
+    Ok(treasury_sol.lamports().saturating_sub(min_rent))
+}
+
+/// Books a commit's SOL service fee as refundable: on the round and protocol-wide.
+pub fn book_service_fee(cfg: &mut Config, round: &mut Round, amount: u64) -> Result<()> {
+    round.fees_outstanding = round
+        .fees_outstanding
+        .checked_add(amount)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    cfg.sol_fees_outstanding = cfg
+        .sol_fees_outstanding
+        .checked_add(amount)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    Ok(())
+}
+
+/// Drops up to `amount` from the round's refundable fees and the same part from the
+/// protocol-wide counter (saturating: rounds and configs older than the counters never
+/// booked their fees).
+fn release_service_fees(cfg: &mut Config, round: &mut Round, amount: u64) {
+    let released = amount.min(round.fees_outstanding);
+    round.fees_outstanding -= released;
+    cfg.sol_fees_outstanding = cfg.sol_fees_outstanding.saturating_sub(released);
+}
+
+/// A finalized round keeps its service fees: zeroes `round.fees_outstanding` and returns
+/// what has to leave `cfg.sol_fees_outstanding` (see `release_finalized_fees`).
+pub fn take_finalized_fees(round: &mut Round) -> u64 {
+    if !round.finalized {
+        return 0;
+    }
+    std::mem::take(&mut round.fees_outstanding)
+}
+
+/// Stops reserving a finalized round's service fees in `treasury_sol`. Idempotent, so
+/// every path that may finalize a round calls it afterwards.
+pub fn release_finalized_fees(cfg: &mut Config, round: &mut Round) {
+    cfg.sol_fees_outstanding = cfg.sol_fees_outstanding.saturating_sub(take_finalized_fees(round));
+}
+
+/// Returns `amount` lamports of service fee from `treasury_sol` to the user and drops it
+/// from the outstanding counters. A treasury that cannot cover it skips the refund (logged):
+/// the stake refund around it must never depend on how much SOL the treasury holds.
+pub fn refund_service_fee<'info>(
+    cfg: &mut Config,
+    round: &mut Round,
+    treasury_sol: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    release_service_fees(cfg, round, amount);
+    if treasury_sol_spendable(treasury_sol)? < amount {
+        msg!("service fee refund skipped: treasury_sol cannot cover {} lamports", amount);
+        return Ok(());
+    }
+    treasury_sol_payout(cfg, treasury_sol, to, system_program, amount)
+}
+
+/// Pays `cfg.cranker_fee_lamports` from `treasury_sol` to a permissionless cranker, but only
+/// out of the surplus left after reserving every service fee any round's refund paths may
+/// still return (`cfg.sol_fees_outstanding`), so cranks never eat into what
+/// `refund_service_fee` needs. Skipped (logged) otherwise.
+pub fn pay_cranker_fee<'info>(
+    cfg: &Config,
+    treasury_sol: &AccountInfo<'info>,
+    cranker: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let fee = cfg.cranker_fee_lamports;
+    if fee == 0 {
+        return Ok(());
+    }
+    let surplus = treasury_sol_spendable(treasury_sol)?.saturating_sub(cfg.sol_fees_outstanding);
+    if surplus < fee {
+        msg!("cranker fee skipped: treasury_sol has no surplus over user refunds");
+        return Ok(());
+    }
+    treasury_sol_payout(cfg, treasury_sol, cranker, system_program, fee)
+}
+
+/// Paths that only move TIMLG stakes reject SOL-staked rounds.
+pub fn require_spl_stake(round: &Round) -> Result<()> {
+    require!(!round.stake_in_sol, TimlgError::SolStakeUnsupported);
+    Ok(())
+}
+
+/// Moves `amount` lamports out of the round's system-owned `vault` (vault PDA signs). No-op for 0.
+pub fn vault_lamports_transfer<'info>(
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    round: &Round,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let round_le = round.round_id.to_le_bytes();
+    let ix = anchor_lang::solana_program::system_instruction::transfer(vault.key, to.key, amount);
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        &[vault.clone(), to.clone(), system_program.clone()],
+        &[&[VAULT_SEED, &round_le, &[round.vault_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Whether the Config PDA still holds the TIMLG mint authority (see `transfer_mint_authority`).
+/// Mint-mode payouts fall back to the stake refund when it doesn't.
+pub fn config_can_mint(mint: &AccountInfo, config: &Pubkey) -> bool {
+    let Ok(data) = mint.try_borrow_data() else {
+        return false;
+    };
+    Mint::try_deserialize(&mut &data[..]).is_ok_and(|m| m.mint_authority == COption::Some(*config))
+}
+
+/// TIMLG mint signed by the Config PDA. No-op for 0.
+pub fn config_mint<'info>(
+    token_program: &Program<'info, Token>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    config_ai: &AccountInfo<'info>,
+    config_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            MintTo {
+                mint: mint.clone(),
+                to: to.clone(),
+                authority: config_ai.clone(),
+            },
+            &[&[crate::CONFIG_SEED, &[config_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// (reward_fee_pool cut, replication_pool cut) of a fee, per `fee_to_replication_bps`.
+pub fn fee_split(tokenomics: &Tokenomics, fee: u64) -> Result<(u64, u64)> {
+    require!(tokenomics.fee_to_replication_bps <= 10_000, TimlgError::InvalidBps);
+
+    let replication_cut = fee
+        .checked_mul(tokenomics.fee_to_replication_bps as u64)
+        .ok_or(TimlgError::MathOverflow)?
+        / 10_000;
+    Ok((fee - replication_cut, replication_cut))
+}
+
+/// Decodes a Round account, zero-padding layouts older than the current one
+/// (new trailing fields read as 0). `migrate_round` makes the padding permanent.
+pub fn deserialize_round_padded(data: &[u8]) -> Result<Round> {
+    let size = 8 + Round::INIT_SPACE;
+    if data.len() >= size {
+        let mut slice: &[u8] = data;
+        return Round::try_deserialize(&mut slice);
+    }
+    let mut padded = vec![0u8; size];
+    padded[..data.len()].copy_from_slice(data);
+    let mut slice: &[u8] = &padded;
+    Round::try_deserialize(&mut slice)
+}
+
+/// Size of a Config created before `max_extensions` was appended: every field up to and
+/// including a full `cpi_allowlist`. `migrate_config` seeds the default limit below it.
+pub const CONFIG_PRE_EXTENSIONS_SPACE: usize =
+    8 + 32 + 1 + 8 * 4 + 32 + 1 + 2 + 32 + 32 + 1 + 32 + 1 + 8 + (4 + 8 * 32);
+
+/// Size of a ticket created before `sol_fee_paid` / `fee_payer` were appended.
+pub const TICKET_PRE_FEE_SPACE: usize = 8 + Ticket::INIT_SPACE - 8 - 32;
+
+/// Size of a ticket created before `Ticket.weight` was appended (one byte shorter still).
+pub const TICKET_LEGACY_SPACE: usize = TICKET_PRE_FEE_SPACE - 1;
+
+/// True for the ticket sizes this program has ever created (current, pre-fee, pre-`weight`).
+pub fn is_known_ticket_size(len: usize) -> bool {
+    len == 8 + Ticket::INIT_SPACE || len == TICKET_PRE_FEE_SPACE || len == TICKET_LEGACY_SPACE
+}
+
+/// Decodes a Ticket, zero-padding the older layouts (weight reads 0 = 1 unit, no fee
+/// recorded). `migrate_ticket` makes the padding permanent.
+pub fn deserialize_ticket_padded(data: &[u8]) -> Result<Ticket> {
+    let size = 8 + Ticket::INIT_SPACE;
+    if data.len() >= size {
+        let mut slice: &[u8] = data;
+        return Ticket::try_deserialize(&mut slice);
+    }
+    let mut padded = vec![0u8; size];
+    padded[..data.len()].copy_from_slice(data);
+    let mut slice: &[u8] = &padded;
+    Ticket::try_deserialize(&mut slice)
+}
+
+/// Writes a ticket back into its account. An older account has no room for the trailing
+/// fields, which are always 0 there: nothing changes a ticket's weight or fee after commit.
+pub fn serialize_ticket_into(ticket: &Ticket, data: &mut [u8]) -> Result<()> {
+    let mut buf = Vec::with_capacity(8 + Ticket::INIT_SPACE);
+    ticket.try_serialize(&mut buf)?;
+    let n = buf.len().min(data.len());
+    require!(buf[n..].iter().all(|b| *b == 0), TimlgError::TicketSizeMismatch);
+    data[..n].copy_from_slice(&buf[..n]);
+    Ok(())
+}
+
+/// True once the vault that pays this round's claims has been swept.
+pub fn claims_swept(round: &Round) -> bool {
+    round.swept || if round.stake_in_sol { round.sol_swept } else { round.tokens_swept }
+}
+
+/// Rejects a batch that repeats a ticket key; the second `create_account` would
+/// otherwise fail with an opaque system-program error.
+pub fn require_unique_nonces<T: PartialEq>(keys: &[T]) -> Result<()> {
+    for (i, k) in keys.iter().enumerate() {
+        require!(!keys[..i].contains(k), TimlgError::DuplicateNonceInBatch);
+    }
+    Ok(())
+}
+
+/// The commit window is inclusive: a commit landing in `commit_deadline_slot`
+/// itself is accepted, the first rejected slot is `commit_deadline_slot + 1`.
+pub fn commit_window_open(commit_deadline_slot: u64, current_slot: u64) -> bool {
+    current_slot <= commit_deadline_slot
+}
+
+/// Rejects a commit once the round's commit window has closed.
+pub fn require_commit_open(round: &Round, current_slot: u64) -> Result<()> {
+    require!(
+        commit_window_open(round.commit_deadline_slot, current_slot),
+        TimlgError::CommitClosed
+    );
+    Ok(())
+}
+
+/// Rejects a commit of `n` tickets if the round is frozen or would exceed `max_committed`.
+pub fn require_commit_capacity(round: &Round, n: u64) -> Result<()> {
+    require!(!round.commit_frozen, TimlgError::CommitFrozen);
+    if round.max_committed > 0 {
+        let after = round
+            .committed_count
+            .checked_add(n)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        require!(after <= round.max_committed, TimlgError::RoundFull);
+    }
+    Ok(())
+}
+
+/// Moves `round.state` forward to `to`, never back: the states are ordered
+/// Announced < PulseSet < Finalized/Refunding < Swept (e.g. a late pulse leaves a voided round Refunding).
+/// The only exception is `dispute_pulse`, which sets PulseSet back to Announced directly.
+pub fn advance_round_state(round: &mut Round, to: RoundState) {
+    round.state = round.state.max(to as u8);
+}
+
+/// Ticket weights run from 1 up to `config.max_weight` (0/1 there = base stake only).
+pub fn require_valid_weight(cfg: &Config, weight: u8) -> Result<()> {
+    require!(
+        weight >= 1 && weight <= cfg.max_weight.max(1),
+        TimlgError::InvalidWeight
+    );
+    Ok(())
+}
+
+/// Stake units a ticket paid for: `stake_amount * ticket_weight` went into the vault.
+pub fn ticket_weight(ticket: &Ticket) -> u64 {
+    ticket.weight.max(1) as u64
+}
+
+/// Won under `UnrevealedPolicy::DefaultGuessZero`: resolved at settle, so never before finalize
+/// (reveals close at finalize). Public rounds resolve every ticket from its clear guess instead.
+pub fn is_default_zero_win(round: &Round, ticket: &Ticket) -> bool {
+    round.unrevealed_policy == UnrevealedPolicy::DefaultGuessZero as u8
+        && !round.public_mode
+        && ticket.win
+        && ticket.revealed_slot >= round.finalized_slot
+}
+
+/// Stake a winning ticket gets back: `stake_amount` per unit, or `round.unrevealed_refund`
+/// for a default-zero win (settle burned the unrevealed penalty out of it).
+pub fn winner_stake_refund(cfg: &Config, round: &Round, ticket: &Ticket) -> Result<u64> {
+    let per_unit = if is_default_zero_win(round, ticket) {
+        round.unrevealed_refund
+    } else {
+        cfg.stake_amount
+    };
+    per_unit
+        .checked_mul(ticket_weight(ticket))
+        .ok_or_else(|| error!(TimlgError::MathOverflow))
+}
+
+/// Stake units of the round's winners (each winner counts its weight).
+pub fn win_units(round: &Round) -> u64 {
+    round.win_count.saturating_add(round.win_extra_weight)
+}
+
+/// Stake units of the winners that haven't claimed yet.
+pub fn unclaimed_win_units(round: &Round) -> u64 {
+    win_units(round).saturating_sub(
+        round
+            .claimed_win_count
+            .saturating_add(round.claimed_win_extra_weight),
+    )
+}
+
+/// Books the extra units of a winning weighted ticket into `round.win_extra_weight`.
+pub fn record_win_weight(round: &mut Round, ticket: &Ticket) -> Result<()> {
+    round.win_extra_weight = round
+        .win_extra_weight
+        .checked_add(ticket_weight(ticket) - 1)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    Ok(())
+}
+
+/// Closes the commit window as soon as the round hits `max_committed`, so clients
+/// see a full round immediately instead of probing with failing commits.
+pub fn freeze_commits_if_full(round: &mut Round, current_slot: u64) {
+    if round.max_committed > 0 && round.committed_count >= round.max_committed && !round.commit_frozen {
+        round.commit_frozen = true;
+        round.commit_closed_slot = current_slot;
+    }
+}
+
+/// True when the round did not reach its configured `min_participants`.
+pub fn is_under_participated(round: &Round) -> bool {
+    round.min_participants > 0 && round.committed_count < round.min_participants
+}
+
+/// Claim grace for this round: the per-round override when set, else the config default.
+pub fn claim_grace_slots(round: &Round, cfg: &Config) -> u64 {
+    if round.claim_grace_slots_override > 0 {
+        round.claim_grace_slots_override
+    } else {
+        cfg.claim_grace_slots
+    }
+}
+
+/// Last slot reveals are accepted: `reveal_deadline_slot`, pushed out so that at least
+/// `config.min_reveal_after_pulse_slots` remain after the pulse was set.
+pub fn effective_reveal_deadline(round: &Round, cfg: &Config) -> u64 {
+    if round.pulse_set && cfg.min_reveal_after_pulse_slots > 0 {
+        round
+            .reveal_deadline_slot
+            .max(round.pulse_set_slot.saturating_add(cfg.min_reveal_after_pulse_slots))
+    } else {
+        round.reveal_deadline_slot
+    }
+}
+
+/// Last slot a winner can claim: effective reveal deadline + claim grace.
+pub fn claim_deadline_slot(round: &Round, cfg: &Config) -> u64 {
+    effective_reveal_deadline(round, cfg).saturating_add(claim_grace_slots(round, cfg))
+}
+
+/// Public-guess rounds store the guess in clear at commit, so the outcome is
+/// resolved straight from the pulse (no commitment to check). Also used for
+/// `UnrevealedPolicy::DefaultGuessZero` tickets once their guess is set to 0.
+pub fn resolve_public_ticket(round: &mut Round, ticket: &mut Ticket, current_slot: u64) -> Result<()> {
+    let outcome = get_pulse_bits(&round.pulse, ticket.bit_index, guess_bits(round));
+
+    ticket.revealed = true;
+    ticket.win = outcome == ticket.guess;
     ticket.revealed_slot = current_slot;
 
+    round.revealed_count = round
+        .revealed_count
+        .checked_add(1)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+
+    if ticket.win {
+        round.win_count = round
+            .win_count
+            .checked_add(1)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        round.win_revealed_count = round
+            .win_revealed_count
+            .checked_add(1)
+            .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+        record_win_weight(round, ticket)?;
+    }
+
     Ok(())
 }
 
@@ -124,6 +753,48 @@ pub fn derive_bit_index(round_id: u64, user: &Pubkey, nonce: u64) -> u16 {
     u16::from_le_bytes([h[0], h[1]]) % 512
 }
 
+/// `derive_bit_index` aligned down to a multiple of `guess_bits`, so reading
+/// `guess_bits` bits from it never runs past bit 511. Same index for 1-bit rounds.
+pub fn derive_bit_index_for(round_id: u64, user: &Pubkey, nonce: u64, guess_bits: u8) -> u16 {
+    let idx = derive_bit_index(round_id, user, nonce);
+    idx - idx % guess_bits.max(1) as u16
+}
+
+/// Oracle key `set_pulse_signed` must see for `round`: its override, else `config.oracle_pubkey`.
+pub fn round_oracle(cfg: &Config, round: &Round) -> Pubkey {
+    if round.oracle_pubkey_override != Pubkey::default() {
+        round.oracle_pubkey_override
+    } else {
+        cfg.oracle_pubkey
+    }
+}
+
+/// Pulse bits per ticket for `round` (legacy rounds stored 0 = 1 bit).
+pub fn guess_bits(round: &Round) -> u8 {
+    round.guess_bits.max(1)
+}
+
+pub fn is_valid_guess_bits(bits: u8) -> bool {
+    matches!(bits, 1 | 2 | 4)
+}
+
+pub fn guess_fits(bits: u8, guess: u8) -> bool {
+    (guess as u16) < (1u16 << bits)
+}
+
+/// Guess must fit in the round's `guess_bits`.
+pub fn require_valid_guess(round: &Round, guess: u8) -> Result<()> {
+    require!(guess_fits(guess_bits(round), guess), TimlgError::InvalidGuess);
+    Ok(())
+}
+
+/// Reveal-side range check: `GuessOutOfRange` instead of `InvalidGuess`, so clients can
+/// tell a malformed guess apart from a `CommitmentMismatch`.
+pub fn require_guess_in_range(round: &Round, guess: u8) -> Result<()> {
+    require!(guess_fits(guess_bits(round), guess), TimlgError::GuessOutOfRange);
+    Ok(())
+}
+
 // -------------------------
 // Commit hash + pulse bit
 // -------------------------
@@ -145,11 +816,60 @@ pub fn commit_hash(
     h.to_bytes()
 }
 
+// -------------------------
+// Merkle reveal (domain-separated)
+// -------------------------
+// Leaves and internal nodes use distinct prefixes (0x00 / 0x01) so an internal
+// node can never be passed off as a leaf (second-preimage), and the leaf carries
+// its own tag + round + user so a tree can't be replayed across rounds/users.
+const MERKLE_LEAF_PREFIX: &[u8] = &[0x00];
+const MERKLE_NODE_PREFIX: &[u8] = &[0x01];
+
+pub fn merkle_reveal_leaf(
+    round_id: u64,
+    user: &Pubkey,
+    nonce: u64,
+    guess: u8,
+    salt: &[u8; 32],
+) -> [u8; 32] {
+    hashv(&[
+        MERKLE_LEAF_PREFIX,
+        b"timlg-protocol:reveal_leaf_v1".as_ref(),
+        round_id.to_le_bytes().as_ref(),
+        user.as_ref(),
+        nonce.to_le_bytes().as_ref(),
+        &[guess],
+        salt.as_ref(),
+    ])
+    .to_bytes()
+}
+
+/// Sorted-pair node hash: order-independent, so proofs need no direction bits.
+pub fn merkle_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[MERKLE_NODE_PREFIX, lo.as_ref(), hi.as_ref()]).to_bytes()
+}
+
+pub fn merkle_verify(leaf: [u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    if proof.len() > MAX_MERKLE_DEPTH {
+        return false;
+    }
+    let computed = proof.iter().fold(leaf, |acc, sib| merkle_node(&acc, sib));
+    computed == *root
+}
+
 pub fn get_pulse_bit(pulse: &[u8; 64], bit_index: u16) -> u8 {
     let idx = bit_index as usize;
     let byte_i = idx / 8;
     let bit_i = idx % 8;
-    ((pulse[byte_i] >> bit_i) & 1) as u8
+    (pulse[byte_i] >> bit_i) & 1
+}
+
+/// `n` consecutive pulse bits from `bit_index`; bit `bit_index + i` is value bit `i`.
+pub fn get_pulse_bits(pulse: &[u8; 64], bit_index: u16, n: u8) -> u8 {
+    (0..n as u16).fold(0u8, |acc, i| {
+        acc | (get_pulse_bit(pulse, (bit_index + i) % 512) << i)
+    })
 }
 
 pub fn init_user_stats_if_needed(
@@ -166,6 +886,32 @@ pub fn init_user_stats_if_needed(
     Ok(())
 }
 
+/// Counts `n` new tickets for `user` in the round, enforcing `max_per_user` (0 = unlimited).
+pub fn record_user_round_commits(
+    urs: &mut crate::state::UserRoundStats,
+    round_id: u64,
+    user: Pubkey,
+    bump: u8,
+    n: u64,
+    max_per_user: u64,
+) -> Result<()> {
+    if urs.user == Pubkey::default() {
+        urs.round_id = round_id;
+        urs.user = user;
+        urs.bump = bump;
+    }
+    let committed = urs
+        .committed
+        .checked_add(n)
+        .ok_or_else(|| error!(TimlgError::MathOverflow))?;
+    require!(
+        max_per_user == 0 || committed <= max_per_user,
+        TimlgError::TooManyTicketsPerUser
+    );
+    urs.committed = committed;
+    Ok(())
+}
+
 // -------------------------
 // Signed commit message + ed25519 parsing
 // -------------------------
@@ -175,9 +921,91 @@ pub fn expected_commit_msg(
     user: &Pubkey,
     nonce: u64,
     commitment: &[u8; 32],
+) -> Vec<u8> {
+    commit_msg(b"timlg-protocol:commit_v1", program_id, round_id, user, nonce, commitment)
+}
+
+/// `commit_v1` fields plus `rent_timlg`: the user's consent to pay the relayer that much
+/// TIMLG per ticket for its rent (see `rent_in_timlg`).
+pub fn expected_commit_msg_v2(
+    program_id: &Pubkey,
+    round_id: u64,
+    user: &Pubkey,
+    nonce: u64,
+    commitment: &[u8; 32],
+    rent_timlg: u64,
+) -> Vec<u8> {
+    let mut v = commit_msg(b"timlg-protocol:commit_v2", program_id, round_id, user, nonce, commitment);
+    v.extend_from_slice(&rent_timlg.to_le_bytes());
+    v
+}
+
+/// `commit_v1` fields plus the round's live deadlines and `rent_timlg` (0 when the relayer
+/// isn't reimbursed): a signature only fits the round configuration the user saw.
+#[allow(clippy::too_many_arguments)]
+pub fn expected_commit_msg_v3(
+    program_id: &Pubkey,
+    round_id: u64,
+    user: &Pubkey,
+    nonce: u64,
+    commitment: &[u8; 32],
+    commit_deadline_slot: u64,
+    reveal_deadline_slot: u64,
+    rent_timlg: u64,
+) -> Vec<u8> {
+    let mut v = commit_msg(b"timlg-protocol:commit_v3", program_id, round_id, user, nonce, commitment);
+    v.extend_from_slice(&commit_deadline_slot.to_le_bytes());
+    v.extend_from_slice(&reveal_deadline_slot.to_le_bytes());
+    v.extend_from_slice(&rent_timlg.to_le_bytes());
+    v
+}
+
+/// Signed-commit consent shared by every `commit_*_signed` path: `commit_v3` over the
+/// round's live deadlines, or the deadline-less `commit_v2`/`commit_v1` only while
+/// `cfg.accept_legacy_commit_msg` is set.
+#[allow(clippy::too_many_arguments)]
+pub fn require_commit_consent(
+    sigs: &[(Pubkey, &[u8])],
+    cfg: &Config,
+    program_id: &Pubkey,
+    round: &Round,
+    user: &Pubkey,
+    nonce: u64,
+    commitment: &[u8; 32],
+    rent_timlg: u64,
+) -> Result<()> {
+    let expected = expected_commit_msg_v3(
+        program_id,
+        round.round_id,
+        user,
+        nonce,
+        commitment,
+        round.commit_deadline_slot,
+        round.reveal_deadline_slot,
+        rent_timlg,
+    );
+    let v3 = require_ed25519_sig_present(sigs, user, &expected);
+    if v3.is_ok() || !cfg.accept_legacy_commit_msg {
+        return v3;
+    }
+    let legacy = if rent_timlg > 0 {
+        expected_commit_msg_v2(program_id, round.round_id, user, nonce, commitment, rent_timlg)
+    } else {
+        expected_commit_msg(program_id, round.round_id, user, nonce, commitment)
+    };
+    require_ed25519_sig_present(sigs, user, &legacy)
+}
+
+fn commit_msg(
+    tag: &[u8],
+    program_id: &Pubkey,
+    round_id: u64,
+    user: &Pubkey,
+    nonce: u64,
+    commitment: &[u8; 32],
 ) -> Vec<u8> {
     let mut v = Vec::new();
-    v.extend_from_slice(b"timlg-protocol:commit_v1");
+    v.extend_from_slice(tag);
     v.extend_from_slice(program_id.as_ref());
     v.extend_from_slice(&round_id.to_le_bytes());
     v.extend_from_slice(user.as_ref());
@@ -186,29 +1014,71 @@ pub fn expected_commit_msg(
     v
 }
 
+/// `rent_lamports` priced at `price_per_sol` TIMLG base units per SOL (rounded down).
+pub fn rent_in_timlg(rent_lamports: u64, price_per_sol: u64) -> Option<u64> {
+    let v = rent_lamports as u128 * price_per_sol as u128 / LAMPORTS_PER_SOL as u128;
+    u64::try_from(v).ok()
+}
+
+/// Single-signature view used by the oracle path: rejects ixs carrying more than one signature.
 pub fn parse_ed25519_ix_pubkey_and_msg(ix: &Instruction) -> Result<(Pubkey, Vec<u8>)> {
+    let (pk, msg_off, msg_sz) = parse_ed25519_ix_parts(ix)?;
+    let msg = ix.data[msg_off..msg_off + msg_sz].to_vec();
+
+    Ok((pk, msg))
+}
+
+/// Every (pubkey, msg) pair of an ed25519 verify ix, in offsets order. Each of the
+/// `num_sigs` offset structs must be self-contained, same as the single-sig parser.
+/// Messages are borrowed from the ix data, never copied.
+pub fn parse_ed25519_ix_all(ix: &Instruction) -> Result<Vec<(Pubkey, &[u8])>> {
+    let num_sigs = ed25519_num_sigs(ix)?;
+    (0..num_sigs)
+        .map(|i| {
+            let (pk, msg_off, msg_sz) = ed25519_sig_parts(&ix.data, i)?;
+            Ok((pk, &ix.data[msg_off..msg_off + msg_sz]))
+        })
+        .collect()
+}
+
+/// Validates the ed25519 ix layout and returns (pubkey, msg_off, msg_sz) without copying the message.
+fn parse_ed25519_ix_parts(ix: &Instruction) -> Result<(Pubkey, usize, usize)> {
+    require!(ed25519_num_sigs(ix)? == 1, TimlgError::MissingOrInvalidEd25519Ix);
+    ed25519_sig_parts(&ix.data, 0)
+}
+
+/// Program id + header check; returns how many offset structs follow.
+fn ed25519_num_sigs(ix: &Instruction) -> Result<usize> {
     require!(
         ix.program_id == ed25519_program_id(),
         TimlgError::MissingOrInvalidEd25519Ix
     );
 
     let data = &ix.data;
-    require!(data.len() >= 16, TimlgError::MissingOrInvalidEd25519Ix);
+    require!(data.len() >= 2, TimlgError::MissingOrInvalidEd25519Ix);
 
-    let num_sigs = data[0];
-    require!(num_sigs == 1, TimlgError::MissingOrInvalidEd25519Ix);
+    let num_sigs = data[0] as usize;
+    require!(num_sigs >= 1, TimlgError::MissingOrInvalidEd25519Ix);
+    require!(data.len() >= 2 + 14 * num_sigs, TimlgError::MissingOrInvalidEd25519Ix);
+    Ok(num_sigs)
+}
+
+/// (pubkey, msg_off, msg_sz) of the `i`-th offsets struct.
+fn ed25519_sig_parts(data: &[u8], i: usize) -> Result<(Pubkey, usize, usize)> {
+    let o = 2 + 14 * i;
+    let field = |at: usize| u16::from_le_bytes([data[o + at], data[o + at + 1]]);
 
     // Require "self-contained" offsets (instruction_index == u16::MAX)
-    let sig_ix = u16::from_le_bytes([data[4], data[5]]);
-    let pk_ix = u16::from_le_bytes([data[8], data[9]]);
-    let msg_ix = u16::from_le_bytes([data[14], data[15]]);
+    let sig_ix = field(2);
+    let pk_ix = field(6);
+    let msg_ix = field(12);
     require!(sig_ix == u16::MAX, TimlgError::MissingOrInvalidEd25519Ix);
     require!(pk_ix == u16::MAX, TimlgError::MissingOrInvalidEd25519Ix);
     require!(msg_ix == u16::MAX, TimlgError::MissingOrInvalidEd25519Ix);
 
-    let pk_off = u16::from_le_bytes([data[6], data[7]]) as usize;
-    let msg_off = u16::from_le_bytes([data[10], data[11]]) as usize;
-    let msg_sz = u16::from_le_bytes([data[12], data[13]]) as usize;
+    let pk_off = field(4) as usize;
+    let msg_off = field(8) as usize;
+    let msg_sz = field(10) as usize;
 
     require!(pk_off + 32 <= data.len(), TimlgError::MissingOrInvalidEd25519Ix);
     require!(msg_off + msg_sz <= data.len(), TimlgError::MissingOrInvalidEd25519Ix);
@@ -216,9 +1086,8 @@ pub fn parse_ed25519_ix_pubkey_and_msg(ix: &Instruction) -> Result<(Pubkey, Vec<
     let pk_bytes: [u8; 32] = data[pk_off..pk_off + 32]
         .try_into()
         .map_err(|_| error!(TimlgError::MissingOrInvalidEd25519Ix))?;
-    let msg = data[msg_off..msg_off + msg_sz].to_vec();
 
-    Ok((Pubkey::new_from_array(pk_bytes), msg))
+    Ok((Pubkey::new_from_array(pk_bytes), msg_off, msg_sz))
 }
 
 // -------------------------
@@ -261,6 +1130,25 @@ pub fn expected_pulse_msg(
     out
 }
 
+/// Rejects oracle pulses that would bias every ticket: constant 0x00 / 0xFF bytes, or fewer
+/// than `min_popcount` set bits (0 = only the constant check).
+pub fn require_nondegenerate_pulse(pulse: &[u8; 64], min_popcount: u32) -> Result<()> {
+    require!(
+        pulse.iter().any(|b| *b != 0x00) && pulse.iter().any(|b| *b != 0xFF),
+        TimlgError::DegeneratePulse
+    );
+    let popcount: u32 = pulse.iter().map(|b| b.count_ones()).sum();
+    require!(popcount >= min_popcount, TimlgError::DegeneratePulse);
+    Ok(())
+}
+
+/// Hash of the ordered oracle allowlist; `PulseAttestations.attested` bit indexes are only
+/// meaningful against the set they were recorded for.
+pub fn oracle_set_digest(os: &OracleSet) -> [u8; 32] {
+    let keys: Vec<&[u8]> = os.oracles.iter().map(|k| k.as_ref()).collect();
+    hashv(&keys).to_bytes()
+}
+
 pub fn assert_ed25519_ix_matches(
     ix: &anchor_lang::solana_program::instruction::Instruction,
     expected_pubkey: &Pubkey,
@@ -268,10 +1156,124 @@ pub fn assert_ed25519_ix_matches(
 ) -> Result<()> {
     // Reusa el parser "seguro" que ya exige offsets self-contained
     // (signature_instruction_index == pubkey_instruction_index == message_instruction_index == u16::MAX)
-    let (pk, msg) = parse_ed25519_ix_pubkey_and_msg(ix)?;
+    let (pk, msg_off, msg_sz) = parse_ed25519_ix_parts(ix)?;
 
     require_keys_eq!(pk, *expected_pubkey, TimlgError::Ed25519PubkeyMismatch);
-    require!(msg.as_slice() == expected_msg, TimlgError::Ed25519MessageMismatch);
+
+    // Expected messages are fixed-size: reject on length before touching the payload,
+    // so an oversized message costs nothing to compare.
+    require!(msg_sz == expected_msg.len(), TimlgError::Ed25519MessageMismatch);
+    require!(
+        &ix.data[msg_off..msg_off + msg_sz] == expected_msg,
+        TimlgError::Ed25519MessageMismatch
+    );
+
+    Ok(())
+}
+
+/// Ed25519 verify ixs anywhere before the current ix, nearest first. Other ixs in between
+/// (compute budget, ATA create, ...) are skipped, so relayers can bundle freely.
+pub fn ed25519_ixs_before_current(ix_sys: &AccountInfo) -> Result<Vec<Instruction>> {
+    let current_ix = load_current_index_checked(ix_sys)? as usize;
+    let mut out = Vec::new();
+    for idx in (0..current_ix).rev() {
+        let ix = load_instruction_at_checked(idx, ix_sys)
+            .map_err(|_| error!(TimlgError::MissingOrInvalidEd25519Ix))?;
+        if ix.program_id == ed25519_program_id() {
+            out.push(ix);
+        }
+    }
+    Ok(out)
+}
+
+/// Requires one of `ixs` to verify `expected_msg` signed by `expected_pubkey`. When none
+/// does, surfaces the nearest ix's mismatch (or `MissingOrInvalidEd25519Ix` if there is none).
+pub fn require_ed25519_ix_present(
+    ixs: &[Instruction],
+    expected_pubkey: &Pubkey,
+    expected_msg: &[u8],
+) -> Result<()> {
+    let mut nearest_err = None;
+    for ix in ixs {
+        match assert_ed25519_ix_matches(ix, expected_pubkey, expected_msg) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                nearest_err.get_or_insert(e);
+            }
+        }
+    }
+    Err(nearest_err.unwrap_or_else(|| error!(TimlgError::MissingOrInvalidEd25519Ix)))
+}
+
+/// All (signer, msg) pairs carried by `ixs` (single- or multi-signature), nearest ix first.
+/// Ixs with a layout we don't accept are skipped.
+pub fn ed25519_sigs(ixs: &[Instruction]) -> Vec<(Pubkey, &[u8])> {
+    ixs.iter()
+        .filter_map(|ix| parse_ed25519_ix_all(ix).ok())
+        .flatten()
+        .collect()
+}
+
+/// Requires a signature of `expected_msg` by `expected_pubkey` among `sigs`. When none
+/// matches, reports against the nearest signature (or `MissingOrInvalidEd25519Ix` if none).
+/// Like `assert_ed25519_ix_matches`, a message of the wrong length is rejected unread.
+pub fn require_ed25519_sig_present(
+    sigs: &[(Pubkey, &[u8])],
+    expected_pubkey: &Pubkey,
+    expected_msg: &[u8],
+) -> Result<()> {
+    if sigs.iter().any(|(pk, msg)| {
+        pk == expected_pubkey && msg.len() == expected_msg.len() && *msg == expected_msg
+    }) {
+        return Ok(());
+    }
+    match sigs.first() {
+        None => err!(TimlgError::MissingOrInvalidEd25519Ix),
+        Some((pk, _)) if pk != expected_pubkey => err!(TimlgError::Ed25519PubkeyMismatch),
+        Some(_) => err!(TimlgError::Ed25519MessageMismatch),
+    }
+}
+
+/// Signer of an ed25519 verify ix whose message is exactly `expected_msg`.
+pub fn ed25519_ix_signer(ix: &Instruction, expected_msg: &[u8]) -> Result<Pubkey> {
+    let (pk, msg_off, msg_sz) = parse_ed25519_ix_parts(ix)?;
+    require!(msg_sz == expected_msg.len(), TimlgError::Ed25519MessageMismatch);
+    require!(
+        &ix.data[msg_off..msg_off + msg_sz] == expected_msg,
+        TimlgError::Ed25519MessageMismatch
+    );
+    Ok(pk)
+}
+
+// -------------------------
+// CPI caller allowlist
+// -------------------------
+/// Rejects CPI invocations whose calling program is not in `allowlist`.
+/// Top-level calls and an empty allowlist always pass. The caller is read
+/// from the top-level instruction, which only names the direct caller at the
+/// first CPI level, so deeper (nested) CPI is rejected outright.
+pub fn assert_cpi_caller_allowed(
+    allowlist: &[Pubkey],
+    ix_sysvar: Option<&AccountInfo>,
+) -> Result<()> {
+    let height = get_stack_height();
+    if allowlist.is_empty() || height <= TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Ok(());
+    }
+    require!(
+        height == TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+        TimlgError::CpiNotAllowed
+    );
+
+    let ix_sys = ix_sysvar.ok_or_else(|| error!(TimlgError::CpiNotAllowed))?;
+    let current_ix = load_current_index_checked(ix_sys)? as usize;
+    let outer_ix = load_instruction_at_checked(current_ix, ix_sys)
+        .map_err(|_| error!(TimlgError::CpiNotAllowed))?;
+
+    require!(
+        allowlist.contains(&outer_ix.program_id),
+        TimlgError::CpiNotAllowed
+    );
 
     Ok(())
 }
@@ -311,7 +1313,7 @@ mod tests {
         let o = 2usize;
 
         // signature_offset
-        data[o + 0..o + 2].copy_from_slice(&u16le(sig_off));
+        data[o..o + 2].copy_from_slice(&u16le(sig_off));
         // signature_instruction_index
         data[o + 2..o + 4].copy_from_slice(&u16le(sig_ix));
 
@@ -328,7 +1330,6 @@ mod tests {
         data[o + 12..o + 14].copy_from_slice(&u16le(msg_ix));
 
         // signature bytes: dejamos 0s (no se verifica en estos tests)
-        let sig_start = sig_off as usize;
         let pk_start = pk_off as usize;
         let msg_start = msg_off as usize;
 
@@ -396,4 +1397,357 @@ mod tests {
         let res_msg = assert_ed25519_ix_matches(&ix, &user, b"bad");
         assert!(res_msg.is_err());
     }
+
+    #[test]
+    fn parse_ed25519_all_walks_every_offsets_struct() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let (msg_a, msg_b) = (b"msg-a".to_vec(), b"msg-bb".to_vec());
+
+        // [num_sigs, pad, offsets x2, (sig, pk, msg) x2]
+        let header_len = 2 + 14 * 2;
+        let mut data = vec![2u8, 0];
+        data.resize(header_len, 0);
+        for (i, (pk, msg)) in [(a, &msg_a), (b, &msg_b)].into_iter().enumerate() {
+            let sig_off = data.len() as u16;
+            data.extend_from_slice(&[0u8; 64]);
+            let pk_off = data.len() as u16;
+            data.extend_from_slice(pk.as_ref());
+            let msg_off = data.len() as u16;
+            data.extend_from_slice(msg);
+
+            let o = 2 + 14 * i;
+            for (at, v) in [
+                (0, sig_off),
+                (2, u16::MAX),
+                (4, pk_off),
+                (6, u16::MAX),
+                (8, msg_off),
+                (10, msg.len() as u16),
+                (12, u16::MAX),
+            ] {
+                data[o + at..o + at + 2].copy_from_slice(&u16le(v));
+            }
+        }
+        let ix = Instruction { program_id: ed25519_program_id(), accounts: vec![], data };
+
+        assert_eq!(parse_ed25519_ix_all(&ix).unwrap(), vec![(a, msg_a.as_slice()), (b, msg_b.as_slice())]);
+        // the single-sig parser keeps rejecting multi-sig ixs
+        assert!(parse_ed25519_ix_pubkey_and_msg(&ix).is_err());
+
+        let ixs = [ix];
+        let sigs = ed25519_sigs(&ixs);
+        assert!(require_ed25519_sig_present(&sigs, &b, &msg_b).is_ok());
+        assert_eq!(
+            require_ed25519_sig_present(&sigs, &b, &msg_a).unwrap_err(),
+            error!(TimlgError::Ed25519PubkeyMismatch)
+        );
+    }
+
+    #[test]
+    fn merkle_verify_accepts_valid_proofs_and_rejects_tampering() {
+        let user = Pubkey::new_unique();
+        let salt = [7u8; 32];
+        let leaves: Vec<[u8; 32]> = (0..4u64)
+            .map(|n| merkle_reveal_leaf(42, &user, n, (n % 2) as u8, &salt))
+            .collect();
+
+        let n01 = merkle_node(&leaves[0], &leaves[1]);
+        let n23 = merkle_node(&leaves[2], &leaves[3]);
+        let root = merkle_node(&n01, &n23);
+
+        assert!(merkle_verify(leaves[0], &[leaves[1], n23], &root));
+        assert!(merkle_verify(leaves[3], &[leaves[2], n01], &root));
+
+        // wrong guess -> different leaf
+        let bad = merkle_reveal_leaf(42, &user, 0, 1, &salt);
+        assert!(!merkle_verify(bad, &[leaves[1], n23], &root));
+
+        // other round / user -> different leaf (no cross-round replay)
+        let other_round = merkle_reveal_leaf(43, &user, 0, 0, &salt);
+        assert!(!merkle_verify(other_round, &[leaves[1], n23], &root));
+
+        // proof longer than MAX_MERKLE_DEPTH is rejected outright
+        let long = vec![[0u8; 32]; MAX_MERKLE_DEPTH + 1];
+        assert!(!merkle_verify(leaves[0], &long, &root));
+    }
+
+    #[test]
+    fn assert_ed25519_ix_matches_rejects_oversized_msg_on_length() {
+        let user = Pubkey::new_unique();
+        let expected = b"expected-msg".to_vec();
+
+        // same prefix, padded well past the expected size
+        let mut big = expected.clone();
+        big.resize(4096, 0xAB);
+        let ix = make_ed25519_ix(user.to_bytes(), &big, u16::MAX, u16::MAX, u16::MAX);
+
+        let res = assert_ed25519_ix_matches(&ix, &user, &expected);
+        assert_eq!(res.unwrap_err(), error!(TimlgError::Ed25519MessageMismatch));
+    }
+
+    #[test]
+    fn require_ed25519_sig_present_rejects_oversized_msg_on_length() {
+        let user = Pubkey::new_unique();
+        let expected = b"expected-msg".to_vec();
+
+        let mut big = expected.clone();
+        big.resize(4096, 0xAB);
+        let ix = make_ed25519_ix(user.to_bytes(), &big, u16::MAX, u16::MAX, u16::MAX);
+
+        // the multi-sig parser hands out the message in place, not a copy
+        let sigs = ed25519_sigs(std::slice::from_ref(&ix));
+        assert_eq!(sigs.len(), 1);
+        assert!(ix.data.as_ptr_range().contains(&sigs[0].1.as_ptr()));
+        assert_eq!(sigs[0].1.len(), big.len());
+
+        assert_eq!(
+            require_ed25519_sig_present(&sigs, &user, &expected).unwrap_err(),
+            error!(TimlgError::Ed25519MessageMismatch)
+        );
+    }
+
+    #[test]
+    fn verify_ticket_pda_dual_reads_only_in_migration_window() {
+        let program_id = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let (v3, b3) = find_ticket_pda(SEED_VERSION, 7, &user, 1, &program_id).unwrap();
+        let (v4, b4) = find_ticket_pda(NEXT_SEED_VERSION, 7, &user, 1, &program_id).unwrap();
+        assert_ne!(v3, v4);
+
+        // outside the window (incl. legacy 0): current generation only
+        for cfg_v in [0, SEED_VERSION] {
+            assert_eq!(verify_ticket_pda(cfg_v, &v3, 7, &user, 1, &program_id).unwrap(), b3);
+            assert!(verify_ticket_pda(cfg_v, &v4, 7, &user, 1, &program_id).is_err());
+        }
+
+        // window open: both generations
+        assert_eq!(verify_ticket_pda(NEXT_SEED_VERSION, &v3, 7, &user, 1, &program_id).unwrap(), b3);
+        assert_eq!(verify_ticket_pda(NEXT_SEED_VERSION, &v4, 7, &user, 1, &program_id).unwrap(), b4);
+        assert!(verify_ticket_pda(NEXT_SEED_VERSION, &v4, 8, &user, 1, &program_id).is_err());
+    }
+
+    #[test]
+    fn ed25519_ix_signer_returns_pubkey_only_for_exact_msg() {
+        let oracle = Pubkey::new_unique();
+        let msg = b"pulse-msg".to_vec();
+        let ix = make_ed25519_ix(oracle.to_bytes(), &msg, u16::MAX, u16::MAX, u16::MAX);
+
+        assert_eq!(ed25519_ix_signer(&ix, &msg).unwrap(), oracle);
+        assert_eq!(
+            ed25519_ix_signer(&ix, b"other-msg").unwrap_err(),
+            error!(TimlgError::Ed25519MessageMismatch)
+        );
+    }
+
+    #[test]
+    fn multi_bit_reads_stay_in_range() {
+        let mut pulse = [0u8; 64];
+        pulse[0] = 0b1011_0110;
+        pulse[63] = 0b1100_0000;
+
+        assert_eq!(get_pulse_bits(&pulse, 1, 1), 1);
+        assert_eq!(get_pulse_bits(&pulse, 0, 2), 0b10);
+        assert_eq!(get_pulse_bits(&pulse, 4, 4), 0b1011);
+        assert_eq!(get_pulse_bits(&pulse, 508, 4), 0b1100);
+
+        let user = Pubkey::new_unique();
+        for nonce in 0..64 {
+            let raw = derive_bit_index(9, &user, nonce);
+            assert_eq!(derive_bit_index_for(9, &user, nonce, 1), raw);
+            for bits in [2u8, 4] {
+                let idx = derive_bit_index_for(9, &user, nonce, bits);
+                assert_eq!(idx % bits as u16, 0);
+                assert!(idx + bits as u16 <= 512);
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_pulses_are_rejected() {
+        assert!(require_nondegenerate_pulse(&[0x00; 64], 0).is_err());
+        assert!(require_nondegenerate_pulse(&[0xFF; 64], 0).is_err());
+        assert!(require_nondegenerate_pulse(&[0xAA; 64], 0).is_ok());
+
+        let mut sparse = [0u8; 64];
+        sparse[7] = 0b0000_0011;
+        assert!(require_nondegenerate_pulse(&sparse, 2).is_ok());
+        assert!(require_nondegenerate_pulse(&sparse, 3).is_err());
+    }
+
+    #[test]
+    fn oracle_set_digest_tracks_membership_and_order() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let set = |oracles: Vec<Pubkey>| OracleSet {
+            admin: Pubkey::default(),
+            bump: 0,
+            threshold: 1,
+            oracles,
+            version: 1,
+        };
+
+        let ab = oracle_set_digest(&set(vec![a, b]));
+        assert_eq!(ab, oracle_set_digest(&set(vec![a, b])));
+        assert_ne!(ab, oracle_set_digest(&set(vec![b, a])));
+        assert_ne!(ab, oracle_set_digest(&set(vec![a])));
+    }
+
+    #[test]
+    fn guess_fits_bit_length() {
+        assert!(guess_fits(1, 1));
+        assert!(!guess_fits(1, 2));
+        assert!(guess_fits(2, 3));
+        assert!(!guess_fits(2, 4));
+        assert!(guess_fits(4, 15));
+        assert!(!guess_fits(4, 16));
+    }
+
+    #[test]
+    fn commit_window_includes_deadline_slot() {
+        assert!(commit_window_open(100, 99));
+        assert!(commit_window_open(100, 100));
+        assert!(!commit_window_open(100, 101));
+    }
+
+    #[test]
+    fn rent_in_timlg_prices_per_sol() {
+        assert_eq!(rent_in_timlg(LAMPORTS_PER_SOL, 7), Some(7));
+        assert_eq!(rent_in_timlg(1_000_000, 2_000_000_000), Some(2_000_000));
+        assert_eq!(rent_in_timlg(1, 1), Some(0));
+        assert_eq!(rent_in_timlg(u64::MAX, u64::MAX), None);
+    }
+
+    #[test]
+    fn commit_v2_msg_extends_v1_fields() {
+        let (p, u) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let v1 = expected_commit_msg(&p, 1, &u, 2, &[3u8; 32]);
+        let v2 = expected_commit_msg_v2(&p, 1, &u, 2, &[3u8; 32], 9);
+        assert_eq!(v2.len(), v1.len() + 8);
+        assert_eq!(&v2[v2.len() - 8..], &9u64.to_le_bytes());
+        assert_ne!(v1[..v1.len() - 8], v2[..v1.len() - 8]);
+    }
+
+    #[test]
+    fn legacy_ticket_round_trips_without_weight_byte() {
+        assert!(is_known_ticket_size(8 + Ticket::INIT_SPACE));
+        assert!(is_known_ticket_size(TICKET_PRE_FEE_SPACE));
+        assert!(is_known_ticket_size(TICKET_LEGACY_SPACE));
+        assert!(!is_known_ticket_size(TICKET_LEGACY_SPACE - 1));
+        assert!(!is_known_ticket_size(8 + Ticket::INIT_SPACE + 1));
+
+        let mut legacy = Ticket::DISCRIMINATOR.to_vec();
+        legacy.resize(TICKET_LEGACY_SPACE, 0);
+        assert!(Ticket::try_deserialize(&mut &legacy[..]).is_err());
+
+        let mut ticket = deserialize_ticket_padded(&legacy).unwrap();
+        assert_eq!(ticket_weight(&ticket), 1);
+
+        ticket.processed = true;
+        serialize_ticket_into(&ticket, &mut legacy).unwrap();
+        assert!(deserialize_ticket_padded(&legacy).unwrap().processed);
+
+        // a weight cannot be silently dropped on write-back
+        ticket.weight = 2;
+        assert!(serialize_ticket_into(&ticket, &mut legacy).is_err());
+
+        // pre-fee tickets read as "no fee recorded", and cannot gain one in place
+        let mut pre_fee = Ticket::DISCRIMINATOR.to_vec();
+        pre_fee.resize(TICKET_PRE_FEE_SPACE, 0);
+        let mut ticket = deserialize_ticket_padded(&pre_fee).unwrap();
+        assert_eq!(ticket.sol_fee_paid, 0);
+        ticket.weight = 2;
+        serialize_ticket_into(&ticket, &mut pre_fee).unwrap();
+        ticket.sol_fee_paid = 1;
+        assert!(serialize_ticket_into(&ticket, &mut pre_fee).is_err());
+    }
+
+    #[test]
+    fn reveals_up_to_the_dispute_slot_are_voided() {
+        let mut round = deserialize_round_padded(Round::DISCRIMINATOR).unwrap();
+        let mut legacy = Ticket::DISCRIMINATOR.to_vec();
+        legacy.resize(TICKET_LEGACY_SPACE, 0);
+        let mut ticket = deserialize_ticket_padded(&legacy).unwrap();
+        ticket.revealed = true;
+        ticket.guess = 1;
+        ticket.win = true;
+        ticket.revealed_slot = 40;
+
+        // never disputed
+        assert!(!reveal_voided(&round, &ticket));
+
+        // a reveal after the dispute (against the replacement pulse) stands
+        round.pulse_disputed_slot = 39;
+        assert!(!reveal_voided(&round, &ticket));
+
+        round.pulse_disputed_slot = 40;
+        assert!(reveal_voided(&round, &ticket));
+        assert!(clear_voided_reveal(&round, &mut ticket));
+        assert!(!ticket.revealed && !ticket.win && ticket.guess == 0 && ticket.revealed_slot == 0);
+        assert!(!reveal_voided(&round, &ticket));
+        assert!(!clear_voided_reveal(&round, &mut ticket));
+    }
+
+    #[test]
+    fn default_zero_wins_refund_the_stake_minus_the_penalty() {
+        let mut cfg = zeroed_config();
+        cfg.stake_amount = 100;
+        let mut round = deserialize_round_padded(Round::DISCRIMINATOR).unwrap();
+        round.unrevealed_policy = UnrevealedPolicy::DefaultGuessZero as u8;
+        round.unrevealed_refund = 80;
+        round.finalized_slot = 50;
+        let mut legacy = Ticket::DISCRIMINATOR.to_vec();
+        legacy.resize(TICKET_LEGACY_SPACE, 0);
+        let mut ticket = deserialize_ticket_padded(&legacy).unwrap();
+        ticket.revealed = true;
+        ticket.win = true;
+
+        // revealed before finalize: a real reveal keeps its whole stake
+        ticket.revealed_slot = 49;
+        assert!(!is_default_zero_win(&round, &ticket));
+        assert_eq!(winner_stake_refund(&cfg, &round, &ticket).unwrap(), 100);
+
+        // resolved at settle
+        ticket.revealed_slot = 60;
+        ticket.weight = 2;
+        assert!(is_default_zero_win(&round, &ticket));
+        assert_eq!(winner_stake_refund(&cfg, &round, &ticket).unwrap(), 160);
+
+        round.public_mode = true;
+        assert!(!is_default_zero_win(&round, &ticket));
+    }
+
+    fn zeroed_config() -> Config {
+        let mut data = Config::DISCRIMINATOR.to_vec();
+        data.resize(8 + Config::INIT_SPACE, 0);
+        Config::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn deadline_extension_is_capped_per_call_and_per_round() {
+        let mut cfg = zeroed_config();
+        cfg.reveal_window_slots = 50;
+        cfg.max_extensions = 2;
+        let mut round = deserialize_round_padded(Round::DISCRIMINATOR).unwrap();
+        round.reveal_deadline_slot = 100;
+
+        assert!(apply_deadline_extension(&mut round, &cfg, 0).is_err());
+        assert!(apply_deadline_extension(&mut round, &cfg, 51).is_err());
+        assert!(apply_deadline_extension(&mut round, &cfg, u64::MAX).is_err());
+        assert_eq!((round.reveal_deadline_slot, round.extension_count), (100, 0));
+
+        apply_deadline_extension(&mut round, &cfg, 50).unwrap();
+        apply_deadline_extension(&mut round, &cfg, 1).unwrap();
+        assert_eq!((round.reveal_deadline_slot, round.extension_count), (151, 2));
+        assert!(apply_deadline_extension(&mut round, &cfg, 1).is_err());
+    }
+
+    #[test]
+    fn max_extensions_sits_right_after_a_full_cpi_allowlist() {
+        let mut cfg = zeroed_config();
+        cfg.cpi_allowlist = vec![Pubkey::new_unique(); 8];
+        cfg.max_extensions = 0xAB;
+        let mut data = Vec::new();
+        cfg.try_serialize(&mut data).unwrap();
+        assert_eq!(data[CONFIG_PRE_EXTENSIONS_SPACE], 0xAB);
+    }
 }
@@ -21,6 +21,7 @@ import {
   getPdaRewardFeePool,
   getPdaReplicationPool,
   getPdaUserStats,
+  getPdaUserStreak,
   getPdaRoundRegistry,
   getPdaTreasury,
   getPdaGlobalStats
@@ -190,7 +191,7 @@ export class TimlgPlayer extends TimlgBase {
 
   async commitBatch(
     roundId: number,
-    entries: { guess: number; nonce?: number; salt?: Uint8Array }[],
+    entries: { guess: number; nonce?: number; salt?: Uint8Array; weight?: number }[],
     options: { timlgMint: PublicKey; userTimlgAta?: PublicKey }
   ): Promise<{ signature: string; receipts: Receipt[] }> {
     const user = (this.program.provider as anchor.AnchorProvider).wallet.publicKey;
@@ -211,19 +212,22 @@ export class TimlgPlayer extends TimlgBase {
           nonce,
           salt,
           commitment,
+          weight: e.weight,
           ticketPda: getPdaTicket(this.program.programId, roundId, user, nonce),
         };
       })
     );
 
+    // commit_batch keeps the unweighted entry layout; any weight switches to commit_batch_weighted
+    const weighted = processedEntries.some((e) => e.weight !== undefined);
     const anchorEntries = processedEntries.map((e) => ({
       user,
       nonce: toBN(e.nonce),
       commitment: Array.from(e.commitment),
+      ...(weighted ? { weight: e.weight ?? 1 } : {}),
     }));
 
-    const tx = await (this.program.methods as any)
-      .commitBatch(toBN(roundId), anchorEntries)
+    const tx = await (this.program.methods as any)[weighted ? "commitBatchWeighted" : "commitBatch"](toBN(roundId), anchorEntries)
       .accounts({
         config: configPda,
         round: roundPda,
@@ -329,6 +333,7 @@ export class TimlgPlayer extends TimlgBase {
     const configPda = getPdaConfig(this.program.programId);
     const tokenomicsPda = getPdaTokenomics(this.program.programId, configPda);
     const rewardFeePoolPda = getPdaRewardFeePool(this.program.programId, tokenomicsPda);
+    const replicationPoolPda = getPdaReplicationPool(this.program.programId, tokenomicsPda);
     const roundPda = getPdaRound(this.program.programId, receipt.roundId);
     const timlgVaultPda = getPdaTIMLGVault(this.program.programId, receipt.roundId);
     const userStatsPda = getPdaUserStats(this.program.programId, user);
@@ -343,10 +348,12 @@ export class TimlgPlayer extends TimlgBase {
         ticket: new PublicKey(receipt.ticketPda),
         user: user,
         userStats: userStatsPda,
+        userStreak: getPdaUserStreak(this.program.programId, user),
         timlgMint: options.timlgMint,
         timlgVault: timlgVaultPda,
         userTimlgAta: userTimlgAta,
         rewardFeePool: rewardFeePoolPda,
+        replicationPool: replicationPoolPda,
         globalStats: getPdaGlobalStats(this.program.programId),
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -423,7 +430,14 @@ export class TimlgSupervisor extends TimlgBase {
     timlgMint: PublicKey,
     pulseIndexTarget?: number,
     commitDeadlineSlots?: number,
-    revealDeadlineSlots?: number
+    revealDeadlineSlots?: number,
+    claimGraceSlotsOverride?: number,
+    label?: number[],
+    oraclePubkeyOverride?: PublicKey,
+    maxTotalReward?: number,
+    minParticipants?: number,
+    /** Pulse bits each ticket reads: 1 (default), 2 or 4. */
+    guessBits?: number
   }): Promise<string> {
     const admin = (this.program.provider as anchor.AnchorProvider).wallet.publicKey;
     const configPda = getPdaConfig(this.program.programId);
@@ -445,7 +459,17 @@ export class TimlgSupervisor extends TimlgBase {
     }
 
     return (this.program.methods as any)
-      .createRoundAuto(pulseTarget, commitDeadline, revealDeadline)
+      .createRoundAuto(
+        pulseTarget,
+        commitDeadline,
+        revealDeadline,
+        toBN(options.claimGraceSlotsOverride ?? 0),
+        options.label ?? Array(32).fill(0),
+        options.oraclePubkeyOverride ?? PublicKey.default,
+        toBN(options.maxTotalReward ?? 0),
+        toBN(options.minParticipants ?? 0),
+        options.guessBits ?? 1
+      )
       .accounts({
         config: configPda,
         timlgMint: mint,
@@ -616,6 +640,7 @@ export class TimlgSupervisor extends TimlgBase {
     const timlgVaultPda = getPdaTIMLGVault(this.program.programId, roundId);
     const tokenomicsPda = getPdaTokenomics(this.program.programId, configPda);
     const treasuryPda = getPdaTreasury(this.program.programId); // Removed configPda
+    const rewardFeePoolPda = getPdaRewardFeePool(this.program.programId, tokenomicsPda);
     const replicationPoolPda = getPdaReplicationPool(this.program.programId, tokenomicsPda); // Changed configPda to tokenomicsPda
 
     return (this.program.methods as any)
@@ -627,6 +652,7 @@ export class TimlgSupervisor extends TimlgBase {
         timlgMint: options.timlgMint,
         timlgVault: timlgVaultPda,
         treasury: treasuryPda,
+        rewardFeePool: rewardFeePoolPda,
         replicationPool: replicationPoolPda,
         payer: admin,
         admin,
@@ -639,7 +665,8 @@ export class TimlgSupervisor extends TimlgBase {
 
   /**
    * Sweeps unclaimed tokens after the claim grace period has passed.
-   * Burns loser/unclaimed stakes (deflation) and clears the SOL vault.
+   * Burns loser/unclaimed stakes (deflation) and clears the SOL vault
+   * (unclaimed SOL stakes go to the SOL treasury, the rent to the admin).
    * Requires admin signer.
    */
   async sweepUnclaimed(roundId: number, options: { timlgMint: PublicKey }): Promise<string> {
@@ -649,6 +676,7 @@ export class TimlgSupervisor extends TimlgBase {
     const vaultPda = getPdaVault(this.program.programId, roundId);
     const timlgVaultPda = getPdaTIMLGVault(this.program.programId, roundId);
     const treasuryPda = getPdaTreasury(this.program.programId);
+    const treasurySolPda = getPdaTreasurySol(this.program.programId);
 
     return (this.program.methods as any)
       .sweepUnclaimed(toBN(roundId))
@@ -659,6 +687,7 @@ export class TimlgSupervisor extends TimlgBase {
         timlgVault: timlgVaultPda,
         treasury: treasuryPda,
         timlgMint: options.timlgMint,
+        treasurySol: treasurySolPda,
         admin,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -902,6 +931,12 @@ export class TimlgAdmin extends TimlgBase {
     commitWindowSlots: number | bigint;
     revealWindowSlots: number | bigint;
     timlgMint: PublicKey;
+    allowNonzeroSupply?: boolean;
+    minStakeAmount?: number | bigint;
+    maxStakeAmount?: number | bigint;
+    /** Key allowed to `emergency_drain` while paused. Must differ from the admin wallet. */
+    rescueAuthority: PublicKey;
+    rescueDestination?: PublicKey;
   }): Promise<string> {
     const admin = (this.program.provider as anchor.AnchorProvider).wallet.publicKey;
     const configPda = getPdaConfig(this.program.programId);
@@ -912,7 +947,12 @@ export class TimlgAdmin extends TimlgBase {
       .initializeConfig(
         toBN(options.stakeAmount),
         toBN(options.commitWindowSlots),
-        toBN(options.revealWindowSlots)
+        toBN(options.revealWindowSlots),
+        options.allowNonzeroSupply ?? false,
+        toBN(options.minStakeAmount ?? 1),
+        toBN(options.maxStakeAmount ?? 0),
+        options.rescueAuthority,
+        options.rescueDestination ?? admin
       )
       .accounts({
         config: configPda,
@@ -121,6 +121,17 @@ export function getPdaUserStats(programId: PublicKey, user: PublicKey): PublicKe
   return pda;
 }
 
+/**
+ * Derives the UserStreak PDA (consecutive winning rounds).
+ */
+export function getPdaUserStreak(programId: PublicKey, user: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("streak"), user.toBuffer()],
+    programId
+  );
+  return pda;
+}
+
 /**
  * Derives the Replication Pool PDA.
  */
@@ -3,9 +3,12 @@ const {
   getOrCreateAssociatedTokenAccount,
   mintTo,
   getMint,
+  getAccount,
   setAuthority,
   AuthorityType,
   TOKEN_PROGRAM_ID,
+  transfer: splTransfer,
+  approve: splApprove,
 } = require("@solana/spl-token");
 
 const anchor = require("@coral-xyz/anchor");
@@ -15,6 +18,7 @@ const {
   Keypair,
   Transaction,
   Ed25519Program,
+  ComputeBudgetProgram,
   sendAndConfirmTransaction,
 } = require("@solana/web3.js");
 const BN = require("bn.js");
@@ -38,6 +42,8 @@ if (!HAS_MOCK_PULSE) {
 // --------------------
 // Helpers
 // --------------------
+const NO_LABEL = Array(32).fill(0); // create_round label: opaque [u8; 32]
+const RESCUE_KP = Keypair.fromSeed(Buffer.alloc(32, 0x5c)); // rescue authority, never the admin wallet
 function leU64(n) {
   const b = Buffer.alloc(8);
   b.writeBigUInt64LE(BigInt(n));
@@ -352,12 +358,12 @@ async function ensureConfig(program, provider /*, configPdaIgnored */) {
     );
   }
 
-  // 2) Crea mint TIMLG (decimals = 0). (De momento la mint authority = payer)
+  // 2) Crea mint TIMLG (decimals = 0). (De momento la mint y freeze authority = payer)
   TIMLG_MINT = await createMint(
     connection,
     payer,
     payer.publicKey,
-    null,
+    payer.publicKey, // freeze authority: initialize_config moves it to CONFIG_PDA
     0,
     undefined,
     { commitment: "confirmed" }
@@ -400,7 +406,12 @@ async function ensureConfig(program, provider /*, configPdaIgnored */) {
       .initializeConfig(
         new BN(1),   // stakeAmount
         new BN(100), // commitWindowSlots
-        new BN(100)  // revealWindowSlots
+        new BN(100), // revealWindowSlots
+        true,        // allowNonzeroSupply (el test mintea antes de init)
+        new BN(1),   // minStakeAmount
+        new BN(0),   // maxStakeAmount (0 = sin tope)
+        RESCUE_KP.publicKey, // rescueAuthority
+        payer.publicKey      // rescueDestination
       )
       .accounts({
         config: CONFIG_PDA,
@@ -494,6 +505,56 @@ async function ensureBoot(program, provider) {
   return cfg;
 }
 
+// Rebuilds the (singleton) oracle set as exactly `oracles` with `threshold`; creates it if missing.
+async function resetOracleSet(program, provider, configPda, oracles, threshold) {
+  const admin = provider.wallet.publicKey;
+  const [oracleSetPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("oracle_set"), configPda.toBytes()],
+    program.programId
+  );
+  const accounts = { config: configPda, oracleSet: oracleSetPda, admin };
+
+  if (!(await program.account.oracleSet.fetchNullable(oracleSetPda, "confirmed"))) {
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .initializeOracleSet(threshold, oracles)
+        .accounts({ ...accounts, systemProgram: SystemProgram.programId, rent: anchor.web3.SYSVAR_RENT_PUBKEY })
+        .rpc()
+    );
+    return oracleSetPda;
+  }
+
+  await rpcConfirmed(provider, program.methods.clearOracleSet().accounts(accounts).rpc());
+  for (const pk of oracles) {
+    await rpcConfirmed(provider, program.methods.addOracle(pk).accounts(accounts).rpc());
+  }
+  await rpcConfirmed(provider, program.methods.setOracleThreshold(threshold).accounts(accounts).rpc());
+  return oracleSetPda;
+}
+
+// set_pulse_multisig preceded by one ed25519 ix per oracle keypair in `signers`.
+async function sendPulseMultisig(program, provider, { configPda, oracleSetPda, roundPda, roundId, pulseIndexTarget, pulse, signers }) {
+  const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
+  const tx = new Transaction();
+  for (const kp of signers) {
+    tx.add(Ed25519Program.createInstructionWithPrivateKey({ privateKey: kp.secretKey, message: msg }));
+  }
+  tx.add(
+    await program.methods
+      .setPulseMultisig(new BN(roundId), Array.from(pulse))
+      .accounts({
+        payer: provider.wallet.publicKey,
+        config: configPda,
+        oracleSet: oracleSetPda,
+        round: roundPda,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction()
+  );
+  return provider.sendAndConfirm(tx, [], { commitment: "confirmed" });
+}
+
 // MUST match Rust expected_commit_msg():
 // b"timlg-protocol:commit_v1" + program_id + round_id(le) + user + nonce(le) + commitment(32)
 function expectedCommitMsg(programId, roundId, userPubkey, nonce, commitment32) {
@@ -507,6 +568,35 @@ function expectedCommitMsg(programId, roundId, userPubkey, nonce, commitment32)
   ]);
 }
 
+// MUST match Rust expected_commit_msg_v2(): commit_v1 fields (tag "commit_v2") + rent_timlg(le)
+function expectedCommitMsgV2(programId, roundId, userPubkey, nonce, commitment32, rentTimlg) {
+  return Buffer.concat([
+    Buffer.from("timlg-protocol:commit_v2", "utf8"),
+    programId.toBytes(),
+    leU64(roundId),
+    userPubkey.toBytes(),
+    leU64(nonce),
+    Buffer.from(commitment32),
+    leU64(rentTimlg),
+  ]);
+}
+
+// MUST match Rust expected_commit_msg_v3(): commit_v1 fields (tag "commit_v3")
+// + commit_deadline_slot(le) + reveal_deadline_slot(le) + rent_timlg(le)
+function expectedCommitMsgV3(programId, roundId, userPubkey, nonce, commitment32, commitDeadline, revealDeadline, rentTimlg) {
+  return Buffer.concat([
+    Buffer.from("timlg-protocol:commit_v3", "utf8"),
+    programId.toBytes(),
+    leU64(roundId),
+    userPubkey.toBytes(),
+    leU64(nonce),
+    Buffer.from(commitment32),
+    leU64(commitDeadline),
+    leU64(revealDeadline),
+    leU64(rentTimlg),
+  ]);
+}
+
 function expectedPulseMsg(programId, roundId, pulseIndexTarget, pulse64) {
   return Buffer.concat([
     Buffer.from("timlg-protocol:pulse_v1", "utf8"),
@@ -605,11 +695,13 @@ describe("timlg_protocol - BATCHING", () => {
     const slot = await provider.connection.getSlot("confirmed");
     const commitDeadline = slot + 10;
     const revealDeadline = slot + 25;
+    const label = Buffer.alloc(32);
+    label.write("coinflip");
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline), new BN(0), Array.from(label), PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
           timlgMint: TIMLG_MINT,
@@ -624,6 +716,9 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
+    const r0 = await program.account.round.fetch(roundPda, "confirmed");
+    if (!Buffer.from(r0.label).equals(label)) throw new Error("round label mismatch");
+
     const nonce1 = 1;
     const nonce2 = 2;
     const guess1 = 1;
@@ -682,6 +777,38 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
+    // a round_id that doesn't match the round account must fail before touching any ticket
+    await expectTxFail(
+      async () =>
+        rpcConfirmed(
+          provider,
+          program.methods
+            .revealBatch(new BN(roundId + 1), [
+              { nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) },
+            ])
+            .accounts({ config: configPda, round: roundPda, user: admin })
+            .remainingAccounts([{ pubkey: ticket1Pda, isSigner: false, isWritable: true }])
+            .rpc()
+        ),
+      "ConstraintSeeds"
+    );
+
+    // a guess outside the round's bit length is reported as such, not as a bad commitment
+    await expectTxFail(
+      async () =>
+        rpcConfirmed(
+          provider,
+          program.methods
+            .revealBatch(new BN(roundId), [
+              { nonce: new BN(nonce1), guess: 2, salt: Array.from(salt1) },
+            ])
+            .accounts({ config: configPda, round: roundPda, user: admin })
+            .remainingAccounts([{ pubkey: ticket1Pda, isSigner: false, isWritable: true }])
+            .rpc()
+        ),
+      "GuessOutOfRange"
+    );
+
     // reveal_batch (no timlgVault needed aquí)
     await rpcConfirmed(
       provider,
@@ -701,39 +828,46 @@ describe("timlg_protocol - BATCHING", () => {
         ])
         .rpc()
     );
-  });
 
-  it("commit_batch_signed (relayer pays, user authorizes via ed25519)", async () => {
-    const adminKp = provider.wallet.payer;
-    const userKp = adminKp; // user = admin (simple)
-    const user = userKp.publicKey;
+    // the transcript chains the batch entries in order: sha256(prev, user, nonce, guess, win)
+    let transcript = Buffer.alloc(32);
+    for (const pda of [ticket1Pda, ticket2Pda]) {
+      const t = await program.account.ticket.fetch(pda, "confirmed");
+      transcript = crypto
+        .createHash("sha256")
+        .update(transcript)
+        .update(admin.toBuffer())
+        .update(t.nonce.toArrayLike(Buffer, "le", 8))
+        .update(Buffer.from([t.guess, t.win ? 1 : 0]))
+        .digest();
+    }
+    const r1 = await program.account.round.fetch(roundPda, "confirmed");
+    if (!Buffer.from(r1.revealTranscript).equals(transcript)) {
+      throw new Error("reveal_transcript does not match the replayed reveal log");
+    }
+  });
 
-    const relayer = anchor.web3.Keypair.generate();
-    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
-    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
+  it("commit_batch rejects duplicate nonces in one batch (DuplicateNonceInBatch)", async () => {
+    const admin = provider.wallet.publicKey;
 
     const { configPda } = await ensureConfig(program, provider);
 
-    // round
-    const roundId = (Math.floor(Date.now() / 1000) + 2222) % 1_000_000;
+    const roundId = (Math.floor(Date.now() / 1000) + 1155) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
     const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 20;
-    const revealDeadline = slot + 40;
-    const pulseIndexTarget = 4242;
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(new BN(roundId), new BN(777), new BN(slot + 10), new BN(slot + 25), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
           timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
-          admin: user,
+          admin,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -741,110 +875,56 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // escrow exists + fund it
-    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
-      configPda,
-      timlgMint: TIMLG_MINT,
-      userPk: user,
-    });
-
-    await ensureEscrowFunds(program, provider, {
-      configPda,
-      timlgMint: TIMLG_MINT, // ✅ IMPORTANTE (tu fallo anterior)
-      userPk: user,
-      userEscrowPda,
-      userEscrowAtaPda,
-      userTIMLGAta: USER_TIMLG_ATA,
-      neededRaw: 1n, // ✅ solo 1 entry para evitar "tx too large"
-    });
-
-    // ✅ SOLO 1 ENTRY
-    const nonce = 11;
-    const salt = crypto.randomBytes(32);
-    const guess = 1;
-    const commitment = commitHash(roundId, user, nonce, guess, salt);
-
-    const entries = [{ user, nonce: new BN(nonce), commitment: Array.from(commitment) }];
-
-    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
-
-    // ed25519 ix immediately before program ix
-    const msg = expectedCommitMsg(program.programId, roundId, user, nonce, commitment);
-    const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: userKp.secretKey,
-      message: msg,
-    });
-
-    const progIx = await program.methods
-      .commitBatchSigned(new BN(roundId), entries)
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        timlgMint: TIMLG_MINT,
-        timlgVault: timlgVaultPda,
-        payer: relayer.publicKey,
-        userEscrow: userEscrowPda,
-        userEscrowAta: userEscrowAtaPda,
-        user,
-        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-      })
-      .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
-      .instruction();
-
-    const tx = new anchor.web3.Transaction().add(edIx, progIx);
-    tx.feePayer = relayer.publicKey;
-
-    const { blockhash, lastValidBlockHeight } = await provider.connection.getLatestBlockhash("confirmed");
-    tx.recentBlockhash = blockhash;
-    tx.sign(relayer);
-
-    const sig = await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-      commitment: "confirmed",
-    });
-    await provider.connection.confirmTransaction({ signature: sig, blockhash, lastValidBlockHeight }, "confirmed");
+    const commitment = commitHash(roundId, admin, 1, 1, Buffer.alloc(32, 3));
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, 1);
 
-    const t = await program.account.ticket.fetch(ticketPda);
-    if (Buffer.from(t.commitment).compare(commitment) !== 0) throw new Error("ticket commitment mismatch");
+    await expectTxFail(async () => {
+      await program.methods
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(1), commitment: Array.from(commitment) },
+          { nonce: new BN(1), commitment: Array.from(commitment) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint: TIMLG_MINT,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta: USER_TIMLG_ATA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: ticketPda, isSigner: false, isWritable: true },
+          { pubkey: ticketPda, isSigner: false, isWritable: true },
+        ])
+        .rpc();
+    }, "DuplicateNonceInBatch");
   });
 
-  it("reveal_batch_signed (relayer pays, user authorizes via ed25519)", async () => {
-    const adminKp = provider.wallet.payer;
-    const userKp = adminKp;                 // user = admin
-    const user = userKp.publicKey;
-
-    const relayer = anchor.web3.Keypair.generate();
-    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
-    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
+  it("reveal_batch_lenient skips a bad entry and keeps the good ones", async () => {
+    const admin = provider.wallet.publicKey;
 
     const { configPda } = await ensureConfig(program, provider);
 
-    // round
-    const roundId = (Math.floor(Date.now() / 1000) + 3333) % 1_000_000;
+    const roundId = (Math.floor(Date.now() / 1000) + 1177) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
     const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 15;
-    const revealDeadline = slot + 35;
-    const pulseIndexTarget = 8080;
+    const commitDeadline = slot + 10;
+    const revealDeadline = slot + 25;
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(
-          new BN(roundId),
-          new BN(pulseIndexTarget),
-          new BN(commitDeadline),
-          new BN(revealDeadline)
-        )
+        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
           timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
-          admin: user,
+          admin,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -852,23 +932,25 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // commit 2 tickets normally (so the reveal-signed test only tests reveal path)
-    let nonce1 = 21;
-    let nonce2 = 22;
-    while (deriveBitIndex(roundId, user, nonce1) === deriveBitIndex(roundId, user, nonce2)) {
-      nonce2++;
-    }
-
-    const salt1 = crypto.randomBytes(32);
-    const salt2 = crypto.randomBytes(32);
+    const nonce1 = 1;
+    const nonce2 = 2;
     const guess1 = 1;
     const guess2 = 0;
+    const salt1 = Buffer.alloc(32, 7);
+    const salt2 = Buffer.alloc(32, 9);
+    const badSalt2 = Buffer.alloc(32, 0x42);
 
-    const commitment1 = commitHash(roundId, user, nonce1, guess1, salt1);
-    const commitment2 = commitHash(roundId, user, nonce2, guess2, salt2);
+    const commitment1 = commitHash(roundId, admin, nonce1, guess1, salt1);
+    const commitment2 = commitHash(roundId, admin, nonce2, guess2, salt2);
 
-    const ticketPda1 = deriveTicketPda(program.programId, roundId, user, nonce1);
-    const ticketPda2 = deriveTicketPda(program.programId, roundId, user, nonce2);
+    const [ticket1Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce1)],
+      program.programId
+    );
+    const [ticket2Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce2)],
+      program.programId
+    );
 
     await rpcConfirmed(
       provider,
@@ -882,136 +964,92 @@ describe("timlg_protocol - BATCHING", () => {
           round: roundPda,
           timlgMint: TIMLG_MINT,
           timlgVault: timlgVaultPda,
-          user,
+          user: admin,
           userTIMLGAta: USER_TIMLG_ATA,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .remainingAccounts([
-          { pubkey: ticketPda1, isWritable: true, isSigner: false },
-          { pubkey: ticketPda2, isWritable: true, isSigner: false },
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
         ])
         .rpc()
     );
 
-    // wait until after commitDeadline for setPulseMock
     while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
       await new Promise((r) => setTimeout(r, 120));
     }
 
-    // set pulse bits so both are winners
-    const pulse = Buffer.alloc(64, 0);
-    setBit(pulse, deriveBitIndex(roundId, user, nonce1), guess1);
-    setBit(pulse, deriveBitIndex(roundId, user, nonce2), guess2);
-
+    const pulse = Buffer.alloc(64, 0xaa);
     await rpcConfirmed(
       provider,
       program.methods
         .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin: user })
+        .accounts({ config: configPda, round: roundPda, admin })
         .rpc()
     );
 
-    // reveal signed (relayer pays)
-    const msg1 = expectedRevealMsg(program.programId, roundId, user, nonce1, guess1, salt1);
-    const msg2 = expectedRevealMsg(program.programId, roundId, user, nonce2, guess2, salt2);
+    // entry #2 uses the wrong salt -> CommitmentMismatch, skipped instead of aborting
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatchLenient(new BN(roundId), [
+          { nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) },
+          { nonce: new BN(nonce2), guess: guess2, salt: Array.from(badSalt2) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          user: admin,
+        })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
 
-    const edIx1 = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: userKp.secretKey,
-      message: msg1,
-    });
-    const edIx2 = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: userKp.secretKey,
-      message: msg2,
-    });
+    const t1 = await program.account.ticket.fetch(ticket1Pda, "confirmed");
+    const t2 = await program.account.ticket.fetch(ticket2Pda, "confirmed");
+    if (!t1.revealed) throw new Error("good entry should be revealed");
+    if (t2.revealed) throw new Error("bad entry must not be revealed");
 
-    const revealIx = await program.methods
-      .revealBatchSigned(new BN(roundId), [
-        { user, nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) },
-        { user, nonce: new BN(nonce2), guess: guess2, salt: Array.from(salt2) },
-      ])
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        payer: relayer.publicKey,
-        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-      })
-      .remainingAccounts([
-        { pubkey: ticketPda1, isWritable: true, isSigner: false },
-        { pubkey: ticketPda2, isWritable: true, isSigner: false },
-      ])
-      .instruction();
-
-    const tx = new anchor.web3.Transaction().add(edIx1, edIx2, revealIx);
-    tx.feePayer = relayer.publicKey;
-
-    const { blockhash, lastValidBlockHeight } = await provider.connection.getLatestBlockhash("confirmed");
-    tx.recentBlockhash = blockhash;
-    tx.sign(relayer);
-
-    const sig = await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-      commitment: "confirmed",
-    });
-    await provider.connection.confirmTransaction({ signature: sig, blockhash, lastValidBlockHeight }, "confirmed");
-
-    const t1 = await program.account.ticket.fetch(ticketPda1);
-    const t2 = await program.account.ticket.fetch(ticketPda2);
-    if (!t1.revealed || !t2.revealed) throw new Error("tickets should be revealed");
-
-    if (!t1.win || !t2.win) throw new Error("expected both tickets to be winners (pulse bits were set accordingly)");
-  });
-
-  it("set_pulse_signed (oracle ed25519)", async () => {
-    const adminKp = provider.wallet.payer;
-    const admin = adminKp.publicKey;
-
-    const oracle = Keypair.generate();
-    const relayer = Keypair.generate();
-
-    // fund relayer
-    {
-      const sig = await provider.connection.requestAirdrop(
-        relayer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await confirmSig(provider.connection, sig, "confirmed");
+    const r = await program.account.round.fetch(roundPda, "confirmed");
+    const revealedCount = (r.revealedCount ?? r.revealed_count).toString();
+    if (revealedCount !== "1") {
+      throw new Error(`revealed_count should count only processed entries, got ${revealedCount}`);
     }
 
-    const [configPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("config")],
-      program.programId
+    // strict reveal_batch still aborts on the same bad entry
+    await expectTxFail(
+      () =>
+        program.methods
+          .revealBatch(new BN(roundId), [
+            { nonce: new BN(nonce2), guess: guess2, salt: Array.from(badSalt2) },
+          ])
+          .accounts({ config: configPda, round: roundPda, user: admin })
+          .remainingAccounts([{ pubkey: ticket2Pda, isSigner: false, isWritable: true }])
+          .rpc(),
+      "CommitmentMismatch"
     );
-    await ensureConfig(program, provider, configPda);
+  });
 
-    // Set oracle pubkey (admin only)
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setOraclePubkey(oracle.publicKey)
-        .accounts({ config: configPda, admin })
-        .rpc()
-    );
+  it("sol_service_fee_lamports: skipped at 0, charged per ticket on commit and commit_batch", async () => {
+    const admin = provider.wallet.publicKey;
 
-    const roundId = (Math.floor(Date.now() / 1000) + 999) % 1000000;
+    const { configPda, timlgMint, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
 
+    const roundId = (Math.floor(Date.now() / 1000) + 1191) % 1_000_000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
     const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 15;
-    const pulseIndexTarget = 424242;
-
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(
-          new BN(roundId),
-          new BN(pulseIndexTarget),
-          new BN(commitDeadline),
-          new BN(slot + 200)
-        )
+        .createRound(new BN(roundId), new BN(777), new BN(slot + 60), new BN(slot + 90), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
+          timlgMint,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -1023,84 +1061,117 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // Wait past commit deadline
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 100));
-    }
-
-    const pulse = crypto.randomBytes(64);
-
-    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
-    const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: oracle.secretKey,
-      message: msg,
-    });
-
-    const anchorIx = await program.methods
-      .setPulseSigned(new BN(roundId), Array.from(pulse))
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-      })
-      .instruction();
-
-    const tx = new Transaction().add(edIx, anchorIx);
-    tx.feePayer = relayer.publicKey;
-
-    const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-    tx.recentBlockhash = blockhash;
-
-    tx.sign(relayer);
-
-    await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-      commitment: "confirmed",
-    });
+    const setFee = (lamports) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .updateSolServiceFee(new BN(lamports))
+          .accounts({ config: configPda, admin })
+          .rpc()
+      );
+    const treasuryBalance = () => provider.connection.getBalance(treasurySolPda, "confirmed");
 
-    const r = await program.account.round.fetch(roundPda);
-    if (!r.pulseSet) throw new Error("setPulseSigned did not persist pulseSet=true");
+    const commitOne = (nonce) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .commitTicket(
+            new BN(roundId),
+            new BN(nonce),
+            Array.from(commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, nonce)))
+          )
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            ticket: deriveTicketPda(program.programId, roundId, admin, nonce),
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+      );
 
-    const onchainPulse = Buffer.from(r.pulse);
-    if (!onchainPulse.equals(Buffer.from(pulse))) {
-      throw new Error("on-chain pulse mismatch");
+    const FEE = 5000;
+    try {
+      // fee = 0 -> no transfer
+      await setFee(0);
+      let before = await treasuryBalance();
+      await commitOne(1);
+      let after = await treasuryBalance();
+      if (after !== before) throw new Error(`fee=0 should not charge, delta=${after - before}`);
+
+      // fee > 0, single commit
+      await setFee(FEE);
+      before = await treasuryBalance();
+      await commitOne(2);
+      after = await treasuryBalance();
+      if (after - before !== FEE) throw new Error(`single commit should charge ${FEE}, got ${after - before}`);
+
+      // fee > 0, batch of 2 -> fee * n
+      const nonces = [3, 4];
+      before = await treasuryBalance();
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitBatch(
+            new BN(roundId),
+            nonces.map((n) => ({
+              nonce: new BN(n),
+              commitment: Array.from(commitHash(roundId, admin, n, 0, Buffer.alloc(32, n))),
+            }))
+          )
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            nonces.map((n) => ({
+              pubkey: deriveTicketPda(program.programId, roundId, admin, n),
+              isSigner: false,
+              isWritable: true,
+            }))
+          )
+          .rpc()
+      );
+      after = await treasuryBalance();
+      if (after - before !== FEE * nonces.length) {
+        throw new Error(`batch should charge ${FEE * nonces.length}, got ${after - before}`);
+      }
+    } finally {
+      await setFee(0);
     }
   });
 
-  it("finalize_round + sweep_unclaimed (to treasury_sol)", async () => {
-    const adminKp = provider.wallet.payer;
-    const admin = adminKp.publicKey;
-
-    // ✅ Usa ensureConfig para obtener treasurySolPda (lamports) y treasuryPda (SPL)
-    const { configPda, treasurySolPda } = await ensureConfig(program, provider);
+  it("max_tickets_per_user caps commits per user per round (TooManyTicketsPerUser)", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setClaimGraceSlots(new BN(0)) // para que el test no espere
-        .accounts({ config: configPda, admin })
-        .rpc()
-    );
+    const setMax = (n) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setMaxTicketsPerUser(new BN(n)).accounts({ config: configPda, admin }).rpc()
+      );
 
-    const roundId = (Math.floor(Date.now() / 1000) + 5555) % 1000000;
+    const roundId = (Math.floor(Date.now() / 1000) + 4747) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 10;
-    const revealDeadline = slot + 20;
-    const pulseIndexTarget = 777;
-
+    const slot0 = await provider.connection.getSlot("confirmed");
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(
-          new BN(roundId),
-          new BN(pulseIndexTarget),
-          new BN(commitDeadline),
-          new BN(revealDeadline)
-        )
+        .createRound(new BN(roundId), new BN(4747), new BN(slot0 + 60), new BN(slot0 + 120), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
+          timlgMint,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -1112,276 +1183,300 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // fund vault so there's something to sweep
-    const fundAmount = 200_000; // lamports
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .fundVault(new BN(roundId), new BN(fundAmount))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          vault: vaultPda,
-          funder: admin,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc()
-    );
+    const commit = (nonces) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .commitBatch(
+            new BN(roundId),
+            nonces.map((n) => ({
+              nonce: new BN(n),
+              commitment: Array.from(commitHash(roundId, admin, n, 1, Buffer.alloc(32, n))),
+            }))
+          )
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            nonces.map((n) => ({
+              pubkey: deriveTicketPda(program.programId, roundId, admin, n),
+              isSigner: false,
+              isWritable: true,
+            }))
+          )
+          .rpc()
+      );
 
-    // wait until after reveal deadline
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+    await setMax(2);
+    try {
+      await commit([1, 2]);
+
+      let threw = false;
+      try {
+        await commit([3]);
+      } catch (e) {
+        threw = true;
+        if (!String(e).includes("TooManyTicketsPerUser")) {
+          throw new Error("Expected TooManyTicketsPerUser, got: " + String(e));
+        }
+      }
+      if (!threw) throw new Error("third ticket should exceed max_tickets_per_user");
 
-    // ✅ finalize exige pulse_set = true -> setPulseMock antes
-    // Nota: setPulseMock exige haber pasado commitDeadline
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+      const [userRoundPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_round"), leU64(roundId), admin.toBytes()],
+        program.programId
+      );
+      const urs = await program.account.userRoundStats.fetch(userRoundPda, "confirmed");
+      if (Number(urs.committed) !== 2) throw new Error(`committed should be 2, got ${urs.committed}`);
+    } finally {
+      await setMax(0);
     }
+  });
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xaa)))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
+  it("max_tickets_per_user also caps commit_batch_signed_multi via per-entry user_round_stats", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
 
-    const treasuryBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
-    const vaultBefore = await provider.connection.getBalance(vaultPda, "confirmed");
-    if (vaultBefore === 0) throw new Error("vault should have lamports before sweep");
+    const setMax = (n) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setMaxTicketsPerUser(new BN(n)).accounts({ config: configPda, admin }).rpc()
+      );
 
-    // finalize
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint,
+      userPk: admin,
+    });
+    await ensureEscrowFunds(program, provider, {
+      configPda,
+      timlgMint,
+      userPk: admin,
+      userEscrowPda,
+      userEscrowAtaPda,
+      userTIMLGAta,
+      neededRaw: stake * 2n,
+    });
+
+    const roundId = (Math.floor(Date.now() / 1000) + 10_029) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 60;
+    const revealDeadline = slot0 + 120;
     await rpcConfirmed(
       provider,
       program.methods
-        .finalizeRound(new BN(roundId))
+        .createRound(new BN(roundId), new BN(797), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
+          timlgMint,
           round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
           admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
         .rpc()
     );
 
-    // sweep -> ✅ ahora a treasurySol
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .sweepUnclaimed(new BN(roundId))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          vault: vaultPda,
-          treasurySol: treasurySolPda, // ✅ NUEVO (lamports PDA)
-          admin,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc()
+    const [userStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stats_v3"), admin.toBytes()],
+      program.programId
     );
+    const [userRoundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_round"), leU64(roundId), admin.toBytes()],
+      program.programId
+    );
+    const commitMulti = async (nonce) => {
+      const commitment = commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, nonce));
+      const tx = new Transaction().add(
+        Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: adminKp.secretKey,
+          message: expectedCommitMsgV3(program.programId, roundId, admin, nonce, commitment, commitDeadline, revealDeadline, 0),
+        }),
+        await program.methods
+          .commitBatchSignedMulti(new BN(roundId), [
+            { user: admin, nonce: new BN(nonce), commitment: Array.from(commitment) },
+          ])
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgVault: timlgVaultPda,
+            payer: admin,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([
+            { pubkey: userEscrowPda, isWritable: true, isSigner: false },
+            { pubkey: userEscrowAtaPda, isWritable: true, isSigner: false },
+            { pubkey: userStatsPda, isWritable: true, isSigner: false },
+            { pubkey: userRoundPda, isWritable: true, isSigner: false },
+            { pubkey: deriveTicketPda(program.programId, roundId, admin, nonce), isWritable: true, isSigner: false },
+          ])
+          .instruction()
+      );
+      return provider.sendAndConfirm(tx, [], { commitment: "confirmed" });
+    };
 
-    const treasuryAfter = await provider.connection.getBalance(treasurySolPda, "confirmed");
-    const vaultAfter = await provider.connection.getBalance(vaultPda, "confirmed");
-
-    if (vaultAfter !== 0) throw new Error("vault should be empty after sweep");
-    if (treasuryAfter <= treasuryBefore) throw new Error("treasury_sol balance did not increase after sweep");
-
-    const r = await program.account.round.fetch(roundPda);
-    if (!r.finalized) throw new Error("round.finalized should be true");
+    await setMax(1);
+    try {
+      // the cap no longer rejects the path outright: the first ticket goes through
+      await commitMulti(1);
+      const urs = await program.account.userRoundStats.fetch(userRoundPda, "confirmed");
+      if (Number(urs.committed) !== 1) throw new Error(`committed should be 1, got ${urs.committed}`);
+
+      await expectTxFail(() => commitMulti(2), "TooManyTicketsPerUser");
+    } finally {
+      await setMax(0);
+    }
   });
 
-  it("grace period prevents early sweep (SweepTooEarly)", async () => {
-    const adminKp = provider.wallet.payer;
-    const admin = adminKp.publicKey;
-
-    // ✅ Usa ensureConfig para obtener treasurySolPda
-    const { configPda, treasurySolPda } = await ensureConfig(program, provider);
+  it("set_stake_bounds: update_stake_amount outside [min, max] fails (StakeOutOfBounds)", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda } = await ensureConfig(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = cfg.stakeAmount;
 
-    // ✅ set grace slots > 0
-    const graceSlots = 5;
-    const sigSetGrace = await program.methods
-      .setClaimGraceSlots(new BN(graceSlots))
-      .accounts({ config: configPda, admin })
-      .rpc();
-    await provider.connection.confirmTransaction(sigSetGrace, "confirmed");
+    const setBounds = (min, max) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setStakeBounds(min, max).accounts({ config: configPda, admin }).rpc()
+      );
 
-    const roundId = (Math.floor(Date.now() / 1000) + 9999) % 1000000;
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    await setBounds(stake, stake.muln(10));
+    try {
+      await expectTxFail(
+        async () =>
+          rpcConfirmed(
+            provider,
+            program.methods
+              .updateStakeAmount(stake.muln(10).addn(1))
+              .accounts({ config: configPda, admin })
+              .rpc()
+          ),
+        "StakeOutOfBounds"
+      );
 
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 5;
-    const revealDeadline = slot + 10;
-    const pulseIndexTarget = 1234;
+      // bounds that exclude the current stake are rejected too
+      await expectTxFail(async () => setBounds(stake.addn(1), new BN(0)), "StakeOutOfBounds");
+    } finally {
+      await setBounds(cfg.minStakeAmount, cfg.maxStakeAmount);
+    }
+  });
 
-    // create round
-    const sigCreate = await program.methods
-      .createRound(
-        new BN(roundId),
-        new BN(pulseIndexTarget),
-        new BN(commitDeadline),
-        new BN(revealDeadline)
-      )
-      .accounts({
-        config: configPda,
-        timlgMint: TIMLG_MINT,
-        round: roundPda,
-        vault: vaultPda,
-        timlgVault: timlgVaultPda,
-        admin,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-      })
-      .rpc();
-    await provider.connection.confirmTransaction(sigCreate, "confirmed");
+  it("withdraw_escrow with amount 0 drains the full escrow balance", async () => {
+    const userPk = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
 
-    // fund vault so sweep has something to move
-    const sigFund = await program.methods
-      .fundVault(new BN(roundId), new BN(200_000))
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        vault: vaultPda,
-        funder: admin,
-        systemProgram: SystemProgram.programId,
-      })
-      .rpc();
-    await provider.connection.confirmTransaction(sigFund, "confirmed");
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint,
+      userPk,
+    });
 
-    // wait until just after reveal deadline (so finalize is allowed)
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+    await ensureEscrowFunds(program, provider, {
+      configPda,
+      timlgMint,
+      userPk,
+      userTIMLGAta,
+      userEscrowPda,
+      userEscrowAtaPda,
+      neededRaw: 3n,
+    });
 
-    // ✅ finalize exige pulse_set=true -> setPulseMock antes de finalize
-    // setPulseMock exige haber pasado commitDeadline
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+    const escrowBefore = await getTokenAmountRaw(provider.connection, userEscrowAtaPda);
+    const userBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
 
     await rpcConfirmed(
       provider,
       program.methods
-        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xaa)))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
-
-    // finalize
-    const sigFinalize = await program.methods
-      .finalizeRound(new BN(roundId))
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        admin,
-      })
-      .rpc();
-    await provider.connection.confirmTransaction(sigFinalize, "confirmed");
-
-    // Read round from chain (source of truth) to compute sweep gate
-    let round = await program.account.round.fetch(roundPda, "confirmed");
-    const revealDeadlineOnchain = Number(round.revealDeadlineSlot);
-    const minSweepSlot = revealDeadlineOnchain + graceSlots;
-
-    // ❌ sweep too early: should fail with SweepTooEarly
-    let threw = false;
-    try {
-      const sigEarly = await program.methods
-        .sweepUnclaimed(new BN(roundId))
+        .withdrawEscrow(new BN(0))
         .accounts({
           config: configPda,
-          round: roundPda,
-          vault: vaultPda,
-          treasurySol: treasurySolPda, // ✅ NUEVO
-          admin,
-          systemProgram: SystemProgram.programId,
+          timlgMint,
+          userEscrow: userEscrowPda,
+          userEscrowAta: userEscrowAtaPda,
+          user: userPk,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .rpc();
+        .rpc()
+    );
 
-      // even if it returns a sig, force confirm to surface program error
-      await provider.connection.confirmTransaction(sigEarly, "confirmed");
-    } catch (e) {
-      threw = true;
-      const msg = String(e);
-      if (!msg.includes("SweepTooEarly") && !msg.includes("grace period")) {
-        throw new Error("Expected SweepTooEarly, got: " + msg);
-      }
-    }
-    if (!threw) {
-      throw new Error("Expected sweep to fail during grace period, but it succeeded");
-    }
+    const escrowAfter = await getTokenAmountRaw(provider.connection, userEscrowAtaPda);
+    const userAfter = await getTokenAmountRaw(provider.connection, userTIMLGAta);
 
-    // ✅ wait until grace period elapsed (using on-chain computed minSweepSlot)
-    while ((await provider.connection.getSlot("confirmed")) <= minSweepSlot) {
-      await new Promise((r) => setTimeout(r, 120));
+    if (escrowAfter !== 0n) throw new Error(`escrow should be drained, got ${escrowAfter}`);
+    if (userAfter - userBefore !== escrowBefore) {
+      throw new Error(`user should receive ${escrowBefore}, got ${userAfter - userBefore}`);
     }
+  });
 
-    // sweep should succeed
-    const sigSweep = await program.methods
-      .sweepUnclaimed(new BN(roundId))
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        vault: vaultPda,
-        treasurySol: treasurySolPda, // ✅ NUEVO
-        admin,
-        systemProgram: SystemProgram.programId,
-      })
-      .rpc();
-    await provider.connection.confirmTransaction(sigSweep, "confirmed");
-
-    const vaultAfter = await provider.connection.getBalance(vaultPda, "confirmed");
-    if (vaultAfter !== 0) throw new Error("vault should be empty after successful sweep");
+  it("create_round rejects a commit deadline in the past or too close (CommitDeadlineInPast / CommitWindowTooShort)", async () => {
+    const admin = provider.wallet.publicKey;
 
-    // Fetch state at confirmed
-    round = await program.account.round.fetch(roundPda, "confirmed");
+    const { configPda } = await ensureConfig(program, provider);
 
-    // ✅ robust checks (naming/typing safe)
-    const sweptSlot =
-      (round.sweptSlot ?? round.swept_slot ?? round.sweptslot ?? round.sweptSlot?.toNumber?.()) ??
-      (round.sweptSlot?.toNumber?.() ?? 0);
+    const roundId = (Math.floor(Date.now() / 1000) + 1319) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const sweptSlotNum =
-      typeof sweptSlot === "number"
-        ? sweptSlot
-        : sweptSlot?.toNumber
-          ? sweptSlot.toNumber()
-          : Number(sweptSlot ?? 0);
+    const createRound = (commitDeadline) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(commitDeadline + 100), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint: TIMLG_MINT,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
 
-    if (sweptSlotNum <= 0) {
-      throw new Error("round.swept_slot should be > 0 after sweep");
-    }
+    const slot = await provider.connection.getSlot("confirmed");
+    await expectTxFail(() => createRound(slot - 1), "CommitDeadlineInPast");
 
-    const sweptBool = round.swept ?? round.isSwept ?? round.sweptBool;
-    if (sweptBool === false) throw new Error("round.swept should be true after sweep");
+    // MIN_COMMIT_WINDOW_SLOTS = 4: a deadline 3 slots ahead is still in the future but too short
+    const slot2 = await provider.connection.getSlot("processed");
+    await expectTxFail(() => createRound(slot2 + 3), "CommitWindowTooShort");
   });
 
-  it("commit → reveal → settle → claim_reward (balance checks, whitepaper-aligned)", async () => {
+  it("migrate_round is a no-op on a round already at the current size", async () => {
     const admin = provider.wallet.publicKey;
 
-    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
-
-    const cfg = await program.account.config.fetch(configPda, "confirmed");
-    const stakeBn = cfg.stakeAmount ?? cfg.stake_amount ?? cfg.stake ?? new BN(1);
-    const stake = BigInt(stakeBn.toString());
-
-    const before = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    const { configPda } = await ensureConfig(program, provider);
 
-    const roundId = (Math.floor(Date.now() / 1000) + 2468) % 1000000;
+    const roundId = (Math.floor(Date.now() / 1000) + 1317) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const slot0 = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot0 + 10;
-    const revealDeadline = slot0 + 25;
-    const pulseIndexTarget = 4242;
+    const slot = await provider.connection.getSlot("confirmed");
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(new BN(roundId), new BN(777), new BN(slot + 10), new BN(slot + 25), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -1393,184 +1488,120 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // Two tickets: one WIN, one LOSE
-    const nonce1 = 111;
-    const guess1 = 1;
-    const salt1 = Buffer.alloc(32, 7);
-
-    const nonce2 = 222;
-    const guess2 = 0;
-    const salt2 = Buffer.alloc(32, 9);
-
-    const commitment1 = commitHash(roundId, admin, nonce1, guess1, salt1);
-    const commitment2 = commitHash(roundId, admin, nonce2, guess2, salt2);
-
-    const [ticket1Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce1)],
-      program.programId
-    );
-    const [ticket2Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce2)],
-      program.programId
-    );
+    const sizeBefore = (await provider.connection.getAccountInfo(roundPda, "confirmed")).data.length;
 
-    // Commit: transfers 2*stake to timlgVault
     await rpcConfirmed(
       provider,
       program.methods
-        .commitBatch(new BN(roundId), [
-          { nonce: new BN(nonce1), commitment: Array.from(commitment1) },
-          { nonce: new BN(nonce2), commitment: Array.from(commitment2) },
-        ])
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda, // ✅ REQUIRED
-          user: admin,
-          userTIMLGAta,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .remainingAccounts([
-          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
-          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
-        ])
+        .migrateRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin, systemProgram: SystemProgram.programId })
         .rpc()
     );
 
-    const afterCommit = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
-    if (afterCommit !== before - 2n * stake) {
-      throw new Error(`afterCommit mismatch: got=${afterCommit} expected=${before - 2n * stake}`);
-    }
-
-    // Pulse such that ticket1 wins and ticket2 loses
-    const bitIndex1 = deriveBitIndex(roundId, admin, nonce1);
-    const bitIndex2 = deriveBitIndex(roundId, admin, nonce2);
-
-    const pulse = Buffer.alloc(64, 0);
-    setBit(pulse, bitIndex1, guess1);
-    setBit(pulse, bitIndex2, 1 - guess2);
+    const sizeAfter = (await provider.connection.getAccountInfo(roundPda, "confirmed")).data.length;
+    if (sizeAfter !== sizeBefore) throw new Error(`round size changed: ${sizeBefore} -> ${sizeAfter}`);
+    await program.account.round.fetch(roundPda, "confirmed");
+  });
 
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+  it("migrate_user_escrow is a no-op on a current escrow and rejects other accounts", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureConfig(program, provider);
+    const { userEscrowPda } = await ensureUserEscrow(program, provider, { configPda, timlgMint, userPk: admin });
 
+    const sizeBefore = (await provider.connection.getAccountInfo(userEscrowPda, "confirmed")).data.length;
     await rpcConfirmed(
       provider,
       program.methods
-        .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin })
+        .migrateUserEscrow()
+        .accounts({ userEscrow: userEscrowPda, payer: admin, systemProgram: SystemProgram.programId })
         .rpc()
     );
+    const sizeAfter = (await provider.connection.getAccountInfo(userEscrowPda, "confirmed")).data.length;
+    if (sizeAfter !== sizeBefore) throw new Error(`escrow size changed: ${sizeBefore} -> ${sizeAfter}`);
+    const esc = await program.account.userEscrow.fetch(userEscrowPda, "confirmed");
+    if (esc.lastRevealSlot === undefined) throw new Error("escrow should decode with last_reveal_slot");
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .revealBatch(new BN(roundId), [
-          { nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) },
-          { nonce: new BN(nonce2), guess: guess2, salt: Array.from(salt2) },
-        ])
-        .accounts({ config: configPda, round: roundPda, user: admin })
-        .remainingAccounts([
-          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
-          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
-        ])
-        .rpc()
+    // a program account that is not an escrow (the config) is never resized
+    await expectTxFail(
+      () =>
+        program.methods
+          .migrateUserEscrow()
+          .accounts({ userEscrow: configPda, payer: admin, systemProgram: SystemProgram.programId })
+          .rpc(),
+      "EscrowPdaMismatch"
     );
+  });
 
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+  it("fund_timlg_vault seeds the round's token vault and tracks seeded_amount", async () => {
+    const admin = provider.wallet.publicKey;
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .finalizeRound(new BN(roundId))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
+    const { configPda } = await ensureConfig(program, provider);
 
-    const supplyBeforeSettle = BigInt((await getMint(provider.connection, timlgMint, "confirmed")).supply.toString());
+    const roundId = (Math.floor(Date.now() / 1000) + 1806) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
 
     await rpcConfirmed(
       provider,
       program.methods
-        .settleRoundTokens(new BN(roundId))
+        .createRound(new BN(roundId), new BN(777), new BN(slot + 10), new BN(slot + 25), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
-          timlgMint,
+          vault: vaultPda,
           timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
           admin,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
-        .remainingAccounts([
-          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
-          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
-        ])
         .rpc()
     );
 
-    const supplyAfterSettle = BigInt((await getMint(provider.connection, timlgMint, "confirmed")).supply.toString());
-    if (supplyAfterSettle !== supplyBeforeSettle - stake) {
-      throw new Error(`supply after settle mismatch: got=${supplyAfterSettle} expected=${supplyBeforeSettle - stake}`);
-    }
-
-    // Claim winner: refund 1 + mint 1 -> back to before
+    const seed = 5_000n;
     await rpcConfirmed(
       provider,
       program.methods
-        .claimReward(new BN(roundId), new BN(nonce1))
+        .fundTimlgVault(new BN(roundId), new BN(seed.toString()))
         .accounts({
           config: configPda,
           round: roundPda,
-          ticket: ticket1Pda,
-          user: admin,
-          timlgMint,
           timlgVault: timlgVaultPda,
-          userTIMLGAta,
-
-          tokenomics: tokenomicsPda,
-          rewardFeePool: rewardFeePoolPda,
-
+          source: USER_TIMLG_ATA,
+          admin,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc()
     );
 
-    const afterClaim = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
-    if (afterClaim !== before) {
-      throw new Error(`afterClaim mismatch: got=${afterClaim} expected=${before}`);
+    const vaultBal = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+    if (vaultBal !== seed) throw new Error(`timlg_vault balance ${vaultBal}, expected ${seed}`);
+
+    const r = await program.account.round.fetch(roundPda, "confirmed");
+    if (BigInt(r.seededAmount.toString()) !== seed) {
+      throw new Error(`seededAmount ${r.seededAmount.toString()}, expected ${seed}`);
     }
   });
 
-  it("no-reveal stake goes to treasury SPL (settle)", async () => {
+  it("cancel_round closes an empty round and its vaults", async () => {
     const admin = provider.wallet.publicKey;
 
-    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
-
-    const cfg = await program.account.config.fetch(configPda, "confirmed");
-    const stakeBn = cfg.stakeAmount ?? cfg.stake_amount ?? cfg.stake ?? new BN(1);
-    const stake = BigInt(stakeBn.toString());
+    const { configPda } = await ensureConfig(program, provider);
 
-    const roundId = (Math.floor(Date.now() / 1000) + 8642) % 1000000;
+    const roundId = (Math.floor(Date.now() / 1000) + 1313) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const slot0 = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot0 + 10;
-    const revealDeadline = slot0 + 25;
+    const slot = await provider.connection.getSlot("confirmed");
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(new BN(roundId), new BN(777), new BN(slot + 10), new BN(slot + 25), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -1582,158 +1613,248 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    const nonce1 = 101;
-    const guess1 = 1;
-    const salt1 = Buffer.alloc(32, 3);
-
-    const nonce2 = 202; // no-reveal
-    const guess2 = 0;
-    const salt2 = Buffer.alloc(32, 5);
-
-    const commitment1 = commitHash(roundId, admin, nonce1, guess1, salt1);
-    const commitment2 = commitHash(roundId, admin, nonce2, guess2, salt2);
-
-    const [ticket1Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce1)],
-      program.programId
-    );
-    const [ticket2Pda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce2)],
-      program.programId
-    );
-
-    const treBefore = BigInt((await provider.connection.getTokenAccountBalance(treasuryPda, "confirmed")).value.amount);
-
     await rpcConfirmed(
       provider,
       program.methods
-        .commitBatch(new BN(roundId), [
-          { nonce: new BN(nonce1), commitment: Array.from(commitment1) },
-          { nonce: new BN(nonce2), commitment: Array.from(commitment2) },
-        ])
+        .cancelRound(new BN(roundId))
         .accounts({
           config: configPda,
           round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda, // ✅ REQUIRED
-          user: admin,
-          userTIMLGAta,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          roundRegistry: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
-        .remainingAccounts([
-          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
-          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
-        ])
         .rpc()
     );
 
-    // pulse: ticket1 wins
-    const bitIndex1 = deriveBitIndex(roundId, admin, nonce1);
-    const pulse = Buffer.alloc(64, 0);
-    setBit(pulse, bitIndex1, guess1);
-
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+    for (const pk of [roundPda, vaultPda, timlgVaultPda]) {
+      const info = await provider.connection.getAccountInfo(pk, "confirmed");
+      if (info !== null) throw new Error(`account ${pk.toBase58()} should be closed`);
     }
+  });
+
+  it("close_round_batch skips rounds that are not closable yet", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 1810) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
 
     await rpcConfirmed(
       provider,
       program.methods
-        .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin })
+        .createRound(new BN(roundId), new BN(777), new BN(slot + 10), new BN(slot + 25), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
         .rpc()
     );
 
-    // reveal only ticket1
+    // not swept: skipped instead of failing the whole batch
     await rpcConfirmed(
       provider,
       program.methods
-        .revealBatch(new BN(roundId), [{ nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) }])
-        .accounts({ config: configPda, round: roundPda, user: admin })
-        .remainingAccounts([{ pubkey: ticket1Pda, isSigner: false, isWritable: true }])
+        .closeRoundBatch()
+        .accounts({ config: configPda, admin, roundRegistry: null, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts([
+          { pubkey: roundPda, isSigner: false, isWritable: true },
+          { pubkey: timlgVaultPda, isSigner: false, isWritable: true },
+        ])
         .rpc()
     );
 
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+    for (const pk of [roundPda, timlgVaultPda]) {
+      const info = await provider.connection.getAccountInfo(pk, "confirmed");
+      if (info === null) throw new Error(`account ${pk.toBase58()} should still exist`);
     }
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .finalizeRound(new BN(roundId))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
+    // unpaired remaining_accounts are rejected
+    await expectTxFail(async () => {
+      await program.methods
+        .closeRoundBatch()
+        .accounts({ config: configPda, admin, roundRegistry: null, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts([{ pubkey: roundPda, isSigner: false, isWritable: true }])
+        .rpc();
+    }, "TicketPdaMismatch");
+  });
+
+  it("recover_funds returns the stake and the ticket rent in one tx", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const roundId = (Math.floor(Date.now() / 1000) + 1783) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 20;
+    const revealDeadline = slot0 + 40;
     await rpcConfirmed(
       provider,
       program.methods
-        .settleRoundTokens(new BN(roundId))
+        .createRound(new BN(roundId), new BN(1783), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          round: roundPda,
           timlgMint,
+          round: roundPda,
+          vault: vaultPda,
           timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
           admin,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
-        .remainingAccounts([
-          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
-          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
-        ])
         .rpc()
     );
 
-    const treAfter = BigInt((await provider.connection.getTokenAccountBalance(treasuryPda, "confirmed")).value.amount);
-    if (treAfter !== treBefore + stake) {
-      throw new Error(`treasury did not receive no-reveal stake: got=${treAfter} expected=${treBefore + stake}`);
-    }
-  });
-
-  it("hardening: replayed commit_batch_signed fails with TicketAlreadyExists", async () => {
-    const adminKp = provider.wallet.payer;
-    const userKp = adminKp;
-    const user = userKp.publicKey;
-
-    const relayer = anchor.web3.Keypair.generate();
-    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
-    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
-
-    const { configPda } = await ensureConfig(program, provider);
-
-    // round
-    const roundId = (Math.floor(Date.now() / 1000) + 9901) % 1_000_000;
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
-
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 8;
-    const revealDeadline = slot + 20;
-
+    const nonce = 1;
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(123), new BN(commitDeadline), new BN(revealDeadline))
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonce), commitment: Array.from(commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, 7))) },
+        ])
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
           round: roundPda,
-          vault: vaultPda,
+          timlgMint,
           timlgVault: timlgVaultPda,
-          admin: user,
+          user: admin,
+          userTIMLGAta,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
         .rpc()
     );
 
-    // escrow exists + fund it (1 entry)
-    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+    // No pulse: wait out the refund timeout (REFUND_TIMEOUT_SLOTS = 150).
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline + 150) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    // a destination of another mint is rejected before any refund moves
+    const otherMint = await createMint(provider.connection, provider.wallet.payer, admin, null, 9);
+    const otherAta = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      provider.wallet.payer,
+      otherMint,
+      admin
+    );
+    await expectTxFail(async () => {
+      await program.methods
+        .recoverFunds(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          userTokenAccount: otherAta.address,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          timlgMint,
+          treasurySol: cfg.treasurySol,
+          feePayer: admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }, "ConstraintTokenMint");
+
+    const ticketRent = (await provider.connection.getAccountInfo(ticketPda, "confirmed")).lamports;
+    const tokensBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    const lamportsBefore = await provider.connection.getBalance(admin, "confirmed");
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .recoverFunds(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          userTokenAccount: userTIMLGAta,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          timlgMint,
+          treasurySol: cfg.treasurySol,
+          feePayer: admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const tokensAfter = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    const lamportsAfter = await provider.connection.getBalance(admin, "confirmed");
+
+    if (tokensAfter - tokensBefore !== BigInt(cfg.stakeAmount.toString())) {
+      throw new Error(`stake not refunded: before=${tokensBefore} after=${tokensAfter}`);
+    }
+    // Rent (plus any service fee refund) minus the tx fee.
+    if (lamportsAfter - lamportsBefore < ticketRent - 10_000) {
+      throw new Error(`ticket rent not refunded: delta=${lamportsAfter - lamportsBefore} rent=${ticketRent}`);
+    }
+    const info = await provider.connection.getAccountInfo(ticketPda, "confirmed");
+    if (info !== null) throw new Error("ticket should be closed by recover_funds");
+  });
+
+  it("commit_batch_signed (relayer pays, user authorizes via ed25519)", async () => {
+    const adminKp = provider.wallet.payer;
+    const userKp = adminKp; // user = admin (simple)
+    const user = userKp.publicKey;
+
+    const relayer = anchor.web3.Keypair.generate();
+    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
+    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    // round
+    const roundId = (Math.floor(Date.now() / 1000) + 2222) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 20;
+    const revealDeadline = slot + 40;
+    const pulseIndexTarget = 4242;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin: user,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // escrow exists + fund it
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
       configPda,
       timlgMint: TIMLG_MINT,
       userPk: user,
@@ -1741,30 +1862,30 @@ describe("timlg_protocol - BATCHING", () => {
 
     await ensureEscrowFunds(program, provider, {
       configPda,
-      timlgMint: TIMLG_MINT,
+      timlgMint: TIMLG_MINT, // ✅ IMPORTANTE (tu fallo anterior)
       userPk: user,
       userEscrowPda,
       userEscrowAtaPda,
       userTIMLGAta: USER_TIMLG_ATA,
-      neededRaw: 1n,
+      neededRaw: 1n, // ✅ solo 1 entry para evitar "tx too large"
     });
 
-    const nonce = 777;
+    // ✅ SOLO 1 ENTRY
+    const nonce = 11;
     const salt = crypto.randomBytes(32);
     const guess = 1;
     const commitment = commitHash(roundId, user, nonce, guess, salt);
 
     const entries = [{ user, nonce: new BN(nonce), commitment: Array.from(commitment) }];
+
     const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
 
-    const msg = expectedCommitMsg(program.programId, roundId, user, nonce, commitment);
-    const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: userKp.secretKey,
-      message: msg,
-    });
+    // ed25519 ix need not be adjacent: a compute-budget ix sits in between
+    const edIxFor = (message) =>
+      Ed25519Program.createInstructionWithPrivateKey({ privateKey: userKp.secretKey, message });
 
     const progIx = await program.methods
-      .commitBatchSigned(new BN(roundId), entries)
+      .commitBatchSigned(new BN(roundId), entries, null)
       .accounts({
         config: configPda,
         round: roundPda,
@@ -1781,60 +1902,79 @@ describe("timlg_protocol - BATCHING", () => {
       .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
       .instruction();
 
-    // 1) first tx ok
-    {
-      const tx = new Transaction().add(edIx, progIx);
+    const cuIx = ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 });
+    const sendSigned = async (message) => {
+      const tx = new anchor.web3.Transaction().add(edIxFor(message), cuIx, progIx);
       tx.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+
+      const { blockhash, lastValidBlockHeight } = await provider.connection.getLatestBlockhash("confirmed");
       tx.recentBlockhash = blockhash;
       tx.sign(relayer);
 
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], { commitment: "confirmed" });
-    }
-
-    // 2) replay same tx should fail with TicketAlreadyExists
-    await expectTxFail(async () => {
-      const edIx2 = Ed25519Program.createInstructionWithPrivateKey({
-        privateKey: userKp.secretKey,
-        message: msg,
+      const sig = await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+        commitment: "confirmed",
       });
-      const tx2 = new Transaction().add(edIx2, progIx);
-      tx2.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx2.recentBlockhash = blockhash;
-      tx2.sign(relayer);
+      await provider.connection.confirmTransaction({ signature: sig, blockhash, lastValidBlockHeight }, "confirmed");
+    };
 
-      await sendAndConfirmTransaction(provider.connection, tx2, [relayer], { commitment: "confirmed" });
-    }, "TicketAlreadyExists");
+    // the deadline-less commit_v1 message is only taken while accept_legacy_commit_msg is set
+    const legacyMsg = expectedCommitMsg(program.programId, roundId, user, nonce, commitment);
+    await expectTxFail(() => sendSigned(legacyMsg), "Ed25519MessageMismatch");
+    // commit_v3 must carry the live deadlines
+    await expectTxFail(
+      () => sendSigned(expectedCommitMsgV3(program.programId, roundId, user, nonce, commitment, commitDeadline + 1, revealDeadline, 0)),
+      "Ed25519MessageMismatch"
+    );
+
+    const cfgAccounts = { config: configPda, admin: user };
+    await rpcConfirmed(provider, program.methods.setAcceptLegacyCommitMsg(true).accounts(cfgAccounts).rpc());
+    try {
+      await sendSigned(legacyMsg);
+    } finally {
+      await rpcConfirmed(provider, program.methods.setAcceptLegacyCommitMsg(false).accounts(cfgAccounts).rpc());
+    }
+
+    const t = await program.account.ticket.fetch(ticketPda);
+    if (Buffer.from(t.commitment).compare(commitment) !== 0) throw new Error("ticket commitment mismatch");
   });
 
-  it("hardening: settle rejects calls only after fully settled (RoundTokensAlreadySettled) and claim is one-shot (AlreadyClaimed)", async () => {
-    const admin = provider.wallet.publicKey;
+  it("commit_batch_signed with rent_reimburse repays the relayer in TIMLG (commit_v2)", async () => {
+    const adminKp = provider.wallet.payer;
+    const user = adminKp.publicKey;
 
-    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const relayer = anchor.web3.Keypair.generate();
+    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
+    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
 
+    const { configPda } = await ensureConfig(program, provider);
     const cfg = await program.account.config.fetch(configPda, "confirmed");
-    const stakeBn = cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1);
-    const stake = BigInt(stakeBn.toString());
+    const stake = BigInt(cfg.stakeAmount.toString());
 
-    const roundId = (Math.floor(Date.now() / 1000) + 9902) % 1_000_000;
+    // price 1000 TIMLG base units per SOL of rent
+    const price = 1000n;
+    const ticketRent = BigInt(
+      await provider.connection.getMinimumBalanceForRentExemption(program.account.ticket.size)
+    );
+    const rentTimlg = (ticketRent * price) / 1_000_000_000n;
+
+    const roundId = (Math.floor(Date.now() / 1000) + 1809) % 1_000_000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const slot0 = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot0 + 8;
-    const revealDeadline = slot0 + 18;
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 20;
+    const revealDeadline = slot + 90;
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(555), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(new BN(roundId), new BN(1809), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
-          admin,
+          admin: user,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -1842,181 +1982,375 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // commit 1 ticket
-    const nonce = 1;
-    const guess = 1;
-    const salt = crypto.randomBytes(32);
-    const commitment = commitHash(roundId, admin, nonce, guess, salt);
-    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint: TIMLG_MINT,
+      userPk: user,
+    });
+    await ensureEscrowFunds(program, provider, {
+      configPda,
+      timlgMint: TIMLG_MINT,
+      userPk: user,
+      userEscrowPda,
+      userEscrowAtaPda,
+      userTIMLGAta: USER_TIMLG_ATA,
+      neededRaw: stake + rentTimlg,
+    });
 
-    const before = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    const relayerAta = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      adminKp,
+      TIMLG_MINT,
+      relayer.publicKey
+    );
+
+    const nonce = 1;
+    const commitment = commitHash(roundId, user, nonce, 1, crypto.randomBytes(32));
+    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
 
     await rpcConfirmed(
       provider,
       program.methods
-        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+        .setRentReimburse(true, new BN(price.toString()))
+        .accounts({ config: configPda, admin: user })
+        .rpc()
+    );
+
+    try {
+      const edIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: adminKp.secretKey,
+        message: expectedCommitMsgV3(program.programId, roundId, user, nonce, commitment, commitDeadline, revealDeadline, rentTimlg),
+      });
+
+      const progIx = await program.methods
+        .commitBatchSigned(new BN(roundId), [{ user, nonce: new BN(nonce), commitment: Array.from(commitment) }], null)
         .accounts({
           config: configPda,
           round: roundPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           timlgVault: timlgVaultPda,
-          ticket: ticketPda,
-          user: admin,
-          userTIMLGAta,
+          payer: relayer.publicKey,
+          userEscrow: userEscrowPda,
+          userEscrowAta: userEscrowAtaPda,
+          user,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
+          relayerTimlgAta: relayerAta.address,
         })
-        .rpc()
-    );
-
-    const afterCommit = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
-    if (afterCommit !== before - stake) throw new Error("commit stake mismatch");
+        .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
+        .instruction();
 
-    // wait >= commitDeadline then set pulse so ticket wins
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+      const tx = new anchor.web3.Transaction().add(edIx, progIx);
+      tx.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], { commitment: "confirmed" });
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .setRentReimburse(false, new BN(0))
+          .accounts({ config: configPda, admin: user })
+          .rpc()
+      );
     }
-    const bitIndex = deriveBitIndex(roundId, admin, nonce);
-    const pulse = Buffer.alloc(64, 0);
-    setBit(pulse, bitIndex, guess);
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
+    const relayerBal = await getTokenAmountRaw(provider.connection, relayerAta.address);
+    if (relayerBal !== rentTimlg) throw new Error(`relayer got ${relayerBal}, expected ${rentTimlg}`);
+  });
 
-    // reveal
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .revealTicket(new BN(roundId), new BN(nonce), guess, Array.from(salt))
-        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
-        .rpc()
-    );
+  it("commit_ticket_signed (single entry, relayer pays rent, stake from escrow)", async () => {
+    const userKp = provider.wallet.payer;
+    const user = userKp.publicKey;
 
-    // wait > revealDeadline then finalize
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+    const relayer = anchor.web3.Keypair.generate();
+    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
+    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .finalizeRound(new BN(roundId))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
+    const { configPda } = await ensureConfig(program, provider);
 
-    // settle once OK
+    const roundId = (Math.floor(Date.now() / 1000) + 1792) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 20;
+    const revealDeadline = slot + 40;
     await rpcConfirmed(
       provider,
       program.methods
-        .settleRoundTokens(new BN(roundId))
+        .createRound(new BN(roundId), new BN(1792), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
-          timlgMint,
+          vault: vaultPda,
           timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
-          admin,
+          admin: user,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
-        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
         .rpc()
     );
 
-    // settle again must fail
-    await expectTxFail(async () => {
-      await program.methods
-        .settleRoundTokens(new BN(roundId))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
-          admin,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
-        .rpc();
-    }, "RoundTokensAlreadySettled");
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint: TIMLG_MINT,
+      userPk: user,
+    });
+    await ensureEscrowFunds(program, provider, {
+      configPda,
+      timlgMint: TIMLG_MINT,
+      userPk: user,
+      userEscrowPda,
+      userEscrowAtaPda,
+      userTIMLGAta: USER_TIMLG_ATA,
+      neededRaw: 1n,
+    });
+
+    const nonce = 12;
+    const commitment = commitHash(roundId, user, nonce, 1, crypto.randomBytes(32));
+    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
+
+    const edIxFor = (message) =>
+      Ed25519Program.createInstructionWithPrivateKey({ privateKey: userKp.secretKey, message });
+    const progIx = await program.methods
+      .commitTicketSigned(new BN(roundId), new BN(nonce), Array.from(commitment))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        timlgVault: timlgVaultPda,
+        payer: relayer.publicKey,
+        ticket: ticketPda,
+        userEscrow: userEscrowPda,
+        userEscrowAta: userEscrowAtaPda,
+        user,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .instruction();
+
+    const sendSigned = async (message) => {
+      const tx = new anchor.web3.Transaction().add(edIxFor(message), progIx);
+      tx.feePayer = relayer.publicKey;
+      tx.recentBlockhash = (await provider.connection.getLatestBlockhash("confirmed")).blockhash;
+      tx.sign(relayer);
+      await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [relayer], { commitment: "confirmed" });
+    };
+
+    // a deadline-less commit_v1 signature cannot be replayed through this entrypoint
+    await expectTxFail(
+      () => sendSigned(expectedCommitMsg(program.programId, roundId, user, nonce, commitment)),
+      "Ed25519MessageMismatch"
+    );
+    await sendSigned(expectedCommitMsgV3(program.programId, roundId, user, nonce, commitment, commitDeadline, revealDeadline, 0));
+
+    const t = await program.account.ticket.fetch(ticketPda, "confirmed");
+    if (Buffer.from(t.commitment).compare(commitment) !== 0) throw new Error("ticket commitment mismatch");
+    if (!t.user.equals(user)) throw new Error("ticket user mismatch");
+    if (!t.stakePaid) throw new Error("stake should be paid from escrow");
+  });
+
+  it("commit_batch_signed_multi (per-entry escrow + user_stats from remaining_accounts)", async () => {
+    const admin = provider.wallet.publicKey;
+    const userKp = Keypair.generate();
+    const user = userKp.publicKey;
+    const relayer = Keypair.generate();
+
+    for (const pk of [user, relayer.publicKey]) {
+      const sig = await provider.connection.requestAirdrop(pk, 2_000_000_000);
+      await provider.connection.confirmTransaction(sig, "confirmed");
+    }
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    // fund the new user's escrow with 1 raw TIMLG from the admin ATA
+    const userAta = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      provider.wallet.payer,
+      TIMLG_MINT,
+      user
+    );
+    await splTransfer(provider.connection, provider.wallet.payer, USER_TIMLG_ATA, userAta.address, admin, 1);
+
+    const { userEscrowPda, userEscrowAtaPda } = deriveUserEscrowPdas(program.programId, user);
+    await program.methods
+      .initUserEscrow()
+      .accounts({
+        config: configPda,
+        timlgMint: TIMLG_MINT,
+        userEscrow: userEscrowPda,
+        userEscrowAta: userEscrowAtaPda,
+        user,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([userKp])
+      .rpc();
+    await program.methods
+      .depositEscrow(new BN(1))
+      .accounts({
+        config: configPda,
+        timlgMint: TIMLG_MINT,
+        userEscrow: userEscrowPda,
+        userEscrowAta: userEscrowAtaPda,
+        user,
+        userTIMLGAta: userAta.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([userKp])
+      .rpc();
+
+    const roundId = (Math.floor(Date.now() / 1000) + 2323) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 20;
+    const revealDeadline = slot + 40;
 
-    // claim once OK (refund + mint)
     await rpcConfirmed(
       provider,
       program.methods
-        .claimReward(new BN(roundId), new BN(nonce))
+        .createRound(new BN(roundId), new BN(4242), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
-          ticket: ticket1Pda,
-          user: admin,
-          timlgMint,
+          vault: vaultPda,
           timlgVault: timlgVaultPda,
-          userTIMLGAta,
-
-          tokenomics: tokenomicsPda,
-          rewardFeePool: rewardFeePoolPda,
-
+          admin,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
         .rpc()
     );
 
-    // claim again must fail
-    await expectTxFail(async () => {
-      await program.methods
-        .claimReward(new BN(roundId), new BN(nonce))
+    const nonce = 1;
+    const commitment = commitHash(roundId, user, nonce, 1, crypto.randomBytes(32));
+    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
+    const [userStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stats_v3"), user.toBytes()],
+      program.programId
+    );
+    const [userRoundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_round"), leU64(roundId), user.toBytes()],
+      program.programId
+    );
+
+    const edIxFor = (message) =>
+      Ed25519Program.createInstructionWithPrivateKey({ privateKey: userKp.secretKey, message });
+
+    // stats PDAs someone already sent lamports to must still be created on first use
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: userStatsPda, lamports: 1_000 }),
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: userRoundPda, lamports: 1_000 })
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+
+    // remaining_accounts per entry: [user_escrow, user_escrow_ata, user_stats, user_round_stats, ticket]
+    const commitIx = (instructions) =>
+      program.methods
+        .commitBatchSignedMulti(new BN(roundId), [
+          { user, nonce: new BN(nonce), commitment: Array.from(commitment) },
+        ])
         .accounts({
           config: configPda,
           round: roundPda,
-          ticket: ticket1Pda,
-          user: admin,
-          timlgMint,
           timlgVault: timlgVaultPda,
-          userTIMLGAta,
-
-          tokenomics: tokenomicsPda,
-          rewardFeePool: rewardFeePoolPda,
-
+          payer: relayer.publicKey,
+          instructions,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
-        .rpc();
-    }, "AlreadyClaimed");
+        .remainingAccounts([
+          { pubkey: userEscrowPda, isWritable: true, isSigner: false },
+          { pubkey: userEscrowAtaPda, isWritable: true, isSigner: false },
+          { pubkey: userStatsPda, isWritable: true, isSigner: false },
+          { pubkey: userRoundPda, isWritable: true, isSigner: false },
+          { pubkey: ticketPda, isWritable: true, isSigner: false },
+        ])
+        .instruction();
+    const progIx = await commitIx(anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY);
+
+    const sendSigned = async (message, ix = progIx) => {
+      const tx = new Transaction().add(edIxFor(message), ix);
+      tx.feePayer = relayer.publicKey;
+      tx.recentBlockhash = (await provider.connection.getLatestBlockhash("confirmed")).blockhash;
+      tx.sign(relayer);
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], { commitment: "confirmed" });
+    };
+
+    // a deadline-less commit_v1 signature cannot be replayed through this entrypoint
+    await expectTxFail(
+      () => sendSigned(expectedCommitMsg(program.programId, roundId, user, nonce, commitment)),
+      "Ed25519MessageMismatch"
+    );
+    const v3Msg = expectedCommitMsgV3(program.programId, roundId, user, nonce, commitment, commitDeadline, revealDeadline, 0);
+    // the instructions account must be the real sysvar
+    await expectTxFail(
+      async () => sendSigned(v3Msg, await commitIx(Keypair.generate().publicKey)),
+      "ConstraintAddress"
+    );
+    await sendSigned(v3Msg);
+
+    const t = await program.account.ticket.fetch(ticketPda);
+    if (!t.user.equals(user)) throw new Error("ticket user mismatch");
+    const us = await program.account.userStats.fetch(userStatsPda);
+    if (us.gamesPlayed.toNumber() !== 1) throw new Error(`gamesPlayed should be 1, got ${us.gamesPlayed}`);
+    if (t.userCommitIndex.toNumber() !== 1) throw new Error("userCommitIndex should be 1");
+    const urs = await program.account.userRoundStats.fetch(userRoundPda, "confirmed");
+    if (Number(urs.committed) !== 1) throw new Error(`user_round_stats.committed should be 1, got ${urs.committed}`);
   });
 
-  it("hardening: claim is rejected after sweep (ClaimAfterSweep)", async () => {
-    const admin = provider.wallet.publicKey;
+  it("reveal_batch_signed (relayer pays, user authorizes via ed25519)", async () => {
+    const adminKp = provider.wallet.payer;
+    const userKp = adminKp;                 // user = admin
+    const user = userKp.publicKey;
 
-    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const relayer = anchor.web3.Keypair.generate();
+    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
+    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
 
-    const roundId = (Math.floor(Date.now() / 1000) + 9903) % 1_000_000;
+    const { configPda } = await ensureConfig(program, provider);
+
+    // round
+    const roundId = (Math.floor(Date.now() / 1000) + 3333) % 1_000_000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const slot0 = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot0 + 8;
-    const revealDeadline = slot0 + 18;
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 15;
+    const revealDeadline = slot + 35;
+    const pulseIndexTarget = 8080;
 
-    // create round
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(
+          new BN(roundId),
+          new BN(pulseIndexTarget),
+          new BN(commitDeadline),
+          new BN(revealDeadline),
+          new BN(0),
+          NO_LABEL,
+          PublicKey.default,
+          new BN(0),
+          new BN(0),
+          1
+        )
         .accounts({
           config: configPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
-          admin,
+          admin: user,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -2024,173 +2358,283 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // commit
-    const nonce = 9;
-    const guess = 1;
-    const salt = crypto.randomBytes(32);
-    const commitment = commitHash(roundId, admin, nonce, guess, salt);
-    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+    // commit 2 tickets normally (so the reveal-signed test only tests reveal path)
+    let nonce1 = 21;
+    let nonce2 = 22;
+    while (deriveBitIndex(roundId, user, nonce1) === deriveBitIndex(roundId, user, nonce2)) {
+      nonce2++;
+    }
+
+    const salt1 = crypto.randomBytes(32);
+    const salt2 = crypto.randomBytes(32);
+    const guess1 = 1;
+    const guess2 = 0;
+
+    const commitment1 = commitHash(roundId, user, nonce1, guess1, salt1);
+    const commitment2 = commitHash(roundId, user, nonce2, guess2, salt2);
+
+    const ticketPda1 = deriveTicketPda(program.programId, roundId, user, nonce1);
+    const ticketPda2 = deriveTicketPda(program.programId, roundId, user, nonce2);
 
     await rpcConfirmed(
       provider,
       program.methods
-        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonce1), commitment: Array.from(commitment1) },
+          { nonce: new BN(nonce2), commitment: Array.from(commitment2) },
+        ])
         .accounts({
           config: configPda,
           round: roundPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           timlgVault: timlgVaultPda,
-          ticket: ticketPda,
-          user: admin,
-          userTIMLGAta,
+          user,
+          userTIMLGAta: USER_TIMLG_ATA,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
+        .remainingAccounts([
+          { pubkey: ticketPda1, isWritable: true, isSigner: false },
+          { pubkey: ticketPda2, isWritable: true, isSigner: false },
+        ])
         .rpc()
     );
 
-    // wait until commit window ends
+    // wait until after commitDeadline for setPulseMock
     while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 200));
+      await new Promise((r) => setTimeout(r, 120));
     }
 
-    // set pulse (make ticket win)
-    const bitIndex = deriveBitIndex(roundId, admin, nonce);
+    // set pulse bits so both are winners
     const pulse = Buffer.alloc(64, 0);
-    setBit(pulse, bitIndex, guess);
+    setBit(pulse, deriveBitIndex(roundId, user, nonce1), guess1);
+    setBit(pulse, deriveBitIndex(roundId, user, nonce2), guess2);
 
     await rpcConfirmed(
       provider,
       program.methods
         .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
-
-    // reveal
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .revealTicket(new BN(roundId), new BN(nonce), guess, Array.from(salt))
-        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .accounts({ config: configPda, round: roundPda, admin: user })
         .rpc()
     );
 
-    // wait until reveal deadline passed, then finalize
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 200));
-    }
+    // reveal signed (relayer pays)
+    const msg1 = expectedRevealMsg(program.programId, roundId, user, nonce1, guess1, salt1);
+    const msg2 = expectedRevealMsg(program.programId, roundId, user, nonce2, guess2, salt2);
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .finalizeRound(new BN(roundId))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
+    const edIx1 = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: userKp.secretKey,
+      message: msg1,
+    });
+    const edIx2 = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: userKp.secretKey,
+      message: msg2,
+    });
+
+    const revealIx = await program.methods
+      .revealBatchSigned(new BN(roundId), [
+        { user, nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) },
+        { user, nonce: new BN(nonce2), guess: guess2, salt: Array.from(salt2) },
+      ])
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        payer: relayer.publicKey,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .remainingAccounts([
+        { pubkey: ticketPda1, isWritable: true, isSigner: false },
+        { pubkey: ticketPda2, isWritable: true, isSigner: false },
+      ])
+      .instruction();
+
+    const tx = new anchor.web3.Transaction().add(edIx1, edIx2, revealIx);
+    tx.feePayer = relayer.publicKey;
+
+    const { blockhash, lastValidBlockHeight } = await provider.connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+    tx.sign(relayer);
+
+    const sig = await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+      commitment: "confirmed",
+    });
+    await provider.connection.confirmTransaction({ signature: sig, blockhash, lastValidBlockHeight }, "confirmed");
+
+    const t1 = await program.account.ticket.fetch(ticketPda1);
+    const t2 = await program.account.ticket.fetch(ticketPda2);
+    if (!t1.revealed || !t2.revealed) throw new Error("tickets should be revealed");
+
+    if (!t1.win || !t2.win) throw new Error("expected both tickets to be winners (pulse bits were set accordingly)");
+  });
+
+  it("set_pulse_signed (oracle ed25519)", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+
+    const oracle = Keypair.generate();
+    const relayer = Keypair.generate();
+
+    // fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
+
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
     );
+    await ensureConfig(program, provider, configPda);
 
-    // settle tokens (so claim would normally be allowed)
+    // Set oracle pubkey (admin only)
     await rpcConfirmed(
       provider,
       program.methods
-        .settleRoundTokens(new BN(roundId))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
-          admin,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
         .rpc()
     );
 
-    // --- IMPORTANT: wait using on-chain reveal_deadline_slot + cfg.claim_grace_slots ---
-    const roundAcc = await program.account.round.fetch(roundPda, "confirmed");
-    const revealDlOnChain = Number(
-      roundAcc.revealDeadlineSlot ?? roundAcc.reveal_deadline_slot
-    );
-
-    const cfgAcc = await program.account.config.fetch(configPda, "confirmed");
-    const graceBn =
-      (cfgAcc.claimGraceSlots ??
-        cfgAcc.claim_grace_slots ??
-        new BN(0));
-
-    const grace = Number(graceBn.toString());
+    const roundId = (Math.floor(Date.now() / 1000) + 999) % 1000000;
 
-    // sweep_unclaimed requires current_slot > reveal_deadline_slot + claim_grace_slots
-    const targetSlot = revealDlOnChain + grace + 1;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    while ((await provider.connection.getSlot("confirmed")) < targetSlot) {
-      await new Promise((r) => setTimeout(r, 200));
-    }
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 15;
+    const pulseIndexTarget = 424242;
 
-    // sweep
     await rpcConfirmed(
       provider,
       program.methods
-        .sweepUnclaimed(new BN(roundId))
+        .createRound(
+          new BN(roundId),
+          new BN(pulseIndexTarget),
+          new BN(commitDeadline),
+          new BN(slot + 200),
+          new BN(0),
+          NO_LABEL,
+          PublicKey.default,
+          new BN(0),
+          new BN(0),
+          1
+        )
         .accounts({
           config: configPda,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
-          treasurySol: treasurySolPda,
+          timlgVault: timlgVaultPda,
           admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
         .rpc()
     );
 
-    // claim after sweep must fail
-    await expectTxFail(async () => {
-      await program.methods
-        .claimReward(new BN(roundId), new BN(nonce))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          ticket: ticketPda,
-          user: admin,
-          timlgMint,
-          timlgVault: timlgVaultPda,
-          userTIMLGAta,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .rpc();
-    }, "ClaimAfterSweep");
+    // Wait past commit deadline
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 100));
+    }
+
+    const pulse = crypto.randomBytes(64);
+
+    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: oracle.secretKey,
+      message: msg,
+    });
+
+    const anchorIx = await program.methods
+      .setPulseSigned(new BN(roundId), Array.from(pulse))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    const tx = new Transaction().add(edIx, anchorIx);
+    tx.feePayer = relayer.publicKey;
+
+    const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+
+    tx.sign(relayer);
+
+    await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+      commitment: "confirmed",
+    });
+
+    const r = await program.account.round.fetch(roundPda);
+    if (!r.pulseSet) throw new Error("setPulseSigned did not persist pulseSet=true");
+
+    const onchainPulse = Buffer.from(r.pulse);
+    if (!onchainPulse.equals(Buffer.from(pulse))) {
+      throw new Error("on-chain pulse mismatch");
+    }
   });
 
-  it("hardening: reveal_batch_signed rejects mixed users (SignedBatchMixedUsers)", async () => {
-    const admin = provider.wallet.publicKey;
+  it("set_pulse_and_close_commit sets the pulse and records the closed commit window", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
 
-    // userA = wallet, userB = random pubkey (no hace falta que tenga fondos)
-    const userA = admin;
-    const userB = anchor.web3.Keypair.generate().publicKey;
+    const oracle = Keypair.generate();
+    const relayer = Keypair.generate();
 
-    // payer/relayer (solo para firmar el ix; fee payer será la wallet por defecto)
-    const relayer = anchor.web3.Keypair.generate();
+    // fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
 
-    const { configPda, timlgMint } = await ensureConfig(program, provider);
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    await ensureConfig(program, provider, configPda);
+
+    // Set oracle pubkey (admin only)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 997) % 1000000;
 
-    const roundId = (Math.floor(Date.now() / 1000) + 9920) % 1_000_000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
-    const slot0 = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot0 + 6;
-    const revealDeadline = slot0 + 200; // grande para que no cierre
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 15;
+    const pulseIndexTarget = 424242;
 
-    // create round
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(111), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(
+          new BN(roundId),
+          new BN(pulseIndexTarget),
+          new BN(commitDeadline),
+          new BN(slot + 200),
+          new BN(0),
+          NO_LABEL,
+          PublicKey.default,
+          new BN(0),
+          new BN(0),
+          1
+        )
         .accounts({
           config: configPda,
-          timlgMint,
+          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -2202,212 +2646,63 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // wait end commit window (si tu setPulseMock lo exige)
+    // Wait past commit deadline
     while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 200));
+      await new Promise((r) => setTimeout(r, 100));
     }
 
-    // set pulse mock -> imprescindible porque reveal_batch_signed requiere round.pulse_set
-    const pulse = Buffer.alloc(64, 0);
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin })
-        .rpc()
-    );
-
-    // Mixed entries (A y B en el mismo batch) => debe fallar ANTES de chequear ed25519/tickets
-    const entries = [
-      {
-        user: userA,
-        nonce: new BN(1),
-        guess: 1,
-        salt: Array.from(Buffer.alloc(32, 1)),
-      },
-      {
-        user: userB,
-        nonce: new BN(2),
-        guess: 0,
-        salt: Array.from(Buffer.alloc(32, 2)),
-      },
-    ];
+    const pulse = crypto.randomBytes(64);
 
-    // remainingAccounts debe tener la misma longitud que entries (aunque no se use por el early-fail)
-    const dummy1 = anchor.web3.Keypair.generate().publicKey;
-    const dummy2 = anchor.web3.Keypair.generate().publicKey;
-
-    await expectTxFail(async () => {
-      await program.methods
-        .revealBatchSigned(new BN(roundId), entries)
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          payer: relayer.publicKey,
-          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-          systemProgram: SystemProgram.programId,
-        })
-        .remainingAccounts([
-          { pubkey: dummy1, isWritable: true, isSigner: false },
-          { pubkey: dummy2, isWritable: true, isSigner: false },
-        ])
-        .signers([relayer])
-        .rpc();
-    }, "SignedBatchMixedUsers");
-  });
-
-  it("hardening: reveal_batch_signed replay fails with AlreadyRevealed", async () => {
-    const userKp = provider.wallet.payer;
-    const user = userKp.publicKey;
-
-    const relayer = anchor.web3.Keypair.generate();
-    await provider.connection.confirmTransaction(
-      await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000),
-      "confirmed"
-    );
-
-    const { configPda, timlgMint, userTIMLGAta } = await ensureConfig(program, provider);
-
-    const roundId = (Math.floor(Date.now() / 1000) + 9921) % 1_000_000;
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
-
-    const slot0 = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot0 + 8;
-    const revealDeadline = slot0 + 200;
-
-    // create round
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .createRound(new BN(roundId), new BN(222), new BN(commitDeadline), new BN(revealDeadline))
-        .accounts({
-          config: configPda,
-          timlgMint,
-          round: roundPda,
-          vault: vaultPda,
-          timlgVault: timlgVaultPda,
-          admin: user,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-        })
-        .rpc()
-    );
-
-    // commit 1 ticket (no signed)
-    const nonce = 7;
-    const guess = 1;
-    const salt = crypto.randomBytes(32);
-    const commitment = commitHash(roundId, user, nonce, guess, salt);
-    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
-
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda,
-          ticket: ticketPda,
-          user,
-          userTIMLGAta,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc()
-    );
-
-    // wait commit window end
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 200));
-    }
-
-    // set pulse mock so ticket wins (opcional para este test, pero consistente)
-    const pulse = Buffer.alloc(64, 0);
-    setBit(pulse, deriveBitIndex(roundId, user, nonce), guess);
-
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setPulseMock(new BN(roundId), Array.from(pulse))
-        .accounts({ config: configPda, round: roundPda, admin: user })
-        .rpc()
-    );
-
-    // build reveal_batch_signed tx (ed25519 ix must be immediately before the program ix)
-    const entries = [{ user, nonce: new BN(nonce), guess, salt: Array.from(salt) }];
-
-    const msg = expectedRevealMsg(program.programId, roundId, user, nonce, guess, salt);
+    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
     const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: userKp.secretKey,
+      privateKey: oracle.secretKey,
       message: msg,
     });
 
-    const progIx = await program.methods
-      .revealBatchSigned(new BN(roundId), entries)
+    const anchorIx = await program.methods
+      .setPulseAndCloseCommit(new BN(roundId), Array.from(pulse))
       .accounts({
         config: configPda,
         round: roundPda,
-        payer: relayer.publicKey,
         instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-        systemProgram: SystemProgram.programId,
       })
-      .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
       .instruction();
 
-    // 1) first reveal ok
-    {
-      const tx = new Transaction().add(edIx, progIx);
-      tx.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
+    const tx = new Transaction().add(edIx, anchorIx);
+    tx.feePayer = relayer.publicKey;
 
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-        commitment: "confirmed",
-      });
-    }
+    const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
 
-    // 2) replay reveal must fail with AlreadyRevealed
-    await expectTxFail(async () => {
-      const edIx2 = Ed25519Program.createInstructionWithPrivateKey({
-        privateKey: userKp.secretKey,
-        message: msg,
-      });
+    tx.sign(relayer);
 
-      const tx2 = new Transaction().add(edIx2, progIx);
-      tx2.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx2.recentBlockhash = blockhash;
-      tx2.sign(relayer);
+    await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+      commitment: "confirmed",
+    });
 
-      await sendAndConfirmTransaction(provider.connection, tx2, [relayer], {
-        commitment: "confirmed",
-      });
-    }, "AlreadyRevealed");
+    const r = await program.account.round.fetch(roundPda);
+    if (!r.pulseSet) throw new Error("setPulseAndCloseCommit did not persist pulseSet=true");
+    if (!r.commitFrozen) throw new Error("commit window should be recorded as closed");
+    if (Number(r.commitClosedSlot) < commitDeadline) throw new Error("commit_closed_slot should be set");
+
+    const onchainPulse = Buffer.from(r.pulse);
+    if (!onchainPulse.equals(Buffer.from(pulse))) {
+      throw new Error("on-chain pulse mismatch");
+    }
   });
 
-  it("hardening: set_pulse_signed replay is rejected (PulseAlreadySet)", async () => {
+  it("commit_batch error precedence: CommitClosed at the deadline, CommitAfterPulseSet once the pulse is set", async () => {
     const adminKp = provider.wallet.payer;
     const admin = adminKp.publicKey;
 
     const oracle = Keypair.generate();
-    const relayer = Keypair.generate();
-
-    // fund relayer
-    {
-      const sig = await provider.connection.requestAirdrop(
-        relayer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await confirmSig(provider.connection, sig, "confirmed");
-    }
 
-    const { configPda } = await ensureConfig(program, provider);
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    await ensureConfig(program, provider, configPda);
 
-    // set oracle pubkey (admin only)
     await rpcConfirmed(
       provider,
       program.methods
@@ -2416,16 +2711,28 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    const roundId = (Math.floor(Date.now() / 1000) + 4444) % 1000000;
+    const roundId = (Math.floor(Date.now() / 1000) + 1805) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
     const slot = await provider.connection.getSlot("confirmed");
     const commitDeadline = slot + 10;
+    const pulseIndexTarget = 180500;
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(9999), new BN(commitDeadline), new BN(slot + 200))
+        .createRound(
+          new BN(roundId),
+          new BN(pulseIndexTarget),
+          new BN(commitDeadline),
+          new BN(slot + 200),
+          new BN(0),
+          NO_LABEL,
+          PublicKey.default,
+          new BN(0),
+          new BN(0),
+          1
+        )
         .accounts({
           config: configPda,
           timlgMint: TIMLG_MINT,
@@ -2440,20 +2747,38 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // wait past commit deadline
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+    while ((await provider.connection.getSlot("confirmed")) <= commitDeadline) {
+      await new Promise((r) => setTimeout(r, 100));
     }
 
-    const pulse = crypto.randomBytes(64);
+    const commitLate = async (nonce) => {
+      const commitment = commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, 5));
+      const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+      await program.methods
+        .commitBatch(new BN(roundId), [{ nonce: new BN(nonce), commitment: Array.from(commitment) }])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint: TIMLG_MINT,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta: USER_TIMLG_ATA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc();
+    };
 
-    // --- first set_pulse_signed succeeds ---
-    const msg = expectedPulseMsg(program.programId, roundId, 9999, pulse);
+    // Deadline passed, no pulse yet: the deadline is the reason.
+    await expectTxFail(() => commitLate(1), "CommitClosed");
+
+    const pulse = crypto.randomBytes(64);
+    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
     const edIx = Ed25519Program.createInstructionWithPrivateKey({
       privateKey: oracle.secretKey,
       message: msg,
     });
-
     const anchorIx = await program.methods
       .setPulseSigned(new BN(roundId), Array.from(pulse))
       .accounts({
@@ -2462,58 +2787,57 @@ describe("timlg_protocol - BATCHING", () => {
         instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
       })
       .instruction();
+    const tx = new Transaction().add(edIx, anchorIx);
+    tx.feePayer = admin;
+    const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+    tx.sign(adminKp);
+    await sendAndConfirmTransaction(provider.connection, tx, [adminKp], {
+      commitment: "confirmed",
+    });
 
-    {
-      const tx = new Transaction().add(edIx, anchorIx);
-      tx.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
-
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-        commitment: "confirmed",
-      });
-    }
-
-    // --- replay: should fail BEFORE ed25519 checks with PulseAlreadySet ---
-    await expectTxFail(async () => {
-      await program.methods
-        .setPulseSigned(new BN(roundId), Array.from(pulse))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-        })
-        .rpc();
-    }, "PulseAlreadySet");
+    // Both conditions hold now; pulse_set is checked first.
+    await expectTxFail(() => commitLate(2), "CommitAfterPulseSet");
   });
 
-  it("hardening: set_pulse_signed without ed25519 ix fails (MissingOrInvalidEd25519Ix)", async () => {
+  it("finalize_round + sweep_unclaimed (to treasury_sol)", async () => {
     const adminKp = provider.wallet.payer;
     const admin = adminKp.publicKey;
 
-    const oracle = Keypair.generate();
-
-    const { configPda } = await ensureConfig(program, provider);
+    // ✅ Usa ensureConfig para obtener treasurySolPda (lamports) y treasuryPda (SPL)
+    const { configPda, treasurySolPda } = await ensureConfig(program, provider);
 
     await rpcConfirmed(
       provider,
       program.methods
-        .setOraclePubkey(oracle.publicKey)
+        .setClaimGraceSlots(new BN(0)) // para que el test no espere
         .accounts({ config: configPda, admin })
         .rpc()
     );
 
-    const roundId = (Math.floor(Date.now() / 1000) + 4445) % 1000000;
+    const roundId = (Math.floor(Date.now() / 1000) + 5555) % 1000000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
 
     const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 8;
+    const commitDeadline = slot + 10;
+    const revealDeadline = slot + 20;
+    const pulseIndexTarget = 777;
 
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(12345), new BN(commitDeadline), new BN(slot + 200))
+        .createRound(
+          new BN(roundId),
+          new BN(pulseIndexTarget),
+          new BN(commitDeadline),
+          new BN(revealDeadline),
+          new BN(0),
+          NO_LABEL,
+          PublicKey.default,
+          new BN(0),
+          new BN(0),
+          1
+        )
         .accounts({
           config: configPda,
           timlgMint: TIMLG_MINT,
@@ -2528,242 +2852,6914 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
-
-    const pulse = crypto.randomBytes(64);
-
-    await expectTxFail(async () => {
-      // no ed25519 instruction in the tx
-      await program.methods
-        .setPulseSigned(new BN(roundId), Array.from(pulse))
+    // fund vault so there's something to sweep
+    const fundAmount = 200_000; // lamports
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .fundVault(new BN(roundId), new BN(fundAmount))
         .accounts({
           config: configPda,
           round: roundPda,
-          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          vault: vaultPda,
+          funder: admin,
+          systemProgram: SystemProgram.programId,
         })
-        .rpc();
-    }, "MissingOrInvalidEd25519Ix");
-  });
-
-  it("hardening: set_pulse_signed message is frozen; wrong payload fails (Ed25519MessageMismatch)", async () => {
-    const adminKp = provider.wallet.payer;
-    const admin = adminKp.publicKey;
-
-    const oracle = Keypair.generate();
-    const relayer = Keypair.generate();
+        .rpc()
+    );
 
-    // fund relayer
-    {
-      const sig = await provider.connection.requestAirdrop(
-        relayer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await confirmSig(provider.connection, sig, "confirmed");
+    // wait until after reveal deadline
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
     }
 
-    const { configPda } = await ensureConfig(program, provider);
+    // ✅ finalize exige pulse_set = true -> setPulseMock antes
+    // Nota: setPulseMock exige haber pasado commitDeadline
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
 
     await rpcConfirmed(
       provider,
       program.methods
-        .setOraclePubkey(oracle.publicKey)
-        .accounts({ config: configPda, admin })
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xaa)))
+        .accounts({ config: configPda, round: roundPda, admin })
         .rpc()
     );
 
-    const roundId = (Math.floor(Date.now() / 1000) + 4447) % 1000000;
-    const pulseIndexTarget = 77777;
-
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const treasuryBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
+    const vaultBefore = await provider.connection.getBalance(vaultPda, "confirmed");
+    if (vaultBefore === 0) throw new Error("vault should have lamports before sweep");
 
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 8;
+    // finalize
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          admin,
+        })
+        .rpc()
+    );
 
+    // sweep -> ✅ ahora a treasurySol
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot + 200))
+        .sweepUnclaimed(new BN(roundId))
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
-          timlgVault: timlgVaultPda,
+          treasurySol: treasurySolPda, // ✅ NUEVO (lamports PDA)
           admin,
-          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
         .rpc()
     );
 
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
+    const treasuryAfter = await provider.connection.getBalance(treasurySolPda, "confirmed");
+    const vaultAfter = await provider.connection.getBalance(vaultPda, "confirmed");
 
-    const pulse = crypto.randomBytes(64);
+    if (vaultAfter !== 0) throw new Error("vault should be empty after sweep");
+    if (treasuryAfter <= treasuryBefore) throw new Error("treasury_sol balance did not increase after sweep");
 
-    // WRONG payload: pulseIndexTarget changed => msg mismatch
-    const wrongMsg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget + 1, pulse);
+    const r = await program.account.round.fetch(roundPda);
+    if (!r.finalized) throw new Error("round.finalized should be true");
+  });
 
-    const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: oracle.secretKey,
-      message: wrongMsg,
-    });
+  it("grace period prevents early sweep (SweepTooEarly)", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
 
-    const anchorIx = await program.methods
-      .setPulseSigned(new BN(roundId), Array.from(pulse))
+    // ✅ Usa ensureConfig para obtener treasurySolPda
+    const { configPda, treasurySolPda } = await ensureConfig(program, provider);
+
+    // ✅ set grace slots > 0
+    const graceSlots = 5;
+    const sigSetGrace = await program.methods
+      .setClaimGraceSlots(new BN(graceSlots))
+      .accounts({ config: configPda, admin })
+      .rpc();
+    await provider.connection.confirmTransaction(sigSetGrace, "confirmed");
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9999) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 8;
+    const revealDeadline = slot + 13;
+    const pulseIndexTarget = 1234;
+
+    // create round
+    const sigCreate = await program.methods
+      .createRound(
+        new BN(roundId),
+        new BN(pulseIndexTarget),
+        new BN(commitDeadline),
+        new BN(revealDeadline),
+        new BN(0),
+        NO_LABEL,
+        PublicKey.default,
+        new BN(0),
+        new BN(0),
+        1
+      )
       .accounts({
         config: configPda,
+        timlgMint: TIMLG_MINT,
         round: roundPda,
-        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        vault: vaultPda,
+        timlgVault: timlgVaultPda,
+        admin,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
-      .instruction();
-
-    await expectTxFail(async () => {
-      const tx = new Transaction().add(edIx, anchorIx);
-      tx.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
-
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-        commitment: "confirmed",
-      });
-    }, "Ed25519MessageMismatch");
-  });
-
-  it("hardening: set_pulse_signed wrong oracle pubkey fails (Ed25519PubkeyMismatch)", async () => {
-    const admin = provider.wallet.publicKey;
+      .rpc();
+    await provider.connection.confirmTransaction(sigCreate, "confirmed");
 
-    const oracle = Keypair.generate();
-    const wrongOracle = Keypair.generate();
-    const relayer = Keypair.generate();
+    // fund vault so sweep has something to move
+    const sigFund = await program.methods
+      .fundVault(new BN(roundId), new BN(200_000))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        vault: vaultPda,
+        funder: admin,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await provider.connection.confirmTransaction(sigFund, "confirmed");
 
-    // fund relayer
-    {
-      const sig = await provider.connection.requestAirdrop(
-        relayer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
-      );
-      await confirmSig(provider.connection, sig, "confirmed");
+    // wait until just after reveal deadline (so finalize is allowed)
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
     }
 
-    const { configPda } = await ensureConfig(program, provider);
+    // ✅ finalize exige pulse_set=true -> setPulseMock antes de finalize
+    // setPulseMock exige haber pasado commitDeadline
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
 
-    // config expects THIS oracle
     await rpcConfirmed(
       provider,
       program.methods
-        .setOraclePubkey(oracle.publicKey)
-        .accounts({ config: configPda, admin })
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xaa)))
+        .accounts({ config: configPda, round: roundPda, admin })
         .rpc()
     );
 
-    const roundId = (Math.floor(Date.now() / 1000) + 4450) % 1000000;
-    const pulseIndexTarget = 11111;
-
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    // finalize
+    const sigFinalize = await program.methods
+      .finalizeRound(new BN(roundId))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        admin,
+      })
+      .rpc();
+    await provider.connection.confirmTransaction(sigFinalize, "confirmed");
 
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 8;
+    // Read round from chain (source of truth) to compute sweep gate
+    let round = await program.account.round.fetch(roundPda, "confirmed");
+    const revealDeadlineOnchain = Number(round.revealDeadlineSlot);
+    const minSweepSlot = revealDeadlineOnchain + graceSlots;
 
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot + 200))
+    // ❌ sweep too early: should fail with SweepTooEarly
+    let threw = false;
+    try {
+      const sigEarly = await program.methods
+        .sweepUnclaimed(new BN(roundId))
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
           round: roundPda,
           vault: vaultPda,
-          timlgVault: timlgVaultPda,
+          treasurySol: treasurySolPda, // ✅ NUEVO
           admin,
-          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
-        .rpc()
-    );
+        .rpc();
 
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+      // even if it returns a sig, force confirm to surface program error
+      await provider.connection.confirmTransaction(sigEarly, "confirmed");
+    } catch (e) {
+      threw = true;
+      const msg = String(e);
+      if (!msg.includes("SweepTooEarly") && !msg.includes("grace period")) {
+        throw new Error("Expected SweepTooEarly, got: " + msg);
+      }
+    }
+    if (!threw) {
+      throw new Error("Expected sweep to fail during grace period, but it succeeded");
     }
 
-    const pulse = crypto.randomBytes(64);
-
-    // message is correct, but signer is wrongOracle
-    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
-
-    const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: wrongOracle.secretKey,
-      message: msg,
-    });
+    // ✅ wait until grace period elapsed (using on-chain computed minSweepSlot)
+    while ((await provider.connection.getSlot("confirmed")) <= minSweepSlot) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
 
-    const anchorIx = await program.methods
-      .setPulseSigned(new BN(roundId), Array.from(pulse))
+    // sweep should succeed
+    const sigSweep = await program.methods
+      .sweepUnclaimed(new BN(roundId))
       .accounts({
         config: configPda,
         round: roundPda,
-        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-      })
-      .instruction();
+        vault: vaultPda,
+        treasurySol: treasurySolPda, // ✅ NUEVO
+        admin,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await provider.connection.confirmTransaction(sigSweep, "confirmed");
+
+    const vaultAfter = await provider.connection.getBalance(vaultPda, "confirmed");
+    if (vaultAfter !== 0) throw new Error("vault should be empty after successful sweep");
+
+    // Fetch state at confirmed
+    round = await program.account.round.fetch(roundPda, "confirmed");
+
+    // ✅ robust checks (naming/typing safe)
+    const sweptSlot =
+      (round.sweptSlot ?? round.swept_slot ?? round.sweptslot ?? round.sweptSlot?.toNumber?.()) ??
+      (round.sweptSlot?.toNumber?.() ?? 0);
+
+    const sweptSlotNum =
+      typeof sweptSlot === "number"
+        ? sweptSlot
+        : sweptSlot?.toNumber
+          ? sweptSlot.toNumber()
+          : Number(sweptSlot ?? 0);
+
+    if (sweptSlotNum <= 0) {
+      throw new Error("round.swept_slot should be > 0 after sweep");
+    }
+
+    const sweptBool = round.swept ?? round.isSwept ?? round.sweptBool;
+    if (sweptBool === false) throw new Error("round.swept should be true after sweep");
+
+    // claim_reward now closes at reveal_deadline + grace: leave room for the claim tests below
+    const sigRestoreGrace = await program.methods
+      .setClaimGraceSlots(new BN(100))
+      .accounts({ config: configPda, admin })
+      .rpc();
+    await provider.connection.confirmTransaction(sigRestoreGrace, "confirmed");
+  });
+
+  it("commit → reveal → settle → claim_reward (balance checks, whitepaper-aligned)", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stakeBn = cfg.stakeAmount ?? cfg.stake_amount ?? cfg.stake ?? new BN(1);
+    const stake = BigInt(stakeBn.toString());
+
+    const before = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 2468) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+    const pulseIndexTarget = 4242;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // Two tickets: one WIN, one LOSE
+    const nonce1 = 111;
+    const guess1 = 1;
+    const salt1 = Buffer.alloc(32, 7);
+
+    const nonce2 = 222;
+    const guess2 = 0;
+    const salt2 = Buffer.alloc(32, 9);
+
+    const commitment1 = commitHash(roundId, admin, nonce1, guess1, salt1);
+    const commitment2 = commitHash(roundId, admin, nonce2, guess2, salt2);
+
+    const [ticket1Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce1)],
+      program.programId
+    );
+    const [ticket2Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce2)],
+      program.programId
+    );
+
+    // Commit: transfers 2*stake to timlgVault
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonce1), commitment: Array.from(commitment1) },
+          { nonce: new BN(nonce2), commitment: Array.from(commitment2) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda, // ✅ REQUIRED
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    const afterCommit = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    if (afterCommit !== before - 2n * stake) {
+      throw new Error(`afterCommit mismatch: got=${afterCommit} expected=${before - 2n * stake}`);
+    }
+
+    // Pulse such that ticket1 wins and ticket2 loses
+    const bitIndex1 = deriveBitIndex(roundId, admin, nonce1);
+    const bitIndex2 = deriveBitIndex(roundId, admin, nonce2);
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, bitIndex1, guess1);
+    setBit(pulse, bitIndex2, 1 - guess2);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [
+          { nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) },
+          { nonce: new BN(nonce2), guess: guess2, salt: Array.from(salt2) },
+        ])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    const supplyBeforeSettle = BigInt((await getMint(provider.connection, timlgMint, "confirmed")).supply.toString());
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    const supplyAfterSettle = BigInt((await getMint(provider.connection, timlgMint, "confirmed")).supply.toString());
+    if (supplyAfterSettle !== supplyBeforeSettle - stake) {
+      throw new Error(`supply after settle mismatch: got=${supplyAfterSettle} expected=${supplyBeforeSettle - stake}`);
+    }
+
+    // Claim winner: refund 1 + mint 1 -> back to before
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .claimReward(new BN(roundId), new BN(nonce1))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticket1Pda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          userTIMLGAta,
+
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+
+    const afterClaim = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    if (afterClaim !== before) {
+      throw new Error(`afterClaim mismatch: got=${afterClaim} expected=${before}`);
+    }
+  });
+
+  it("guess_bits: a 2-bit round reads two aligned pulse bits per ticket, commit to claim", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 8803) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+    const createRound = (guessBits) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), guessBits)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+
+    await expectTxFail(() => createRound(3), "InvalidGuessBits");
+    await createRound(2);
+    if ((await program.account.round.fetch(roundPda, "confirmed")).guessBits !== 2) {
+      throw new Error("round should be created as a 2-bit game");
+    }
+
+    // guess 2 (bits 0b10) wins, guess 3 loses, guess 4 does not fit in two bits
+    const plays = [
+      { nonce: 41, guess: 2 },
+      { nonce: 42, guess: 3 },
+      { nonce: 43, guess: 4 },
+    ].map((p) => ({
+      ...p,
+      salt: crypto.randomBytes(32),
+      ticket: deriveTicketPda(program.programId, roundId, admin, p.nonce),
+    }));
+    for (const p of plays) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitTicket(new BN(roundId), new BN(p.nonce), Array.from(commitHash(roundId, admin, p.nonce, p.guess, p.salt)))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            ticket: p.ticket,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+      );
+      // the bit index is aligned down so both bits stay inside the pulse
+      const raw = deriveBitIndex(roundId, admin, p.nonce);
+      const t = await program.account.ticket.fetch(p.ticket, "confirmed");
+      if (t.bitIndex !== raw - (raw % 2)) {
+        throw new Error(`bit_index should be ${raw - (raw % 2)}, got ${t.bitIndex}`);
+      }
+    }
+    const [win, lose, tooWide] = plays;
+
+    // outcome 0b10 at the winner's index (bit i of the outcome = pulse bit index + i); every
+    // other aligned pair reads 0b00 or 0b10, never 0b11
+    const pulse = Buffer.alloc(64, 0);
+    const winIndex = (await program.account.ticket.fetch(win.ticket, "confirmed")).bitIndex;
+    setBit(pulse, winIndex + 1, 1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    const reveal = (p) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .revealTicket(new BN(roundId), new BN(p.nonce), p.guess, Array.from(p.salt))
+          .accounts({ config: configPda, round: roundPda, ticket: p.ticket, user: admin })
+          .rpc()
+      );
+    await reveal(win);
+    await reveal(lose);
+    await expectTxFail(() => reveal(tooWide), "GuessOutOfRange");
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(plays.map((p) => ({ pubkey: p.ticket, isSigner: false, isWritable: true })))
+        .rpc()
+    );
+    for (const [p, expected] of [[win, true], [lose, false]]) {
+      if ((await program.account.ticket.fetch(p.ticket, "confirmed")).win !== expected) {
+        throw new Error(`guess ${p.guess} should ${expected ? "win" : "lose"} against outcome 2`);
+      }
+    }
+
+    const claim = (p) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .claimReward(new BN(roundId), new BN(p.nonce))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            ticket: p.ticket,
+            user: admin,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            userTIMLGAta,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc()
+      );
+    const balanceBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    await claim(win);
+    if ((await getTokenAmountRaw(provider.connection, userTIMLGAta)) <= balanceBefore) {
+      throw new Error("the 2-bit winner should be paid on claim");
+    }
+    await expectTxFail(() => claim(lose), "NotWinner");
+  });
+
+  it("claim_reward_to sends stake refund + reward to a delegated ATA", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt((cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1)).toString());
+
+    const custody = Keypair.generate().publicKey;
+    const custodyAta = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, provider.wallet.payer, timlgMint, custody)
+    ).address;
+
+    const roundId = (Math.floor(Date.now() / 1000) + 7373) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(4343), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce = 333;
+    const guess = 1;
+    const salt = Buffer.alloc(32, 5);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonce), commitment: Array.from(commitHash(roundId, admin, nonce, guess, salt)) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), guess);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [{ nonce: new BN(nonce), guess, salt: Array.from(salt) }])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const userBefore = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .claimRewardTo(new BN(roundId), new BN(nonce), custody)
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          destinationAta: custodyAta,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+
+    const userAfter = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    const custodyAfter = BigInt((await provider.connection.getTokenAccountBalance(custodyAta, "confirmed")).value.amount);
+
+    if (userAfter !== userBefore) {
+      throw new Error(`user ATA should be untouched: before=${userBefore} after=${userAfter}`);
+    }
+    if (custodyAfter <= stake) {
+      throw new Error(`custody ATA should get stake refund + reward: got=${custodyAfter} stake=${stake}`);
+    }
+  });
+
+  it("stake_in_sol: commit stakes lamports into the round vault and claim refunds them", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt((cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1)).toString());
+
+    const setStakeInSol = (flag) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setStakeInSol(flag).accounts({ config: configPda, admin }).rpc()
+      );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 6161) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    // the flag is snapshotted at creation, so it can be restored right away
+    await setStakeInSol(true);
+    try {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(roundId), new BN(6161), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+    } finally {
+      await setStakeInSol(false);
+    }
+
+    const roundAcc = await program.account.round.fetch(roundPda, "confirmed");
+    if (!roundAcc.stakeInSol) throw new Error("round.stake_in_sol should be snapshotted as true");
+
+    const nonce = 61;
+    const guess = 1;
+    const salt = Buffer.alloc(32, 6);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    const vaultBefore = BigInt(await provider.connection.getBalance(vaultPda, "confirmed"));
+    const tokensBefore = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, guess, salt)))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const vaultAfterCommit = BigInt(await provider.connection.getBalance(vaultPda, "confirmed"));
+    const tokensAfterCommit = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    if (vaultAfterCommit !== vaultBefore + stake) {
+      throw new Error(`vault lamports mismatch: got=${vaultAfterCommit} expected=${vaultBefore + stake}`);
+    }
+    if (tokensAfterCommit !== tokensBefore) throw new Error("SOL-staked commit must not move TIMLG");
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), guess);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [{ nonce: new BN(nonce), guess, salt: Array.from(salt) }])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .claimReward(new BN(roundId), new BN(nonce))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          userTIMLGAta,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+
+    const vaultAfterClaim = BigInt(await provider.connection.getBalance(vaultPda, "confirmed"));
+    if (vaultAfterClaim !== vaultBefore) {
+      throw new Error(`stake should be refunded from vault: got=${vaultAfterClaim} expected=${vaultBefore}`);
+    }
+  });
+
+  it("stake_in_sol: sweep_sol sends unclaimed SOL stakes to treasury_sol and only the rent to the admin", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+
+    const roundId = (Math.floor(Date.now() / 1000) + 6163) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 16;
+    const claimGrace = 2;
+
+    await rpcConfirmed(
+      provider,
+      program.methods.setStakeInSol(true).accounts({ config: configPda, admin }).rpc()
+    );
+    try {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(roundId), new BN(6163), new BN(commitDeadline), new BN(revealDeadline), new BN(claimGrace), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods.setStakeInSol(false).accounts({ config: configPda, admin }).rpc()
+      );
+    }
+
+    // the admin funds the vault rent; the winner's stake lands on top and is never claimed
+    const rentFloor = BigInt(await provider.connection.getMinimumBalanceForRentExemption(0));
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .fundVault(new BN(roundId), new BN(rentFloor.toString()))
+        .accounts({ config: configPda, round: roundPda, vault: vaultPda, funder: admin, systemProgram: SystemProgram.programId })
+        .rpc()
+    );
+    const nonce = 63;
+    const guess = 1;
+    const salt = Buffer.alloc(32, 63);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, guess, salt)))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), guess);
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [{ nonce: new BN(nonce), guess, salt: Array.from(salt) }])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    const claimDeadline = Number((await program.account.round.fetch(roundPda, "confirmed")).claimDeadlineSlot);
+    while ((await provider.connection.getSlot("confirmed")) <= claimDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const vaultBefore = BigInt(await provider.connection.getBalance(vaultPda, "confirmed"));
+    if (vaultBefore !== rentFloor + stake) {
+      throw new Error(`vault should hold rent + one stake, got ${vaultBefore}`);
+    }
+    const treasuryBefore = BigInt(await provider.connection.getBalance(treasurySolPda, "confirmed"));
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .sweepSol(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, vault: vaultPda, treasurySol: treasurySolPda, admin, systemProgram: SystemProgram.programId })
+        .rpc()
+    );
+    const treasuryGain = BigInt(await provider.connection.getBalance(treasurySolPda, "confirmed")) - treasuryBefore;
+    if (treasuryGain !== stake) throw new Error(`treasury_sol should get the unclaimed stake ${stake}, got ${treasuryGain}`);
+    if ((await provider.connection.getBalance(vaultPda, "confirmed")) !== 0) throw new Error("the vault should be drained");
+  });
+
+  it("no-reveal stake goes to treasury SPL (settle)", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stakeBn = cfg.stakeAmount ?? cfg.stake_amount ?? cfg.stake ?? new BN(1);
+    const stake = BigInt(stakeBn.toString());
+
+    const roundId = (Math.floor(Date.now() / 1000) + 8642) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce1 = 101;
+    const guess1 = 1;
+    const salt1 = Buffer.alloc(32, 3);
+
+    const nonce2 = 202; // no-reveal
+    const guess2 = 0;
+    const salt2 = Buffer.alloc(32, 5);
+
+    const commitment1 = commitHash(roundId, admin, nonce1, guess1, salt1);
+    const commitment2 = commitHash(roundId, admin, nonce2, guess2, salt2);
+
+    const [ticket1Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce1)],
+      program.programId
+    );
+    const [ticket2Pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), leU64(roundId), admin.toBytes(), leU64(nonce2)],
+      program.programId
+    );
+
+    const treBefore = BigInt((await provider.connection.getTokenAccountBalance(treasuryPda, "confirmed")).value.amount);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonce1), commitment: Array.from(commitment1) },
+          { nonce: new BN(nonce2), commitment: Array.from(commitment2) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda, // ✅ REQUIRED
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    // pulse: ticket1 wins
+    const bitIndex1 = deriveBitIndex(roundId, admin, nonce1);
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, bitIndex1, guess1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // reveal only ticket1
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [{ nonce: new BN(nonce1), guess: guess1, salt: Array.from(salt1) }])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([{ pubkey: ticket1Pda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    const treAfter = BigInt((await provider.connection.getTokenAccountBalance(treasuryPda, "confirmed")).value.amount);
+    if (treAfter !== treBefore + stake) {
+      throw new Error(`treasury did not receive no-reveal stake: got=${treAfter} expected=${treBefore + stake}`);
+    }
+  });
+
+  it("unrevealed_policy DefaultGuessZero: an unrevealed ticket wins at settle if its bit is 0, minus the penalty", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 1813) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(1813), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // two unrevealed tickets on distinct bits: one lands on a 0 bit, the other on a 1 bit
+    const nonceWin = 1;
+    let nonceLose = 2;
+    while (deriveBitIndex(roundId, admin, nonceLose) === deriveBitIndex(roundId, admin, nonceWin)) nonceLose++;
+    const ticketWinPda = deriveTicketPda(program.programId, roundId, admin, nonceWin);
+    const ticketLosePda = deriveTicketPda(program.programId, roundId, admin, nonceLose);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonceWin), commitment: Array.from(commitHash(roundId, admin, nonceWin, 1, Buffer.alloc(32, 7))) },
+          { nonce: new BN(nonceLose), commitment: Array.from(commitHash(roundId, admin, nonceLose, 0, Buffer.alloc(32, 8))) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: ticketWinPda, isSigner: false, isWritable: true },
+          { pubkey: ticketLosePda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonceLose), 1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const setPolicy = (policy, penaltyBps) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .setUnrevealedPolicy(policy, penaltyBps)
+          .accounts({ config: configPda, tokenomics: tokenomicsPda, admin })
+          .rpc()
+      );
+
+    await setPolicy(3, 2000);
+    try {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            treasury: treasuryPda,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([
+            { pubkey: ticketWinPda, isSigner: false, isWritable: true },
+            { pubkey: ticketLosePda, isSigner: false, isWritable: true },
+          ])
+          .rpc()
+      );
+    } finally {
+      await setPolicy(tok.unrevealedPolicy, tok.unrevealedPenaltyBps);
+    }
+
+    const tWin = await program.account.ticket.fetch(ticketWinPda, "confirmed");
+    const tLose = await program.account.ticket.fetch(ticketLosePda, "confirmed");
+    if (!tWin.win || tWin.guess !== 0) throw new Error("unrevealed ticket on a 0 bit should win with guess 0");
+    if (tLose.win || !tLose.stakeSlashed) throw new Error("unrevealed ticket on a 1 bit should lose");
+
+    const r = await program.account.round.fetch(roundPda, "confirmed");
+    if (Number(r.winCount) !== 1) throw new Error(`winCount ${r.winCount}, expected 1`);
+
+    // the default-zero win gets its stake back minus the 20% unrevealed penalty
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const refund = BigInt(cfg.stakeAmount.toString()) - (BigInt(cfg.stakeAmount.toString()) * 2000n) / 10000n;
+    if (BigInt(r.unrevealedRefund.toString()) !== refund) throw new Error(`unrevealedRefund ${r.unrevealedRefund}, expected ${refund}`);
+    const vaultBefore = BigInt((await provider.connection.getTokenAccountBalance(timlgVaultPda, "confirmed")).value.amount);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .claimReward(new BN(roundId), new BN(nonceWin))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketWinPda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          userTIMLGAta,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    const vaultAfter = BigInt((await provider.connection.getTokenAccountBalance(timlgVaultPda, "confirmed")).value.amount);
+    if (r.rewardMode === 0 && vaultBefore - vaultAfter !== refund) {
+      throw new Error(`claim should refund ${refund} from the vault, got ${vaultBefore - vaultAfter}`);
+    }
+  });
+
+  it("slash_to_pool: loser stake goes to replication_pool instead of being burned", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt((cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1)).toString());
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const feeBps = tok.rewardFeeBps ?? tok.reward_fee_bps;
+
+    const setSlashToPool = (flag) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .updateTokenomics(feeBps, flag, null)
+          .accounts({ config: configPda, tokenomics: tokenomicsPda, admin })
+          .rpc()
+      );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 8653) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // ticket1 wins, ticket2 reveals the wrong bit and loses
+    const nonce1 = 111;
+    const nonce2 = 222;
+    const salt1 = Buffer.alloc(32, 11);
+    const salt2 = Buffer.alloc(32, 22);
+    const ticket1Pda = deriveTicketPda(program.programId, roundId, admin, nonce1);
+    const ticket2Pda = deriveTicketPda(program.programId, roundId, admin, nonce2);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(new BN(roundId), [
+          { nonce: new BN(nonce1), commitment: Array.from(commitHash(roundId, admin, nonce1, 1, salt1)) },
+          { nonce: new BN(nonce2), commitment: Array.from(commitHash(roundId, admin, nonce2, 0, salt2)) },
+        ])
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce1), 1);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce2), 1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [
+          { nonce: new BN(nonce1), guess: 1, salt: Array.from(salt1) },
+          { nonce: new BN(nonce2), guess: 0, salt: Array.from(salt2) },
+        ])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([
+          { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+          { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+        ])
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await setSlashToPool(true);
+    try {
+      const poolBefore = BigInt((await provider.connection.getTokenAccountBalance(replicationPoolPda, "confirmed")).value.amount);
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            treasury: treasuryPda,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([
+            { pubkey: ticket1Pda, isSigner: false, isWritable: true },
+            { pubkey: ticket2Pda, isSigner: false, isWritable: true },
+          ])
+          .rpc()
+      );
+
+      const poolAfter = BigInt((await provider.connection.getTokenAccountBalance(replicationPoolPda, "confirmed")).value.amount);
+      if (poolAfter !== poolBefore + stake) {
+        throw new Error(`replication_pool should grow by the loser stake: got=${poolAfter} expected=${poolBefore + stake}`);
+      }
+    } finally {
+      await setSlashToPool(false);
+    }
+  });
+
+  it("prefund_fees: settle mints the winners' fees once, claim skips its fee mint", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt((cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1)).toString());
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const feeBps = tok.rewardFeeBps ?? tok.reward_fee_bps;
+    const slashToPool = tok.slashToPool ?? tok.slash_to_pool;
+    const toReplicationBps = BigInt(tok.feeToReplicationBps ?? tok.fee_to_replication_bps ?? 0);
+
+    const setTokenomics = (bps, prefund) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .updateTokenomics(bps, slashToPool, null)
+          .accounts({ config: configPda, tokenomics: tokenomicsPda, admin })
+          .rpc()
+      ).then(() =>
+        rpcConfirmed(
+          provider,
+          program.methods
+            .setPrefundFees(prefund)
+            .accounts({ config: configPda, tokenomics: tokenomicsPda, admin })
+            .rpc()
+        )
+      );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 8761) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce = 333;
+    const salt = Buffer.alloc(32, 33);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, salt)))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), 1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), 1, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const bps = 500;
+    const fee = (stake * BigInt(bps)) / 10_000n;
+    const poolFee = fee - (fee * toReplicationBps) / 10_000n;
+
+    await setTokenomics(bps, true);
+    try {
+      const poolBefore = await getTokenAmountRaw(provider.connection, rewardFeePoolPda);
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            treasury: treasuryPda,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+          .rpc()
+      );
+
+      const poolAfterSettle = await getTokenAmountRaw(provider.connection, rewardFeePoolPda);
+      if (poolAfterSettle !== poolBefore + poolFee) {
+        throw new Error(`settle should prefund the fee: got=${poolAfterSettle} expected=${poolBefore + poolFee}`);
+      }
+
+      const r = await program.account.round.fetch(roundPda, "confirmed");
+      if (!(r.feesPrefunded ?? r.fees_prefunded)) throw new Error("round should be marked fees_prefunded");
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .claimReward(new BN(roundId), new BN(nonce))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            ticket: ticketPda,
+            user: admin,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            userTIMLGAta,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc()
+      );
+
+      const poolAfterClaim = await getTokenAmountRaw(provider.connection, rewardFeePoolPda);
+      if (poolAfterClaim !== poolAfterSettle) {
+        throw new Error(`claim must not mint the fee again: got=${poolAfterClaim} expected=${poolAfterSettle}`);
+      }
+    } finally {
+      await setTokenomics(feeBps, false);
+    }
+  });
+
+  it("max_total_reward: a claim over the round's reward cap only refunds the stake", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt((cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1)).toString());
+
+    const roundId = (Math.floor(Date.now() / 1000) + 8779) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    // cap below a single reward: nothing can be minted for this round
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(1), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce = 444;
+    const salt = Buffer.alloc(32, 44);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, salt)))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), 1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), 1, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    const before = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .claimReward(new BN(roundId), new BN(nonce))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          userTIMLGAta,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+
+    const after = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    if (after - before !== stake) {
+      throw new Error(`capped claim should refund only the stake: got=${after - before} expected=${stake}`);
+    }
+
+    const r = await program.account.round.fetch(roundPda, "confirmed");
+    const mintedTotal = BigInt((r.rewardMintedTotal ?? r.reward_minted_total).toString());
+    if (mintedTotal !== 0n) throw new Error(`nothing should be minted under the cap, got ${mintedTotal}`);
+  });
+
+  it("prefund_fees + max_total_reward: settle does not prefund a capped round's fees", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt((cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1)).toString());
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const feeBps = tok.rewardFeeBps ?? tok.reward_fee_bps;
+    const slashToPool = tok.slashToPool ?? tok.slash_to_pool;
+
+    const setTokenomics = (bps, prefund) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .updateTokenomics(bps, slashToPool, null)
+          .accounts({ config: configPda, tokenomics: tokenomicsPda, admin })
+          .rpc()
+      ).then(() =>
+        rpcConfirmed(
+          provider,
+          program.methods
+            .setPrefundFees(prefund)
+            .accounts({ config: configPda, tokenomics: tokenomicsPda, admin })
+            .rpc()
+        )
+      );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 8783) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    // cap below a single reward: the winner only gets the stake back, so it owes no fee
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(1), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce = 445;
+    const salt = Buffer.alloc(32, 45);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, salt)))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), 1);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), 1, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await setTokenomics(500, true);
+    try {
+      const poolsBefore =
+        (await getTokenAmountRaw(provider.connection, rewardFeePoolPda)) +
+        (await getTokenAmountRaw(provider.connection, replicationPoolPda));
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            treasury: treasuryPda,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+          .rpc()
+      );
+
+      const r = await program.account.round.fetch(roundPda, "confirmed");
+      if (r.feesPrefunded ?? r.fees_prefunded) throw new Error("a capped round must not be prefunded");
+
+      const before = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .claimReward(new BN(roundId), new BN(nonce))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            ticket: ticketPda,
+            user: admin,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            userTIMLGAta,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc()
+      );
+      const after = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      if (after - before !== stake) {
+        throw new Error(`capped claim should refund only the stake: got=${after - before} expected=${stake}`);
+      }
+
+      // no fee was minted at settle or at claim, and nothing was booked against the cap
+      const poolsAfter =
+        (await getTokenAmountRaw(provider.connection, rewardFeePoolPda)) +
+        (await getTokenAmountRaw(provider.connection, replicationPoolPda));
+      if (poolsAfter !== poolsBefore) {
+        throw new Error(`no fee should be minted for a capped round: got=${poolsAfter - poolsBefore}`);
+      }
+      const claimed = await program.account.round.fetch(roundPda, "confirmed");
+      const mintedTotal = BigInt((claimed.rewardMintedTotal ?? claimed.reward_minted_total).toString());
+      if (mintedTotal !== 0n) throw new Error(`nothing should be minted under the cap, got ${mintedTotal}`);
+    } finally {
+      await setTokenomics(feeBps, false);
+    }
+  });
+
+  it("withdraw_replication_pool with amount 0 drains the pool to the admin ATA", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const poolBefore = await getTokenAmountRaw(provider.connection, replicationPoolPda);
+    const adminBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .withdrawReplicationPool(new BN(0))
+        .accounts({
+          config: configPda,
+          tokenomics: tokenomicsPda,
+          replicationPool: replicationPoolPda,
+          destination: userTIMLGAta,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+
+    const poolAfter = await getTokenAmountRaw(provider.connection, replicationPoolPda);
+    const adminAfter = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+
+    if (poolAfter !== 0n) throw new Error(`replication_pool should be drained, got ${poolAfter}`);
+    if (adminAfter - adminBefore !== poolBefore) {
+      throw new Error(`admin should receive ${poolBefore}, got ${adminAfter - adminBefore}`);
+    }
+  });
+
+  it("hardening: replayed commit_batch_signed fails with TicketAlreadyExists", async () => {
+    const adminKp = provider.wallet.payer;
+    const userKp = adminKp;
+    const user = userKp.publicKey;
+
+    const relayer = anchor.web3.Keypair.generate();
+    const sigAirdrop = await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000);
+    await provider.connection.confirmTransaction(sigAirdrop, "confirmed");
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    // round
+    const roundId = (Math.floor(Date.now() / 1000) + 9901) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 8;
+    const revealDeadline = slot + 20;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(123), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin: user,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // escrow exists + fund it (1 entry)
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint: TIMLG_MINT,
+      userPk: user,
+    });
+
+    await ensureEscrowFunds(program, provider, {
+      configPda,
+      timlgMint: TIMLG_MINT,
+      userPk: user,
+      userEscrowPda,
+      userEscrowAtaPda,
+      userTIMLGAta: USER_TIMLG_ATA,
+      neededRaw: 1n,
+    });
+
+    const nonce = 777;
+    const salt = crypto.randomBytes(32);
+    const guess = 1;
+    const commitment = commitHash(roundId, user, nonce, guess, salt);
+
+    const entries = [{ user, nonce: new BN(nonce), commitment: Array.from(commitment) }];
+    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
+
+    const msg = expectedCommitMsgV3(program.programId, roundId, user, nonce, commitment, commitDeadline, revealDeadline, 0);
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: userKp.secretKey,
+      message: msg,
+    });
+
+    const progIx = await program.methods
+      .commitBatchSigned(new BN(roundId), entries, null)
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        timlgMint: TIMLG_MINT,
+        timlgVault: timlgVaultPda,
+        payer: relayer.publicKey,
+        userEscrow: userEscrowPda,
+        userEscrowAta: userEscrowAtaPda,
+        user,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
+      .instruction();
+
+    // 0) a relayer bound already in the past fails fast, before any transfer
+    await expectTxFail(async () => {
+      const staleSlot = (await provider.connection.getSlot("confirmed")) - 1;
+      const staleIx = await program.methods
+        .commitBatchSigned(new BN(roundId), entries, new BN(staleSlot))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint: TIMLG_MINT,
+          timlgVault: timlgVaultPda,
+          payer: relayer.publicKey,
+          userEscrow: userEscrowPda,
+          userEscrowAta: userEscrowAtaPda,
+          user,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
+        .instruction();
+      const tx0 = new Transaction().add(edIx, staleIx);
+      tx0.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx0.recentBlockhash = blockhash;
+      tx0.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx0, [relayer], { commitment: "confirmed" });
+    }, "CommitLandedTooLate");
+
+    // 1) first tx ok
+    {
+      const tx = new Transaction().add(edIx, progIx);
+      tx.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], { commitment: "confirmed" });
+    }
+
+    // 2) replay same tx should fail with TicketAlreadyExists
+    await expectTxFail(async () => {
+      const edIx2 = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: userKp.secretKey,
+        message: msg,
+      });
+      const tx2 = new Transaction().add(edIx2, progIx);
+      tx2.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx2.recentBlockhash = blockhash;
+      tx2.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx2, [relayer], { commitment: "confirmed" });
+    }, "TicketAlreadyExists");
+  });
+
+  it("hardening: settle rejects calls only after fully settled (RoundTokensAlreadySettled) and claim is one-shot (AlreadyClaimed)", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stakeBn = cfg.stakeAmount ?? cfg.stake_amount ?? new BN(1);
+    const stake = BigInt(stakeBn.toString());
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9902) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 18;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(555), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // commit 1 ticket
+    const nonce = 1;
+    const guess = 1;
+    const salt = crypto.randomBytes(32);
+    const commitment = commitHash(roundId, admin, nonce, guess, salt);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    const before = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const afterCommit = BigInt((await provider.connection.getTokenAccountBalance(userTIMLGAta, "confirmed")).value.amount);
+    if (afterCommit !== before - stake) throw new Error("commit stake mismatch");
+
+    // wait >= commitDeadline then set pulse so ticket wins
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+    const bitIndex = deriveBitIndex(roundId, admin, nonce);
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, bitIndex, guess);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // reveal
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), guess, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc()
+    );
+
+    // wait > revealDeadline then finalize
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // settle once OK
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    // settle again must fail
+    await expectTxFail(async () => {
+      await program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc();
+    }, "RoundTokensAlreadySettled");
+
+    // claims are blocked while the protocol is paused
+    await rpcConfirmed(
+      provider,
+      program.methods.setPause(true).accounts({ config: configPda, admin }).rpc()
+    );
+    try {
+      await expectTxFail(async () => {
+        await program.methods
+          .claimReward(new BN(roundId), new BN(nonce))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            ticket: ticketPda,
+            user: admin,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            userTIMLGAta,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+      }, "Paused");
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods.setPause(false).accounts({ config: configPda, admin }).rpc()
+      );
+    }
+
+    // claim once OK (refund + mint)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .claimReward(new BN(roundId), new BN(nonce))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticket1Pda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          userTIMLGAta,
+
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+
+    // claim again must fail
+    await expectTxFail(async () => {
+      await program.methods
+        .claimReward(new BN(roundId), new BN(nonce))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticket1Pda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          userTIMLGAta,
+
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }, "AlreadyClaimed");
+  });
+
+  it("hardening: settle rejects malformed ticket accounts (TicketSizeMismatch / TicketPdaMismatch)", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda } = await ensureBoot(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 1807) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(555), new BN(commitDeadline), new BN(slot0 + 18), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0)))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    const settleWith = (pubkey) =>
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey, isSigner: false, isWritable: true }])
+        .rpc();
+
+    // The round account is program-owned but not ticket-sized: rejected before deserializing.
+    await expectTxFail(() => settleWith(roundPda), "TicketSizeMismatch");
+
+    // Spoofed accounts: anyone can allocate zeroed data and assign it to the program.
+    const spoof = async (space) => {
+      const kp = Keypair.generate();
+      const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: admin,
+            newAccountPubkey: kp.publicKey,
+            lamports,
+            space,
+            programId: program.programId,
+          })
+        ),
+        [kp],
+        { commitment: "confirmed" }
+      );
+      return kp.publicKey;
+    };
+    const ticketSize = program.account.ticket.size;
+    await expectTxFail(async () => settleWith(await spoof(ticketSize - 5)), "TicketSizeMismatch");
+    await expectTxFail(async () => settleWith(await spoof(ticketSize + 1)), "TicketSizeMismatch");
+    // Known sizes (current and pre-weight) get past the size check but not the discriminator.
+    await expectTxFail(async () => settleWith(await spoof(ticketSize)), "TicketPdaMismatch");
+    await expectTxFail(async () => settleWith(await spoof(ticketSize - 1)), "TicketPdaMismatch");
+    // Not owned by the program at all.
+    await expectTxFail(() => settleWith(Keypair.generate().publicKey), "TicketNotOwnedByProgram");
+  });
+
+  it("hardening: claim is rejected after sweep (ClaimAfterSweep), close_ticket still works", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9903) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 18;
+
+    // create round
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // commit
+    const nonce = 9;
+    const guess = 1;
+    const salt = crypto.randomBytes(32);
+    const commitment = commitHash(roundId, admin, nonce, guess, salt);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    // wait until commit window ends
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // set pulse (make ticket win)
+    const bitIndex = deriveBitIndex(roundId, admin, nonce);
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, bitIndex, guess);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // reveal
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), guess, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc()
+    );
+
+    // wait until reveal deadline passed, then finalize
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // settle tokens (so claim would normally be allowed)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    // --- IMPORTANT: wait using on-chain reveal_deadline_slot + cfg.claim_grace_slots ---
+    const roundAcc = await program.account.round.fetch(roundPda, "confirmed");
+    const revealDlOnChain = Number(
+      roundAcc.revealDeadlineSlot ?? roundAcc.reveal_deadline_slot
+    );
+
+    const cfgAcc = await program.account.config.fetch(configPda, "confirmed");
+    const graceBn =
+      (cfgAcc.claimGraceSlots ??
+        cfgAcc.claim_grace_slots ??
+        new BN(0));
+
+    const grace = Number(graceBn.toString());
+
+    // sweep_unclaimed requires current_slot > reveal_deadline_slot + claim_grace_slots
+    const targetSlot = revealDlOnChain + grace + 1;
+
+    while ((await provider.connection.getSlot("confirmed")) < targetSlot) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // sweep
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .sweepUnclaimed(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          vault: vaultPda,
+          treasurySol: treasurySolPda,
+          admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    // claim after sweep must fail
+    await expectTxFail(async () => {
+      await program.methods
+        .claimReward(new BN(roundId), new BN(nonce))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }, "ClaimAfterSweep");
+
+    // reveal after sweep is rejected with its own error (before RoundFinalized)
+    await expectTxFail(async () => {
+      await program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), guess, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc();
+    }, "RevealAfterSweep");
+
+    // ...but the stranded winner can still close the ticket and reclaim its rent
+    const [userStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stats_v3"), admin.toBytes()],
+      program.programId
+    );
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .closeTicket(new BN(roundId), new BN(nonce))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          userStats: userStatsPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+    const info = await provider.connection.getAccountInfo(ticketPda, "confirmed");
+    if (info !== null) throw new Error("swept winner ticket should be closable");
+  });
+
+  it("hardening: reveal_batch_signed rejects mixed users (SignedBatchMixedUsers)", async () => {
+    const admin = provider.wallet.publicKey;
+
+    // userA = wallet, userB = random pubkey (no hace falta que tenga fondos)
+    const userA = admin;
+    const userB = anchor.web3.Keypair.generate().publicKey;
+
+    // payer/relayer (solo para firmar el ix; fee payer será la wallet por defecto)
+    const relayer = anchor.web3.Keypair.generate();
+
+    const { configPda, timlgMint } = await ensureConfig(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9920) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 6;
+    const revealDeadline = slot0 + 200; // grande para que no cierre
+
+    // create round
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(111), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // wait end commit window (si tu setPulseMock lo exige)
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // set pulse mock -> imprescindible porque reveal_batch_signed requiere round.pulse_set
+    const pulse = Buffer.alloc(64, 0);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // Mixed entries (A y B en el mismo batch) => debe fallar ANTES de chequear ed25519/tickets
+    const entries = [
+      {
+        user: userA,
+        nonce: new BN(1),
+        guess: 1,
+        salt: Array.from(Buffer.alloc(32, 1)),
+      },
+      {
+        user: userB,
+        nonce: new BN(2),
+        guess: 0,
+        salt: Array.from(Buffer.alloc(32, 2)),
+      },
+    ];
+
+    // remainingAccounts debe tener la misma longitud que entries (aunque no se use por el early-fail)
+    const dummy1 = anchor.web3.Keypair.generate().publicKey;
+    const dummy2 = anchor.web3.Keypair.generate().publicKey;
+
+    await expectTxFail(async () => {
+      await program.methods
+        .revealBatchSigned(new BN(roundId), entries)
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          payer: relayer.publicKey,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: dummy1, isWritable: true, isSigner: false },
+          { pubkey: dummy2, isWritable: true, isSigner: false },
+        ])
+        .signers([relayer])
+        .rpc();
+    }, "SignedBatchMixedUsers");
+  });
+
+  it("hardening: reveal_batch_signed replay fails with AlreadyRevealed", async () => {
+    const userKp = provider.wallet.payer;
+    const user = userKp.publicKey;
+
+    const relayer = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(relayer.publicKey, 2_000_000_000),
+      "confirmed"
+    );
+
+    const { configPda, timlgMint, userTIMLGAta } = await ensureConfig(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9921) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 200;
+
+    // create round
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(222), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin: user,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // commit 1 ticket (no signed)
+    const nonce = 7;
+    const guess = 1;
+    const salt = crypto.randomBytes(32);
+    const commitment = commitHash(roundId, user, nonce, guess, salt);
+    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    // wait commit window end
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // set pulse mock so ticket wins (opcional para este test, pero consistente)
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, user, nonce), guess);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin: user })
+        .rpc()
+    );
+
+    // build reveal_batch_signed tx (ed25519 ix must be immediately before the program ix)
+    const entries = [{ user, nonce: new BN(nonce), guess, salt: Array.from(salt) }];
+
+    const msg = expectedRevealMsg(program.programId, roundId, user, nonce, guess, salt);
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: userKp.secretKey,
+      message: msg,
+    });
+
+    const progIx = await program.methods
+      .revealBatchSigned(new BN(roundId), entries)
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        payer: relayer.publicKey,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([{ pubkey: ticketPda, isWritable: true, isSigner: false }])
+      .instruction();
+
+    // 1) first reveal ok
+    {
+      const tx = new Transaction().add(edIx, progIx);
+      tx.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+        commitment: "confirmed",
+      });
+    }
+
+    // 2) replay reveal must fail with AlreadyRevealed
+    await expectTxFail(async () => {
+      const edIx2 = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: userKp.secretKey,
+        message: msg,
+      });
+
+      const tx2 = new Transaction().add(edIx2, progIx);
+      tx2.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx2.recentBlockhash = blockhash;
+      tx2.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx2, [relayer], {
+        commitment: "confirmed",
+      });
+    }, "AlreadyRevealed");
+  });
+
+  it("hardening: set_pulse_signed replay is rejected (PulseAlreadySet)", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+
+    const oracle = Keypair.generate();
+    const relayer = Keypair.generate();
+
+    // fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    // set oracle pubkey (admin only)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 4444) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 10;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(9999), new BN(commitDeadline), new BN(slot + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // wait past commit deadline
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const pulse = crypto.randomBytes(64);
+
+    // --- first set_pulse_signed succeeds ---
+    const msg = expectedPulseMsg(program.programId, roundId, 9999, pulse);
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: oracle.secretKey,
+      message: msg,
+    });
+
+    const anchorIx = await program.methods
+      .setPulseSigned(new BN(roundId), Array.from(pulse))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    {
+      const tx = new Transaction().add(edIx, anchorIx);
+      tx.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+        commitment: "confirmed",
+      });
+    }
+
+    // --- replay: should fail BEFORE ed25519 checks with PulseAlreadySet ---
+    await expectTxFail(async () => {
+      await program.methods
+        .setPulseSigned(new BN(roundId), Array.from(pulse))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .rpc();
+    }, "PulseAlreadySet");
+  });
+
+  it("hardening: set_pulse_signed without ed25519 ix fails (MissingOrInvalidEd25519Ix)", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+
+    const oracle = Keypair.generate();
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 4445) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 8;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(12345), new BN(commitDeadline), new BN(slot + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const pulse = crypto.randomBytes(64);
+
+    await expectTxFail(async () => {
+      // no ed25519 instruction in the tx
+      await program.methods
+        .setPulseSigned(new BN(roundId), Array.from(pulse))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .rpc();
+    }, "MissingOrInvalidEd25519Ix");
+  });
+
+  it("hardening: set_pulse_signed message is frozen; wrong payload fails (Ed25519MessageMismatch)", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+
+    const oracle = Keypair.generate();
+    const relayer = Keypair.generate();
+
+    // fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 4447) % 1000000;
+    const pulseIndexTarget = 77777;
+
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 8;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const pulse = crypto.randomBytes(64);
+
+    // WRONG payload: pulseIndexTarget changed => msg mismatch
+    const wrongMsg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget + 1, pulse);
+
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: oracle.secretKey,
+      message: wrongMsg,
+    });
+
+    const anchorIx = await program.methods
+      .setPulseSigned(new BN(roundId), Array.from(pulse))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    await expectTxFail(async () => {
+      const tx = new Transaction().add(edIx, anchorIx);
+      tx.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+        commitment: "confirmed",
+      });
+    }, "Ed25519MessageMismatch");
+  });
+
+  it("hardening: set_pulse_signed wrong oracle pubkey fails (Ed25519PubkeyMismatch)", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const oracle = Keypair.generate();
+    const wrongOracle = Keypair.generate();
+    const relayer = Keypair.generate();
+
+    // fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    // config expects THIS oracle
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 4450) % 1000000;
+    const pulseIndexTarget = 11111;
+
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 8;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const pulse = crypto.randomBytes(64);
+
+    // message is correct, but signer is wrongOracle
+    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
+
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: wrongOracle.secretKey,
+      message: msg,
+    });
+
+    const anchorIx = await program.methods
+      .setPulseSigned(new BN(roundId), Array.from(pulse))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    await expectTxFail(async () => {
+      const tx = new Transaction().add(edIx, anchorIx);
+      tx.feePayer = relayer.publicKey;
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+        commitment: "confirmed",
+      });
+    }, "Ed25519PubkeyMismatch");
+  });
+ 
+  it("set_pulse_signed accepts the ed25519 ix with a compute-budget ix interleaved", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const oracle = Keypair.generate();
+    const relayer = Keypair.generate();
+
+    // fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracle.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 4451) % 1000000;
+    const pulseIndexTarget = 22222;
+
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 8;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    const pulse = crypto.randomBytes(64);
+
+    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
+
+    const edIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: oracle.secretKey,
+      message: msg,
+    });
+
+    // compute-budget instruction between ed25519 and anchor ix
+    const cuIx = ComputeBudgetProgram.setComputeUnitLimit({ units: 300_000 });
+
+    const anchorIx = await program.methods
+      .setPulseSigned(new BN(roundId), Array.from(pulse))
+      .accounts({
+        config: configPda,
+        round: roundPda,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    const tx = new Transaction().add(edIx, cuIx, anchorIx);
+    tx.feePayer = relayer.publicKey;
+    const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+    tx.recentBlockhash = blockhash;
+    tx.sign(relayer);
+
+    await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+      commitment: "confirmed",
+    });
+
+    const r = await program.account.round.fetch(roundPda);
+    if (!r.pulseSet) throw new Error("pulse should be set");
+    if (Buffer.from(r.pulse).compare(pulse) !== 0) throw new Error("pulse mismatch");
+  });
+
+  it("oracle rotation: old oracle can no longer set_pulse_signed after update", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+
+    const oracleOld = Keypair.generate();
+    const oracleNew = Keypair.generate();
+    const relayer = Keypair.generate();
+
+    // Fund relayer
+    {
+      const sig = await provider.connection.requestAirdrop(
+        relayer.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await confirmSig(provider.connection, sig, "confirmed");
+    }
+
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    await ensureConfig(program, provider, configPda);
+
+    // 1) Set OLD oracle pubkey
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracleOld.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    // Create round
+    const roundId = (Math.floor(Date.now() / 1000) + 777) % 1000000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot + 10;
+    const revealDeadline = slot + 200;
+    const pulseIndexTarget = 111111;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(
+          new BN(roundId),
+          new BN(pulseIndexTarget),
+          new BN(commitDeadline),
+          new BN(revealDeadline),
+          new BN(0),
+          NO_LABEL,
+          PublicKey.default,
+          new BN(0),
+          new BN(0),
+          1
+        )
+        .accounts({
+          config: configPda,
+          timlgMint: TIMLG_MINT,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // Wait past commit deadline
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 100));
+    }
+
+    // 2) Rotate to NEW oracle pubkey
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOraclePubkey(oracleNew.publicKey)
+        .accounts({ config: configPda, admin })
+        .rpc()
+    );
+
+    // Build pulse message (canonical)
+    const pulse = crypto.randomBytes(64);
+    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
+
+    // 3) Try with OLD oracle => must fail (pubkey mismatch)
+    {
+      const edIxOld = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: oracleOld.secretKey,
+        message: msg,
+      });
+
+      const anchorIx = await program.methods
+        .setPulseSigned(new BN(roundId), Array.from(pulse))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .instruction();
+
+      const tx = new Transaction().add(edIxOld, anchorIx);
+      tx.feePayer = relayer.publicKey;
+
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await expectTxFail(
+        async () =>
+          sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+            commitment: "confirmed",
+          }),
+        "Ed25519PubkeyMismatch"
+      );
+    }
+
+    // 4) Try with NEW oracle => must succeed
+    {
+      const edIxNew = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: oracleNew.secretKey,
+        message: msg,
+      });
+
+      const anchorIx = await program.methods
+        .setPulseSigned(new BN(roundId), Array.from(pulse))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .instruction();
+
+      const tx = new Transaction().add(edIxNew, anchorIx);
+      tx.feePayer = relayer.publicKey;
+
+      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
+      tx.recentBlockhash = blockhash;
+      tx.sign(relayer);
+
+      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
+        commitment: "confirmed",
+      });
+    }
+
+    const r = await program.account.round.fetch(roundPda);
+    if (!r.pulseSet) throw new Error("pulseSet should be true after NEW oracle succeeds");
+  });
+
+  it("settle_round_tokens supports incremental settlement via settled_count", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 4242) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 25;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // Create 4 tickets
+    const tickets = [];
+    for (let nonce = 1; nonce <= 4; nonce++) {
+      const guess = 1;
+      const salt = crypto.randomBytes(32);
+      const commitment = commitHash(roundId, admin, nonce, guess, salt);
+      const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            ticket: ticketPda,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+      );
+
+      tickets.push({ nonce, guess, salt, ticketPda });
+    }
+
+    // Wait >= commitDeadline then set pulse
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    // Make nonce=1 win (bit=1), nonce=2 lose (bit=0). Others irrelevant (unrevealed).
+    const pulse = Buffer.alloc(64, 0);
+    const bit1 = deriveBitIndex(roundId, admin, 1);
+    const bit2 = deriveBitIndex(roundId, admin, 2);
+    setBit(pulse, bit1, 1);
+    setBit(pulse, bit2, 0);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // Reveal only 2 tickets (nonce 1 and 2). Leave 3 and 4 unrevealed.
+    for (const t of tickets.filter((x) => x.nonce === 1 || x.nonce === 2)) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .revealTicket(new BN(roundId), new BN(t.nonce), t.guess, Array.from(t.salt))
+          .accounts({ config: configPda, round: roundPda, ticket: t.ticketPda, user: admin })
+          .rpc()
+      );
+    }
+
+    // Wait > revealDeadline then finalize
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 120));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // --- Incremental settle #1: settle two tickets (1 and 3)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: tickets[0].ticketPda, isSigner: false, isWritable: true }, // nonce 1
+          { pubkey: tickets[2].ticketPda, isSigner: false, isWritable: true }, // nonce 3
+        ])
+        .rpc()
+    );
+
+    let r1 = await program.account.round.fetch(roundPda, "confirmed");
+    const settled1 = (r1.settledCount ?? r1.settled_count).toString();
+    const committed = (r1.committedCount ?? r1.committed_count).toString();
+    const tokenSettled1 = (r1.tokenSettled ?? r1.token_settled);
+
+    if (committed !== "4") throw new Error(`expected committed_count=4, got ${committed}`);
+    if (settled1 !== "2") throw new Error(`expected settled_count=2, got ${settled1}`);
+    if (tokenSettled1) throw new Error("token_settled should be false after partial settle");
+
+    // --- Incremental settle #2: settle remaining tickets (2 and 4)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: tickets[1].ticketPda, isSigner: false, isWritable: true }, // nonce 2
+          { pubkey: tickets[3].ticketPda, isSigner: false, isWritable: true }, // nonce 4
+        ])
+        .rpc()
+    );
+
+    let r2 = await program.account.round.fetch(roundPda, "confirmed");
+    const settled2 = (r2.settledCount ?? r2.settled_count).toString();
+    const tokenSettled2 = (r2.tokenSettled ?? r2.token_settled);
+
+    if (settled2 !== "4") throw new Error(`expected settled_count=4, got ${settled2}`);
+    if (!tokenSettled2) throw new Error("token_settled should be true after full settle");
+
+    // calling again must fail now
+    await expectTxFail(async () => {
+      await program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: tickets[0].ticketPda, isSigner: false, isWritable: true }])
+        .rpc();
+    }, "RoundTokensAlreadySettled");
+  });
+
+  it("oracle_set: initialize + add/remove + set_threshold", async () => {
+    const admin = provider.wallet.publicKey;
+    const payer = provider.wallet.payer; // Keypair (NodeWallet)
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    const [oracleSetPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("oracle_set"), configPda.toBytes()],
+      program.programId
+    );
+
+    const oracle1 = Keypair.generate();
+    const oracle2 = Keypair.generate();
+
+    // init (if not exists)
+    const existing = await program.account.oracleSet.fetchNullable(oracleSetPda);
+    if (!existing) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .initializeOracleSet(
+            1, // threshold
+            [oracle1.publicKey] // initial_oracles
+          )
+          .accounts({
+            config: configPda,
+            oracleSet: oracleSetPda,
+            admin,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .signers([payer])
+          .rpc()
+      );
+    }
+
+    // add oracle2
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .addOracle(oracle2.publicKey)
+        .accounts({
+          config: configPda,
+          oracleSet: oracleSetPda,
+          admin,
+        })
+        .rpc()
+    );
+
+    // set threshold to 2 (must be <= allowlist length)
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOracleThreshold(2)
+        .accounts({
+          config: configPda,
+          oracleSet: oracleSetPda,
+          admin,
+        })
+        .rpc()
+    );
+
+    // removing oracle2 now should fail (threshold would exceed oracle count)
+    await expectTxFail(
+      async () =>
+        rpcConfirmed(
+          provider,
+          program.methods
+            .removeOracle(oracle2.publicKey)
+            .accounts({
+              config: configPda,
+              oracleSet: oracleSetPda,
+              admin,
+            })
+            .rpc()
+        ),
+      "Threshold exceeds current oracle count"
+    );
+
+    // lower threshold back to 1
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setOracleThreshold(1)
+        .accounts({
+          config: configPda,
+          oracleSet: oracleSetPda,
+          admin,
+        })
+        .rpc()
+    );
+
+    // now remove oracle2 should succeed
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .removeOracle(oracle2.publicKey)
+        .accounts({
+          config: configPda,
+          oracleSet: oracleSetPda,
+          admin,
+        })
+        .rpc()
+    );
+
+    const os = await program.account.oracleSet.fetch(oracleSetPda);
+    if (os.threshold !== 1) throw new Error("threshold mismatch");
+    // oracle2 should be gone
+    const has2 = os.oracles.some((pk) => pk.equals(oracle2.publicKey));
+    if (has2) throw new Error("oracle2 still present after removal");
+  });
+
+  it("oracle_set: clear_oracle_set decommissions the set, then it can be rebuilt", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda } = await ensureConfig(program, provider);
+
+    const [oracleSetPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("oracle_set"), configPda.toBytes()],
+      program.programId
+    );
+    const accounts = { config: configPda, oracleSet: oracleSetPda, admin };
+
+    // threshold 0 is only valid for an empty allowlist
+    const before = await program.account.oracleSet.fetch(oracleSetPda);
+    if (before.oracles.length > 0) {
+      await expectTxFail(
+        async () => rpcConfirmed(provider, program.methods.setOracleThreshold(0).accounts(accounts).rpc()),
+        "InvalidThreshold"
+      );
+    }
+
+    await rpcConfirmed(provider, program.methods.clearOracleSet().accounts(accounts).rpc());
+
+    let os = await program.account.oracleSet.fetch(oracleSetPda);
+    if (os.oracles.length !== 0) throw new Error("allowlist should be empty");
+    if (os.threshold !== 0) throw new Error("threshold should be reset to 0");
+
+    await rpcConfirmed(provider, program.methods.setOracleThreshold(0).accounts(accounts).rpc());
+
+    // rebuild
+    const oracle = Keypair.generate();
+    await rpcConfirmed(provider, program.methods.addOracle(oracle.publicKey).accounts(accounts).rpc());
+    await rpcConfirmed(provider, program.methods.setOracleThreshold(1).accounts(accounts).rpc());
+
+    os = await program.account.oracleSet.fetch(oracleSetPda);
+    if (os.threshold !== 1) throw new Error("threshold mismatch after rebuild");
+    if (os.oracles.length !== 1 || !os.oracles[0].equals(oracle.publicKey)) {
+      throw new Error("rebuilt allowlist mismatch");
+    }
+  });
+
+  it("transfer_mint_authority: reassigns the mint authority away from config and back", async () => {
+    const payer = provider.wallet.payer;
+    const { configPda, timlgMint } = await ensureConfig(program, provider);
+    const accounts = {
+      config: configPda,
+      timlgMint,
+      admin: payer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await rpcConfirmed(
+      provider,
+      program.methods.transferMintAuthority(payer.publicKey).accounts(accounts).rpc()
+    );
+    let mintInfo = await getMint(provider.connection, timlgMint, "confirmed");
+    if (!mintInfo.mintAuthority || !mintInfo.mintAuthority.equals(payer.publicKey)) {
+      throw new Error("mint authority should have moved to the admin");
+    }
+
+    // config no longer signs for the mint
+    await expectTxFail(
+      async () => rpcConfirmed(
+        provider,
+        program.methods.transferMintAuthority(payer.publicKey).accounts(accounts).rpc()
+      ),
+      "owner does not match"
+    );
+
+    // hand it back so the rest of the suite keeps minting
+    await setAuthority(
+      provider.connection,
+      payer,
+      timlgMint,
+      payer.publicKey,
+      AuthorityType.MintTokens,
+      configPda,
+      [],
+      { commitment: "confirmed" }
+    );
+    mintInfo = await getMint(provider.connection, timlgMint, "confirmed");
+    if (!mintInfo.mintAuthority || !mintInfo.mintAuthority.equals(configPda)) {
+      throw new Error("mint authority should be back on config");
+    }
+  });
+
+  it("transfer_mint_authority: None disables minting (simulated; irreversible on the shared mint)", async () => {
+    const payer = provider.wallet.payer;
+    const { configPda, timlgMint } = await ensureConfig(program, provider);
+    const accounts = {
+      config: configPda,
+      timlgMint,
+      admin: payer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    const outsider = Keypair.generate();
+    await expectTxFail(
+      async () => rpcConfirmed(
+        provider,
+        program.methods
+          .transferMintAuthority(null)
+          .accounts({ ...accounts, admin: outsider.publicKey })
+          .signers([outsider])
+          .rpc()
+      ),
+      "Unauthorized"
+    );
+
+    const sim = await program.methods.transferMintAuthority(null).accounts(accounts).simulate();
+    if (!sim.raw.some((l) => l.includes("TIMLG minting disabled permanently"))) {
+      throw new Error("expected the disable warning in the simulated logs");
+    }
+
+    const mintInfo = await getMint(provider.connection, timlgMint, "confirmed");
+    if (!mintInfo.mintAuthority || !mintInfo.mintAuthority.equals(configPda)) {
+      throw new Error("simulation must not change the mint authority");
+    }
+  });
+
+  it("deposit_escrow_checked: enforces the minimum post-deposit escrow balance", async () => {
+    const userPk = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureConfig(program, provider);
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint,
+      userPk,
+    });
+    const accounts = {
+      config: configPda,
+      timlgMint,
+      userEscrow: userEscrowPda,
+      userEscrowAta: userEscrowAtaPda,
+      user: userPk,
+      userTIMLGAta,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    const before = await getTokenAmountRaw(provider.connection, userEscrowAtaPda);
+
+    // client assumed a higher starting balance than the escrow actually holds
+    await expectTxFail(
+      async () => rpcConfirmed(
+        provider,
+        program.methods
+          .depositEscrowChecked(new BN(1), new BN((before + 2n).toString()))
+          .accounts(accounts)
+          .rpc()
+      ),
+      "EscrowBalanceTooLow"
+    );
+    if ((await getTokenAmountRaw(provider.connection, userEscrowAtaPda)) !== before) {
+      throw new Error("failed checked deposit must not move funds");
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .depositEscrowChecked(new BN(1), new BN((before + 1n).toString()))
+        .accounts(accounts)
+        .rpc()
+    );
+    if ((await getTokenAmountRaw(provider.connection, userEscrowAtaPda)) !== before + 1n) {
+      throw new Error("escrow balance mismatch after checked deposit");
+    }
+  });
+
+  it("sweep_burns_tokens: sweep burns the unclaimed winner stake instead of paying the treasury", async () => {
+    const admin = provider.wallet.publicKey;
+
+    const { configPda, timlgMint, treasuryPda, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfgAccounts = { config: configPda, admin };
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9917) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 18;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce = 11;
+    const guess = 1;
+    const salt = crypto.randomBytes(32);
+    const commitment = commitHash(roundId, admin, nonce, guess, salt);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // winning ticket that never claims
+    const pulse = Buffer.alloc(64, 0);
+    setBit(pulse, deriveBitIndex(roundId, admin, nonce), guess);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealTicket(new BN(roundId), new BN(nonce), guess, Array.from(salt))
+        .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+
+    const cfgAcc = await program.account.config.fetch(configPda, "confirmed");
+    const targetSlot = revealDeadline + Number(cfgAcc.claimGraceSlots.toString()) + 1;
+    while ((await provider.connection.getSlot("confirmed")) < targetSlot) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    const vaultBefore = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+    const treasuryBefore = await getTokenAmountRaw(provider.connection, treasuryPda);
+    const supplyBefore = (await getMint(provider.connection, timlgMint, "confirmed")).supply;
+    if (vaultBefore === 0n) throw new Error("expected the unclaimed winner stake in the vault");
+
+    await rpcConfirmed(provider, program.methods.setSweepBurnsTokens(true).accounts(cfgAccounts).rpc());
+    try {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .sweepUnclaimed(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            vault: vaultPda,
+            treasurySol: treasurySolPda,
+            admin,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+      );
+    } finally {
+      await rpcConfirmed(provider, program.methods.setSweepBurnsTokens(false).accounts(cfgAccounts).rpc());
+    }
+
+    const supplyAfter = (await getMint(provider.connection, timlgMint, "confirmed")).supply;
+    if (supplyBefore - supplyAfter !== vaultBefore) {
+      throw new Error(`supply should drop by ${vaultBefore}, dropped by ${supplyBefore - supplyAfter}`);
+    }
+    if ((await getTokenAmountRaw(provider.connection, treasuryPda)) !== treasuryBefore) {
+      throw new Error("treasury must not receive swept tokens when burning");
+    }
+    if ((await getTokenAmountRaw(provider.connection, timlgVaultPda)) !== 0n) {
+      throw new Error("timlg_vault should be empty after sweep");
+    }
+  });
+
+  it("cranker_fee_lamports: a settle that processes tickets pays the payer from treasury_sol's surplus", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const cfgAccounts = { config: configPda, admin };
+    const fee = 5_000;
+
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: treasurySolPda, lamports: 1_000_000 })
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9931) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 18;
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const guess = 1;
+    const salt = crypto.randomBytes(32);
+    const [ticketPda, reservedTicketPda] = [13, 14].map((n) => deriveTicketPda(program.programId, roundId, admin, n));
+    for (const nonce of [13, 14]) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, guess, salt)))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            ticket: deriveTicketPda(program.programId, roundId, admin, nonce),
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+      );
+    }
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0)))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    const settleAccounts = {
+      config: configPda,
+      round: roundPda,
+      timlgMint,
+      timlgVault: timlgVaultPda,
+      treasury: treasuryPda,
+      tokenomics: tokenomicsPda,
+      rewardFeePool: rewardFeePoolPda,
+      replicationPool: replicationPoolPda,
+      admin,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    // non-admin cannot set the fee
+    const outsider = Keypair.generate();
+    await expectTxFail(
+      async () => rpcConfirmed(
+        provider,
+        program.methods
+          .setCrankerFee(new BN(fee))
+          .accounts({ config: configPda, admin: outsider.publicKey })
+          .signers([outsider])
+          .rpc()
+      ),
+      "Unauthorized"
+    );
+
+    await rpcConfirmed(provider, program.methods.setCrankerFee(new BN(fee)).accounts(cfgAccounts).rpc());
+    try {
+      // a call that settles nothing earns nothing
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts(settleAccounts)
+          .remainingAccounts([{ pubkey: reservedTicketPda, isSigner: false, isWritable: true }])
+          .rpc()
+      );
+      const idleBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts(settleAccounts)
+          .remainingAccounts([{ pubkey: reservedTicketPda, isSigner: false, isWritable: true }])
+          .rpc()
+      );
+      if ((await provider.connection.getBalance(treasurySolPda, "confirmed")) !== idleBefore) {
+        throw new Error("a settle call that processed nothing must not pay the cranker");
+      }
+
+      const treasuryBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts(settleAccounts)
+          .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+          .rpc()
+      );
+      const treasuryAfter = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      if (treasuryBefore - treasuryAfter !== fee) {
+        throw new Error(`treasury_sol should pay ${fee}, paid ${treasuryBefore - treasuryAfter}`);
+      }
+    } finally {
+      await rpcConfirmed(provider, program.methods.setCrankerFee(new BN(0)).accounts(cfgAccounts).rpc());
+    }
+  });
+
+  it("cranker fee: per-ticket recover cranks never eat into the round's outstanding fee refunds", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const cfgAccounts = { config: configPda, admin };
+    const serviceFee = 10_000;
+    const setServiceFee = (lamports) =>
+      rpcConfirmed(provider, program.methods.updateSolServiceFee(new BN(lamports)).accounts(cfgAccounts).rpc());
+    const setCrankerFee = (lamports) =>
+      rpcConfirmed(provider, program.methods.setCrankerFee(new BN(lamports)).accounts(cfgAccounts).rpc());
+
+    const cranker = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: cranker.publicKey, lamports: 10_000_000 })
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 10_049) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const revealDeadline = slot0 + 20;
+    const nonces = [1, 2, 3, 4];
+    const tickets = nonces.map((n) => deriveTicketPda(program.programId, roundId, admin, n));
+
+    try {
+      await setServiceFee(serviceFee);
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(roundId), new BN(789), new BN(slot0 + 10), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(5), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+      // four tickets against a minimum of five: voided and refundable
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitBatch(
+            new BN(roundId),
+            nonces.map((n) => ({ nonce: new BN(n), commitment: Array.from(commitHash(roundId, admin, n, 1, Buffer.alloc(32, 21))) }))
+          )
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(tickets.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+          .rpc()
+      );
+      const committed = await program.account.round.fetch(roundPda, "confirmed");
+      if (committed.feesOutstanding.toNumber() !== 4 * serviceFee) {
+        throw new Error(`fees_outstanding should be ${4 * serviceFee}, got ${committed.feesOutstanding}`);
+      }
+
+      while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .finalizeRound(new BN(roundId))
+          .accounts({ config: configPda, round: roundPda, admin })
+          .rpc()
+      );
+
+      const recover = (ticket) =>
+        program.methods
+          .recoverFundsAnyone(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            ticket,
+            user: admin,
+            userTokenAccount: userTIMLGAta,
+            timlgVault: timlgVaultPda,
+            timlgMint,
+            cranker: cranker.publicKey,
+            treasurySol: treasurySolPda,
+            feePayer: admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([cranker])
+          .rpc();
+      const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(0);
+      const spendable = async () => (await provider.connection.getBalance(treasurySolPda, "confirmed")) - rentFloor;
+      // returns [service fee refunded, cranker fee paid]
+      const crank = async (ticket) => {
+        const treasuryBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
+        const crankerBefore = await provider.connection.getBalance(cranker.publicKey, "confirmed");
+        await rpcConfirmed(provider, recover(ticket));
+        const crankerPaid = (await provider.connection.getBalance(cranker.publicKey, "confirmed")) - crankerBefore;
+        const treasuryOut = treasuryBefore - (await provider.connection.getBalance(treasurySolPda, "confirmed"));
+        return [treasuryOut - crankerPaid, crankerPaid];
+      };
+
+      await setCrankerFee(0);
+      await crank(tickets[0]);
+
+      // three refunds of this round still owed (plus whatever other rounds owe): a crank fee one
+      // lamport over the surplus beyond them is skipped, even though a single reserved refund
+      // would leave room for it
+      const owed = (await program.account.config.fetch(configPda, "confirmed")).solFeesOutstanding.toNumber();
+      if (owed < 3 * serviceFee) throw new Error(`sol_fees_outstanding ${owed} should cover the round's three refunds`);
+      const s = await spendable();
+      await setCrankerFee(s - owed + 1);
+      const [refunded1, paid1] = await crank(tickets[1]);
+      if (refunded1 !== serviceFee || paid1 !== 0) {
+        throw new Error(`expected refund ${serviceFee} and no crank fee, got ${refunded1} / ${paid1}`);
+      }
+
+      // exactly the surplus is payable, and what is left still covers the last refund
+      await setCrankerFee(s - owed);
+      const [refunded2, paid2] = await crank(tickets[2]);
+      if (refunded2 !== serviceFee || paid2 !== s - owed) {
+        throw new Error(`expected refund ${serviceFee} and crank fee ${s - owed}, got ${refunded2} / ${paid2}`);
+      }
+      const [refunded3, paid3] = await crank(tickets[3]);
+      if (refunded3 !== serviceFee || paid3 !== 0) {
+        throw new Error(`the last refund must still be paid in full, got ${refunded3} / ${paid3}`);
+      }
+      if ((await program.account.round.fetch(roundPda, "confirmed")).feesOutstanding.toNumber() !== 0) {
+        throw new Error("fees_outstanding should be 0 once every ticket is refunded");
+      }
+    } finally {
+      await setServiceFee(cfg.solServiceFeeLamports.toString());
+      await setCrankerFee(0);
+    }
+  });
+
+  it("cranker fee: settle cranks on a finalized round never eat into another round's fee refunds", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const cfgAccounts = { config: configPda, admin };
+    const serviceFee = 10_000;
+    const setServiceFee = (lamports) =>
+      rpcConfirmed(provider, program.methods.updateSolServiceFee(new BN(lamports)).accounts(cfgAccounts).rpc());
+    const setCrankerFee = (lamports) =>
+      rpcConfirmed(provider, program.methods.setCrankerFee(new BN(lamports)).accounts(cfgAccounts).rpc());
+    const owed = async () => (await program.account.config.fetch(configPda, "confirmed")).solFeesOutstanding.toNumber();
+
+    // leave a surplus over every reserved refund for the crank fee to come out of
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: treasurySolPda, lamports: 1_000_000 })
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+
+    // round A is voided (two tickets against a minimum of five) and still owes both fees;
+    // round B resolves normally and is cranked by settle
+    const base = (Math.floor(Date.now() / 1000) + 10_061) % 1_000_000;
+    const rounds = [base, base + 1].map((roundId) => ({ roundId, ...deriveRoundPdas(program.programId, roundId) }));
+    const [a, b] = rounds;
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 20;
+    const nonces = [1, 2];
+    const ticketsOf = (r) => nonces.map((n) => deriveTicketPda(program.programId, r.roundId, admin, n));
+
+    try {
+      await setServiceFee(serviceFee);
+      for (const [r, minParticipants] of [[a, 5], [b, 0]]) {
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .createRound(new BN(r.roundId), new BN(790), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(minParticipants), 1)
+            .accounts({
+              config: configPda,
+              timlgMint,
+              round: r.roundPda,
+              vault: r.vaultPda,
+              timlgVault: r.timlgVaultPda,
+              admin,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: SystemProgram.programId,
+              rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            })
+            .rpc()
+        );
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .commitBatch(
+              new BN(r.roundId),
+              nonces.map((n) => ({ nonce: new BN(n), commitment: Array.from(commitHash(r.roundId, admin, n, 1, Buffer.alloc(32, 23))) }))
+            )
+            .accounts({
+              config: configPda,
+              round: r.roundPda,
+              timlgMint,
+              timlgVault: r.timlgVaultPda,
+              user: admin,
+              userTIMLGAta,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: SystemProgram.programId,
+            })
+            .remainingAccounts(ticketsOf(r).map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+            .rpc()
+        );
+      }
+
+      while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseMock(new BN(b.roundId), Array.from(Buffer.alloc(64, 0)))
+          .accounts({ config: configPda, round: b.roundPda, admin })
+          .rpc()
+      );
+      while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      const owedBefore = await owed();
+      for (const r of rounds) {
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .finalizeRound(new BN(r.roundId))
+            .accounts({ config: configPda, round: r.roundPda, admin })
+            .rpc()
+        );
+      }
+      // B keeps its fees once finalized; A's stay reserved
+      if (owedBefore - (await owed()) !== 2 * serviceFee) {
+        throw new Error("finalizing round B should release exactly its two service fees");
+      }
+      if ((await program.account.round.fetch(a.roundPda, "confirmed")).feesOutstanding.toNumber() !== 2 * serviceFee) {
+        throw new Error("the voided round A should still owe both service fees");
+      }
+
+      const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(0);
+      const treasurySol = async () => provider.connection.getBalance(treasurySolPda, "confirmed");
+      const settle = (ticket) =>
+        rpcConfirmed(
+          provider,
+          program.methods
+            .settleRoundTokens(new BN(b.roundId))
+            .accounts({
+              config: configPda,
+              round: b.roundPda,
+              timlgMint,
+              timlgVault: b.timlgVaultPda,
+              treasury: treasuryPda,
+              tokenomics: tokenomicsPda,
+              rewardFeePool: rewardFeePoolPda,
+              replicationPool: replicationPoolPda,
+              admin,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .remainingAccounts([{ pubkey: ticket, isSigner: false, isWritable: true }])
+            .rpc()
+        );
+      const [ticketB1, ticketB2] = ticketsOf(b);
+
+      // one lamport over the surplus beyond every round's outstanding fees: skipped
+      const surplus = (await treasurySol()) - rentFloor - (await owed());
+      await setCrankerFee(surplus + 1);
+      let before = await treasurySol();
+      await settle(ticketB1);
+      if ((await treasurySol()) !== before) throw new Error("the crank fee must not dip into round A's refunds");
+
+      await setCrankerFee(surplus);
+      before = await treasurySol();
+      await settle(ticketB2);
+      if (before - (await treasurySol()) !== surplus) throw new Error(`exactly the surplus ${surplus} should be paid`);
+
+      // round A's refunds are still paid in full
+      await setCrankerFee(0);
+      for (const ticket of ticketsOf(a)) {
+        before = await treasurySol();
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .recoverFundsAnyone(new BN(a.roundId))
+            .accounts({
+              config: configPda,
+              round: a.roundPda,
+              ticket,
+              user: admin,
+              userTokenAccount: userTIMLGAta,
+              timlgVault: a.timlgVaultPda,
+              timlgMint,
+              cranker: admin,
+              treasurySol: treasurySolPda,
+              feePayer: admin,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: SystemProgram.programId,
+            })
+            .rpc()
+        );
+        if (before - (await treasurySol()) !== serviceFee) throw new Error("round A's service fee refund was skipped");
+      }
+    } finally {
+      await setServiceFee(cfg.solServiceFeeLamports.toString());
+      await setCrankerFee(0);
+    }
+  });
+
+  it("freeze_treasury / thaw_treasury: admin can freeze and thaw the SPL treasury", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda } = await ensureConfig(program, provider);
+    const accounts = {
+      config: configPda,
+      timlgMint,
+      treasury: treasuryPda,
+      admin,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    const mintInfo = await getMint(provider.connection, timlgMint, "confirmed");
+    if (!mintInfo.freezeAuthority || !mintInfo.freezeAuthority.equals(configPda)) {
+      throw new Error("initialize_config should hand the freeze authority to config");
+    }
+
+    const outsider = Keypair.generate();
+    await expectTxFail(
+      async () => rpcConfirmed(
+        provider,
+        program.methods
+          .freezeTreasury()
+          .accounts({ ...accounts, admin: outsider.publicKey })
+          .signers([outsider])
+          .rpc()
+      ),
+      "Unauthorized"
+    );
+
+    await rpcConfirmed(provider, program.methods.freezeTreasury().accounts(accounts).rpc());
+    try {
+      const frozen = await getAccount(provider.connection, treasuryPda, "confirmed");
+      if (!frozen.isFrozen) throw new Error("treasury should be frozen");
+    } finally {
+      await rpcConfirmed(provider, program.methods.thawTreasury().accounts(accounts).rpc());
+    }
+
+    const thawed = await getAccount(provider.connection, treasuryPda, "confirmed");
+    if (thawed.isFrozen) throw new Error("treasury should be thawed");
+  });
+
+  it("max_open_rounds: create_round_auto stops at the cap, cancel frees a slot", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
+
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("round_registry_v3"), configPda.toBytes()],
+      program.programId
+    );
+    if (!(await program.account.roundRegistry.fetchNullable(registryPda))) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .initializeRoundRegistry(new BN(900_000))
+          .accounts({ config: configPda, roundRegistry: registryPda, admin, systemProgram: SystemProgram.programId })
+          .rpc()
+      );
+    }
+    const registryAccounts = { config: configPda, roundRegistry: registryPda, admin };
+
+    const createAuto = async () => {
+      const rr = await program.account.roundRegistry.fetch(registryPda, "confirmed");
+      const roundId = rr.nextRoundId.toNumber();
+      const pdas = deriveRoundPdas(program.programId, roundId);
+      const slot = await provider.connection.getSlot("confirmed");
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRoundAuto(new BN(1), new BN(slot + 100), new BN(slot + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            roundRegistry: registryPda,
+            round: pdas.roundPda,
+            vault: pdas.vaultPda,
+            timlgVault: pdas.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+      return { roundId, ...pdas };
+    };
+
+    const open0 = (await program.account.roundRegistry.fetch(registryPda, "confirmed")).openRounds;
+    await rpcConfirmed(
+      provider,
+      program.methods.setMaxOpenRounds(open0 + 1).accounts(registryAccounts).rpc()
+    );
+
+    try {
+      const { roundId, roundPda, vaultPda, timlgVaultPda } = await createAuto();
+      let rr = await program.account.roundRegistry.fetch(registryPda, "confirmed");
+      if (rr.openRounds !== open0 + 1) throw new Error("open_rounds should count the new round");
+
+      await expectTxFail(async () => createAuto(), "TooManyOpenRounds");
+
+      // a registry-tracked round can't be cancelled without the registry
+      await expectTxFail(async () => {
+        await program.methods
+          .cancelRound(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            roundRegistry: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }, "RoundRegistryRequired");
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .cancelRound(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            vault: vaultPda,
+            timlgVault: timlgVaultPda,
+            admin,
+            roundRegistry: registryPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+      );
+      rr = await program.account.roundRegistry.fetch(registryPda, "confirmed");
+      if (rr.openRounds !== open0) throw new Error("cancel should free the open-round slot");
+    } finally {
+      await rpcConfirmed(provider, program.methods.setMaxOpenRounds(0).accounts(registryAccounts).rpc());
+    }
+  });
+
+  it("min_participants: set at creation, an under-subscribed round is voided at finalize and refunded via recover_funds", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9947) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 18;
+    // a lone committer would be a guaranteed self-win: require two from the start
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(777), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(2), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const created = await program.account.round.fetch(roundPda, "confirmed");
+    if (Number(created.minParticipants) !== 2) throw new Error(`min_participants should be 2, got ${created.minParticipants}`);
+
+    const nonce = 17;
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, 3))))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // no pulse needed: the round is voided instead of resolved
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    const round = await program.account.round.fetch(roundPda, "confirmed");
+    if (round.state !== 3) throw new Error(`round should be Refunding (3), got ${round.state}`);
+    if (round.finalized) throw new Error("a voided round is not finalized");
+
+    await expectTxFail(async () => {
+      await program.methods
+        .settleRoundTokens(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          treasury: treasuryPda,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc();
+    }, "RoundRefunding");
+
+    // refundable right away, without the stuck-round timeout
+    const tokensBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .recoverFunds(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          userTokenAccount: userTIMLGAta,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          timlgMint,
+          treasurySol: cfg.treasurySol,
+          feePayer: admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+    const tokensAfter = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    if (tokensAfter - tokensBefore !== BigInt(cfg.stakeAmount.toString())) {
+      throw new Error(`stake not refunded: before=${tokensBefore} after=${tokensAfter}`);
+    }
+  });
+
+
+  it("weighted stake: commit_ticket_weighted pulls stake * weight, bounded by max_weight", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9953) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(778), new BN(slot0 + 200), new BN(slot0 + 400), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const commitWeighted = (nonce, weight) =>
+      program.methods
+        .commitTicketWeighted(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, 5))), weight)
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: deriveTicketPda(program.programId, roundId, admin, nonce),
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    // weighting is off by default
+    await expectTxFail(() => commitWeighted(1, 2), "InvalidWeight");
+
+    await rpcConfirmed(
+      provider,
+      program.methods.setMaxWeight(3).accounts({ config: configPda, admin }).rpc()
+    );
+    try {
+      const vaultBefore = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+      await rpcConfirmed(provider, commitWeighted(2, 3));
+      const vaultAfter = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+      if (vaultAfter - vaultBefore !== BigInt(cfg.stakeAmount.toString()) * 3n) {
+        throw new Error(`weighted stake not pulled: before=${vaultBefore} after=${vaultAfter}`);
+      }
+      const ticket = await program.account.ticket.fetch(
+        deriveTicketPda(program.programId, roundId, admin, 2),
+        "confirmed"
+      );
+      if (ticket.weight !== 3) throw new Error(`ticket weight should be 3, got ${ticket.weight}`);
+
+      await expectTxFail(() => commitWeighted(3, 4), "InvalidWeight");
+      await expectTxFail(() => commitWeighted(3, 0), "InvalidWeight");
+
+      // batches take weights through commit_batch_weighted; commit_batch keeps its layout
+      const batchNonces = [4, 5];
+      const batchBefore = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitBatchWeighted(
+            new BN(roundId),
+            batchNonces.map((n, i) => ({
+              nonce: new BN(n),
+              commitment: Array.from(commitHash(roundId, admin, n, 1, Buffer.alloc(32, 5))),
+              weight: i + 1,
+            }))
+          )
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            batchNonces.map((n) => ({
+              pubkey: deriveTicketPda(program.programId, roundId, admin, n),
+              isSigner: false,
+              isWritable: true,
+            }))
+          )
+          .rpc()
+      );
+      const batchAfter = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+      if (batchAfter - batchBefore !== BigInt(cfg.stakeAmount.toString()) * 3n) {
+        throw new Error(`weighted batch should pull 1 + 2 stakes, got ${batchAfter - batchBefore}`);
+      }
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods.setMaxWeight(0).accounts({ config: configPda, admin }).rpc()
+      );
+    }
+  });
+
+  it("round state advances Announced -> PulseSet -> Finalized -> Swept, then close_round deletes it", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasurySolPda } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9959) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const expectState = async (want) => {
+      const r = await program.account.round.fetch(roundPda, "confirmed");
+      if (r.state !== want) throw new Error(`round state should be ${want}, got ${r.state}`);
+    };
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 6;
+    const revealDeadline = slot0 + 12;
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(779), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+    await expectState(0);
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 1)))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await expectState(1);
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await expectState(2);
+
+    const targetSlot = revealDeadline + Number(cfg.claimGraceSlots.toString()) + 1;
+    while ((await provider.connection.getSlot("confirmed")) < targetSlot) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .sweepUnclaimed(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          vault: vaultPda,
+          treasurySol: treasurySolPda,
+          admin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+    await expectState(4);
+
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .closeRound(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgVault: timlgVaultPda,
+          timlgMint,
+          admin,
+          roundRegistry: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    if ((await provider.connection.getAccountInfo(roundPda, "confirmed")) !== null) {
+      throw new Error("close_round should delete the round account");
+    }
+  });
+
+  it("emergency_drain: only the rescue authority, only while paused, moves both treasuries", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, treasuryPda, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    if (!cfg.rescueAuthority.equals(RESCUE_KP.publicKey)) throw new Error("bootstrap sets RESCUE_KP as rescue authority");
+
+    // something to rescue in both treasuries
+    await splTransfer(provider.connection, provider.wallet.payer, userTIMLGAta, treasuryPda, admin, 5);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: treasurySolPda, lamports: 1_000_000 })
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+
+    const drain = (signer) =>
+      program.methods
+        .emergencyDrain()
+        .accounts({
+          config: configPda,
+          rescueAuthority: signer.publicKey,
+          treasurySol: treasurySolPda,
+          treasury: treasuryPda,
+          rescueDestination: admin,
+          rescueDestinationAta: userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(signer === provider.wallet.payer ? [] : [signer])
+        .rpc();
+
+    await expectTxFail(() => drain(RESCUE_KP), "NotPaused");
+
+    await rpcConfirmed(
+      provider,
+      program.methods.setPause(true).accounts({ config: configPda, admin }).rpc()
+    );
+    try {
+      await expectTxFail(() => drain(Keypair.generate()), "Unauthorized");
+      // the admin key is not a rescue key
+      await expectTxFail(() => drain(provider.wallet.payer), "Unauthorized");
+
+      const tokensBefore = await getTokenAmountRaw(provider.connection, treasuryPda);
+      if (tokensBefore === 0n) throw new Error("expected TIMLG in the treasury");
+      const destBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      await rpcConfirmed(provider, drain(RESCUE_KP));
+
+      if ((await getTokenAmountRaw(provider.connection, treasuryPda)) !== 0n) {
+        throw new Error("treasury tokens should be drained");
+      }
+      const destAfter = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      if (destAfter - destBefore !== tokensBefore) {
+        throw new Error(`destination should receive ${tokensBefore}, got ${destAfter - destBefore}`);
+      }
+      const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(0);
+      const solLeft = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      if (solLeft > rentFloor) throw new Error(`treasury_sol should be at its rent floor, has ${solLeft}`);
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods.setPause(false).accounts({ config: configPda, admin }).rpc()
+      );
+    }
+  });
+
+  it("emergency_drain still moves treasury_sol while the SPL treasury is frozen", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const freezeAccounts = {
+      config: configPda,
+      timlgMint,
+      treasury: treasuryPda,
+      admin,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await splTransfer(provider.connection, provider.wallet.payer, userTIMLGAta, treasuryPda, admin, 3);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({ fromPubkey: admin, toPubkey: treasurySolPda, lamports: 1_000_000 })
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+
+    await rpcConfirmed(provider, program.methods.freezeTreasury().accounts(freezeAccounts).rpc());
+    await rpcConfirmed(
+      provider,
+      program.methods.setPause(true).accounts({ config: configPda, admin }).rpc()
+    );
+    try {
+      const tokensBefore = await getTokenAmountRaw(provider.connection, treasuryPda);
+      const destBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .emergencyDrain()
+          .accounts({
+            config: configPda,
+            rescueAuthority: RESCUE_KP.publicKey,
+            treasurySol: treasurySolPda,
+            treasury: treasuryPda,
+            rescueDestination: admin,
+            rescueDestinationAta: userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([RESCUE_KP])
+          .rpc()
+      );
+
+      const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(0);
+      const solLeft = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      if (solLeft > rentFloor) throw new Error(`treasury_sol should be drained, has ${solLeft}`);
+      // the frozen token leg is skipped, not failed
+      if ((await getTokenAmountRaw(provider.connection, treasuryPda)) !== tokensBefore) {
+        throw new Error("a frozen treasury should keep its tokens");
+      }
+      if ((await getTokenAmountRaw(provider.connection, userTIMLGAta)) !== destBefore) {
+        throw new Error("no tokens should reach the destination");
+      }
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods.setPause(false).accounts({ config: configPda, admin }).rpc()
+      );
+      await rpcConfirmed(provider, program.methods.thawTreasury().accounts(freezeAccounts).rpc());
+    }
+  });
+
+  it("commit_ticket_delegated: stake moves via a round-scoped approval, only with the user's signature", async () => {
+    const admin = provider.wallet.publicKey;
+    const payerKp = provider.wallet.payer;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9961) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(780), new BN(slot0 + 200), new BN(slot0 + 400), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    // the user holds TIMLG but never pays fees or rent: the wallet does
+    const userKp = Keypair.generate();
+    const user = userKp.publicKey;
+    const userAta = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payerKp, timlgMint, user, false, "confirmed")
+    ).address;
+    await splTransfer(provider.connection, payerKp, userTIMLGAta, userAta, admin, stake * 2n);
+    const approveTo = (delegate, amount) =>
+      splApprove(provider.connection, payerKp, userAta, delegate, userKp, amount, [], { commitment: "confirmed" });
+
+    const delegatedIx = (nonce) =>
+      program.methods
+        .commitTicketDelegated(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, user, nonce, 1, Buffer.alloc(32, 7))))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: deriveTicketPda(program.programId, roundId, user, nonce),
+          user,
+          payer: admin,
+          userTIMLGAta: userAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        });
+    const commitAsUser = (nonce) => delegatedIx(nonce).signers([userKp]).rpc();
+
+    // a config-wide approval would be spendable in any round: rejected
+    await approveTo(configPda, stake);
+    await expectTxFail(() => commitAsUser(1), "InvalidDelegate");
+
+    await approveTo(roundPda, stake - 1n);
+    await expectTxFail(() => commitAsUser(1), "InsufficientDelegation");
+
+    await approveTo(roundPda, stake);
+
+    // a third party cannot spend the approval without the user's signature
+    const ix = await delegatedIx(1).instruction();
+    for (const k of ix.keys) {
+      if (k.pubkey.equals(user)) k.isSigner = false;
+    }
+    await expectTxFail(
+      () => provider.sendAndConfirm(new Transaction().add(ix), [], { commitment: "confirmed" }),
+      "AccountNotSigner"
+    );
+
+    const vaultBefore = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+    await rpcConfirmed(provider, commitAsUser(1));
+    const vaultAfter = await getTokenAmountRaw(provider.connection, timlgVaultPda);
+    if (vaultAfter - vaultBefore !== stake) {
+      throw new Error(`delegated stake not pulled: before=${vaultBefore} after=${vaultAfter}`);
+    }
+    const ticket = await program.account.ticket.fetch(deriveTicketPda(program.programId, roundId, user, 1), "confirmed");
+    if (!ticket.user.equals(user)) throw new Error("ticket should belong to the user, not the payer");
+    const ata = await getAccount(provider.connection, userAta, "confirmed");
+    if (ata.delegatedAmount !== 0n) throw new Error(`approval should be spent, ${ata.delegatedAmount} left`);
+  });
+  it("dispute_pulse: PulseSet -> Announced is the one step back, a Refunding round is never reopened", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const oracles = [Keypair.generate(), Keypair.generate()];
+    const oracleSetPda = await resetOracleSet(program, provider, configPda, oracles.map((o) => o.publicKey), 2);
+    await rpcConfirmed(
+      provider,
+      program.methods.setPulseDisputeWindow(new BN(10_000)).accounts({ config: configPda, admin }).rpc()
+    );
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const revealDeadline = slot0 + 16;
+    const base = (Math.floor(Date.now() / 1000) + 9967) % 1_000_000;
+    const rounds = [base, base + 1].map((roundId) => {
+      const [attestationsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("attest"), leU64(roundId)],
+        program.programId
+      );
+      return { roundId, attestationsPda, ...deriveRoundPdas(program.programId, roundId) };
+    });
+    for (const r of rounds) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(r.roundId), new BN(780), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: r.roundPda,
+            vault: r.vaultPda,
+            timlgVault: r.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+    }
+    const [live, voided] = rounds;
+    // nobody commits to the second round: it is voided at finalize
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setRoundMinParticipants(new BN(voided.roundId), new BN(1))
+        .accounts({ config: configPda, round: voided.roundPda, admin })
+        .rpc()
+    );
+
+    const setPulse = (r, fill) =>
+      program.methods
+        .setPulseMock(new BN(r.roundId), Array.from(Buffer.alloc(64, fill)))
+        .accounts({ config: configPda, round: r.roundPda, admin })
+        .rpc();
+    // one oracle attests a different pulse than the one the mock path stores next
+    const attestOther = async (r) => {
+      const pulse = Buffer.alloc(64, 9);
+      const edIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: oracles[0].secretKey,
+        message: expectedPulseMsg(program.programId, r.roundId, 780, pulse),
+      });
+      const ix = await program.methods
+        .submitAttestation(new BN(r.roundId), Array.from(pulse))
+        .accounts({
+          payer: admin,
+          config: configPda,
+          oracleSet: oracleSetPda,
+          round: r.roundPda,
+          attestations: r.attestationsPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+      return provider.sendAndConfirm(new Transaction().add(edIx, ix), [], { commitment: "confirmed" });
+    };
+    const dispute = (r) =>
+      program.methods
+        .disputePulse(new BN(r.roundId))
+        .accounts({ config: configPda, oracleSet: oracleSetPda, round: r.roundPda, attestations: r.attestationsPda, admin })
+        .rpc();
+    const stateOf = async (r) => (await program.account.round.fetch(r.roundPda, "confirmed")).state;
+
+    try {
+      while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      for (const r of rounds) await attestOther(r);
+
+      // a mock pulse carries no attestations (below the threshold of 2) and contradicts the record
+      await rpcConfirmed(provider, setPulse(live, 1));
+      if ((await stateOf(live)) !== 1) throw new Error("round should be PulseSet (1)");
+      await rpcConfirmed(provider, dispute(live));
+      const disputed = await program.account.round.fetch(live.roundPda, "confirmed");
+      if (disputed.state !== 0) throw new Error(`dispute should return the round to Announced (0), got ${disputed.state}`);
+      if (disputed.pulseSet) throw new Error("dispute should clear pulse_set");
+      await rpcConfirmed(provider, setPulse(live, 2));
+      if ((await stateOf(live)) !== 1) throw new Error("a replacement pulse should move the round forward again");
+
+      await rpcConfirmed(provider, setPulse(voided, 1));
+      while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .finalizeRound(new BN(voided.roundId))
+          .accounts({ config: configPda, round: voided.roundPda, admin })
+          .rpc()
+      );
+      const v = await program.account.round.fetch(voided.roundPda, "confirmed");
+      if (v.state !== 3 || v.finalized || !v.pulseSet) {
+        throw new Error(`expected an unfinalized Refunding round with a pulse, got state=${v.state}`);
+      }
+      await expectTxFail(() => dispute(voided), "PulseDisputeNotAllowed");
+      if ((await stateOf(voided)) !== 3) throw new Error("a voided round must stay Refunding");
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseDisputeWindow(new BN(cfg.pulseDisputeWindowSlots.toString()))
+          .accounts({ config: configPda, admin })
+          .rpc()
+      );
+    }
+  });
+  it("dispute_pulse: re-revealing a voided ticket leaves the lifetime reveal counters untouched", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const oracles = [Keypair.generate(), Keypair.generate()];
+    const oracleSetPda = await resetOracleSet(program, provider, configPda, oracles.map((o) => o.publicKey), 2);
+    await rpcConfirmed(
+      provider,
+      program.methods.setPulseDisputeWindow(new BN(10_000)).accounts({ config: configPda, admin }).rpc()
+    );
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9969) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const [attestationsPda] = PublicKey.findProgramAddressSync([Buffer.from("attest"), leU64(roundId)], program.programId);
+    const [userStatsPda] = PublicKey.findProgramAddressSync([Buffer.from("user_stats_v3"), admin.toBytes()], program.programId);
+    const [userRoundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_round"), leU64(roundId), admin.toBytes()],
+      program.programId
+    );
+    const [globalStatsPda] = PublicKey.findProgramAddressSync([Buffer.from("global_stats_v4")], program.programId);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(780), new BN(commitDeadline), new BN(slot0 + 300), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+
+    const nonce = 71;
+    const salt = crypto.randomBytes(32);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, salt)))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+
+    const counters = async () => {
+      const c = await program.account.config.fetch(configPda, "confirmed");
+      const gs = await program.account.globalStats.fetch(globalStatsPda, "confirmed");
+      const us = await program.account.userStats.fetch(userStatsPda, "confirmed");
+      const urs = await program.account.userRoundStats.fetch(userRoundPda, "confirmed");
+      return [
+        c.totalRevealed,
+        gs.totalReveals,
+        gs.totalWins,
+        us.ticketsRevealed,
+        us.gamesWon,
+        us.gamesLost,
+        urs.revealed,
+      ].map((v) => Number(v.toString()));
+    };
+    const setPulse = (fill) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, fill)))
+          .accounts({ config: configPda, round: roundPda, admin })
+          .rpc()
+      );
+    const reveal = () =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .revealTicket(new BN(roundId), new BN(nonce), 1, Array.from(salt))
+          .accounts({ config: configPda, round: roundPda, ticket: ticketPda, user: admin, userRoundStats: userRoundPda })
+          .rpc()
+      );
+
+    try {
+      while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      // one oracle attests a pulse the mock path then contradicts
+      const attested = Buffer.alloc(64, 9);
+      const edIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: oracles[0].secretKey,
+        message: expectedPulseMsg(program.programId, roundId, 780, attested),
+      });
+      const attestIx = await program.methods
+        .submitAttestation(new BN(roundId), Array.from(attested))
+        .accounts({
+          payer: admin,
+          config: configPda,
+          oracleSet: oracleSetPda,
+          round: roundPda,
+          attestations: attestationsPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+      await provider.sendAndConfirm(new Transaction().add(edIx, attestIx), [], { commitment: "confirmed" });
+
+      const before = await counters();
+      await setPulse(0xff);
+      await reveal();
+      const afterFirst = await counters();
+
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .disputePulse(new BN(roundId))
+          .accounts({ config: configPda, oracleSet: oracleSetPda, round: roundPda, attestations: attestationsPda, admin })
+          .rpc()
+      );
+      const { pulseDisputedSlot } = await program.account.round.fetch(roundPda, "confirmed");
+      while ((await provider.connection.getSlot("confirmed")) <= Number(pulseDisputedSlot.toString())) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      // the replacement pulse flips the outcome; only the round counters see the re-reveal
+      await setPulse(0x00);
+      await reveal();
+      const afterSecond = await counters();
+
+      const deltas = afterFirst.map((v, i) => v - before[i]);
+      if (deltas.join() !== [1, 1, 1, 1, 1, 0, 1].join()) {
+        throw new Error(`first reveal should count one winning reveal everywhere, got ${deltas}`);
+      }
+      if (afterSecond.join() !== afterFirst.join()) {
+        throw new Error(`re-reveal must not move lifetime counters: ${afterFirst} -> ${afterSecond}`);
+      }
+      const r = await program.account.round.fetch(roundPda, "confirmed");
+      if (Number(r.revealedCount) !== 1 || Number(r.winCount) !== 0) {
+        throw new Error(`round counters should hold only the re-reveal, got revealed=${r.revealedCount} wins=${r.winCount}`);
+      }
+    } finally {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseDisputeWindow(new BN(cfg.pulseDisputeWindowSlots.toString()))
+          .accounts({ config: configPda, admin })
+          .rpc()
+      );
+    }
+  });
+  it("dispute_pulse: admin only, inside the window, below the oracle threshold, and only against contradicting attestations", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const oracles = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const oracleSetPda = await resetOracleSet(program, provider, configPda, oracles.map((o) => o.publicKey), 2);
+    const setWindow = (slots) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setPulseDisputeWindow(new BN(slots)).accounts({ config: configPda, admin }).rpc()
+      );
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const pulseIndexTarget = 781;
+    const base = (Math.floor(Date.now() / 1000) + 10_037) % 1_000_000;
+    const [below, agreeing, unrecorded, atThreshold, aboveThreshold, late, afterPulse] = [0, 1, 2, 3, 4, 5, 6].map((i) => {
+      const [attestationsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("attest"), leU64(base + i)],
+        program.programId
+      );
+      return { roundId: base + i, attestationsPda, ...deriveRoundPdas(program.programId, base + i) };
+    });
+    for (const r of [below, agreeing, unrecorded, atThreshold, aboveThreshold, late, afterPulse]) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(r.roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot0 + 300), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: r.roundPda,
+            vault: r.vaultPda,
+            timlgVault: r.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+    }
+
+    const dispute = (r, signer) =>
+      program.methods
+        .disputePulse(new BN(r.roundId))
+        .accounts({
+          config: configPda,
+          oracleSet: oracleSetPda,
+          round: r.roundPda,
+          attestations: r.attestationsPda,
+          admin: signer ? signer.publicKey : admin,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+    const MOCK_PULSE = Buffer.alloc(64, 7);
+    const mockPulse = (r) =>
+      rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseMock(new BN(r.roundId), Array.from(MOCK_PULSE))
+          .accounts({ config: configPda, round: r.roundPda, admin })
+          .rpc()
+      );
+    const attest = async (r, oracle, pulse) => {
+      const edIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: oracle.secretKey,
+        message: expectedPulseMsg(program.programId, r.roundId, pulseIndexTarget, pulse),
+      });
+      const ix = await program.methods
+        .submitAttestation(new BN(r.roundId), Array.from(pulse))
+        .accounts({
+          payer: admin,
+          config: configPda,
+          oracleSet: oracleSetPda,
+          round: r.roundPda,
+          attestations: r.attestationsPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+      return provider.sendAndConfirm(new Transaction().add(edIx, ix), [], { commitment: "confirmed" });
+    };
+    const multisigPulse = (r, signers) =>
+      sendPulseMultisig(program, provider, {
+        configPda,
+        oracleSetPda,
+        roundPda: r.roundPda,
+        roundId: r.roundId,
+        pulseIndexTarget,
+        pulse: crypto.randomBytes(64),
+        signers,
+      });
+
+    try {
+      await setWindow(10_000);
+      while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+
+      // below threshold, contradicted by an oracle, inside the window: only the admin may dispute
+      await attest(below, oracles[0], crypto.randomBytes(64));
+      await mockPulse(below);
+      await expectTxFail(() => dispute(below, Keypair.generate()), "Unauthorized");
+      await rpcConfirmed(provider, dispute(below));
+      if ((await program.account.round.fetch(below.roundPda, "confirmed")).pulseSet) {
+        throw new Error("dispute inside the window should clear the pulse");
+      }
+
+      // evidence may arrive after the bad pulse landed; a successful dispute resets it
+      await mockPulse(afterPulse);
+      await attest(afterPulse, oracles[1], crypto.randomBytes(64));
+      await rpcConfirmed(provider, dispute(afterPulse));
+      const reopened = await program.account.round.fetch(afterPulse.roundPda, "confirmed");
+      if (reopened.pulseSet || reopened.pulseDisputedSlot.toNumber() === 0) {
+        throw new Error("dispute on post-pulse evidence should clear the pulse and stamp pulse_disputed_slot");
+      }
+      const att = await program.account.pulseAttestations.fetch(afterPulse.attestationsPda, "confirmed");
+      if (att.count !== 0 || att.attested !== 0) throw new Error("dispute should reset the attestations");
+
+      // no evidence, no dispute: the attestations agree with the stored pulse, or don't exist
+      await attest(agreeing, oracles[0], MOCK_PULSE);
+      await mockPulse(agreeing);
+      await expectTxFail(() => dispute(agreeing), "PulseDisputeNotAllowed");
+      await mockPulse(unrecorded);
+      await expectTxFail(() => dispute(unrecorded), "AccountNotInitialized");
+
+      // a pulse signed by the full threshold (or more) cannot be disputed, whatever was attested
+      await attest(atThreshold, oracles[2], crypto.randomBytes(64));
+      await attest(aboveThreshold, oracles[2], crypto.randomBytes(64));
+      await multisigPulse(atThreshold, oracles.slice(0, 2));
+      await multisigPulse(aboveThreshold, oracles);
+      const counts = [
+        (await program.account.round.fetch(atThreshold.roundPda, "confirmed")).pulseAttestations,
+        (await program.account.round.fetch(aboveThreshold.roundPda, "confirmed")).pulseAttestations,
+      ];
+      if (counts[0] !== 2 || counts[1] !== 3) throw new Error(`unexpected attestation counts ${counts}`);
+      await expectTxFail(() => dispute(atThreshold), "PulseDisputeNotAllowed");
+      await expectTxFail(() => dispute(aboveThreshold), "PulseDisputeNotAllowed");
+
+      // outside the window: shrink it to 1 slot and let it lapse
+      await attest(late, oracles[0], crypto.randomBytes(64));
+      await mockPulse(late);
+      await setWindow(1);
+      const { pulseSetSlot } = await program.account.round.fetch(late.roundPda, "confirmed");
+      while ((await provider.connection.getSlot("confirmed")) <= Number(pulseSetSlot.toString()) + 1) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      await expectTxFail(() => dispute(late), "PulseDisputeWindowClosed");
+
+      // window 0 turns disputes off entirely
+      await setWindow(0);
+      await expectTxFail(() => dispute(late), "PulseDisputeWindowClosed");
+    } finally {
+      await setWindow(cfg.pulseDisputeWindowSlots.toString());
+    }
+  });
+  it("recover_funds still refunds the stake when treasury_sol cannot cover the service fee", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9973) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const revealDeadline = slot0 + 16;
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(782), new BN(slot0 + 8), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setRoundMinParticipants(new BN(roundId), new BN(2))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    const setServiceFee = (lamports) =>
+      rpcConfirmed(
+        provider,
+        program.methods.updateSolServiceFee(new BN(lamports)).accounts({ config: configPda, admin }).rpc()
+      );
+    const FEE = 5000;
+    await setServiceFee(FEE);
+
+    const nonce = 19;
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, Buffer.alloc(32, 9))))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    const recover = () =>
+      program.methods
+        .recoverFunds(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          ticket: ticketPda,
+          user: admin,
+          userTokenAccount: userTIMLGAta,
+          timlgVault: timlgVaultPda,
+          vault: vaultPda,
+          timlgMint,
+          treasurySol: treasurySolPda,
+          feePayer: admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    // empty treasury_sol down to rent: the fee refund can no longer be paid
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .withdrawTreasurySol(new BN(0))
+        .accounts({ config: configPda, treasurySol: treasurySolPda, admin, systemProgram: SystemProgram.programId })
+        .rpc()
+    );
+    const treasuryBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
+    const stakeBefore = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    try {
+      await rpcConfirmed(provider, recover());
+    } finally {
+      await setServiceFee(cfg.solServiceFeeLamports.toString());
+    }
+
+    if ((await provider.connection.getBalance(treasurySolPda, "confirmed")) !== treasuryBefore) {
+      throw new Error("an unfunded fee refund should be skipped, not paid");
+    }
+    const stakeAfter = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    if (stakeAfter - stakeBefore !== BigInt(cfg.stakeAmount.toString())) {
+      throw new Error("the stake refund must go through regardless of treasury_sol");
+    }
+    if ((await provider.connection.getAccountInfo(ticketPda, "confirmed")) !== null) {
+      throw new Error("recover_funds should close the ticket");
+    }
+  });
+  it("commit deadline is inclusive on every commit entrypoint: a commit landing in commit_deadline_slot passes, +1 fails", async () => {
+    const adminKp = provider.wallet.payer;
+    const admin = adminKp.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+
+    // the signed paths stake from escrow; the delegated path needs a round-scoped approval
+    const { userEscrowPda, userEscrowAtaPda } = await ensureUserEscrow(program, provider, {
+      configPda,
+      timlgMint,
+      userPk: admin,
+    });
+    await ensureEscrowFunds(program, provider, {
+      configPda,
+      timlgMint,
+      userPk: admin,
+      userEscrowPda,
+      userEscrowAtaPda,
+      userTIMLGAta,
+      neededRaw: stake * 3n,
+    });
+    const [userStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stats_v3"), admin.toBytes()],
+      program.programId
+    );
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 30;
+    const revealDeadline = slot0 + 60;
+    const base = (Math.floor(Date.now() / 1000) + 9977) % 1_000_000;
+    const [hashed, publicRound] = [base, base + 1].map((roundId) => ({ roundId, ...deriveRoundPdas(program.programId, roundId) }));
+    for (const r of [hashed, publicRound]) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(r.roundId), new BN(783), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: r.roundPda,
+            vault: r.vaultPda,
+            timlgVault: r.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setRoundPublicMode(new BN(publicRound.roundId), true)
+        .accounts({ config: configPda, round: publicRound.roundPda, admin })
+        .rpc()
+    );
+    await splApprove(provider.connection, adminKp, userTIMLGAta, hashed.roundPda, adminKp, stake * 2n, [], {
+      commitment: "confirmed",
+    });
+
+    const commitment = (r, nonce) => commitHash(r.roundId, admin, nonce, 1, Buffer.alloc(32, 11));
+    const ticket = (r, nonce) => deriveTicketPda(program.programId, r.roundId, admin, nonce);
+    const signedBy = (r, nonce) =>
+      Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: adminKp.secretKey,
+        message: expectedCommitMsgV3(program.programId, r.roundId, admin, nonce, commitment(r, nonce), commitDeadline, revealDeadline, 0),
+      });
+    const plainAccounts = (r, nonce) => ({
+      config: configPda,
+      round: r.roundPda,
+      timlgMint,
+      timlgVault: r.timlgVaultPda,
+      ticket: ticket(r, nonce),
+      user: admin,
+      userTIMLGAta,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+    const batchAccounts = (r) => ({
+      config: configPda,
+      round: r.roundPda,
+      timlgMint,
+      timlgVault: r.timlgVaultPda,
+      user: admin,
+      userTIMLGAta,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+    const signedEntry = (r, nonce) => ({ user: admin, nonce: new BN(nonce), commitment: Array.from(commitment(r, nonce)) });
+
+    // each entrypoint builds the ixs of one commit with the given nonce
+    const entrypoints = {
+      commit_ticket: async (nonce) => [
+        await program.methods
+          .commitTicket(new BN(hashed.roundId), new BN(nonce), Array.from(commitment(hashed, nonce)))
+          .accounts(plainAccounts(hashed, nonce))
+          .instruction(),
+      ],
+      commit_ticket_weighted: async (nonce) => [
+        await program.methods
+          .commitTicketWeighted(new BN(hashed.roundId), new BN(nonce), Array.from(commitment(hashed, nonce)), 1)
+          .accounts(plainAccounts(hashed, nonce))
+          .instruction(),
+      ],
+      commit_ticket_public: async (nonce) => [
+        await program.methods
+          .commitTicketPublic(new BN(publicRound.roundId), new BN(nonce), 1)
+          .accounts(plainAccounts(publicRound, nonce))
+          .instruction(),
+      ],
+      commit_ticket_delegated: async (nonce) => [
+        await program.methods
+          .commitTicketDelegated(new BN(hashed.roundId), new BN(nonce), Array.from(commitment(hashed, nonce)))
+          .accounts({ ...plainAccounts(hashed, nonce), payer: admin })
+          .instruction(),
+      ],
+      commit_batch: async (nonce) => [
+        await program.methods
+          .commitBatch(new BN(hashed.roundId), [{ nonce: new BN(nonce), commitment: Array.from(commitment(hashed, nonce)) }])
+          .accounts(batchAccounts(hashed))
+          .remainingAccounts([{ pubkey: ticket(hashed, nonce), isSigner: false, isWritable: true }])
+          .instruction(),
+      ],
+      commit_batch_signed: async (nonce) => [
+        signedBy(hashed, nonce),
+        await program.methods
+          .commitBatchSigned(new BN(hashed.roundId), [signedEntry(hashed, nonce)], null)
+          .accounts({
+            config: configPda,
+            round: hashed.roundPda,
+            timlgMint,
+            timlgVault: hashed.timlgVaultPda,
+            payer: admin,
+            userEscrow: userEscrowPda,
+            userEscrowAta: userEscrowAtaPda,
+            user: admin,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            relayerTimlgAta: null,
+          })
+          .remainingAccounts([{ pubkey: ticket(hashed, nonce), isSigner: false, isWritable: true }])
+          .instruction(),
+      ],
+      commit_ticket_signed: async (nonce) => [
+        signedBy(hashed, nonce),
+        await program.methods
+          .commitTicketSigned(new BN(hashed.roundId), new BN(nonce), Array.from(commitment(hashed, nonce)))
+          .accounts({
+            config: configPda,
+            round: hashed.roundPda,
+            timlgVault: hashed.timlgVaultPda,
+            payer: admin,
+            ticket: ticket(hashed, nonce),
+            userEscrow: userEscrowPda,
+            userEscrowAta: userEscrowAtaPda,
+            user: admin,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .instruction(),
+      ],
+      commit_batch_signed_multi: async (nonce) => [
+        signedBy(hashed, nonce),
+        await program.methods
+          .commitBatchSignedMulti(new BN(hashed.roundId), [signedEntry(hashed, nonce)])
+          .accounts({
+            config: configPda,
+            round: hashed.roundPda,
+            timlgVault: hashed.timlgVaultPda,
+            payer: admin,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([
+            { pubkey: userEscrowPda, isWritable: true, isSigner: false },
+            { pubkey: userEscrowAtaPda, isWritable: true, isSigner: false },
+            { pubkey: userStatsPda, isWritable: true, isSigner: false },
+            {
+              pubkey: PublicKey.findProgramAddressSync(
+                [Buffer.from("user_round"), leU64(hashed.roundId), admin.toBytes()],
+                program.programId
+              )[0],
+              isWritable: true,
+              isSigner: false,
+            },
+            { pubkey: ticket(hashed, nonce), isWritable: true, isSigner: false },
+          ])
+          .instruction(),
+      ],
+    };
+
+    // Sent without preflight so a rejected commit still lands and reports its slot.
+    const land = async (ixs) => {
+      const tx = new Transaction().add(...ixs);
+      tx.feePayer = admin;
+      tx.recentBlockhash = (await provider.connection.getLatestBlockhash("confirmed")).blockhash;
+      tx.sign(adminKp);
+      const sig = await provider.connection.sendRawTransaction(tx.serialize(), { skipPreflight: true });
+      await provider.connection.confirmTransaction(sig, "confirmed");
+      const res = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return { slot: res.slot, err: res.meta.err, logs: (res.meta.logMessages || []).join("\n") };
+    };
+    const expectOutcome = (name, { slot, err, logs }) => {
+      if (slot <= commitDeadline) {
+        if (err) throw new Error(`${name} landed in slot ${slot} <= deadline ${commitDeadline} but failed:\n${logs}`);
+      } else if (!err || !logs.includes("CommitClosed")) {
+        throw new Error(`${name} landed in slot ${slot} > deadline ${commitDeadline} without CommitClosed:\n${logs}`);
+      }
+    };
+
+    const names = Object.keys(entrypoints);
+    const atDeadline = await Promise.all(names.map((name, i) => entrypoints[name](100 + i)));
+    const afterDeadline = await Promise.all(names.map((name, i) => entrypoints[name](200 + i)));
+
+    // Around the deadline, whichever slot each commit lands in decides the outcome (the exact
+    // slot arithmetic is pinned by the commit_window_includes_deadline_slot unit test).
+    while ((await provider.connection.getSlot("processed")) < commitDeadline - 1) {
+      await new Promise((r) => setTimeout(r, 50));
+    }
+    const landed = await Promise.all(atDeadline.map(land));
+    landed.forEach((l, i) => expectOutcome(names[i], l));
+
+    // past it: every entrypoint rejects
+    while ((await provider.connection.getSlot("confirmed")) <= commitDeadline) {
+      await new Promise((r) => setTimeout(r, 50));
+    }
+    const late = await Promise.all(afterDeadline.map(land));
+    late.forEach((l, i) => {
+      if (l.slot <= commitDeadline) throw new Error(`${names[i]} should land after the deadline`);
+      expectOutcome(names[i], l);
+    });
+  });
+  it("submit_attestation + finalize_pulse: threshold, mismatched pulse, duplicates, non-members, oracle set changes", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
+
+    const oracles = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const oracleSetPda = await resetOracleSet(program, provider, configPda, oracles.map((o) => o.publicKey), 2);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
+    const pulseIndexTarget = 784;
+    const base = (Math.floor(Date.now() / 1000) + 9979) % 1_000_000;
+    const [reached, changed] = [base, base + 1].map((roundId) => {
+      const [attestationsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("attest"), leU64(roundId)],
+        program.programId
+      );
+      return { roundId, attestationsPda, ...deriveRoundPdas(program.programId, roundId) };
+    });
+    for (const r of [reached, changed]) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(r.roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot0 + 300), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: r.roundPda,
+            vault: r.vaultPda,
+            timlgVault: r.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+    }
+
+    const attest = async (r, oracle, pulse) => {
+      const edIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: oracle.secretKey,
+        message: expectedPulseMsg(program.programId, r.roundId, pulseIndexTarget, pulse),
+      });
+      const ix = await program.methods
+        .submitAttestation(new BN(r.roundId), Array.from(pulse))
+        .accounts({
+          payer: admin,
+          config: configPda,
+          oracleSet: oracleSetPda,
+          round: r.roundPda,
+          attestations: r.attestationsPda,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+      return provider.sendAndConfirm(new Transaction().add(edIx, ix), [], { commitment: "confirmed" });
+    };
+    const finalize = (r) =>
+      program.methods
+        .finalizePulse(new BN(r.roundId))
+        .accounts({
+          payer: admin,
+          config: configPda,
+          oracleSet: oracleSetPda,
+          round: r.roundPda,
+          attestations: r.attestationsPda,
+        })
+        .rpc();
+    const countOf = async (r) => (await program.account.pulseAttestations.fetch(r.attestationsPda, "confirmed")).count;
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    const pulse = crypto.randomBytes(64);
+    await attest(reached, oracles[0], pulse);
+    await expectTxFail(() => finalize(reached), "InsufficientAttestations");
+
+    // rejected attestations do not count
+    await expectTxFail(() => attest(reached, oracles[0], pulse), "DuplicateAttestation");
+    await expectTxFail(() => attest(reached, oracles[1], crypto.randomBytes(64)), "AttestationPulseMismatch");
+    await expectTxFail(() => attest(reached, Keypair.generate(), pulse), "OracleNotFound");
+    if ((await countOf(reached)) !== 1) throw new Error("only the first attestation should be recorded");
+
+    await attest(reached, oracles[1], pulse);
+    await rpcConfirmed(provider, finalize(reached));
+    const r = await program.account.round.fetch(reached.roundPda, "confirmed");
+    if (!r.pulseSet || r.state !== 1) throw new Error("finalize_pulse should set the pulse");
+    if (!Buffer.from(r.pulse).equals(pulse)) throw new Error("round pulse should be the attested pulse");
+    if (r.pulseAttestations !== 2) throw new Error(`pulse_attestations should be 2, got ${r.pulseAttestations}`);
+    await expectTxFail(() => finalize(reached), "PulseAlreadySet");
+
+    // the set changes between the attestations and finalize: they no longer count
+    const changedPulse = crypto.randomBytes(64);
+    await attest(changed, oracles[0], changedPulse);
+    await attest(changed, oracles[1], changedPulse);
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .addOracle(Keypair.generate().publicKey)
+        .accounts({ config: configPda, oracleSet: oracleSetPda, admin })
+        .rpc()
+    );
+    await expectTxFail(() => finalize(changed), "OracleSetChanged");
+    await expectTxFail(() => attest(changed, oracles[2], changedPulse), "OracleSetChanged");
+    if ((await program.account.round.fetch(changed.roundPda, "confirmed")).pulseSet) {
+      throw new Error("a stale attestation set must not publish a pulse");
+    }
+  });
+  it("pari-mutuel: all-win and all-lose rounds, rounding dust, weighted winners", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+    const feeBps = cfg.feeExempt.some((pk) => pk.equals(admin)) ? 0n : BigInt(tok.rewardFeeBps);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 40;
+    const revealDeadline = slot0 + 70;
+    const base = (Math.floor(Date.now() / 1000) + 9983) % 1_000_000;
+    // win = guess 1 under an all-ones pulse; weights need max_weight >= 2
+    const plans = {
+      allWin: [{ nonce: 1, weight: 1, win: true }, { nonce: 2, weight: 1, win: true }],
+      allLose: [{ nonce: 1, weight: 1, win: false }, { nonce: 2, weight: 1, win: false }],
+      dust: [{ nonce: 1, weight: 1, win: true }, { nonce: 2, weight: 2, win: true }, { nonce: 3, weight: 2, win: false }],
+    };
+    const rounds = {};
+    Object.keys(plans).forEach((name, i) => {
+      const roundId = base + i;
+      rounds[name] = {
+        roundId,
+        ...deriveRoundPdas(program.programId, roundId),
+        tickets: plans[name].map((t) => ({
+          ...t,
+          salt: Buffer.alloc(32, 40 + t.nonce),
+          pda: deriveTicketPda(program.programId, roundId, admin, t.nonce),
+        })),
+      };
+    });
+    const ticketAccounts = (r) => r.tickets.map((t) => ({ pubkey: t.pda, isSigner: false, isWritable: true }));
+
+    const setRewardMode = (mode) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setRewardMode(mode).accounts({ config: configPda, tokenomics: tokenomicsPda, admin }).rpc()
+      );
+    const claim = (r, t) =>
+      program.methods
+        .claimReward(new BN(r.roundId), new BN(t.nonce))
+        .accounts({
+          config: configPda,
+          round: r.roundPda,
+          ticket: t.pda,
+          user: admin,
+          timlgMint,
+          timlgVault: r.timlgVaultPda,
+          userTIMLGAta,
+          tokenomics: tokenomicsPda,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    // user's net gain from one claim: the weighted stake back plus its share, minus the fee
+    const claimDelta = async (r, t) => {
+      const before = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      await rpcConfirmed(provider, claim(r, t));
+      return (await getTokenAmountRaw(provider.connection, userTIMLGAta)) - before;
+    };
+
+    await rpcConfirmed(provider, program.methods.setMaxWeight(2).accounts({ config: configPda, admin }).rpc());
+    try {
+      for (const r of Object.values(rounds)) {
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .createRound(new BN(r.roundId), new BN(785), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+            .accounts({
+              config: configPda,
+              timlgMint,
+              round: r.roundPda,
+              vault: r.vaultPda,
+              timlgVault: r.timlgVaultPda,
+              admin,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: SystemProgram.programId,
+              rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            })
+            .rpc()
+        );
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .commitBatchWeighted(
+              new BN(r.roundId),
+              r.tickets.map((t) => ({
+                nonce: new BN(t.nonce),
+                commitment: Array.from(commitHash(r.roundId, admin, t.nonce, t.win ? 1 : 0, t.salt)),
+                weight: t.weight,
+              }))
+            )
+            .accounts({
+              config: configPda,
+              round: r.roundPda,
+              timlgMint,
+              timlgVault: r.timlgVaultPda,
+              user: admin,
+              userTIMLGAta,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: SystemProgram.programId,
+            })
+            .remainingAccounts(ticketAccounts(r))
+            .rpc()
+        );
+      }
+
+      // seed the dust round so its pool never splits evenly over its 3 win units
+      const seed = (2n * stake) % 3n === 2n ? 2n : 1n;
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .fundTimlgVault(new BN(rounds.dust.roundId), new BN(seed.toString()))
+          .accounts({
+            config: configPda,
+            round: rounds.dust.roundPda,
+            timlgVault: rounds.dust.timlgVaultPda,
+            source: userTIMLGAta,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc()
+      );
+
+      while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      for (const r of Object.values(rounds)) {
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .setPulseMock(new BN(r.roundId), Array.from(Buffer.alloc(64, 0xff)))
+            .accounts({ config: configPda, round: r.roundPda, admin })
+            .rpc()
+        );
+        await rpcConfirmed(
+          provider,
+          program.methods
+            .revealBatch(
+              new BN(r.roundId),
+              r.tickets.map((t) => ({ nonce: new BN(t.nonce), guess: t.win ? 1 : 0, salt: Array.from(t.salt) }))
+            )
+            .accounts({ config: configPda, round: r.roundPda, user: admin })
+            .remainingAccounts(ticketAccounts(r))
+            .rpc()
+        );
+      }
+      while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+
+      // the mode is snapshotted at settle; claims then follow the round, not tokenomics
+      await setRewardMode(1);
+      try {
+        for (const r of Object.values(rounds)) {
+          await rpcConfirmed(
+            provider,
+            program.methods
+              .settleRoundTokens(new BN(r.roundId))
+              .accounts({
+                config: configPda,
+                round: r.roundPda,
+                timlgMint,
+                timlgVault: r.timlgVaultPda,
+                treasury: treasuryPda,
+                tokenomics: tokenomicsPda,
+                rewardFeePool: rewardFeePoolPda,
+                replicationPool: replicationPoolPda,
+                admin,
+                tokenProgram: TOKEN_PROGRAM_ID,
+              })
+              .remainingAccounts(ticketAccounts(r))
+              .rpc()
+          );
+        }
+      } finally {
+        await setRewardMode(tok.rewardMode);
+      }
+
+      // all win: an empty loser pool, every winner just gets the stake back
+      const allWin = await program.account.round.fetch(rounds.allWin.roundPda, "confirmed");
+      if (allWin.rewardMode !== 1 || allWin.loserStakePool.toString() !== "0") {
+        throw new Error("all-win round should be pari-mutuel with an empty loser pool");
+      }
+      for (const t of rounds.allWin.tickets) {
+        const delta = await claimDelta(rounds.allWin, t);
+        if (delta !== stake) throw new Error(`all-win claim should refund just the stake, got ${delta}`);
+      }
+
+      // all lose: nothing burned at settle, the pool waits in the vault with no one to pay
+      const allLose = await program.account.round.fetch(rounds.allLose.roundPda, "confirmed");
+      if (BigInt(allLose.loserStakePool.toString()) !== 2n * stake) {
+        throw new Error(`all-lose pool should hold both stakes, got ${allLose.loserStakePool}`);
+      }
+      if ((await getTokenAmountRaw(provider.connection, rounds.allLose.timlgVaultPda)) !== 2n * stake) {
+        throw new Error("all-lose stakes should stay in the vault until sweep");
+      }
+      await expectTxFail(() => claim(rounds.allLose, rounds.allLose.tickets[0]), "NotWinner");
+
+      // dust + weights: 3 win units share the pool, the remainder stays in the vault
+      const pool = 2n * stake + seed;
+      const dust = await program.account.round.fetch(rounds.dust.roundPda, "confirmed");
+      if (BigInt(dust.loserStakePool.toString()) !== pool) {
+        throw new Error(`pool should be the loser stake plus the seed, got ${dust.loserStakePool}`);
+      }
+      const share = pool / 3n;
+      for (const t of rounds.dust.tickets.filter((t) => t.win)) {
+        const w = BigInt(t.weight);
+        const reward = share * w;
+        const expected = stake * w + reward - (reward * feeBps) / 10_000n;
+        const delta = await claimDelta(rounds.dust, t);
+        if (delta !== expected) throw new Error(`weight-${t.weight} winner should get ${expected}, got ${delta}`);
+      }
+      const leftover = await getTokenAmountRaw(provider.connection, rounds.dust.timlgVaultPda);
+      if (leftover !== pool - share * 3n || leftover === 0n) {
+        throw new Error(`only the rounding dust should stay in the vault, got ${leftover}`);
+      }
+    } finally {
+      await rpcConfirmed(provider, program.methods.setMaxWeight(0).accounts({ config: configPda, admin }).rpc());
+    }
+  });
+  it("recover_funds_anyone_batch is idempotent over overlapping and repeated ticket sets", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta, treasurySolPda } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+
+    const roundId = (Math.floor(Date.now() / 1000) + 9989) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const revealDeadline = slot0 + 20;
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(786), new BN(slot0 + 10), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+    // three tickets against a minimum of four: the round is voided and refundable
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setRoundMinParticipants(new BN(roundId), new BN(4))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    const nonces = [1, 2, 3];
+    const [a, b, c] = nonces.map((n) => deriveTicketPda(program.programId, roundId, admin, n));
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(
+          new BN(roundId),
+          nonces.map((n) => ({ nonce: new BN(n), commitment: Array.from(commitHash(roundId, admin, n, 1, Buffer.alloc(32, 13))) }))
+        )
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([a, b, c].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .rpc()
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+
+    // every ticket recorded the same service fee, paid by the committer
+    const { solFeePaid: feeBn, feePayer } = await program.account.ticket.fetch(a, "confirmed");
+    const fee = BigInt(feeBn.toString());
+
+    // remaining_accounts per entry: [ticket, user TIMLG ATA, fee payer]
+    const recoverBatch = (tickets) =>
+      program.methods
+        .recoverFundsAnyoneBatch(new BN(roundId))
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgVault: timlgVaultPda,
+          cranker: admin,
+          treasurySol: treasurySolPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          tickets.flatMap((pubkey) => [
+            { pubkey, isSigner: false, isWritable: true },
+            { pubkey: userTIMLGAta, isSigner: false, isWritable: true },
+            { pubkey: feePayer, isSigner: false, isWritable: true },
+          ])
+        )
+        .rpc();
+    // each call must refund exactly the tickets it has not refunded before, stake and fee
+    const expectRecover = async (tickets, newlyRefunded, committedAfter) => {
+      const before = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+      const treasuryBefore = BigInt(await provider.connection.getBalance(treasurySolPda, "confirmed"));
+      await rpcConfirmed(provider, recoverBatch(tickets));
+      const paid = (await getTokenAmountRaw(provider.connection, userTIMLGAta)) - before;
+      if (paid !== stake * BigInt(newlyRefunded)) {
+        throw new Error(`expected ${newlyRefunded} stake(s) refunded, got ${paid}`);
+      }
+      const feesOut = treasuryBefore - BigInt(await provider.connection.getBalance(treasurySolPda, "confirmed"));
+      if (feesOut !== fee * BigInt(newlyRefunded)) {
+        throw new Error(`expected ${newlyRefunded} service fee(s) refunded, got ${feesOut} lamports`);
+      }
+      const r = await program.account.round.fetch(roundPda, "confirmed");
+      if (r.committedCount.toNumber() !== committedAfter) {
+        throw new Error(`committed_count should be ${committedAfter}, got ${r.committedCount}`);
+      }
+    };
+
+    await expectRecover([a, b], 2, 1);
+    await expectRecover([b, c], 1, 0);
+    await expectRecover([a, b, c], 0, 0);
+    await expectRecover([c, c], 0, 0);
+    if ((await getTokenAmountRaw(provider.connection, timlgVaultPda)) !== 0n) {
+      throw new Error("every stake should have left the vault exactly once");
+    }
+    // refunded tickets stay open but can no longer reveal or claim
+    for (const t of [a, b, c]) {
+      const ticket = await program.account.ticket.fetch(t, "confirmed");
+      if (ticket.stakePaid || !ticket.processed) throw new Error("a refunded ticket should drop stake_paid");
+    }
+  });
+  it("extend_reveal_deadline cannot revive a pulse-less round past its reveal deadline", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
 
-    await expectTxFail(async () => {
-      const tx = new Transaction().add(edIx, anchorIx);
-      tx.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
+    const roundId = (Math.floor(Date.now() / 1000) + 10_025) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const revealDeadline = slot0 + 16;
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(795), new BN(slot0 + 8), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+    const extend = () =>
+      program.methods
+        .extendRevealDeadline(new BN(roundId), new BN(5))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc();
 
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-        commitment: "confirmed",
-      });
-    }, "Ed25519PubkeyMismatch");
+    // still live: the extension goes through
+    await rpcConfirmed(provider, extend());
+    const extended = (await program.account.round.fetch(roundPda, "confirmed")).revealDeadlineSlot.toNumber();
+    if (extended !== revealDeadline + 5) throw new Error("the live round should be extended");
+
+    while ((await provider.connection.getSlot("confirmed")) <= extended) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await expectTxFail(extend, "RevealClosed");
   });
- 
-  it("hardening: set_pulse_signed fails if ed25519 ix is not immediately before (MissingOrInvalidEd25519Ix)", async () => {
+  it("public-guess rounds: guesses in clear at commit, resolved at finalize; commit-reveal paths rejected", async () => {
     const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
 
-    const oracle = Keypair.generate();
-    const relayer = Keypair.generate();
+    const roundId = (Math.floor(Date.now() / 1000) + 9991) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 24;
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .createRound(new BN(roundId), new BN(787), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc()
+    );
+    const setPublic = (flag) =>
+      program.methods
+        .setRoundPublicMode(new BN(roundId), flag)
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc();
+    await rpcConfirmed(provider, setPublic(true));
+
+    const ticketPda = (nonce) => deriveTicketPda(program.programId, roundId, admin, nonce);
+    const commitAccounts = (nonce) => ({
+      config: configPda,
+      round: roundPda,
+      timlgMint,
+      timlgVault: timlgVaultPda,
+      ticket: ticketPda(nonce),
+      user: admin,
+      userTIMLGAta,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+    const commitPublic = (nonce, guess) =>
+      program.methods.commitTicketPublic(new BN(roundId), new BN(nonce), guess).accounts(commitAccounts(nonce)).rpc();
 
-    // fund relayer
-    {
-      const sig = await provider.connection.requestAirdrop(
-        relayer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
+    // hashed commits and out-of-range guesses are refused
+    await expectTxFail(
+      () =>
+        program.methods
+          .commitTicket(new BN(roundId), new BN(9), Array.from(commitHash(roundId, admin, 9, 1, Buffer.alloc(32, 1))))
+          .accounts(commitAccounts(9))
+          .rpc(),
+      "RoundModeMismatch"
+    );
+    await expectTxFail(() => commitPublic(9, 2), "InvalidGuess");
+
+    await rpcConfirmed(provider, commitPublic(1, 1));
+    await rpcConfirmed(provider, commitPublic(2, 0));
+    const t1 = await program.account.ticket.fetch(ticketPda(1), "confirmed");
+    if (t1.guess !== 1 || t1.revealed) throw new Error("public ticket should store its guess unrevealed");
+
+    // the mode is frozen once the round has tickets
+    await expectTxFail(() => setPublic(false), "RoundNotEmpty");
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xff)))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await expectTxFail(
+      () =>
+        program.methods
+          .revealBatch(new BN(roundId), [{ nonce: new BN(1), guess: 1, salt: Array.from(Buffer.alloc(32, 0)) }])
+          .accounts({ config: configPda, round: roundPda, user: admin })
+          .remainingAccounts([{ pubkey: ticketPda(1), isSigner: false, isWritable: true }])
+          .rpc(),
+      "PublicRoundRevealNotAllowed"
+    );
+
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .finalizeRound(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .remainingAccounts([1, 2].map((n) => ({ pubkey: ticketPda(n), isSigner: false, isWritable: true })))
+        .rpc()
+    );
+
+    // all-ones pulse: guess 1 wins, guess 0 loses
+    const [won, lost] = await Promise.all([1, 2].map((n) => program.account.ticket.fetch(ticketPda(n), "confirmed")));
+    if (!won.revealed || !won.win) throw new Error("guess 1 should be resolved as a win");
+    if (!lost.revealed || lost.win) throw new Error("guess 0 should be resolved as a loss");
+    const round = await program.account.round.fetch(roundPda, "confirmed");
+    if (round.revealedCount.toNumber() !== 2 || round.winCount.toNumber() !== 1) {
+      throw new Error(`finalize should resolve both tickets: revealed=${round.revealedCount} wins=${round.winCount}`);
+    }
+  });
+  it("min_participants: a round at the minimum resolves normally; the setter and voided rounds reject misuse", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 20;
+    const base = (Math.floor(Date.now() / 1000) + 9997) % 1_000_000;
+    const [met, short] = [base, base + 1].map((roundId) => ({ roundId, ...deriveRoundPdas(program.programId, roundId) }));
+    const setMin = (r, n, signer) =>
+      program.methods
+        .setRoundMinParticipants(new BN(r.roundId), new BN(n))
+        .accounts({ config: configPda, round: r.roundPda, admin: signer ? signer.publicKey : admin })
+        .signers(signer ? [signer] : [])
+        .rpc();
+    const commit = (r, nonce) =>
+      program.methods
+        .commitTicket(new BN(r.roundId), new BN(nonce), Array.from(commitHash(r.roundId, admin, nonce, 1, Buffer.alloc(32, 17))))
+        .accounts({
+          config: configPda,
+          round: r.roundPda,
+          timlgMint,
+          timlgVault: r.timlgVaultPda,
+          ticket: deriveTicketPda(program.programId, r.roundId, admin, nonce),
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    const finalize = (r) =>
+      program.methods
+        .finalizeRound(new BN(r.roundId))
+        .accounts({ config: configPda, round: r.roundPda, admin })
+        .rpc();
+
+    for (const r of [met, short]) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(r.roundId), new BN(788), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: r.roundPda,
+            vault: r.vaultPda,
+            timlgVault: r.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
       );
-      await confirmSig(provider.connection, sig, "confirmed");
     }
 
-    const { configPda } = await ensureConfig(program, provider);
+    await expectTxFail(() => setMin(met, 2, Keypair.generate()), "Unauthorized");
+    await rpcConfirmed(provider, setMin(met, 2));
+    await rpcConfirmed(provider, setMin(short, 2));
+    await rpcConfirmed(provider, commit(met, 1));
+    await rpcConfirmed(provider, commit(met, 2));
+    await rpcConfirmed(provider, commit(short, 1));
+    // the minimum is fixed once tickets exist
+    await expectTxFail(() => setMin(short, 1), "RoundNotEmpty");
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    for (const r of [met, short]) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseMock(new BN(r.roundId), Array.from(Buffer.alloc(64, 0xff)))
+          .accounts({ config: configPda, round: r.roundPda, admin })
+          .rpc()
+      );
+    }
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    await rpcConfirmed(provider, finalize(met));
+    const m = await program.account.round.fetch(met.roundPda, "confirmed");
+    if (m.state !== 2 || !m.finalized) throw new Error(`a round at its minimum should finalize, got state ${m.state}`);
+
+    // even with a pulse, the short round is voided and stays that way
+    await rpcConfirmed(provider, finalize(short));
+    if ((await program.account.round.fetch(short.roundPda, "confirmed")).state !== 3) {
+      throw new Error("the under-subscribed round should be Refunding");
+    }
+    await expectTxFail(() => finalize(short), "RoundRefunding");
+    await expectTxFail(
+      () =>
+        program.methods
+          .claimReward(new BN(short.roundId), new BN(1))
+          .accounts({
+            config: configPda,
+            round: short.roundPda,
+            ticket: deriveTicketPda(program.programId, short.roundId, admin, 1),
+            user: admin,
+            timlgMint,
+            timlgVault: short.timlgVaultPda,
+            userTIMLGAta,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc(),
+      "RoundRefunding"
+    );
+  });
+  it("claim_multi_round: claims winners across rounds once, skips losers, rejects malformed groups", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+    const feeBps = cfg.feeExempt.some((pk) => pk.equals(admin)) ? 0n : BigInt(tok.rewardFeeBps);
+
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 12;
+    const revealDeadline = slot0 + 26;
+    const base = (Math.floor(Date.now() / 1000) + 10_001) % 1_000_000;
+    // nonce 1 guesses 1 (wins under an all-ones pulse), nonce 2 guesses 0 (loses)
+    const rounds = [base, base + 1].map((roundId) => ({
+      roundId,
+      ...deriveRoundPdas(program.programId, roundId),
+      win: deriveTicketPda(program.programId, roundId, admin, 1),
+      lose: deriveTicketPda(program.programId, roundId, admin, 2),
+    }));
+    const salt = Buffer.alloc(32, 19);
+
+    for (const r of rounds) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .createRound(new BN(r.roundId), new BN(789), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+          .accounts({
+            config: configPda,
+            timlgMint,
+            round: r.roundPda,
+            vault: r.vaultPda,
+            timlgVault: r.timlgVaultPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .rpc()
+      );
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .commitBatch(new BN(r.roundId), [
+            { nonce: new BN(1), commitment: Array.from(commitHash(r.roundId, admin, 1, 1, salt)) },
+            { nonce: new BN(2), commitment: Array.from(commitHash(r.roundId, admin, 2, 0, salt)) },
+          ])
+          .accounts({
+            config: configPda,
+            round: r.roundPda,
+            timlgMint,
+            timlgVault: r.timlgVaultPda,
+            user: admin,
+            userTIMLGAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([r.win, r.lose].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+          .rpc()
+      );
+    }
+
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    for (const r of rounds) {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .setPulseMock(new BN(r.roundId), Array.from(Buffer.alloc(64, 0xff)))
+          .accounts({ config: configPda, round: r.roundPda, admin })
+          .rpc()
+      );
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .revealBatch(new BN(r.roundId), [
+            { nonce: new BN(1), guess: 1, salt: Array.from(salt) },
+            { nonce: new BN(2), guess: 0, salt: Array.from(salt) },
+          ])
+          .accounts({ config: configPda, round: r.roundPda, user: admin })
+          .remainingAccounts([r.win, r.lose].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+          .rpc()
+      );
+    }
+    // no finalize_round: claims auto-finalize expired rounds
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+
+    // remaining_accounts per group: [round, ticket, timlg_vault]
+    const claimMulti = (groups) =>
+      program.methods
+        .claimMultiRound()
+        .accounts({
+          config: configPda,
+          tokenomics: tokenomicsPda,
+          user: admin,
+          timlgMint,
+          userTimlgAta: userTIMLGAta,
+          rewardFeePool: rewardFeePoolPda,
+          replicationPool: replicationPoolPda,
+          instructions: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(groups.flat().map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .rpc();
+    const [r1, r2] = rounds;
+    const groups = [
+      [r1.roundPda, r1.win, r1.timlgVaultPda],
+      [r1.roundPda, r1.lose, r1.timlgVaultPda],
+      [r2.roundPda, r2.win, r2.timlgVaultPda],
+    ];
+
+    await expectTxFail(() => claimMulti([[r1.roundPda, r1.win]]), "TicketPdaMismatch");
+    await expectTxFail(() => claimMulti([[r1.roundPda, r1.win, r2.timlgVaultPda]]), "VaultPdaMismatch");
+
+    // two winners: stake back plus the minted reward net of its fee, each
+    const fee = (stake * feeBps) / 10_000n;
+    const before = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    await rpcConfirmed(provider, claimMulti(groups));
+    const afterFirst = await getTokenAmountRaw(provider.connection, userTIMLGAta);
+    if (afterFirst - before !== 2n * (2n * stake - fee)) {
+      throw new Error(`two winning claims should pay ${2n * (2n * stake - fee)}, got ${afterFirst - before}`);
+    }
+    for (const r of rounds) {
+      const [w, l] = await Promise.all([r.win, r.lose].map((pk) => program.account.ticket.fetch(pk, "confirmed")));
+      if (!w.claimed || l.claimed) throw new Error("only the winning tickets should be marked claimed");
+    }
+
+    // a retry over the same groups is a no-op
+    await rpcConfirmed(provider, claimMulti(groups));
+    if ((await getTokenAmountRaw(provider.connection, userTIMLGAta)) !== afterFirst) {
+      throw new Error("re-claiming must not pay twice");
+    }
+  });
+  it("set_pulse_multisig: publishes with threshold oracle signatures, rejects short, foreign and repeated ones", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
+
+    const oracles = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const oracleSetPda = await resetOracleSet(program, provider, configPda, oracles.map((o) => o.publicKey), 2);
 
+    const roundId = (Math.floor(Date.now() / 1000) + 10_007) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const pulseIndexTarget = 790;
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 8;
     await rpcConfirmed(
       provider,
       program.methods
-        .setOraclePubkey(oracle.publicKey)
-        .accounts({ config: configPda, admin })
+        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot0 + 300), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
+        .accounts({
+          config: configPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
+          admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
         .rpc()
     );
+    const pulse = crypto.randomBytes(64);
+    const sendWith = (signers) =>
+      sendPulseMultisig(program, provider, { configPda, oracleSetPda, roundPda, roundId, pulseIndexTarget, pulse, signers });
 
-    const roundId = (Math.floor(Date.now() / 1000) + 4451) % 1000000;
-    const pulseIndexTarget = 22222;
-
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
 
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 8;
+    await expectTxFail(() => sendWith([oracles[0]]), "InsufficientAttestations");
+    await expectTxFail(() => sendWith([oracles[0], Keypair.generate()]), "OracleNotFound");
+    await expectTxFail(() => sendWith([oracles[0], oracles[0]]), "DuplicateAttestation");
+    if ((await program.account.round.fetch(roundPda, "confirmed")).pulseSet) {
+      throw new Error("rejected multisig attempts must not set the pulse");
+    }
+
+    await sendWith([oracles[2], oracles[0]]);
+    const r = await program.account.round.fetch(roundPda, "confirmed");
+    if (!r.pulseSet || !Buffer.from(r.pulse).equals(pulse)) throw new Error("multisig pulse should be stored");
+    if (r.pulseAttestations !== 2 || r.state !== 1) {
+      throw new Error(`expected 2 attestations in PulseSet, got ${r.pulseAttestations} / state ${r.state}`);
+    }
+    await expectTxFail(() => sendWith(oracles), "PulseAlreadySet");
+  });
+  it("reveal_merkle: reveals tickets proven against the committed Merkle root, rejects a tampered leaf", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
 
+    const roundId = (Math.floor(Date.now() / 1000) + 10_009) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(pulseIndexTarget), new BN(commitDeadline), new BN(slot + 200))
+        .createRound(new BN(roundId), new BN(791), new BN(commitDeadline), new BN(slot0 + 200), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
+          timlgMint,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -2775,101 +9771,124 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
-
-    const pulse = crypto.randomBytes(64);
-
-    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
-
-    const edIx = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: oracle.secretKey,
-      message: msg,
-    });
-
-    // NOOP instruction between ed25519 and anchor ix
-    const noopIx = SystemProgram.transfer({
-      fromPubkey: relayer.publicKey,
-      toPubkey: relayer.publicKey,
-      lamports: 0,
-    });
-
-    const anchorIx = await program.methods
-      .setPulseSigned(new BN(roundId), Array.from(pulse))
-      .accounts({
-        config: configPda,
-        round: roundPda,
-        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-      })
-      .instruction();
-
-    await expectTxFail(async () => {
-      const tx = new Transaction().add(edIx, noopIx, anchorIx); // <-- breaks adjacency
-      tx.feePayer = relayer.publicKey;
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
-
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-        commitment: "confirmed",
-      });
-    }, "MissingOrInvalidEd25519Ix");
-  });
-
-  it("oracle rotation: old oracle can no longer set_pulse_signed after update", async () => {
-    const adminKp = provider.wallet.payer;
-    const admin = adminKp.publicKey;
-
-    const oracleOld = Keypair.generate();
-    const oracleNew = Keypair.generate();
-    const relayer = Keypair.generate();
+    const tickets = [1, 2, 3, 4].map((nonce) => ({
+      nonce,
+      guess: nonce % 2,
+      salt: crypto.randomBytes(32),
+      pda: deriveTicketPda(program.programId, roundId, admin, nonce),
+    }));
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .commitBatch(
+          new BN(roundId),
+          tickets.map((t) => ({ nonce: new BN(t.nonce), commitment: Array.from(commitHash(roundId, admin, t.nonce, t.guess, t.salt)) }))
+        )
+        .accounts({
+          config: configPda,
+          round: roundPda,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(tickets.map((t) => ({ pubkey: t.pda, isSigner: false, isWritable: true })))
+        .rpc()
+    );
 
-    // Fund relayer
-    {
-      const sig = await provider.connection.requestAirdrop(
-        relayer.publicKey,
-        2 * anchor.web3.LAMPORTS_PER_SOL
+    // MUST match Rust merkle_reveal_leaf() / merkle_node(): 0x00 leaf / 0x01 sorted-pair node prefixes
+    const sha256 = (...parts) => crypto.createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (t, guess = t.guess) =>
+      sha256(
+        Buffer.from([0]),
+        Buffer.from("timlg-protocol:reveal_leaf_v1", "utf8"),
+        leU64(roundId),
+        admin.toBytes(),
+        leU64(t.nonce),
+        Buffer.from([guess]),
+        t.salt
       );
-      await confirmSig(provider.connection, sig, "confirmed");
-    }
-
-    const [configPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("config")],
+    const node = (a, b) => (Buffer.compare(a, b) <= 0 ? sha256(Buffer.from([1]), a, b) : sha256(Buffer.from([1]), b, a));
+    const leaves = tickets.map((t) => leaf(t));
+    const n01 = node(leaves[0], leaves[1]);
+    const n23 = node(leaves[2], leaves[3]);
+    const root = node(n01, n23);
+    const proofs = [[leaves[1], n23], [leaves[0], n23], [leaves[3], n01], [leaves[2], n01]];
+
+    // the root is fixed while commits are open, before the pulse is known
+    const [userRoundPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_round"), leU64(roundId), admin.toBytes()],
       program.programId
     );
-    await ensureConfig(program, provider, configPda);
+    const commitRoot = (r) =>
+      program.methods
+        .commitRevealRoot(new BN(roundId), Array.from(r))
+        .accounts({ config: configPda, round: roundPda, user: admin, userRoundStats: userRoundPda })
+        .rpc();
+    await rpcConfirmed(provider, commitRoot(root));
 
-    // 1) Set OLD oracle pubkey
+    while ((await provider.connection.getSlot("confirmed")) <= commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await expectTxFail(() => commitRoot(n01), "CommitClosed");
     await rpcConfirmed(
       provider,
       program.methods
-        .setOraclePubkey(oracleOld.publicKey)
-        .accounts({ config: configPda, admin })
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xff)))
+        .accounts({ config: configPda, round: roundPda, admin })
         .rpc()
     );
 
-    // Create round
-    const roundId = (Math.floor(Date.now() / 1000) + 777) % 1000000;
-    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const revealMerkle = (indices, entryFor) =>
+      program.methods
+        .revealMerkle(new BN(roundId), indices.map(entryFor))
+        .accounts({ config: configPda, round: roundPda, user: admin, userRoundStats: userRoundPda })
+        .remainingAccounts(indices.map((i) => ({ pubkey: tickets[i].pda, isSigner: false, isWritable: true })))
+        .rpc();
+    const entry = (i) => ({
+      nonce: new BN(tickets[i].nonce),
+      guess: tickets[i].guess,
+      salt: Array.from(tickets[i].salt),
+      proof: proofs[i].map((h) => Array.from(h)),
+    });
 
-    const slot = await provider.connection.getSlot("confirmed");
-    const commitDeadline = slot + 10;
-    const revealDeadline = slot + 200;
-    const pulseIndexTarget = 111111;
+    // flipping the guess changes the leaf, so the proof no longer reaches the root
+    await expectTxFail(
+      () => revealMerkle([1], (i) => ({ ...entry(i), guess: 1 - tickets[i].guess })),
+      "InvalidMerkleProof"
+    );
+
+    await rpcConfirmed(provider, revealMerkle([0, 2], entry));
+    const revealed = await Promise.all(tickets.map((t) => program.account.ticket.fetch(t.pda, "confirmed")));
+    const flags = revealed.map((t) => t.revealed);
+    if (JSON.stringify(flags) !== JSON.stringify([true, false, true, false])) {
+      throw new Error(`only the proven tickets should be revealed, got ${flags}`);
+    }
+    // guess 1 under an all-ones pulse
+    if (!revealed[0].win || !revealed[2].win) throw new Error("revealed tickets should be scored against the pulse");
+  });
+  it("execute_burns: loser stakes wait out burn_delay_slots in the vault, then burn exactly once", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const tok = await program.account.tokenomics.fetch(tokenomicsPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
+    const delay = 12;
 
+    const roundId = (Math.floor(Date.now() / 1000) + 10_013) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const commitDeadline = slot0 + 10;
+    const revealDeadline = slot0 + 22;
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(
-          new BN(roundId),
-          new BN(pulseIndexTarget),
-          new BN(commitDeadline),
-          new BN(revealDeadline)
-        )
+        .createRound(new BN(roundId), new BN(792), new BN(commitDeadline), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          timlgMint: TIMLG_MINT,
+          timlgMint,
           round: roundPda,
           vault: vaultPda,
           timlgVault: timlgVaultPda,
@@ -2881,104 +9900,123 @@ describe("timlg_protocol - BATCHING", () => {
         .rpc()
     );
 
-    // Wait past commit deadline
-    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 100));
-    }
-
-    // 2) Rotate to NEW oracle pubkey
+    // a single revealed loser: guess 0 under an all-ones pulse
+    const nonce = 1;
+    const salt = Buffer.alloc(32, 23);
+    const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
     await rpcConfirmed(
       provider,
       program.methods
-        .setOraclePubkey(oracleNew.publicKey)
-        .accounts({ config: configPda, admin })
-        .rpc()
-    );
-
-    // Build pulse message (canonical)
-    const pulse = crypto.randomBytes(64);
-    const msg = expectedPulseMsg(program.programId, roundId, pulseIndexTarget, pulse);
-
-    // 3) Try with OLD oracle => must fail (pubkey mismatch)
-    {
-      const edIxOld = Ed25519Program.createInstructionWithPrivateKey({
-        privateKey: oracleOld.secretKey,
-        message: msg,
-      });
-
-      const anchorIx = await program.methods
-        .setPulseSigned(new BN(roundId), Array.from(pulse))
+        .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 0, salt)))
         .accounts({
           config: configPda,
           round: roundPda,
-          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          timlgMint,
+          timlgVault: timlgVaultPda,
+          ticket: ticketPda,
+          user: admin,
+          userTIMLGAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
-        .instruction();
+        .rpc()
+    );
+    while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xff)))
+        .accounts({ config: configPda, round: roundPda, admin })
+        .rpc()
+    );
+    await rpcConfirmed(
+      provider,
+      program.methods
+        .revealBatch(new BN(roundId), [{ nonce: new BN(nonce), guess: 0, salt: Array.from(salt) }])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+        .rpc()
+    );
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
 
-      const tx = new Transaction().add(edIxOld, anchorIx);
-      tx.feePayer = relayer.publicKey;
+    const executeBurns = () =>
+      program.methods
+        .executeBurns(new BN(roundId))
+        .accounts({ config: configPda, round: roundPda, timlgMint, timlgVault: timlgVaultPda, payer: admin, tokenProgram: TOKEN_PROGRAM_ID })
+        .rpc();
+    const setBurnDelay = (slots) =>
+      rpcConfirmed(
+        provider,
+        program.methods.setBurnDelay(new BN(slots)).accounts({ config: configPda, tokenomics: tokenomicsPda, admin }).rpc()
+      );
 
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
+    await expectTxFail(executeBurns, "RoundTokensNotSettled");
 
-      await expectTxFail(
-        async () =>
-          sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-            commitment: "confirmed",
-          }),
-        "Ed25519PubkeyMismatch"
+    // the delay is snapshotted at settle, so it can be restored right after
+    await setBurnDelay(delay);
+    try {
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .settleRoundTokens(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            treasury: treasuryPda,
+            tokenomics: tokenomicsPda,
+            rewardFeePool: rewardFeePoolPda,
+            replicationPool: replicationPoolPda,
+            admin,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([{ pubkey: ticketPda, isSigner: false, isWritable: true }])
+          .rpc()
       );
+    } finally {
+      await setBurnDelay(tok.burnDelaySlots.toString());
     }
 
-    // 4) Try with NEW oracle => must succeed
-    {
-      const edIxNew = Ed25519Program.createInstructionWithPrivateKey({
-        privateKey: oracleNew.secretKey,
-        message: msg,
-      });
-
-      const anchorIx = await program.methods
-        .setPulseSigned(new BN(roundId), Array.from(pulse))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
-        })
-        .instruction();
-
-      const tx = new Transaction().add(edIxNew, anchorIx);
-      tx.feePayer = relayer.publicKey;
-
-      const { blockhash } = await provider.connection.getLatestBlockhash("confirmed");
-      tx.recentBlockhash = blockhash;
-      tx.sign(relayer);
-
-      await sendAndConfirmTransaction(provider.connection, tx, [relayer], {
-        commitment: "confirmed",
-      });
+    const settled = await program.account.round.fetch(roundPda, "confirmed");
+    if (BigInt(settled.pendingBurn.toString()) !== stake) throw new Error(`pending_burn should be the loser stake, got ${settled.pendingBurn}`);
+    if ((await getTokenAmountRaw(provider.connection, timlgVaultPda)) !== stake) {
+      throw new Error("the loser stake should stay in the vault during the audit window");
     }
+    await expectTxFail(executeBurns, "BurnTooEarly");
 
-    const r = await program.account.round.fetch(roundPda);
-    if (!r.pulseSet) throw new Error("pulseSet should be true after NEW oracle succeeds");
+    const burnable = Number(settled.tokenSettledSlot.toString()) + delay;
+    while ((await provider.connection.getSlot("confirmed")) <= burnable) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
+    const supplyBefore = BigInt((await provider.connection.getTokenSupply(timlgMint, "confirmed")).value.amount);
+    await rpcConfirmed(provider, executeBurns());
+    const supplyAfter = BigInt((await provider.connection.getTokenSupply(timlgMint, "confirmed")).value.amount);
+    if (supplyBefore - supplyAfter !== stake) throw new Error(`execute_burns should burn ${stake}, burned ${supplyBefore - supplyAfter}`);
+
+    const burned = await program.account.round.fetch(roundPda, "confirmed");
+    if (burned.pendingBurn.toString() !== "0" || BigInt(burned.burnedTotal.toString()) !== stake) {
+      throw new Error("pending_burn should move into burned_total");
+    }
+    await expectTxFail(executeBurns, "NoPendingBurn");
   });
-
-  it("settle_round_tokens supports incremental settlement via settled_count", async () => {
+  it("Config lifetime totals count every commit and reveal", async () => {
     const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint, userTIMLGAta } = await ensureBoot(program, provider);
+    const before = await program.account.config.fetch(configPda, "confirmed");
 
-    const { configPda, timlgMint, treasuryPda, userTIMLGAta } = await ensureBoot(program, provider);
-
-    const roundId = (Math.floor(Date.now() / 1000) + 4242) % 1_000_000;
+    const roundId = (Math.floor(Date.now() / 1000) + 10_017) % 1_000_000;
     const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
-
     const slot0 = await provider.connection.getSlot("confirmed");
     const commitDeadline = slot0 + 10;
-    const revealDeadline = slot0 + 25;
-
     await rpcConfirmed(
       provider,
       program.methods
-        .createRound(new BN(roundId), new BN(999), new BN(commitDeadline), new BN(revealDeadline))
+        .createRound(new BN(roundId), new BN(793), new BN(commitDeadline), new BN(slot0 + 40), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
           timlgMint,
@@ -2990,27 +10028,21 @@ describe("timlg_protocol - BATCHING", () => {
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
-        .rpc()
-    );
-
-    // Create 4 tickets
-    const tickets = [];
-    for (let nonce = 1; nonce <= 4; nonce++) {
-      const guess = 1;
-      const salt = crypto.randomBytes(32);
-      const commitment = commitHash(roundId, admin, nonce, guess, salt);
-      const ticketPda = deriveTicketPda(program.programId, roundId, admin, nonce);
+        .rpc()
+    );
 
+    const salt = (nonce) => Buffer.alloc(32, 40 + nonce);
+    for (const nonce of [1, 2]) {
       await rpcConfirmed(
         provider,
         program.methods
-          .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitment))
+          .commitTicket(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, admin, nonce, 1, salt(nonce))))
           .accounts({
             config: configPda,
             round: roundPda,
             timlgMint,
             timlgVault: timlgVaultPda,
-            ticket: ticketPda,
+            ticket: deriveTicketPda(program.programId, roundId, admin, nonce),
             user: admin,
             userTIMLGAta,
             tokenProgram: TOKEN_PROGRAM_ID,
@@ -3018,246 +10050,231 @@ describe("timlg_protocol - BATCHING", () => {
           })
           .rpc()
       );
-
-      tickets.push({ nonce, guess, salt, ticketPda });
+    }
+    const committed = await program.account.config.fetch(configPda, "confirmed");
+    if (BigInt(committed.totalCommitted.toString()) - BigInt(before.totalCommitted.toString()) !== 2n) {
+      throw new Error("total_committed should count both tickets");
     }
 
-    // Wait >= commitDeadline then set pulse
     while ((await provider.connection.getSlot("confirmed")) < commitDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
+      await new Promise((r) => setTimeout(r, 200));
     }
-
-    // Make nonce=1 win (bit=1), nonce=2 lose (bit=0). Others irrelevant (unrevealed).
-    const pulse = Buffer.alloc(64, 0);
-    const bit1 = deriveBitIndex(roundId, admin, 1);
-    const bit2 = deriveBitIndex(roundId, admin, 2);
-    setBit(pulse, bit1, 1);
-    setBit(pulse, bit2, 0);
-
     await rpcConfirmed(
       provider,
       program.methods
-        .setPulseMock(new BN(roundId), Array.from(pulse))
+        .setPulseMock(new BN(roundId), Array.from(Buffer.alloc(64, 0xff)))
         .accounts({ config: configPda, round: roundPda, admin })
         .rpc()
     );
-
-    // Reveal only 2 tickets (nonce 1 and 2). Leave 3 and 4 unrevealed.
-    for (const t of tickets.filter((x) => x.nonce === 1 || x.nonce === 2)) {
-      await rpcConfirmed(
-        provider,
-        program.methods
-          .revealTicket(new BN(roundId), new BN(t.nonce), t.guess, Array.from(t.salt))
-          .accounts({ config: configPda, round: roundPda, ticket: t.ticketPda, user: admin })
-          .rpc()
-      );
-    }
-
-    // Wait > revealDeadline then finalize
-    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
-      await new Promise((r) => setTimeout(r, 120));
-    }
-
+    // only one of the two tickets is revealed
     await rpcConfirmed(
       provider,
       program.methods
-        .finalizeRound(new BN(roundId))
-        .accounts({ config: configPda, round: roundPda, admin })
+        .revealBatch(new BN(roundId), [{ nonce: new BN(1), guess: 1, salt: Array.from(salt(1)) }])
+        .accounts({ config: configPda, round: roundPda, user: admin })
+        .remainingAccounts([{ pubkey: deriveTicketPda(program.programId, roundId, admin, 1), isSigner: false, isWritable: true }])
         .rpc()
     );
+    const revealed = await program.account.config.fetch(configPda, "confirmed");
+    if (BigInt(revealed.totalRevealed.toString()) - BigInt(before.totalRevealed.toString()) !== 1n) {
+      throw new Error("total_revealed should count the one revealed ticket");
+    }
+    if (revealed.totalRewardsMinted.toString() !== before.totalRewardsMinted.toString()) {
+      throw new Error("nothing was claimed, total_rewards_minted should not move");
+    }
+  });
+  it("recover_funds refunds the service fee recorded at commit, to whoever paid it", async () => {
+    const admin = provider.wallet.publicKey;
+    const payerKp = provider.wallet.payer;
+    const { configPda, timlgMint, treasurySolPda, userTIMLGAta } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const stake = BigInt(cfg.stakeAmount.toString());
 
-    // --- Incremental settle #1: settle two tickets (1 and 3)
+    const roundId = (Math.floor(Date.now() / 1000) + 10_021) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const revealDeadline = slot0 + 20;
     await rpcConfirmed(
       provider,
       program.methods
-        .settleRoundTokens(new BN(roundId))
+        .createRound(new BN(roundId), new BN(794), new BN(slot0 + 10), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          round: roundPda,
           timlgMint,
+          round: roundPda,
+          vault: vaultPda,
           timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
           admin,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
-        .remainingAccounts([
-          { pubkey: tickets[0].ticketPda, isSigner: false, isWritable: true }, // nonce 1
-          { pubkey: tickets[2].ticketPda, isSigner: false, isWritable: true }, // nonce 3
-        ])
         .rpc()
     );
-
-    let r1 = await program.account.round.fetch(roundPda, "confirmed");
-    const settled1 = (r1.settledCount ?? r1.settled_count).toString();
-    const committed = (r1.committedCount ?? r1.committed_count).toString();
-    const tokenSettled1 = (r1.tokenSettled ?? r1.token_settled);
-
-    if (committed !== "4") throw new Error(`expected committed_count=4, got ${committed}`);
-    if (settled1 !== "2") throw new Error(`expected settled_count=2, got ${settled1}`);
-    if (tokenSettled1) throw new Error("token_settled should be false after partial settle");
-
-    // --- Incremental settle #2: settle remaining tickets (2 and 4)
+    // one ticket against a minimum of two: finalize voids the round
     await rpcConfirmed(
       provider,
       program.methods
-        .settleRoundTokens(new BN(roundId))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
-          admin,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .remainingAccounts([
-          { pubkey: tickets[1].ticketPda, isSigner: false, isWritable: true }, // nonce 2
-          { pubkey: tickets[3].ticketPda, isSigner: false, isWritable: true }, // nonce 4
-        ])
+        .setRoundMinParticipants(new BN(roundId), new BN(2))
+        .accounts({ config: configPda, round: roundPda, admin })
         .rpc()
     );
 
-    let r2 = await program.account.round.fetch(roundPda, "confirmed");
-    const settled2 = (r2.settledCount ?? r2.settled_count).toString();
-    const tokenSettled2 = (r2.tokenSettled ?? r2.token_settled);
-
-    if (settled2 !== "4") throw new Error(`expected settled_count=4, got ${settled2}`);
-    if (!tokenSettled2) throw new Error("token_settled should be true after full settle");
-
-    // calling again must fail now
-    await expectTxFail(async () => {
-      await program.methods
-        .settleRoundTokens(new BN(roundId))
-        .accounts({
-          config: configPda,
-          round: roundPda,
-          timlgMint,
-          timlgVault: timlgVaultPda,
-          treasury: treasuryPda,
-          tokenomics: tokenomicsPda,
-          replicationPool: replicationPoolPda,
-          admin,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .remainingAccounts([{ pubkey: tickets[0].ticketPda, isSigner: false, isWritable: true }])
-        .rpc();
-    }, "RoundTokensAlreadySettled");
-  });
-
-  it("oracle_set: initialize + add/remove + set_threshold", async () => {
-    const admin = provider.wallet.publicKey;
-    const payer = provider.wallet.payer; // Keypair (NodeWallet)
-
-    const { configPda } = await ensureConfig(program, provider);
-
-    const [oracleSetPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("oracle_set"), configPda.toBytes()],
-      program.programId
-    );
-
-    const oracle1 = Keypair.generate();
-    const oracle2 = Keypair.generate();
-
-    // init (if not exists)
-    const existing = await program.account.oracleSet.fetchNullable(oracleSetPda);
-    if (!existing) {
+    const userKp = Keypair.generate();
+    const user = userKp.publicKey;
+    const userAta = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payerKp, timlgMint, user, false, "confirmed")
+    ).address;
+    await splTransfer(provider.connection, payerKp, userTIMLGAta, userAta, admin, stake);
+    await splApprove(provider.connection, payerKp, userAta, roundPda, userKp, stake, [], { commitment: "confirmed" });
+
+    const setServiceFee = (lamports) =>
+      rpcConfirmed(
+        provider,
+        program.methods.updateSolServiceFee(new BN(lamports)).accounts({ config: configPda, admin }).rpc()
+      );
+    const FEE = 7000;
+    const nonce = 1;
+    const ticketPda = deriveTicketPda(program.programId, roundId, user, nonce);
+    try {
+      await setServiceFee(FEE);
+      // the relayer (wallet) pays the fee on the user's behalf
       await rpcConfirmed(
         provider,
         program.methods
-          .initializeOracleSet(
-            1, // threshold
-            [oracle1.publicKey] // initial_oracles
-          )
+          .commitTicketDelegated(new BN(roundId), new BN(nonce), Array.from(commitHash(roundId, user, nonce, 1, Buffer.alloc(32, 8))))
           .accounts({
             config: configPda,
-            oracleSet: oracleSetPda,
-            admin,
+            round: roundPda,
+            timlgMint,
+            timlgVault: timlgVaultPda,
+            ticket: ticketPda,
+            user,
+            payer: admin,
+            userTIMLGAta: userAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
-            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           })
-          .signers([payer])
+          .signers([userKp])
           .rpc()
       );
-    }
+      const ticket = await program.account.ticket.fetch(ticketPda, "confirmed");
+      if (ticket.solFeePaid.toNumber() !== FEE || !ticket.feePayer.equals(admin)) {
+        throw new Error("the ticket should record the fee charged and the relayer that paid it");
+      }
 
-    // add oracle2
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .addOracle(oracle2.publicKey)
-        .accounts({
-          config: configPda,
-          oracleSet: oracleSetPda,
-          admin,
-        })
-        .rpc()
-    );
+      // a later fee change must not change what the ticket gets back
+      await setServiceFee(FEE * 3);
 
-    // set threshold to 2 (must be <= allowlist length)
-    await rpcConfirmed(
-      provider,
-      program.methods
-        .setOracleThreshold(2)
-        .accounts({
-          config: configPda,
-          oracleSet: oracleSetPda,
-          admin,
-        })
-        .rpc()
-    );
+      while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+        await new Promise((r) => setTimeout(r, 200));
+      }
+      await rpcConfirmed(
+        provider,
+        program.methods
+          .finalizeRound(new BN(roundId))
+          .accounts({ config: configPda, round: roundPda, admin })
+          .rpc()
+      );
 
-    // removing oracle2 now should fail (threshold would exceed oracle count)
-    await expectTxFail(
-      async () =>
-        rpcConfirmed(
-          provider,
-          program.methods
-            .removeOracle(oracle2.publicKey)
-            .accounts({
-              config: configPda,
-              oracleSet: oracleSetPda,
-              admin,
-            })
-            .rpc()
-        ),
-      "Threshold exceeds current oracle count"
-    );
+      const recover = (feePayer) =>
+        program.methods
+          .recoverFunds(new BN(roundId))
+          .accounts({
+            config: configPda,
+            round: roundPda,
+            ticket: ticketPda,
+            user,
+            userTokenAccount: userAta,
+            timlgVault: timlgVaultPda,
+            vault: vaultPda,
+            timlgMint,
+            treasurySol: treasurySolPda,
+            feePayer,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([userKp])
+          .rpc();
 
-    // lower threshold back to 1
-    await rpcConfirmed(
-      provider,
+      // the user did not pay the fee, so it cannot be refunded to them
+      await expectTxFail(() => recover(user), "Unauthorized");
+
+      const treasuryBefore = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      await rpcConfirmed(provider, recover(admin));
+      const treasuryAfter = await provider.connection.getBalance(treasurySolPda, "confirmed");
+      if (treasuryBefore - treasuryAfter !== FEE) {
+        throw new Error(`refund should be the ${FEE} lamports charged, got ${treasuryBefore - treasuryAfter}`);
+      }
+    } finally {
+      await setServiceFee(cfg.solServiceFeeLamports.toString());
+    }
+  });
+  it("set_rescue_authority: only the rescue key rotates it, never to unset or to the admin", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda } = await ensureBoot(program, provider);
+    const cfg = await program.account.config.fetch(configPda, "confirmed");
+    const dest = cfg.rescueDestination;
+
+    const rotate = (signer, next) =>
       program.methods
-        .setOracleThreshold(1)
-        .accounts({
-          config: configPda,
-          oracleSet: oracleSetPda,
-          admin,
-        })
-        .rpc()
-    );
+        .setRescueAuthority(next, dest)
+        .accounts({ config: configPda, authority: signer.publicKey })
+        .signers(signer === provider.wallet.payer ? [] : [signer])
+        .rpc();
 
-    // now remove oracle2 should succeed
+    const nextKp = Keypair.generate();
+    // no admin fallback, even for a valid new key
+    await expectTxFail(() => rotate(provider.wallet.payer, nextKp.publicKey), "Unauthorized");
+    await expectTxFail(() => rotate(RESCUE_KP, PublicKey.default), "InvalidRescueAuthority");
+    await expectTxFail(() => rotate(RESCUE_KP, admin), "InvalidRescueAuthority");
+
+    await rpcConfirmed(provider, rotate(RESCUE_KP, nextKp.publicKey));
+    try {
+      const rotated = await program.account.config.fetch(configPda, "confirmed");
+      if (!rotated.rescueAuthority.equals(nextKp.publicKey)) throw new Error("rescue authority should rotate");
+      await expectTxFail(() => rotate(RESCUE_KP, RESCUE_KP.publicKey), "Unauthorized");
+    } finally {
+      await rpcConfirmed(provider, rotate(nextKp, RESCUE_KP.publicKey));
+    }
+  });
+  it("get_round_status: a pulse-less round past its reveal deadline awaits the pulse, it is not finalizable", async () => {
+    const admin = provider.wallet.publicKey;
+    const { configPda, timlgMint } = await ensureBoot(program, provider);
+
+    const roundId = (Math.floor(Date.now() / 1000) + 10_033) % 1_000_000;
+    const { roundPda, vaultPda, timlgVaultPda } = deriveRoundPdas(program.programId, roundId);
+    const slot0 = await provider.connection.getSlot("confirmed");
+    const revealDeadline = slot0 + 8;
     await rpcConfirmed(
       provider,
       program.methods
-        .removeOracle(oracle2.publicKey)
+        .createRound(new BN(roundId), new BN(799), new BN(slot0 + 4), new BN(revealDeadline), new BN(0), NO_LABEL, PublicKey.default, new BN(0), new BN(0), 1)
         .accounts({
           config: configPda,
-          oracleSet: oracleSetPda,
+          timlgMint,
+          round: roundPda,
+          vault: vaultPda,
+          timlgVault: timlgVaultPda,
           admin,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
         })
         .rpc()
     );
+    while ((await provider.connection.getSlot("confirmed")) <= revealDeadline) {
+      await new Promise((r) => setTimeout(r, 200));
+    }
 
-    const os = await program.account.oracleSet.fetch(oracleSetPda);
-    if (os.threshold !== 1) throw new Error("threshold mismatch");
-    // oracle2 should be gone
-    const has2 = os.oracles.some((pk) => pk.equals(oracle2.publicKey));
-    if (has2) throw new Error("oracle2 still present after removal");
+    const PHASE_AWAITING_PULSE = 1;
+    const view = await program.methods
+      .getRoundStatus(new BN(roundId))
+      .accounts({ config: configPda, round: roundPda })
+      .view();
+    if (view.phase !== PHASE_AWAITING_PULSE) {
+      throw new Error(`expected PHASE_AWAITING_PULSE, got ${view.phase}`);
+    }
+    if (view.refundsAllowed) throw new Error("refunds open only after the refund timeout");
   });
-
 });
@@ -17,7 +17,7 @@ describe("timlg_protocol - MVP-0", () => {
     );
 
     await program.methods
-      .initializeConfig(new BN(1), new BN(200), new BN(200))
+      .initializeConfig(new BN(1), new BN(200), new BN(200), false, new BN(1), new BN(0), provider.wallet.publicKey, provider.wallet.publicKey)
       .accounts({
         config: configPda,
         admin: provider.wallet.publicKey,
@@ -34,7 +34,7 @@ describe("timlg_protocol - MVP-0", () => {
     );
 
     await program.methods
-      .createRound(roundId, new BN(12345), new BN(slot + 300), new BN(slot + 600))
+      .createRound(roundId, new BN(12345), new BN(slot + 300), new BN(slot + 600), new BN(0), Array(32).fill(0), PublicKey.default, new BN(0), new BN(0), 1)
       .accounts({
         config: configPda,
         round: roundPda,